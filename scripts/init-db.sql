@@ -46,4 +46,41 @@ CREATE TABLE IF NOT EXISTS technical_agent_overview (
     created_at TIMESTAMPTZ NOT NULL
 );
 
+CREATE TABLE IF NOT EXISTS planner_overview (
+    planner_id VARCHAR(255) PRIMARY KEY,
+    algorithm VARCHAR(50) NOT NULL,
+    active_plans INTEGER NOT NULL DEFAULT 0,
+    completed_plans INTEGER NOT NULL DEFAULT 0,
+    failed_plans INTEGER NOT NULL DEFAULT 0,
+    online_workers INTEGER NOT NULL DEFAULT 0,
+    last_activity TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS path_plan_overview (
+    plan_id VARCHAR(255) PRIMARY KEY,
+    planner_id VARCHAR(255) NOT NULL,
+    agent_id VARCHAR(255) NOT NULL,
+    status VARCHAR(50) NOT NULL,
+    failure_reason TEXT,
+    worker_id VARCHAR(255),
+    parent_plan_id VARCHAR(255),
+    waypoint_count INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL,
+    finished_at TIMESTAMPTZ,
+    archived_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_path_plan_overview_planner ON path_plan_overview(planner_id);
+
+CREATE TABLE IF NOT EXISTS path_plan_worker_overview (
+    planner_id VARCHAR(255) NOT NULL,
+    worker_id VARCHAR(255) NOT NULL,
+    status VARCHAR(50) NOT NULL,
+    current_plan_id VARCHAR(255),
+    capabilities JSONB NOT NULL,
+    last_heartbeat TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (planner_id, worker_id)
+);
+
 -- Add more projection tables as needed for other domains
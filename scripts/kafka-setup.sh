@@ -45,8 +45,10 @@ topics=(
   "kinematic-agent-events"
   "path-planning-events"
   "path-planning-replies"
+  "path-planning-store-events"
   "dynamics-events"
   "gui-events"
+  "dead-letters"
 )
 
 # Default partitions and replication factor
@@ -0,0 +1,28 @@
+use crate::common::{DeadLetter, DeadLetterQueue};
+use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Dead-letter queue kept in memory, for tests and single-process runs
+#[derive(Clone, Default)]
+pub struct InMemoryDeadLetterQueue {
+    letters: Arc<Mutex<Vec<DeadLetter>>>,
+}
+
+impl InMemoryDeadLetterQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn letters(&self) -> Vec<DeadLetter> {
+        self.letters.lock().await.clone()
+    }
+}
+
+#[async_trait]
+impl DeadLetterQueue for InMemoryDeadLetterQueue {
+    async fn send(&self, letter: DeadLetter) -> Result<(), String> {
+        self.letters.lock().await.push(letter);
+        Ok(())
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use esrs::manager::AggregateManager;
+use esrs::store::postgres::PgStore;
+use esrs::store::EventStore as EsrsEventStore;
+use esrs::Aggregate;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::esrs_pg_store::{event_id_for, uuid_for_aggregate_id};
+use crate::common::{AggregateRoot, EventEnvelope};
+use crate::domains::path_planning::{PathPlanner, PathPlanningEvent, PlanningAlgorithm};
+use crate::esrs::path_planning::{PathPlanner as EsrsPathPlanner, PathPlannerState};
+
+/// What the migration found for one aggregate after writing its events
+#[derive(Debug, Clone, PartialEq)]
+pub enum MigrationOutcome {
+    /// Replaying both sides gives the same state
+    Verified,
+    /// The events were migrated but replay to different states
+    StateMismatch,
+    /// The esrs stream holds events that are not a prefix of the source
+    /// history, so nothing was written
+    Diverged(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct AggregateMigration {
+    pub aggregate_id: String,
+    pub esrs_id: Uuid,
+    pub source_events: usize,
+    /// Source events found in esrs before this run
+    pub already_migrated: usize,
+    /// Source events written by this run
+    pub migrated: usize,
+    pub outcome: MigrationOutcome,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MigrationReport {
+    pub aggregates: Vec<AggregateMigration>,
+    /// Envelopes that are not path planner events, or appear twice
+    pub skipped_envelopes: usize,
+}
+
+impl MigrationReport {
+    pub fn is_verified(&self) -> bool {
+        self.aggregates
+            .iter()
+            .all(|aggregate| aggregate.outcome == MigrationOutcome::Verified)
+    }
+}
+
+impl fmt::Display for MigrationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for aggregate in &self.aggregates {
+            let outcome = match &aggregate.outcome {
+                MigrationOutcome::Verified => "state verified".to_string(),
+                MigrationOutcome::StateMismatch => "STATE MISMATCH".to_string(),
+                MigrationOutcome::Diverged(reason) => format!("DIVERGED: {}", reason),
+            };
+            writeln!(
+                f,
+                "{} ({})  events={} already={} migrated={}  {}",
+                aggregate.aggregate_id,
+                aggregate.esrs_id,
+                aggregate.source_events,
+                aggregate.already_migrated,
+                aggregate.migrated,
+                outcome
+            )?;
+        }
+        let verified = self
+            .aggregates
+            .iter()
+            .filter(|aggregate| aggregate.outcome == MigrationOutcome::Verified)
+            .count();
+        write!(
+            f,
+            "{} of {} aggregates verified, {} envelopes skipped",
+            verified,
+            self.aggregates.len(),
+            self.skipped_envelopes
+        )
+    }
+}
+
+/// Group path planner envelopes by aggregate id, in the order given, dropping
+/// other aggregates, unreadable payloads and redelivered envelopes
+fn path_planner_histories(
+    envelopes: &[EventEnvelope],
+) -> (Vec<(String, Vec<PathPlanningEvent>)>, usize) {
+    let mut histories: Vec<(String, Vec<PathPlanningEvent>)> = Vec::new();
+    let mut seen = HashSet::new();
+    let mut skipped = 0;
+    for envelope in envelopes {
+        let event = match serde_json::from_value::<PathPlanningEvent>(envelope.event_data.clone()) {
+            Ok(event) if envelope.aggregate_type == PathPlanner::AGGREGATE_TYPE => event,
+            _ => {
+                skipped += 1;
+                continue;
+            }
+        };
+        if !seen.insert(envelope.event_id) {
+            skipped += 1;
+            continue;
+        }
+        match histories
+            .iter_mut()
+            .find(|(id, _)| *id == envelope.aggregate_id)
+        {
+            Some((_, events)) => events.push(event),
+            None => histories.push((envelope.aggregate_id.clone(), vec![event])),
+        }
+    }
+    (histories, skipped)
+}
+
+/// Copy native path planner history into the esrs store, one aggregate per
+/// string id, keeping the source order. Events already in esrs are recognised
+/// by their `event_id_for` id, so the migration can be stopped and run again.
+/// `store` must record event ids and should not publish: use
+/// `build_pg_store_without_bus`.
+pub async fn migrate_path_planner_history(
+    envelopes: &[EventEnvelope],
+    store: &PgStore<EsrsPathPlanner>,
+    pool: &PgPool,
+) -> anyhow::Result<MigrationReport> {
+    let (histories, skipped_envelopes) = path_planner_histories(envelopes);
+    let manager = AggregateManager::new(store.clone());
+
+    let mut report = MigrationReport {
+        aggregates: Vec::new(),
+        skipped_envelopes,
+    };
+    for (aggregate_id, events) in histories {
+        let esrs_id = uuid_for_aggregate_id(&aggregate_id);
+        let event_ids = events
+            .iter()
+            .map(|event| event_id_for::<EsrsPathPlanner>(&esrs_id, event))
+            .collect::<Result<Vec<_>, _>>()?;
+        let recorded: HashSet<Uuid> =
+            sqlx::query_scalar("SELECT event_id FROM esrs_event_ids WHERE event_id = ANY($1)")
+                .bind(&event_ids)
+                .fetch_all(pool)
+                .await?
+                .into_iter()
+                .collect();
+        let already_migrated = event_ids
+            .iter()
+            .take_while(|id| recorded.contains(id))
+            .count();
+
+        let agg_state = manager
+            .load(esrs_id)
+            .await?
+            .unwrap_or_else(|| esrs::AggregateState::with_id(esrs_id));
+        let stored = *agg_state.sequence_number() as usize;
+
+        let mut migration = AggregateMigration {
+            aggregate_id,
+            esrs_id,
+            source_events: events.len(),
+            already_migrated,
+            migrated: 0,
+            outcome: MigrationOutcome::Verified,
+        };
+        if recorded.len() != already_migrated || stored != already_migrated {
+            // Appending after foreign or out-of-order events would interleave histories
+            migration.outcome = MigrationOutcome::Diverged(format!(
+                "esrs holds {} events, of which the first {} source events match",
+                stored, already_migrated
+            ));
+            report.aggregates.push(migration);
+            continue;
+        }
+
+        let mut agg_state = agg_state;
+        let remaining = events[already_migrated..].to_vec();
+        if !remaining.is_empty() {
+            migration.migrated = remaining.len();
+            store.persist(&mut agg_state, remaining).await?;
+        }
+
+        let stored_events = store.by_aggregate_id(esrs_id).await?;
+        if !same_replayed_state(
+            &migration.aggregate_id,
+            &events,
+            stored_events.into_iter().map(|event| event.payload),
+        ) {
+            migration.outcome = MigrationOutcome::StateMismatch;
+        }
+        report.aggregates.push(migration);
+    }
+    Ok(report)
+}
+
+/// Replay the source history through the native aggregate and the stored one
+/// through the esrs aggregate, from the same starting state, and compare
+fn same_replayed_state(
+    aggregate_id: &str,
+    source: &[PathPlanningEvent],
+    stored: impl IntoIterator<Item = PathPlanningEvent>,
+) -> bool {
+    let mut native = PathPlanner::new(aggregate_id.to_string(), PlanningAlgorithm::AStar);
+    let mut esrs_state = PathPlannerState {
+        id: native.id.clone(),
+        workspace: native.workspace.clone(),
+        ..PathPlannerState::default()
+    };
+    for event in source {
+        // Events the native aggregate refuses are skipped by esrs as well
+        let _ = native.apply(event);
+    }
+    for event in stored {
+        esrs_state = EsrsPathPlanner::apply_event(esrs_state, event);
+    }
+    match (
+        serde_json::to_value(&native),
+        serde_json::to_value(&esrs_state),
+    ) {
+        (Ok(native), Ok(esrs)) => native == esrs,
+        _ => false,
+    }
+}
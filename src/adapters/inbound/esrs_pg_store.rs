@@ -1,40 +1,109 @@
 use esrs::store::postgres::PgStoreBuilder;
 use esrs::store::postgres::PgStore;
 use esrs::bus::EventBus;
-use sqlx::postgres::PgPoolOptions;
+use std::str::FromStr;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{Connection, PgConnection, PgPool};
+
+/// Open the pool shared by a PgStore and the helpers below. Connect once per
+/// process and pass it around instead of connecting per call.
+pub async fn connect_pg_pool(database_url: &str) -> anyhow::Result<PgPool> {
+    connect_pg_pool_in_schema(database_url, None).await
+}
+
+/// `connect_pg_pool` with every connection working in `schema`, which is
+/// created if needed. Used to give each tenant its own set of tables.
+pub async fn connect_pg_pool_in_schema(database_url: &str, schema: Option<&str>) -> anyhow::Result<PgPool> {
+    let mut options = PgConnectOptions::from_str(database_url)?;
+    if let Some(schema) = schema {
+        let mut connection = PgConnection::connect_with(&options).await?;
+        sqlx::query(&format!("CREATE SCHEMA IF NOT EXISTS \"{}\"", schema))
+            .execute(&mut connection)
+            .await?;
+        options = options.options([("search_path", schema)]);
+    }
+    Ok(PgPoolOptions::new()
+        .max_connections(5)
+        .connect_with(options)
+        .await?)
+}
 
 /// Build a PgStore for a specific event type T and attach an EventBus.
 /// The function runs migrations by default via PgStoreBuilder::try_build().
-pub async fn build_pg_store_with_bus<A, B>(database_url: &str, bus: B) -> anyhow::Result<PgStore<A>>
+/// Keep the returned pool for `agg_last_sequence` and `persist_best_effort`.
+/// The store records the `event_id_for` id of every event it persists, so a
+/// mirrored event cannot be stored twice.
+pub async fn build_pg_store_with_bus<A, B>(database_url: &str, bus: B) -> anyhow::Result<(PgStore<A>, PgPool)>
 where
-    A: esrs::Aggregate,
+    A: esrs::Aggregate + Send + Sync + 'static,
     A::Event: serde::Serialize + for<'de> serde::de::Deserialize<'de> + Send + Sync + 'static + Clone,
     B: EventBus<A> + Send + Sync + 'static,
 {
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(database_url)
-        .await?;
+    build_pg_store_in_schema_with_bus(database_url, None, bus).await
+}
 
-    let builder = PgStoreBuilder::new(pool).add_event_bus(bus);
+/// `build_pg_store_with_bus` with the store's tables in `schema`, see
+/// `connect_pg_pool_in_schema`
+pub async fn build_pg_store_in_schema_with_bus<A, B>(
+    database_url: &str,
+    schema: Option<&str>,
+    bus: B,
+) -> anyhow::Result<(PgStore<A>, PgPool)>
+where
+    A: esrs::Aggregate + Send + Sync + 'static,
+    A::Event: serde::Serialize + for<'de> serde::de::Deserialize<'de> + Send + Sync + 'static + Clone,
+    B: EventBus<A> + Send + Sync + 'static,
+{
+    let pool = connect_pg_pool_in_schema(database_url, schema).await?;
+    let builder = event_id_recording_builder::<A>(pool.clone()).await?.add_event_bus(bus);
     let store: PgStore<A> = builder
         .try_build()
         .await
         .map_err(|e| anyhow::anyhow!(e.to_string()))?;
-    Ok(store)
+    Ok((store, pool))
 }
 
-/// Convert a string aggregate id into a stable UUID using UUID v5 (name-based).
-/// This avoids generating new random UUIDs when the application uses string ids
-/// while keeping esrs' UUID-based identity consistent across runs.
-pub fn uuid_for_aggregate_id(id: &str) -> uuid::Uuid {
+/// Build a PgStore that records event ids like `build_pg_store_with_bus` but
+/// publishes nothing, for writing history that was already published
+pub async fn build_pg_store_without_bus<A>(pool: PgPool) -> anyhow::Result<PgStore<A>>
+where
+    A: esrs::Aggregate + Send + Sync + 'static,
+    A::Event: serde::Serialize + for<'de> serde::de::Deserialize<'de> + Send + Sync + 'static + Clone,
+{
+    event_id_recording_builder::<A>(pool)
+        .await?
+        .try_build()
+        .await
+        .map_err(|e| anyhow::anyhow!(e.to_string()))
+}
+
+/// A builder whose store records event ids and hash chains their events
+async fn event_id_recording_builder<A>(pool: PgPool) -> anyhow::Result<PgStoreBuilder<A>>
+where
+    A: esrs::Aggregate + Send + Sync + 'static,
+    A::Event: serde::Serialize + for<'de> serde::de::Deserialize<'de> + Send + Sync + 'static + Clone,
+{
+    sqlx::query(EVENT_IDS_TABLE_SQL).execute(&pool).await?;
+    sqlx::query(EVENT_HASHES_TABLE_SQL).execute(&pool).await?;
+    Ok(PgStoreBuilder::new(pool)
+        .add_transactional_event_handler(EventIdRecorder::<A>::new())
+        .add_transactional_event_handler(EventHashRecorder::<A>::new()))
+}
+
+/// Name of the table esrs keeps the events of `A` in
+pub fn events_table<A: esrs::Aggregate>() -> String {
+    format!("{}_events", A::NAME)
+}
+
+/// Name-based UUID (v3 layout) over `name`
+fn name_uuid(name: &[u8]) -> uuid::Uuid {
     // Implement a stable name-based UUID (v3) using MD5 so we avoid depending on
     // a specific uuid crate feature. This mirrors the semantics of v3/v5 but is
     // deterministic for the given id string.
     // Namespace: use the DNS namespace bytes (UUID::NAMESPACE_DNS)
     let mut input = Vec::new();
     input.extend_from_slice(uuid::Uuid::NAMESPACE_DNS.as_bytes());
-    input.extend_from_slice(id.as_bytes());
+    input.extend_from_slice(name);
     let digest = md5::compute(&input);
     let mut bytes = [0u8; 16];
     bytes.copy_from_slice(&digest[0..16]);
@@ -47,17 +116,267 @@ pub fn uuid_for_aggregate_id(id: &str) -> uuid::Uuid {
     uuid::Uuid::from_bytes(bytes)
 }
 
-/// Fetch the last sequence_number for the aggregate from the esrs events table.
-/// Returns Ok(Some(n)) if found, Ok(None) if no events exist, or Err on DB error.
-pub async fn agg_last_sequence(agg_uuid: &uuid::Uuid) -> anyhow::Result<Option<i64>> {
-    let database_url = std::env::var("DATABASE_URL").map_err(|e| anyhow::anyhow!(e.to_string()))?;
-    let pool = PgPoolOptions::new().max_connections(1).connect(&database_url).await?;
+/// Convert a string aggregate id into a stable UUID using UUID v5 (name-based).
+/// This avoids generating new random UUIDs when the application uses string ids
+/// while keeping esrs' UUID-based identity consistent across runs.
+pub fn uuid_for_aggregate_id(id: &str) -> uuid::Uuid {
+    name_uuid(id.as_bytes())
+}
+
+/// Deterministic id of an event of aggregate `A`: the same event mirrored twice
+/// gets the same id. Events carry their timestamp, so distinct events differ.
+pub fn event_id_for<A>(agg_uuid: &uuid::Uuid, event: &A::Event) -> Result<uuid::Uuid, serde_json::Error>
+where
+    A: esrs::Aggregate,
+    A::Event: serde::Serialize,
+{
+    let mut name = format!("{}/{}/", A::NAME, agg_uuid).into_bytes();
+    name.extend(serde_json::to_vec(event)?);
+    Ok(name_uuid(&name))
+}
+
+const EVENT_IDS_TABLE_SQL: &str = "CREATE TABLE IF NOT EXISTS esrs_event_ids (
+    event_id UUID PRIMARY KEY,
+    aggregate_type TEXT NOT NULL,
+    aggregate_id UUID NOT NULL,
+    store_event_id UUID NOT NULL
+)";
+
+/// Transactional event handler that records the `event_id_for` id of every
+/// persisted event. A second insert of the same id fails the persist, which
+/// `persist_best_effort` reports as already applied.
+pub struct EventIdRecorder<A> {
+    _marker: std::marker::PhantomData<A>,
+}
+
+impl<A> EventIdRecorder<A> {
+    pub fn new() -> Self {
+        Self {
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<A> Default for EventIdRecorder<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl<A> esrs::handler::TransactionalEventHandler<A, esrs::store::postgres::PgStoreError, sqlx::PgConnection> for EventIdRecorder<A>
+where
+    A: esrs::Aggregate + Send + Sync,
+    A::Event: serde::Serialize,
+{
+    async fn handle(
+        &self,
+        store_event: &esrs::store::StoreEvent<A::Event>,
+        transaction: &mut sqlx::PgConnection,
+    ) -> Result<(), esrs::store::postgres::PgStoreError> {
+        sqlx::query(
+            "INSERT INTO esrs_event_ids (event_id, aggregate_type, aggregate_id, store_event_id) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(event_id_for::<A>(&store_event.aggregate_id, &store_event.payload)?)
+        .bind(A::NAME)
+        .bind(store_event.aggregate_id)
+        .bind(store_event.id)
+        .execute(&mut *transaction)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete(
+        &self,
+        aggregate_id: uuid::Uuid,
+        transaction: &mut sqlx::PgConnection,
+    ) -> Result<(), esrs::store::postgres::PgStoreError> {
+        sqlx::query("DELETE FROM esrs_event_ids WHERE aggregate_type = $1 AND aggregate_id = $2")
+            .bind(A::NAME)
+            .bind(aggregate_id)
+            .execute(&mut *transaction)
+            .await?;
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "EventIdRecorder"
+    }
+}
+
+const EVENT_HASHES_TABLE_SQL: &str = "CREATE TABLE IF NOT EXISTS esrs_event_hashes (
+    aggregate_type TEXT NOT NULL,
+    aggregate_id UUID NOT NULL,
+    sequence_number BIGINT NOT NULL,
+    previous_hash TEXT,
+    hash TEXT NOT NULL,
+    PRIMARY KEY (aggregate_type, aggregate_id, sequence_number)
+)";
+
+/// What the hash of an esrs event covers. Times are cut to the microseconds
+/// Postgres keeps.
+fn esrs_chained_content(
+    id: uuid::Uuid,
+    aggregate_id: uuid::Uuid,
+    sequence_number: i64,
+    occurred_on: chrono::DateTime<chrono::Utc>,
+    payload: serde_json::Value,
+) -> serde_json::Value {
+    serde_json::json!({
+        "id": id,
+        "aggregate_id": aggregate_id,
+        "sequence_number": sequence_number,
+        "occurred_on": occurred_on.timestamp_micros(),
+        "payload": payload,
+    })
+}
+
+/// Transactional event handler that hash chains the events of every
+/// aggregate of `A` in the `esrs_event_hashes` table, the way native stores
+/// chain envelopes. `verify_esrs_chains` walks them.
+pub struct EventHashRecorder<A> {
+    _marker: std::marker::PhantomData<A>,
+}
+
+impl<A> EventHashRecorder<A> {
+    pub fn new() -> Self {
+        Self {
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<A> Default for EventHashRecorder<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl<A> esrs::handler::TransactionalEventHandler<A, esrs::store::postgres::PgStoreError, sqlx::PgConnection> for EventHashRecorder<A>
+where
+    A: esrs::Aggregate + Send + Sync,
+    A::Event: serde::Serialize,
+{
+    async fn handle(
+        &self,
+        store_event: &esrs::store::StoreEvent<A::Event>,
+        transaction: &mut sqlx::PgConnection,
+    ) -> Result<(), esrs::store::postgres::PgStoreError> {
+        let sequence_number = store_event.sequence_number as i64;
+        let previous_hash = sqlx::query_as::<_, (String,)>(
+            "SELECT hash FROM esrs_event_hashes WHERE aggregate_type = $1 AND aggregate_id = $2 AND sequence_number = $3",
+        )
+        .bind(A::NAME)
+        .bind(store_event.aggregate_id)
+        .bind(sequence_number - 1)
+        .fetch_optional(&mut *transaction)
+        .await?
+        .map(|(hash,)| hash);
+        let content = esrs_chained_content(
+            store_event.id,
+            store_event.aggregate_id,
+            sequence_number,
+            store_event.occurred_on,
+            serde_json::to_value(&store_event.payload)?,
+        );
+        sqlx::query(
+            "INSERT INTO esrs_event_hashes (aggregate_type, aggregate_id, sequence_number, previous_hash, hash) VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(A::NAME)
+        .bind(store_event.aggregate_id)
+        .bind(sequence_number)
+        .bind(&previous_hash)
+        .bind(crate::common::chain_hash(previous_hash.as_deref(), &content))
+        .execute(&mut *transaction)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete(
+        &self,
+        aggregate_id: uuid::Uuid,
+        transaction: &mut sqlx::PgConnection,
+    ) -> Result<(), esrs::store::postgres::PgStoreError> {
+        sqlx::query("DELETE FROM esrs_event_hashes WHERE aggregate_type = $1 AND aggregate_id = $2")
+            .bind(A::NAME)
+            .bind(aggregate_id)
+            .execute(&mut *transaction)
+            .await?;
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "EventHashRecorder"
+    }
+}
 
-    let row = sqlx::query_as::<_, (Option<i64>,)>(
-        "SELECT max(sequence_number) FROM path_planner_events WHERE aggregate_id = $1",
-    )
+/// Walk the hash chains of the esrs events of aggregate `name` (an
+/// `Aggregate::NAME`). Aggregates without an events table yet are skipped.
+pub async fn verify_esrs_chains(
+    pool: &PgPool,
+    name: &str,
+) -> anyhow::Result<crate::common::IntegrityReport> {
+    let mut report = crate::common::IntegrityReport::default();
+    let table = format!("{}_events", name);
+    let (exists,) = sqlx::query_as::<_, (bool,)>("SELECT to_regclass($1) IS NOT NULL")
+        .bind(&table)
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Ok(report);
+    }
+    sqlx::query(EVENT_HASHES_TABLE_SQL).execute(pool).await?;
+
+    type Row = (
+        uuid::Uuid,
+        uuid::Uuid,
+        i64,
+        chrono::DateTime<chrono::Utc>,
+        serde_json::Value,
+        Option<String>,
+        Option<String>,
+    );
+    let rows = sqlx::query_as::<_, Row>(&format!(
+        "SELECT e.id, e.aggregate_id, e.sequence_number::BIGINT, e.occurred_on, e.payload, h.previous_hash, h.hash
+         FROM {} e
+         LEFT JOIN esrs_event_hashes h
+           ON h.aggregate_type = $1 AND h.aggregate_id = e.aggregate_id AND h.sequence_number = e.sequence_number
+         ORDER BY e.aggregate_id, e.sequence_number",
+        table
+    ))
+    .bind(name)
+    .fetch_all(pool)
+    .await?;
+
+    let mut rows = rows.into_iter().peekable();
+    while let Some(first) = rows.peek() {
+        let aggregate_id = first.1;
+        let mut records = Vec::new();
+        while let Some((id, _, sequence_number, occurred_on, payload, previous_hash, hash)) =
+            rows.next_if(|row| row.1 == aggregate_id)
+        {
+            let link = hash.map(|hash| crate::common::ChainLink { previous_hash, hash });
+            let content = esrs_chained_content(id, aggregate_id, sequence_number, occurred_on, payload);
+            records.push((link, content));
+        }
+        report.check_chain(&format!("{} {}", name, aggregate_id), &records);
+        if !report.is_intact() {
+            break;
+        }
+    }
+    Ok(report)
+}
+
+/// Fetch the last sequence_number for the aggregate from the esrs events table of `A`.
+/// Returns Ok(Some(n)) if found, Ok(None) if no events exist, or Err on DB error.
+pub async fn agg_last_sequence<A: esrs::Aggregate>(pool: &PgPool, agg_uuid: &uuid::Uuid) -> anyhow::Result<Option<i64>> {
+    let row = sqlx::query_as::<_, (Option<i64>,)>(&format!(
+        "SELECT max(sequence_number) FROM {} WHERE aggregate_id = $1",
+        events_table::<A>()
+    ))
     .bind(agg_uuid)
-    .fetch_one(&pool)
+    .fetch_one(pool)
     .await?;
 
     Ok(row.0)
@@ -65,9 +384,12 @@ pub async fn agg_last_sequence(agg_uuid: &uuid::Uuid) -> anyhow::Result<Option<i
 
 /// Persist events into the provided PgStore but treat duplicate-key DB errors as
 /// idempotent (best-effort mirroring). This avoids noisy failures when the same
-/// domain event is mirrored more than once during migration.
+/// domain event is mirrored more than once during migration. The store and pool
+/// must come from `build_pg_store_with_bus` or
+/// `build_pg_store_in_schema_with_outbox`, which record event ids.
 pub async fn persist_best_effort<S>(
     store: &S,
+    pool: &PgPool,
     agg_state: &mut esrs::AggregateState<<S::Aggregate as esrs::Aggregate>::State>,
     events: Vec<<S::Aggregate as esrs::Aggregate>::Event>,
 ) -> anyhow::Result<()>
@@ -78,60 +400,24 @@ where
 {
     use esrs::store::EventStore as EsrsEventStore;
 
-    // Attempt a lightweight pre-check to see if the event(s) already exist in the
-    // esrs-generated events table for this aggregate. This is a best-effort check
-    // to avoid triggering duplicate-key insert attempts when mirroring events.
-    // If the pre-check cannot be performed (e.g. DB connection error), we fall
-    // back to calling the store persist and keep the existing duplicate-key
-    // handling behavior.
-    if let Ok(database_url) = std::env::var("DATABASE_URL") {
-        // Try to connect with a small pool for the pre-check
-        match PgPoolOptions::new().max_connections(1).connect(&database_url).await {
-            Ok(pool) => {
-                // Use the aggregate id from the provided agg_state to scope the query
-                // `id()` returns a Uuid which is Copy; avoid clone_on_copy lint.
-                let agg_id = *agg_state.id();
-
-                // For each event, serialize to JSON and check for an exact payload
-                // match in the table. If any event is already present, skip persist.
-                for evt in &events {
-                    match serde_json::to_value(evt) {
-                        Ok(payload_json) => {
-                            // Query for an exact payload match for this aggregate.
-                            // The payload column is jsonb in the esrs table.
-                            let query = "SELECT count(*) FROM path_planner_events WHERE aggregate_id = $1 AND payload = $2";
-                            let res = sqlx::query_as::<_, (i64,)>(query)
-                                .bind(agg_id)
-                                .bind(payload_json)
-                                .fetch_one(&pool)
-                                .await;
-
-                            match res {
-                                Ok((cnt,)) => {
-                                    if cnt > 0 {
-                                        println!("⤴️ esrs pre-check: matching event already exists for aggregate {} (skipping persist)", agg_id);
-                                        return Ok(());
-                                    }
-                                }
-                                Err(e) => {
-                                    // If the pre-check query fails, log and break to fall back
-                                    // to the normal persist path below.
-                                    println!("⚠️ esrs pre-check query failed: {}. Falling back to persist.", e);
-                                    break;
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            println!("⚠️ esrs pre-check JSON serialization failed: {}. Falling back to persist.", e);
-                            break;
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                println!("⚠️ esrs pre-check DB connection failed: {}. Falling back to persist.", e);
-            }
+    // Skip the persist when an event id is already recorded. A failed pre-check
+    // falls back to the persist, where the recorder rejects duplicates anyway.
+    let agg_id = *agg_state.id();
+    let event_ids = events
+        .iter()
+        .map(|evt| event_id_for::<S::Aggregate>(&agg_id, evt))
+        .collect::<Result<Vec<_>, _>>()?;
+    match sqlx::query_as::<_, (bool,)>("SELECT EXISTS (SELECT 1 FROM esrs_event_ids WHERE event_id = ANY($1))")
+        .bind(&event_ids)
+        .fetch_one(pool)
+        .await
+    {
+        Ok((true,)) => {
+            println!("⤴️ esrs pre-check: event already recorded for aggregate {} (skipping persist)", agg_id);
+            return Ok(());
         }
+        Ok((false,)) => {}
+        Err(e) => println!("⚠️ esrs pre-check query failed: {}. Falling back to persist.", e),
     }
 
     // If we reach here, either the pre-check didn't find a duplicate, or the
@@ -152,3 +438,289 @@ where
         }
     }
 }
+
+/// `MessageBus` decorator that mirrors path planning events published on one topic
+/// into an esrs `PgStore`. Mirroring is best-effort: failures never fail the publish.
+pub struct EsrsMirroringBus {
+    inner: std::sync::Arc<dyn crate::common::MessageBus>,
+    store: PgStore<crate::esrs::path_planning::PathPlanner>,
+    pool: PgPool,
+    topic: String,
+    inbox: Option<std::sync::Arc<dyn crate::common::Inbox>>,
+}
+
+impl EsrsMirroringBus {
+    /// `pool` is the one `store` was built on
+    pub fn new(
+        inner: std::sync::Arc<dyn crate::common::MessageBus>,
+        store: PgStore<crate::esrs::path_planning::PathPlanner>,
+        pool: PgPool,
+        topic: &str,
+    ) -> Self {
+        Self {
+            inner,
+            store,
+            pool,
+            topic: topic.to_string(),
+            inbox: None,
+        }
+    }
+
+    /// Dedupe mirrored events by envelope id instead of comparing sequence
+    /// numbers and event ids with the events tables
+    pub fn with_inbox(mut self, inbox: std::sync::Arc<dyn crate::common::Inbox>) -> Self {
+        self.inbox = Some(inbox);
+        self
+    }
+
+    async fn mirror_once(
+        &self,
+        inbox: &dyn crate::common::Inbox,
+        envelope: &crate::common::EventEnvelope,
+        evt: crate::domains::path_planning::events::PathPlanningEvent,
+    ) -> Result<(), String> {
+        use esrs::store::EventStore as EsrsEventStore;
+
+        const CONSUMER: &str = "esrs-mirror";
+        if inbox.is_processed(CONSUMER, envelope.event_id).await? {
+            return Ok(());
+        }
+        let mut agg_state = esrs::AggregateState::<crate::esrs::path_planning::PathPlannerState>::with_id(uuid_for_aggregate_id(&envelope.aggregate_id));
+        EsrsEventStore::persist(&self.store, &mut agg_state, vec![evt])
+            .await
+            .map_err(|e| e.to_string())?;
+        inbox.mark_processed(CONSUMER, envelope.event_id).await
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::common::MessageBus for EsrsMirroringBus {
+    async fn publish(
+        &self,
+        topic: &str,
+        envelope: &crate::common::EventEnvelope,
+    ) -> Result<(), String> {
+        self.inner.publish(topic, envelope).await?;
+        if topic != self.topic {
+            return Ok(());
+        }
+        if let Ok(evt) = serde_json::from_value::<
+            crate::domains::path_planning::events::PathPlanningEvent,
+        >(envelope.event_data.clone())
+        {
+            if let Some(inbox) = &self.inbox {
+                if let Err(e) = self.mirror_once(inbox.as_ref(), envelope, evt).await {
+                    tracing::warn!("Failed to mirror event {} into esrs: {}", envelope.event_id, e);
+                }
+                return Ok(());
+            }
+            let agg_uuid = uuid_for_aggregate_id(&envelope.aggregate_id);
+            match agg_last_sequence::<crate::esrs::path_planning::PathPlanner>(&self.pool, &agg_uuid).await {
+                Ok(Some(n)) if n >= (envelope.event_version as i64) => {
+                    println!("⤴️ esrs pre-check: event already present for agg {} (seq={}), skipping persist", agg_uuid, n);
+                }
+                _ => {
+                    let mut agg_state = esrs::AggregateState::<
+                        crate::esrs::path_planning::PathPlannerState,
+                    >::with_id(agg_uuid);
+                    let _ = persist_best_effort(&self.store, &self.pool, &mut agg_state, vec![evt]).await;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn subscribe(
+        &self,
+        topic: &str,
+        group_id: &str,
+    ) -> Result<Box<dyn crate::common::MessageSubscription>, String> {
+        self.inner.subscribe(topic, group_id).await
+    }
+}
+
+const OUTBOX_TABLE_SQL: &str = "CREATE TABLE IF NOT EXISTS event_outbox (
+    seq BIGSERIAL PRIMARY KEY,
+    id UUID NOT NULL UNIQUE,
+    topic TEXT NOT NULL,
+    envelope JSONB NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    sent_at TIMESTAMPTZ
+)";
+
+const OUTBOX_INDEX_SQL: &str =
+    "CREATE INDEX IF NOT EXISTS idx_event_outbox_unsent ON event_outbox(seq) WHERE sent_at IS NULL";
+
+/// Transactional event handler that writes every persisted event to the
+/// `event_outbox` table inside the transaction that stores the event, so the
+/// event and its outbox row are committed or rolled back together.
+pub struct OutboxWriter<A> {
+    topic: String,
+    _marker: std::marker::PhantomData<A>,
+}
+
+impl<A> OutboxWriter<A> {
+    pub fn new(topic: &str) -> Self {
+        Self {
+            topic: topic.to_string(),
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+/// The envelope the relay publishes for an esrs event
+pub fn outbox_envelope<A>(
+    store_event: &esrs::store::StoreEvent<A::Event>,
+) -> Result<crate::common::EventEnvelope, serde_json::Error>
+where
+    A: esrs::Aggregate,
+    A::Event: crate::common::DomainEvent + serde::Serialize,
+{
+    use crate::common::DomainEvent;
+
+    let event = &store_event.payload;
+    Ok(crate::common::EventEnvelope {
+        event_id: store_event.id,
+        aggregate_id: event.aggregate_id().to_string(),
+        aggregate_type: A::NAME.to_string(),
+        event_type: event.event_type().to_string(),
+        event_version: store_event.sequence_number as u64,
+        event_data: serde_json::to_value(event)?,
+        metadata: crate::common::EventMetadata {
+            correlation_id: None,
+            causation_id: None,
+            user_id: None,
+            source: "esrs_outbox".to_string(),
+            reply_to: None,
+            tenant: None,
+        },
+        occurred_at: store_event.occurred_on,
+        chain: None,
+    })
+}
+
+#[async_trait::async_trait]
+impl<A> esrs::handler::TransactionalEventHandler<A, esrs::store::postgres::PgStoreError, sqlx::PgConnection> for OutboxWriter<A>
+where
+    A: esrs::Aggregate + Send + Sync,
+    A::Event: crate::common::DomainEvent + serde::Serialize,
+{
+    async fn handle(
+        &self,
+        store_event: &esrs::store::StoreEvent<A::Event>,
+        transaction: &mut sqlx::PgConnection,
+    ) -> Result<(), esrs::store::postgres::PgStoreError> {
+        let envelope = outbox_envelope::<A>(store_event)?;
+        sqlx::query(
+            "INSERT INTO event_outbox (id, topic, envelope) VALUES ($1, $2, $3) ON CONFLICT (id) DO NOTHING",
+        )
+        .bind(envelope.event_id)
+        .bind(&self.topic)
+        .bind(serde_json::to_value(&envelope)?)
+        .execute(&mut *transaction)
+        .await?;
+        Ok(())
+    }
+}
+
+/// `Outbox` over the `event_outbox` table filled by `OutboxWriter`
+#[derive(Clone)]
+pub struct PgOutbox {
+    pool: sqlx::PgPool,
+}
+
+impl PgOutbox {
+    /// Creates the outbox table if it does not exist yet
+    pub async fn new(pool: sqlx::PgPool) -> anyhow::Result<Self> {
+        sqlx::query(OUTBOX_TABLE_SQL).execute(&pool).await?;
+        sqlx::query(OUTBOX_INDEX_SQL).execute(&pool).await?;
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::common::Outbox for PgOutbox {
+    async fn pending(&self, limit: usize) -> Result<Vec<crate::common::OutboxMessage>, String> {
+        let rows = sqlx::query_as::<_, (uuid::Uuid, String, serde_json::Value, i32)>(
+            "SELECT id, topic, envelope, attempts FROM event_outbox WHERE sent_at IS NULL ORDER BY seq LIMIT $1",
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Failed to read outbox: {}", e))?;
+
+        rows.into_iter()
+            .map(|(id, topic, envelope, attempts)| {
+                Ok(crate::common::OutboxMessage {
+                    id,
+                    topic,
+                    envelope: serde_json::from_value(envelope)
+                        .map_err(|e| format!("Failed to decode outbox message {}: {}", id, e))?,
+                    attempts: attempts as u32,
+                })
+            })
+            .collect()
+    }
+
+    async fn mark_sent(&self, id: uuid::Uuid) -> Result<(), String> {
+        sqlx::query("UPDATE event_outbox SET sent_at = now() WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Failed to mark outbox message {} sent: {}", id, e))?;
+        Ok(())
+    }
+
+    async fn record_failure(&self, id: uuid::Uuid, error: &str) -> Result<(), String> {
+        sqlx::query("UPDATE event_outbox SET attempts = attempts + 1, last_error = $2 WHERE id = $1")
+            .bind(id)
+            .bind(error)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Failed to record outbox failure for {}: {}", id, e))?;
+        Ok(())
+    }
+}
+
+/// Build a PgStore whose events are also written to the outbox for `topic`.
+/// Run an `OutboxRelay` over the returned outbox to get them onto the bus.
+pub async fn build_pg_store_with_outbox<A>(database_url: &str, topic: &str) -> anyhow::Result<(PgStore<A>, PgOutbox)>
+where
+    A: esrs::Aggregate + Send + Sync + 'static,
+    A::Event: crate::common::DomainEvent + serde::Serialize + for<'de> serde::de::Deserialize<'de> + Send + Sync + 'static + Clone,
+{
+    let (store, _pool, outbox) = build_pg_store_in_schema_with_outbox::<A>(database_url, None, topic).await?;
+    Ok((store, outbox))
+}
+
+/// `build_pg_store_with_outbox` with the store's tables in `schema`, see
+/// `connect_pg_pool_in_schema`. The store records event ids and hash chains
+/// like `build_pg_store_with_bus`, so the pool works with
+/// `persist_best_effort`. The outbox row is written first: a persist that a
+/// later handler rejects, e.g. a duplicate event id, rolls it back too.
+pub async fn build_pg_store_in_schema_with_outbox<A>(
+    database_url: &str,
+    schema: Option<&str>,
+    topic: &str,
+) -> anyhow::Result<(PgStore<A>, PgPool, PgOutbox)>
+where
+    A: esrs::Aggregate + Send + Sync + 'static,
+    A::Event: crate::common::DomainEvent + serde::Serialize + for<'de> serde::de::Deserialize<'de> + Send + Sync + 'static + Clone,
+{
+    let pool = connect_pg_pool_in_schema(database_url, schema).await?;
+
+    // The tables have to exist before the first event is persisted
+    let outbox = PgOutbox::new(pool.clone()).await?;
+    sqlx::query(EVENT_IDS_TABLE_SQL).execute(&pool).await?;
+    sqlx::query(EVENT_HASHES_TABLE_SQL).execute(&pool).await?;
+    let store: PgStore<A> = PgStoreBuilder::new(pool.clone())
+        .add_transactional_event_handler(OutboxWriter::<A>::new(topic))
+        .add_transactional_event_handler(EventIdRecorder::<A>::new())
+        .add_transactional_event_handler(EventHashRecorder::<A>::new())
+        .try_build()
+        .await
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    Ok((store, pool, outbox))
+}
@@ -0,0 +1,747 @@
+use chrono::{DateTime, Utc};
+use esrs::handler::TransactionalEventHandler;
+use esrs::store::postgres::{PgStore, PgStoreBuilder, PgStoreError};
+use esrs::store::StoreEvent;
+use esrs::Aggregate;
+use futures::StreamExt;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{PgConnection, PgPool};
+
+use crate::domains::logical_agent::{AgentStatus, LogicalAgentEvent};
+use crate::domains::path_planning::{PathPlanningEvent, PlanStatus, WorkerStatus};
+use crate::domains::technical_agent::{TechnicalAgentEvent, TechnicalStatus};
+
+/// A Postgres read model kept up to date by an esrs transactional event
+/// handler, so each row changes in the transaction that stores its event.
+pub trait ReadModel<A: Aggregate>:
+    TransactionalEventHandler<A, PgStoreError, PgConnection>
+{
+    /// Statements creating the read model's tables if they do not exist
+    const SCHEMA: &'static [&'static str];
+    /// Tables emptied before a rebuild
+    const TABLES: &'static [&'static str];
+}
+
+/// Create the tables of a read model; run before building the store it is
+/// registered on
+pub async fn create_read_model_tables<A, R>(pool: &PgPool) -> anyhow::Result<()>
+where
+    A: Aggregate,
+    R: ReadModel<A>,
+{
+    for statement in R::SCHEMA {
+        sqlx::query(statement).execute(pool).await?;
+    }
+    Ok(())
+}
+
+/// Build a PgStore that keeps `read_model` up to date as events are persisted
+pub async fn build_pg_store_with_read_model<A, R>(
+    database_url: &str,
+    read_model: R,
+) -> anyhow::Result<(PgStore<A>, PgPool)>
+where
+    A: Aggregate,
+    A::Event: serde::Serialize + serde::de::DeserializeOwned + Send + Sync + 'static + Clone,
+    R: ReadModel<A> + Send + 'static,
+{
+    let pool = PgPoolOptions::new()
+        .max_connections(5)
+        .connect(database_url)
+        .await?;
+
+    create_read_model_tables::<A, R>(&pool).await?;
+    let store: PgStore<A> = PgStoreBuilder::new(pool.clone())
+        .add_transactional_event_handler(read_model)
+        .try_build()
+        .await
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    Ok((store, pool))
+}
+
+/// Truncate a read model and replay every event of `store` into it, in one
+/// transaction so readers see either the old rows or the rebuilt ones.
+/// Returns the number of events replayed.
+pub async fn rebuild_read_model<A, R>(
+    pool: &PgPool,
+    store: &PgStore<A>,
+    read_model: &R,
+) -> anyhow::Result<u64>
+where
+    A: Aggregate,
+    A::Event: serde::de::DeserializeOwned,
+    R: ReadModel<A>,
+{
+    create_read_model_tables::<A, R>(pool).await?;
+    let mut transaction = pool.begin().await?;
+    sqlx::query(&format!("TRUNCATE {}", R::TABLES.join(", ")))
+        .execute(&mut *transaction)
+        .await?;
+
+    let mut replayed = 0;
+    let mut events = store.stream_events(pool);
+    while let Some(event) = events.next().await {
+        read_model.handle(&event?, &mut *transaction).await?;
+        replayed += 1;
+    }
+    transaction.commit().await?;
+    Ok(replayed)
+}
+
+/// Variant name of an enum value without its fields: `Failed("x")` is `Failed`
+fn label<T: std::fmt::Debug>(value: &T) -> String {
+    format!("{:?}", value)
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .collect()
+}
+
+const PLANNER_OVERVIEW_SQL: &str = "CREATE TABLE IF NOT EXISTS planner_overview (
+    planner_id VARCHAR(255) PRIMARY KEY,
+    algorithm VARCHAR(50) NOT NULL,
+    active_plans INTEGER NOT NULL DEFAULT 0,
+    completed_plans INTEGER NOT NULL DEFAULT 0,
+    failed_plans INTEGER NOT NULL DEFAULT 0,
+    online_workers INTEGER NOT NULL DEFAULT 0,
+    last_activity TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+)";
+
+const PATH_PLAN_OVERVIEW_SQL: &str = "CREATE TABLE IF NOT EXISTS path_plan_overview (
+    plan_id VARCHAR(255) PRIMARY KEY,
+    planner_id VARCHAR(255) NOT NULL,
+    agent_id VARCHAR(255) NOT NULL,
+    status VARCHAR(50) NOT NULL,
+    failure_reason TEXT,
+    worker_id VARCHAR(255),
+    parent_plan_id VARCHAR(255),
+    waypoint_count INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL,
+    finished_at TIMESTAMPTZ,
+    archived_at TIMESTAMPTZ
+)";
+
+const PATH_PLAN_OVERVIEW_INDEX_SQL: &str =
+    "CREATE INDEX IF NOT EXISTS idx_path_plan_overview_planner ON path_plan_overview(planner_id)";
+
+const PATH_PLAN_WORKER_OVERVIEW_SQL: &str = "CREATE TABLE IF NOT EXISTS path_plan_worker_overview (
+    planner_id VARCHAR(255) NOT NULL,
+    worker_id VARCHAR(255) NOT NULL,
+    status VARCHAR(50) NOT NULL,
+    current_plan_id VARCHAR(255),
+    capabilities JSONB NOT NULL,
+    last_heartbeat TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (planner_id, worker_id)
+)";
+
+/// Planners, plans and workers of the path planning domain:
+/// `planner_overview`, `path_plan_overview` and `path_plan_worker_overview`
+pub struct PathPlanningReadModel;
+
+impl PathPlanningReadModel {
+    async fn set_plan_status(
+        conn: &mut PgConnection,
+        plan_id: &str,
+        status: &PlanStatus,
+        worker_id: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let failure_reason = match status {
+            PlanStatus::Failed(reason) => Some(reason.as_str()),
+            _ => None,
+        };
+        sqlx::query(
+            "UPDATE path_plan_overview SET status = $2, failure_reason = $3, worker_id = $4 WHERE plan_id = $1",
+        )
+        .bind(plan_id)
+        .bind(label(status))
+        .bind(failure_reason)
+        .bind(worker_id)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    async fn finish_plan(
+        conn: &mut PgConnection,
+        plan_id: &str,
+        status: &PlanStatus,
+        waypoint_count: Option<usize>,
+        finished_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        let failure_reason = match status {
+            PlanStatus::Failed(reason) => Some(reason.as_str()),
+            _ => None,
+        };
+        sqlx::query(
+            "UPDATE path_plan_overview SET status = $2, failure_reason = $3,
+                waypoint_count = COALESCE($4, waypoint_count), finished_at = $5
+             WHERE plan_id = $1",
+        )
+        .bind(plan_id)
+        .bind(label(status))
+        .bind(failure_reason)
+        .bind(waypoint_count.map(|count| count as i32))
+        .bind(finished_at)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    async fn set_worker(
+        conn: &mut PgConnection,
+        planner_id: &str,
+        worker_id: &str,
+        status: Option<&WorkerStatus>,
+        current_plan_id: Option<Option<&str>>,
+        heartbeat: Option<DateTime<Utc>>,
+    ) -> Result<(), sqlx::Error> {
+        // NULL parameters keep the column as it is
+        sqlx::query(
+            "UPDATE path_plan_worker_overview SET
+                status = COALESCE($3, status),
+                current_plan_id = CASE WHEN $4 THEN $5 ELSE current_plan_id END,
+                last_heartbeat = COALESCE($6, last_heartbeat)
+             WHERE planner_id = $1 AND worker_id = $2",
+        )
+        .bind(planner_id)
+        .bind(worker_id)
+        .bind(status.map(label))
+        .bind(current_plan_id.is_some())
+        .bind(current_plan_id.flatten())
+        .bind(heartbeat)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    async fn refresh_planner(
+        conn: &mut PgConnection,
+        planner_id: &str,
+        at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE planner_overview SET
+                active_plans = (SELECT count(*) FROM path_plan_overview WHERE planner_id = $1 AND finished_at IS NULL AND archived_at IS NULL),
+                completed_plans = (SELECT count(*) FROM path_plan_overview WHERE planner_id = $1 AND status = 'Complete'),
+                failed_plans = (SELECT count(*) FROM path_plan_overview WHERE planner_id = $1 AND status = 'Failed'),
+                online_workers = (SELECT count(*) FROM path_plan_worker_overview WHERE planner_id = $1 AND status <> 'Offline'),
+                last_activity = GREATEST(last_activity, $2)
+             WHERE planner_id = $1",
+        )
+        .bind(planner_id)
+        .bind(at)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl TransactionalEventHandler<crate::esrs::PathPlanner, PgStoreError, PgConnection>
+    for PathPlanningReadModel
+{
+    async fn handle(
+        &self,
+        store_event: &StoreEvent<PathPlanningEvent>,
+        conn: &mut PgConnection,
+    ) -> Result<(), PgStoreError> {
+        use crate::common::DomainEvent;
+
+        let event = &store_event.payload;
+        let planner_id = event.aggregate_id();
+        match event {
+            PathPlanningEvent::PlannerCreated {
+                algorithm,
+                timestamp,
+                ..
+            } => {
+                sqlx::query(
+                    "INSERT INTO planner_overview (planner_id, algorithm, last_activity, created_at)
+                     VALUES ($1, $2, $3, $3)
+                     ON CONFLICT (planner_id) DO UPDATE SET algorithm = EXCLUDED.algorithm",
+                )
+                .bind(planner_id)
+                .bind(label(algorithm))
+                .bind(timestamp)
+                .execute(&mut *conn)
+                .await?;
+            }
+            PathPlanningEvent::PathPlanRequested {
+                plan_id,
+                agent_id,
+                timestamp,
+                ..
+            } => {
+                sqlx::query(
+                    "INSERT INTO path_plan_overview (plan_id, planner_id, agent_id, status, created_at)
+                     VALUES ($1, $2, $3, $4, $5) ON CONFLICT (plan_id) DO NOTHING",
+                )
+                .bind(plan_id)
+                .bind(planner_id)
+                .bind(agent_id)
+                .bind(label(&PlanStatus::Planning))
+                .bind(timestamp)
+                .execute(&mut *conn)
+                .await?;
+            }
+            PathPlanningEvent::MultiGoalPlanRequested {
+                plan_id,
+                agent_id,
+                leg_plan_ids,
+                timestamp,
+                ..
+            } => {
+                let plans = std::iter::once((plan_id, PlanStatus::InProgress, None)).chain(
+                    leg_plan_ids
+                        .iter()
+                        .map(|leg| (leg, PlanStatus::Planning, Some(plan_id))),
+                );
+                for (id, status, parent) in plans {
+                    sqlx::query(
+                        "INSERT INTO path_plan_overview (plan_id, planner_id, agent_id, status, parent_plan_id, created_at)
+                         VALUES ($1, $2, $3, $4, $5, $6) ON CONFLICT (plan_id) DO NOTHING",
+                    )
+                    .bind(id)
+                    .bind(planner_id)
+                    .bind(agent_id)
+                    .bind(label(&status))
+                    .bind(parent)
+                    .bind(timestamp)
+                    .execute(&mut *conn)
+                    .await?;
+                }
+            }
+            PathPlanningEvent::WorkerRegistered {
+                worker_id,
+                capabilities,
+                timestamp,
+                ..
+            } => {
+                sqlx::query(
+                    "INSERT INTO path_plan_worker_overview (planner_id, worker_id, status, capabilities, last_heartbeat)
+                     VALUES ($1, $2, $3, $4, $5)
+                     ON CONFLICT (planner_id, worker_id) DO UPDATE SET
+                        status = EXCLUDED.status, capabilities = EXCLUDED.capabilities,
+                        current_plan_id = NULL, last_heartbeat = EXCLUDED.last_heartbeat",
+                )
+                .bind(planner_id)
+                .bind(worker_id)
+                .bind(label(&WorkerStatus::Idle))
+                .bind(serde_json::to_value(capabilities)?)
+                .bind(timestamp)
+                .execute(&mut *conn)
+                .await?;
+            }
+            PathPlanningEvent::WorkerReady {
+                worker_id,
+                timestamp,
+                ..
+            } => {
+                Self::set_worker(
+                    conn,
+                    planner_id,
+                    worker_id,
+                    Some(&WorkerStatus::Idle),
+                    Some(None),
+                    Some(*timestamp),
+                )
+                .await?;
+            }
+            PathPlanningEvent::WorkerBusy {
+                worker_id,
+                plan_id,
+                timestamp,
+                ..
+            }
+            | PathPlanningEvent::WorkerProcessing {
+                worker_id,
+                plan_id,
+                timestamp,
+                ..
+            } => {
+                Self::set_worker(
+                    conn,
+                    planner_id,
+                    worker_id,
+                    Some(&WorkerStatus::Busy),
+                    Some(Some(plan_id)),
+                    Some(*timestamp),
+                )
+                .await?;
+            }
+            PathPlanningEvent::WorkerOffline { worker_id, .. } => {
+                Self::set_worker(
+                    conn,
+                    planner_id,
+                    worker_id,
+                    Some(&WorkerStatus::Offline),
+                    Some(None),
+                    None,
+                )
+                .await?;
+            }
+            PathPlanningEvent::WorkerHeartbeat {
+                worker_id,
+                timestamp,
+                ..
+            } => {
+                Self::set_worker(conn, planner_id, worker_id, None, None, Some(*timestamp)).await?;
+            }
+            PathPlanningEvent::PlanAssigned {
+                plan_id, worker_id, ..
+            } => {
+                Self::set_plan_status(conn, plan_id, &PlanStatus::Assigned, Some(worker_id))
+                    .await?;
+                Self::set_worker(conn, planner_id, worker_id, None, Some(Some(plan_id)), None)
+                    .await?;
+            }
+            PathPlanningEvent::PlanAssignmentAccepted {
+                plan_id, worker_id, ..
+            } => {
+                Self::set_plan_status(conn, plan_id, &PlanStatus::InProgress, Some(worker_id))
+                    .await?;
+                Self::set_worker(
+                    conn,
+                    planner_id,
+                    worker_id,
+                    Some(&WorkerStatus::Busy),
+                    None,
+                    None,
+                )
+                .await?;
+            }
+            PathPlanningEvent::PlanAssignmentRejected {
+                plan_id, worker_id, ..
+            } => {
+                Self::set_plan_status(conn, plan_id, &PlanStatus::Planning, None).await?;
+                Self::set_worker(
+                    conn,
+                    planner_id,
+                    worker_id,
+                    Some(&WorkerStatus::Idle),
+                    Some(None),
+                    None,
+                )
+                .await?;
+            }
+            PathPlanningEvent::PlanAssignmentTimedOut {
+                plan_id, worker_id, ..
+            } => {
+                Self::set_plan_status(conn, plan_id, &PlanStatus::Planning, None).await?;
+                Self::set_worker(
+                    conn,
+                    planner_id,
+                    worker_id,
+                    Some(&WorkerStatus::Offline),
+                    Some(None),
+                    None,
+                )
+                .await?;
+            }
+            PathPlanningEvent::PlanCompleted {
+                plan_id,
+                worker_id,
+                waypoints,
+                timestamp,
+                ..
+            } => {
+                Self::finish_plan(
+                    conn,
+                    plan_id,
+                    &PlanStatus::Complete,
+                    Some(waypoints.len()),
+                    *timestamp,
+                )
+                .await?;
+                if let Some(worker_id) = worker_id {
+                    Self::set_worker(
+                        conn,
+                        planner_id,
+                        worker_id,
+                        Some(&WorkerStatus::Idle),
+                        Some(None),
+                        None,
+                    )
+                    .await?;
+                }
+            }
+            PathPlanningEvent::PlanFailed {
+                plan_id,
+                worker_id,
+                reason,
+                timestamp,
+                ..
+            } => {
+                Self::finish_plan(
+                    conn,
+                    plan_id,
+                    &PlanStatus::Failed(reason.clone()),
+                    None,
+                    *timestamp,
+                )
+                .await?;
+                if let Some(worker_id) = worker_id {
+                    Self::set_worker(
+                        conn,
+                        planner_id,
+                        worker_id,
+                        Some(&WorkerStatus::Idle),
+                        Some(None),
+                        None,
+                    )
+                    .await?;
+                }
+            }
+            PathPlanningEvent::MultiGoalPlanCompleted {
+                plan_id,
+                waypoints,
+                timestamp,
+                ..
+            } => {
+                Self::finish_plan(
+                    conn,
+                    plan_id,
+                    &PlanStatus::Complete,
+                    Some(waypoints.len()),
+                    *timestamp,
+                )
+                .await?;
+            }
+            PathPlanningEvent::MultiGoalPlanFailed {
+                plan_id,
+                reason,
+                timestamp,
+                ..
+            } => {
+                Self::finish_plan(
+                    conn,
+                    plan_id,
+                    &PlanStatus::Failed(reason.clone()),
+                    None,
+                    *timestamp,
+                )
+                .await?;
+            }
+            PathPlanningEvent::PlanInvalidated { plan_id, .. } => {
+                // A completed multi-goal plan is reopened until the leg is replanned
+                sqlx::query(
+                    "UPDATE path_plan_overview SET status = $2, waypoint_count = 0, finished_at = NULL
+                     WHERE status = 'Complete'
+                        AND plan_id = (SELECT parent_plan_id FROM path_plan_overview WHERE plan_id = $1)",
+                )
+                .bind(plan_id)
+                .bind(label(&PlanStatus::InProgress))
+                .execute(&mut *conn)
+                .await?;
+                sqlx::query(
+                    "UPDATE path_plan_overview SET status = $2, failure_reason = NULL, worker_id = NULL,
+                        waypoint_count = 0, finished_at = NULL
+                     WHERE plan_id = $1",
+                )
+                .bind(plan_id)
+                .bind(label(&PlanStatus::Planning))
+                .execute(&mut *conn)
+                .await?;
+            }
+            PathPlanningEvent::PlanArchived {
+                plan, timestamp, ..
+            } => {
+                sqlx::query("UPDATE path_plan_overview SET archived_at = $2 WHERE plan_id = $1")
+                    .bind(&plan.id)
+                    .bind(timestamp)
+                    .execute(&mut *conn)
+                    .await?;
+            }
+            // Legacy requests, batches and workspace changes have no read model rows
+            PathPlanningEvent::PlanRequested { .. }
+            | PathPlanningEvent::PlanBatchRequested { .. }
+            | PathPlanningEvent::ObstacleAdded { .. }
+            | PathPlanningEvent::ObstacleMoved { .. }
+            | PathPlanningEvent::ObstacleRemoved { .. } => {}
+        }
+        Self::refresh_planner(conn, planner_id, event.occurred_at()).await?;
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "PathPlanningReadModel"
+    }
+}
+
+impl ReadModel<crate::esrs::PathPlanner> for PathPlanningReadModel {
+    const SCHEMA: &'static [&'static str] = &[
+        PLANNER_OVERVIEW_SQL,
+        PATH_PLAN_OVERVIEW_SQL,
+        PATH_PLAN_OVERVIEW_INDEX_SQL,
+        PATH_PLAN_WORKER_OVERVIEW_SQL,
+    ];
+    const TABLES: &'static [&'static str] = &[
+        "planner_overview",
+        "path_plan_overview",
+        "path_plan_worker_overview",
+    ];
+}
+
+const LOGICAL_AGENT_OVERVIEW_SQL: &str = "CREATE TABLE IF NOT EXISTS logical_agent_overview (
+    agent_id VARCHAR(255) PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    status VARCHAR(50) NOT NULL,
+    objectives_count INTEGER DEFAULT 0,
+    completed_objectives INTEGER DEFAULT 0,
+    facts_count INTEGER DEFAULT 0,
+    rules_count INTEGER DEFAULT 0,
+    last_activity TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+)";
+
+/// `logical_agent_overview`, as defined in `scripts/init-db.sql`
+pub struct LogicalAgentReadModel;
+
+#[async_trait::async_trait]
+impl TransactionalEventHandler<crate::esrs::LogicalAgent, PgStoreError, PgConnection>
+    for LogicalAgentReadModel
+{
+    async fn handle(
+        &self,
+        store_event: &StoreEvent<LogicalAgentEvent>,
+        conn: &mut PgConnection,
+    ) -> Result<(), PgStoreError> {
+        use crate::common::DomainEvent;
+
+        let event = &store_event.payload;
+        if let LogicalAgentEvent::AgentCreated {
+            agent_id,
+            name,
+            timestamp,
+        } = event
+        {
+            sqlx::query(
+                "INSERT INTO logical_agent_overview (agent_id, name, status, last_activity, created_at)
+                 VALUES ($1, $2, $3, $4, $4) ON CONFLICT (agent_id) DO NOTHING",
+            )
+            .bind(agent_id)
+            .bind(name)
+            .bind(label(&AgentStatus::Idle))
+            .bind(timestamp)
+            .execute(&mut *conn)
+            .await?;
+            return Ok(());
+        }
+
+        // One counter column per event; the status only changes on StatusChanged
+        let (status, column) = match event {
+            LogicalAgentEvent::StatusChanged { new_status, .. } => (Some(label(new_status)), None),
+            LogicalAgentEvent::ObjectiveAdded { .. } => (None, Some("objectives_count")),
+            LogicalAgentEvent::ObjectiveCompleted { .. } => (None, Some("completed_objectives")),
+            LogicalAgentEvent::FactAdded { .. } => (None, Some("facts_count")),
+            LogicalAgentEvent::RuleAdded { .. } => (None, Some("rules_count")),
+            _ => (None, None),
+        };
+        let increment = column
+            .map(|column| format!(", {column} = {column} + 1"))
+            .unwrap_or_default();
+        sqlx::query(&format!(
+            "UPDATE logical_agent_overview SET status = COALESCE($2, status),
+                last_activity = GREATEST(last_activity, $3){increment}
+             WHERE agent_id = $1"
+        ))
+        .bind(event.aggregate_id())
+        .bind(status)
+        .bind(event.occurred_at())
+        .execute(&mut *conn)
+        .await?;
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "LogicalAgentReadModel"
+    }
+}
+
+impl ReadModel<crate::esrs::LogicalAgent> for LogicalAgentReadModel {
+    const SCHEMA: &'static [&'static str] = &[LOGICAL_AGENT_OVERVIEW_SQL];
+    const TABLES: &'static [&'static str] = &["logical_agent_overview"];
+}
+
+const TECHNICAL_AGENT_OVERVIEW_SQL: &str = "CREATE TABLE IF NOT EXISTS technical_agent_overview (
+    agent_id VARCHAR(255) PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    agent_type VARCHAR(50) NOT NULL,
+    status VARCHAR(50) NOT NULL,
+    capabilities_count INTEGER DEFAULT 0,
+    sensors_count INTEGER DEFAULT 0,
+    actuators_count INTEGER DEFAULT 0,
+    last_activity TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+)";
+
+/// `technical_agent_overview`, as defined in `scripts/init-db.sql`
+pub struct TechnicalAgentReadModel;
+
+#[async_trait::async_trait]
+impl TransactionalEventHandler<crate::esrs::TechnicalAgent, PgStoreError, PgConnection>
+    for TechnicalAgentReadModel
+{
+    async fn handle(
+        &self,
+        store_event: &StoreEvent<TechnicalAgentEvent>,
+        conn: &mut PgConnection,
+    ) -> Result<(), PgStoreError> {
+        use crate::common::DomainEvent;
+
+        let event = &store_event.payload;
+        if let TechnicalAgentEvent::AgentCreated {
+            agent_id,
+            name,
+            agent_type,
+            timestamp,
+        } = event
+        {
+            sqlx::query(
+                "INSERT INTO technical_agent_overview (agent_id, name, agent_type, status, last_activity, created_at)
+                 VALUES ($1, $2, $3, $4, $5, $5) ON CONFLICT (agent_id) DO NOTHING",
+            )
+            .bind(agent_id)
+            .bind(name)
+            .bind(label(agent_type))
+            .bind(label(&TechnicalStatus::Initializing))
+            .bind(timestamp)
+            .execute(&mut *conn)
+            .await?;
+            return Ok(());
+        }
+
+        let (status, column) = match event {
+            TechnicalAgentEvent::StatusChanged { new_status, .. } => {
+                (Some(label(new_status)), None)
+            }
+            TechnicalAgentEvent::CapabilityAdded { .. } => (None, Some("capabilities_count")),
+            TechnicalAgentEvent::SensorAdded { .. } => (None, Some("sensors_count")),
+            TechnicalAgentEvent::ActuatorAdded { .. } => (None, Some("actuators_count")),
+            _ => (None, None),
+        };
+        let increment = column
+            .map(|column| format!(", {column} = {column} + 1"))
+            .unwrap_or_default();
+        sqlx::query(&format!(
+            "UPDATE technical_agent_overview SET status = COALESCE($2, status),
+                last_activity = GREATEST(last_activity, $3){increment}
+             WHERE agent_id = $1"
+        ))
+        .bind(event.aggregate_id())
+        .bind(status)
+        .bind(event.occurred_at())
+        .execute(&mut *conn)
+        .await?;
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "TechnicalAgentReadModel"
+    }
+}
+
+impl ReadModel<crate::esrs::TechnicalAgent> for TechnicalAgentReadModel {
+    const SCHEMA: &'static [&'static str] = &[TECHNICAL_AGENT_OVERVIEW_SQL];
+    const TABLES: &'static [&'static str] = &["technical_agent_overview"];
+}
@@ -0,0 +1,374 @@
+use crate::common::{EventEnvelope, EventStore, Snapshot, SnapshotStore};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+/// Names the layout of an archive file in its manifest
+pub const ARCHIVE_FORMAT: &str = "gryphon-event-archive";
+pub const ARCHIVE_FORMAT_VERSION: u32 = 2;
+/// Version 1 archives lack stream positions, so their events cannot be
+/// imported in stream order
+const MIN_ARCHIVE_FORMAT_VERSION: u32 = 2;
+
+/// Which history an export takes. Empty lists match every aggregate; the time
+/// range applies to when events occurred and when snapshots were taken.
+///
+/// An archive cut off by `from` lacks the start of its aggregates' streams, so
+/// import it only into a store that already has that history.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveFilter {
+    #[serde(default)]
+    pub aggregate_types: Vec<String>,
+    #[serde(default)]
+    pub aggregate_ids: Vec<String>,
+    #[serde(default)]
+    pub from: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl ArchiveFilter {
+    fn matches(&self, aggregate_type: &str, aggregate_id: &str, time: DateTime<Utc>) -> bool {
+        (self.aggregate_types.is_empty()
+            || self.aggregate_types.iter().any(|t| t == aggregate_type))
+            && (self.aggregate_ids.is_empty()
+                || self.aggregate_ids.iter().any(|id| id == aggregate_id))
+            && self.from.is_none_or(|from| time >= from)
+            && self.until.is_none_or(|until| time <= until)
+    }
+
+    pub fn matches_event(&self, event: &EventEnvelope) -> bool {
+        self.matches(
+            &event.aggregate_type,
+            &event.aggregate_id,
+            event.occurred_at,
+        )
+    }
+
+    pub fn matches_snapshot(&self, snapshot: &Snapshot) -> bool {
+        self.matches(
+            &snapshot.aggregate_type,
+            &snapshot.aggregate_id,
+            snapshot.created_at,
+        )
+    }
+}
+
+/// First line of an archive file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub format: String,
+    pub format_version: u32,
+    pub created_at: DateTime<Utc>,
+    pub filter: ArchiveFilter,
+    pub event_count: usize,
+    pub snapshot_count: usize,
+    /// MD5 of every line after the manifest, newlines included
+    pub checksum: String,
+}
+
+/// An archived event with its position in its aggregate's stream.
+/// `event_version` is the version of the event's schema, not its position.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedEvent {
+    /// From 1
+    pub stream_version: u64,
+    pub event: EventEnvelope,
+}
+
+/// One line after the manifest
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum ArchiveRecord {
+    Event(ArchivedEvent),
+    Snapshot(Snapshot),
+}
+
+/// Events and snapshots moved between stores as one NDJSON file: a manifest
+/// line, each aggregate's events in stream order, then the snapshots.
+#[derive(Debug, Clone)]
+pub struct EventArchive {
+    pub manifest: ArchiveManifest,
+    pub events: Vec<ArchivedEvent>,
+    pub snapshots: Vec<Snapshot>,
+}
+
+impl EventArchive {
+    pub fn new(
+        filter: ArchiveFilter,
+        events: Vec<ArchivedEvent>,
+        snapshots: Vec<Snapshot>,
+    ) -> Result<Self, String> {
+        let body = records_to_ndjson(&events, &snapshots)?;
+        Ok(Self {
+            manifest: ArchiveManifest {
+                format: ARCHIVE_FORMAT.to_string(),
+                format_version: ARCHIVE_FORMAT_VERSION,
+                created_at: Utc::now(),
+                filter,
+                event_count: events.len(),
+                snapshot_count: snapshots.len(),
+                checksum: format!("{:x}", md5::compute(body.as_bytes())),
+            },
+            events,
+            snapshots,
+        })
+    }
+
+    pub fn to_ndjson(&self) -> Result<String, String> {
+        let manifest = serde_json::to_string(&self.manifest)
+            .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+        let body = records_to_ndjson(&self.events, &self.snapshots)?;
+        Ok(format!("{}\n{}", manifest, body))
+    }
+
+    /// Parse an archive, failing when its checksum or counts do not match the
+    /// manifest
+    pub fn from_ndjson(content: &str) -> Result<Self, String> {
+        let (manifest_line, body) = content.split_once('\n').unwrap_or((content, ""));
+        let manifest: ArchiveManifest = serde_json::from_str(manifest_line)
+            .map_err(|e| format!("Failed to read archive manifest: {}", e))?;
+        if manifest.format != ARCHIVE_FORMAT
+            || manifest.format_version > ARCHIVE_FORMAT_VERSION
+            || manifest.format_version < MIN_ARCHIVE_FORMAT_VERSION
+        {
+            return Err(format!(
+                "Unsupported archive format {} version {}",
+                manifest.format, manifest.format_version
+            ));
+        }
+        let checksum = format!("{:x}", md5::compute(body.as_bytes()));
+        if checksum != manifest.checksum {
+            return Err(format!(
+                "Archive checksum mismatch: manifest has {}, content has {}",
+                manifest.checksum, checksum
+            ));
+        }
+
+        let mut events = Vec::new();
+        let mut snapshots = Vec::new();
+        for (index, line) in body.lines().enumerate() {
+            // Line numbers count the manifest as line 1
+            let record: ArchiveRecord = serde_json::from_str(line)
+                .map_err(|e| format!("Failed to read archive line {}: {}", index + 2, e))?;
+            match record {
+                ArchiveRecord::Event(event) => events.push(event),
+                ArchiveRecord::Snapshot(snapshot) => snapshots.push(snapshot),
+            }
+        }
+        if events.len() != manifest.event_count || snapshots.len() != manifest.snapshot_count {
+            return Err(format!(
+                "Archive holds {} events and {} snapshots, manifest lists {} and {}",
+                events.len(),
+                snapshots.len(),
+                manifest.event_count,
+                manifest.snapshot_count
+            ));
+        }
+
+        Ok(Self {
+            manifest,
+            events,
+            snapshots,
+        })
+    }
+
+    pub async fn write(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        tokio::fs::write(path, self.to_ndjson()?)
+            .await
+            .map_err(|e| format!("Failed to write archive {}: {}", path.display(), e))
+    }
+
+    pub async fn read(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let content = tokio::fs::read_to_string(path)
+            .await
+            .map_err(|e| format!("Failed to read archive {}: {}", path.display(), e))?;
+        Self::from_ndjson(&content)
+    }
+
+    /// The archived envelopes, each aggregate's in stream order
+    pub fn envelopes(&self) -> Vec<EventEnvelope> {
+        self.events
+            .iter()
+            .map(|archived| archived.event.clone())
+            .collect()
+    }
+}
+
+fn records_to_ndjson(events: &[ArchivedEvent], snapshots: &[Snapshot]) -> Result<String, String> {
+    let records = events
+        .iter()
+        .cloned()
+        .map(ArchiveRecord::Event)
+        .chain(snapshots.iter().cloned().map(ArchiveRecord::Snapshot));
+    let mut body = String::new();
+    for record in records {
+        let line = serde_json::to_string(&record)
+            .map_err(|e| format!("Failed to serialize archive record: {}", e))?;
+        body.push_str(&line);
+        body.push('\n');
+    }
+    Ok(body)
+}
+
+/// Read the history `filter` matches out of any event store, with the
+/// snapshots of `snapshot_store` when given
+pub async fn export_archive(
+    event_store: &(dyn EventStore + Send + Sync),
+    snapshot_store: Option<&(dyn SnapshotStore + Send + Sync)>,
+    filter: ArchiveFilter,
+) -> Result<EventArchive, String> {
+    // `load_all_events` orders by time across aggregates, which need not be
+    // stream order, so it only tells which aggregates there are
+    let mut aggregate_ids: Vec<String> = event_store
+        .load_all_events(filter.from)
+        .await?
+        .into_iter()
+        .filter(|event| filter.matches_event(event))
+        .map(|event| event.aggregate_id)
+        .collect();
+    aggregate_ids.sort();
+    aggregate_ids.dedup();
+
+    let mut events = Vec::new();
+    for aggregate_id in aggregate_ids {
+        let stream = event_store.load_events(&aggregate_id, 0).await?;
+        events.extend(
+            stream
+                .into_iter()
+                .zip(1..)
+                .filter(|(event, _)| filter.matches_event(event))
+                .map(|(event, stream_version)| ArchivedEvent {
+                    stream_version,
+                    event,
+                }),
+        );
+    }
+
+    let mut snapshots = match snapshot_store {
+        Some(snapshot_store) => snapshot_store.load_all_snapshots().await?,
+        None => Vec::new(),
+    };
+    snapshots.retain(|snapshot| filter.matches_snapshot(snapshot));
+
+    EventArchive::new(filter, events, snapshots)
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportReport {
+    pub events_imported: usize,
+    /// Events the store already had, by event id
+    pub events_skipped: usize,
+    pub snapshots_imported: usize,
+    /// Snapshots the store already had at that version, or that had no store
+    pub snapshots_skipped: usize,
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "events: {} imported, {} already present; snapshots: {} imported, {} skipped",
+            self.events_imported,
+            self.events_skipped,
+            self.snapshots_imported,
+            self.snapshots_skipped
+        )
+    }
+}
+
+/// Append the archive's events to any event store in stream order, skipping
+/// those it already has, so an interrupted import can be run again. Snapshots
+/// go to `snapshot_store` when given.
+///
+/// Every aggregate's archived stream has to be contiguous and continue the
+/// stored one; an archive that would leave gaps or reorder events is rejected
+/// before anything is appended.
+pub async fn import_archive(
+    archive: &EventArchive,
+    event_store: &(dyn EventStore + Send + Sync),
+    snapshot_store: Option<&(dyn SnapshotStore + Send + Sync)>,
+) -> Result<ImportReport, String> {
+    let mut report = ImportReport::default();
+
+    // Per aggregate, in the order the archive first mentions them
+    let mut aggregate_ids: Vec<&str> = Vec::new();
+    let mut streams: HashMap<&str, Vec<&ArchivedEvent>> = HashMap::new();
+    for archived in &archive.events {
+        let stream = streams
+            .entry(&archived.event.aggregate_id)
+            .or_insert_with(|| {
+                aggregate_ids.push(&archived.event.aggregate_id);
+                Vec::new()
+            });
+        stream.push(archived);
+    }
+    for (aggregate_id, stream) in &mut streams {
+        stream.sort_by_key(|archived| archived.stream_version);
+        for pair in stream.windows(2) {
+            if pair[1].stream_version != pair[0].stream_version + 1 {
+                return Err(format!(
+                    "Archived events of {} are not contiguous: version {} follows {}",
+                    aggregate_id, pair[1].stream_version, pair[0].stream_version
+                ));
+            }
+        }
+    }
+
+    let mut appends = Vec::new();
+    for aggregate_id in aggregate_ids {
+        let existing = event_store.load_events(aggregate_id, 0).await?;
+        let existing_ids: HashSet<_> = existing.iter().map(|event| event.event_id).collect();
+        let (skipped, new_events): (Vec<_>, Vec<_>) = streams
+            .remove(aggregate_id)
+            .unwrap_or_default()
+            .into_iter()
+            .partition(|archived| existing_ids.contains(&archived.event.event_id));
+        report.events_skipped += skipped.len();
+        let Some(first) = new_events.first() else {
+            continue;
+        };
+        let stored = existing.len() as u64;
+        if first.stream_version != stored + 1 {
+            return Err(format!(
+                "Archive continues {} at version {}, but the store has {} events",
+                aggregate_id, first.stream_version, stored
+            ));
+        }
+        let new_events: Vec<_> = new_events
+            .into_iter()
+            .map(|archived| archived.event.clone())
+            .collect();
+        report.events_imported += new_events.len();
+        appends.push((aggregate_id, stored, new_events));
+    }
+    for (aggregate_id, stored, new_events) in appends {
+        event_store
+            .append_events(aggregate_id, stored, new_events)
+            .await
+            .map_err(|e| format!("Failed to import events of {}: {}", aggregate_id, e))?;
+    }
+
+    let Some(snapshot_store) = snapshot_store else {
+        report.snapshots_skipped = archive.snapshots.len();
+        return Ok(report);
+    };
+    for snapshot in &archive.snapshots {
+        let existing = snapshot_store
+            .load_snapshot(&snapshot.aggregate_id, Some(snapshot.aggregate_version))
+            .await?;
+        if existing.is_some_and(|existing| existing.aggregate_version == snapshot.aggregate_version)
+        {
+            report.snapshots_skipped += 1;
+            continue;
+        }
+        snapshot_store.save_snapshot(snapshot.clone()).await?;
+        report.snapshots_imported += 1;
+    }
+
+    Ok(report)
+}
@@ -1,4 +1,4 @@
-use crate::common::{EventEnvelope, EventStore};
+use crate::common::{chain_events, EventEnvelope, EventStore};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
@@ -24,7 +24,7 @@ impl EventStore for InMemoryEventStore {
         &self,
         aggregate_id: &str,
         expected_version: u64,
-        events: Vec<EventEnvelope>,
+        mut events: Vec<EventEnvelope>,
     ) -> Result<(), String> {
         let mut store = self.events.write().await;
 
@@ -41,6 +41,7 @@ impl EventStore for InMemoryEventStore {
             ));
         }
 
+        chain_events(aggregate_events.last(), &mut events)?;
         aggregate_events.extend(events);
         Ok(())
     }
@@ -89,4 +90,24 @@ impl EventStore for InMemoryEventStore {
 
         Ok(filtered_events)
     }
+
+    async fn load_all_events(
+        &self,
+        from_timestamp: Option<DateTime<Utc>>,
+    ) -> Result<Vec<EventEnvelope>, String> {
+        let store = self.events.read().await;
+
+        // Aggregates in id order, so equal timestamps come out the same every time
+        let mut aggregate_ids: Vec<&String> = store.keys().collect();
+        aggregate_ids.sort();
+        let mut all_events: Vec<EventEnvelope> = aggregate_ids
+            .into_iter()
+            .flat_map(|aggregate_id| store[aggregate_id].iter())
+            .filter(|event| from_timestamp.is_none_or(|from_ts| event.occurred_at >= from_ts))
+            .cloned()
+            .collect();
+        all_events.sort_by(|a, b| a.occurred_at.cmp(&b.occurred_at));
+
+        Ok(all_events)
+    }
 }
@@ -1,24 +1,46 @@
-use crate::common::{EventEnvelope, EventStore};
+use crate::common::{chain_events, EventEnvelope, EventStore};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde_json;
-use std::path::PathBuf;
-use tokio::fs::{File, OpenOptions};
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::Mutex;
 
 /// File-based EventStore implementation for testing and development
 ///
 /// This implementation stores events in JSON Lines format (one JSON object per line)
 /// Events are appended to files named by aggregate_id
-/// This allows multiple processes to share the same event store through the file system
+/// This allows multiple processes to share the same event store through the file system;
+/// appends hold an exclusive lock on the file, so concurrent appenders are serialized
 pub struct FileEventStore {
     base_path: PathBuf,
+    /// Where each file ended after this store last appended to it
+    heads: Mutex<HashMap<String, StreamHead>>,
+}
+
+/// End of an aggregate's file, so appends need not re-read it
+#[derive(Clone)]
+struct StreamHead {
+    /// Length and modification time of the file; when either changed another
+    /// process wrote to it and the head is read again
+    len: u64,
+    modified: Option<SystemTime>,
+    /// Number of events in the file
+    version: u64,
+    last: Option<EventEnvelope>,
 }
 
 impl FileEventStore {
     pub fn new<P: Into<PathBuf>>(base_path: P) -> Self {
         let base_path = base_path.into();
-        Self { base_path }
+        Self {
+            base_path,
+            heads: Mutex::new(HashMap::new()),
+        }
     }
 
     /// Get the file path for a specific aggregate
@@ -45,38 +67,22 @@ impl EventStore for FileEventStore {
     async fn append_events(
         &self,
         aggregate_id: &str,
-        _expected_version: u64,
+        expected_version: u64,
         events: Vec<EventEnvelope>,
     ) -> Result<(), String> {
         self.ensure_base_dir().await?;
 
         let file_path = self.get_file_path(aggregate_id);
-
-        // For simplicity in this demo, we'll ignore version checking
-        // In a production system, you'd want to implement proper optimistic concurrency control
-
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&file_path)
+        let cached = self.heads.lock().await.get(aggregate_id).cloned();
+        let head = tokio::task::spawn_blocking(move || {
+            append_locked(&file_path, cached, expected_version, events)
+        })
+        .await
+        .map_err(|e| format!("Failed to append events: {}", e))??;
+        self.heads
+            .lock()
             .await
-            .map_err(|e| format!("Failed to open event file {}: {}", file_path.display(), e))?;
-
-        for event in events {
-            let json_line = serde_json::to_string(&event)
-                .map_err(|e| format!("Failed to serialize event: {}", e))?;
-
-            file.write_all(json_line.as_bytes())
-                .await
-                .map_err(|e| format!("Failed to write event: {}", e))?;
-            file.write_all(b"\n")
-                .await
-                .map_err(|e| format!("Failed to write newline: {}", e))?;
-        }
-
-        file.flush()
-            .await
-            .map_err(|e| format!("Failed to flush file: {}", e))?;
+            .insert(aggregate_id.to_string(), head);
 
         Ok(())
     }
@@ -127,15 +133,23 @@ impl EventStore for FileEventStore {
         event_type: &str,
         from_timestamp: Option<DateTime<Utc>>,
     ) -> Result<Vec<EventEnvelope>, String> {
-        self.ensure_base_dir().await?;
+        let mut all_events = self.load_all_events(from_timestamp).await?;
+        all_events.retain(|event| event.event_type == event_type);
+        Ok(all_events)
+    }
 
-        let mut all_events = Vec::new();
+    /// Events with equal timestamps keep their order within a file
+    async fn load_all_events(
+        &self,
+        from_timestamp: Option<DateTime<Utc>>,
+    ) -> Result<Vec<EventEnvelope>, String> {
+        self.ensure_base_dir().await?;
 
-        // Read all .jsonl files in the directory
+        // Read all .jsonl files in the directory, in name order
+        let mut paths = Vec::new();
         let mut dir = tokio::fs::read_dir(&self.base_path)
             .await
             .map_err(|e| format!("Failed to read directory: {}", e))?;
-
         while let Some(entry) = dir
             .next_entry()
             .await
@@ -143,32 +157,26 @@ impl EventStore for FileEventStore {
         {
             let path = entry.path();
             if path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
-                let file = File::open(&path)
-                    .await
-                    .map_err(|e| format!("Failed to open file {}: {}", path.display(), e))?;
-
-                let reader = BufReader::new(file);
-                let mut lines = reader.lines();
-
-                while let Some(line) = lines
-                    .next_line()
-                    .await
-                    .map_err(|e| format!("Failed to read line: {}", e))?
-                {
-                    let event: EventEnvelope = serde_json::from_str(&line)
-                        .map_err(|e| format!("Failed to deserialize event: {}", e))?;
-
-                    // Filter by event type
-                    if event.event_type == event_type {
-                        // Filter by timestamp if provided
-                        if let Some(from_ts) = from_timestamp {
-                            if event.occurred_at >= from_ts {
-                                all_events.push(event);
-                            }
-                        } else {
-                            all_events.push(event);
-                        }
-                    }
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let mut all_events = Vec::new();
+        for path in paths {
+            let file = File::open(&path)
+                .await
+                .map_err(|e| format!("Failed to open file {}: {}", path.display(), e))?;
+            let mut lines = BufReader::new(file).lines();
+            while let Some(line) = lines
+                .next_line()
+                .await
+                .map_err(|e| format!("Failed to read line: {}", e))?
+            {
+                let event: EventEnvelope = serde_json::from_str(&line)
+                    .map_err(|e| format!("Failed to deserialize event: {}", e))?;
+                if from_timestamp.is_none_or(|from_ts| event.occurred_at >= from_ts) {
+                    all_events.push(event);
                 }
             }
         }
@@ -180,6 +188,99 @@ impl EventStore for FileEventStore {
     }
 }
 
+/// Append `events` to the file at `path` under an exclusive lock, after
+/// checking that it holds `expected_version` events, and return its new head
+fn append_locked(
+    path: &Path,
+    cached: Option<StreamHead>,
+    expected_version: u64,
+    mut events: Vec<EventEnvelope>,
+) -> Result<StreamHead, String> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open event file {}: {}", path.display(), e))?;
+    // Released when the file is closed
+    file.lock()
+        .map_err(|e| format!("Failed to lock event file {}: {}", path.display(), e))?;
+
+    let metadata = file
+        .metadata()
+        .map_err(|e| format!("Failed to read event file {}: {}", path.display(), e))?;
+    let (len, modified) = (metadata.len(), metadata.modified().ok());
+    let head = match cached {
+        Some(head) if head.len == len && head.modified == modified => head,
+        _ => read_head(&file, path, len, modified)?,
+    };
+
+    if head.version != expected_version {
+        return Err(format!(
+            "Version mismatch: expected {}, got {}",
+            expected_version, head.version
+        ));
+    }
+    chain_events(head.last.as_ref(), &mut events)?;
+
+    let mut lines = String::new();
+    for event in &events {
+        let json_line = serde_json::to_string(event)
+            .map_err(|e| format!("Failed to serialize event: {}", e))?;
+        lines.push_str(&json_line);
+        lines.push('\n');
+    }
+    file.write_all(lines.as_bytes())
+        .map_err(|e| format!("Failed to write events: {}", e))?;
+    file.flush()
+        .map_err(|e| format!("Failed to flush file: {}", e))?;
+
+    let metadata = file
+        .metadata()
+        .map_err(|e| format!("Failed to read event file {}: {}", path.display(), e))?;
+    let version = head.version + events.len() as u64;
+    Ok(StreamHead {
+        len: metadata.len(),
+        modified: metadata.modified().ok(),
+        version,
+        last: events.pop().or(head.last),
+    })
+}
+
+/// Count the lines of an event file and parse only the last one
+fn read_head(
+    file: &std::fs::File,
+    path: &Path,
+    len: u64,
+    modified: Option<SystemTime>,
+) -> Result<StreamHead, String> {
+    let mut version = 0u64;
+    let mut last_line = None;
+    for line in std::io::BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("Failed to read line: {}", e))?;
+        version += 1;
+        last_line = Some(line);
+    }
+    let last = last_line
+        .map(|line| {
+            serde_json::from_str::<EventEnvelope>(&line).map_err(|e| {
+                format!(
+                    "Failed to deserialize event at line {} of {}: {}",
+                    version,
+                    path.display(),
+                    e
+                )
+            })
+        })
+        .transpose()?;
+    Ok(StreamHead {
+        len,
+        modified,
+        version,
+        last,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -205,8 +306,11 @@ mod tests {
                 causation_id: None,
                 user_id: Some("test-user".to_string()),
                 source: "test".to_string(),
+                reply_to: None,
+                tenant: None,
             },
             occurred_at: Utc::now(),
+            chain: None,
         };
 
         // Append event
@@ -225,4 +329,50 @@ mod tests {
         assert_eq!(events_by_type.len(), 1);
         assert_eq!(events_by_type[0].event_id, event.event_id);
     }
+
+    #[tokio::test]
+    async fn test_load_all_events_orders_across_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = FileEventStore::new(temp_dir.path().join("events"));
+
+        let start = Utc::now();
+        let envelope = |aggregate_id: &str, seconds: i64| EventEnvelope {
+            event_id: Uuid::new_v4(),
+            aggregate_id: aggregate_id.to_string(),
+            aggregate_type: "TestAggregate".to_string(),
+            event_type: "TestEvent".to_string(),
+            event_version: 1,
+            event_data: serde_json::json!({}),
+            metadata: EventMetadata {
+                correlation_id: None,
+                causation_id: None,
+                user_id: None,
+                source: "test".to_string(),
+                reply_to: None,
+                tenant: None,
+            },
+            occurred_at: start + chrono::Duration::seconds(seconds),
+            chain: None,
+        };
+        let first = envelope("b", 0);
+        let second = envelope("a", 1);
+        let third = envelope("b", 2);
+        store
+            .append_events("b", 0, vec![first.clone(), third.clone()])
+            .await
+            .unwrap();
+        store
+            .append_events("a", 0, vec![second.clone()])
+            .await
+            .unwrap();
+
+        let ids: Vec<_> = store
+            .load_all_events(None)
+            .await
+            .unwrap()
+            .iter()
+            .map(|event| event.event_id)
+            .collect();
+        assert_eq!(ids, vec![first.event_id, second.event_id, third.event_id]);
+    }
 }
@@ -0,0 +1,90 @@
+use crate::common::{RegisteredSchema, SchemaRegistry};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::path::PathBuf;
+use tokio::sync::Mutex;
+
+/// Schema registry kept in a directory, one file per version:
+/// `<base_path>/<subject>/v<version>.json`. Commit the directory, or put it on
+/// storage shared by every binary that publishes.
+pub struct FileSchemaRegistry {
+    base_path: PathBuf,
+    // Serialises version numbering within this process
+    lock: Mutex<()>,
+}
+
+impl FileSchemaRegistry {
+    pub fn new<P: Into<PathBuf>>(base_path: P) -> Self {
+        Self {
+            base_path: base_path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn subject_dir(&self, subject: &str) -> PathBuf {
+        self.base_path.join(subject)
+    }
+
+    async fn latest_version(&self, subject: &str) -> Result<Option<u32>, String> {
+        let mut entries = match tokio::fs::read_dir(self.subject_dir(subject)).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("Failed to read schemas of {}: {}", subject, e)),
+        };
+        let mut latest = None;
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| format!("Failed to read schemas of {}: {}", subject, e))?
+        {
+            let name = entry.file_name();
+            let version = name
+                .to_str()
+                .and_then(|name| name.strip_prefix('v'))
+                .and_then(|name| name.strip_suffix(".json"))
+                .and_then(|version| version.parse::<u32>().ok());
+            latest = latest.max(version);
+        }
+        Ok(latest)
+    }
+}
+
+#[async_trait]
+impl SchemaRegistry for FileSchemaRegistry {
+    async fn latest(&self, subject: &str) -> Result<Option<RegisteredSchema>, String> {
+        let Some(version) = self.latest_version(subject).await? else {
+            return Ok(None);
+        };
+        let path = self.subject_dir(subject).join(format!("v{}.json", version));
+        let content = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let schema: Value = serde_json::from_str(&content)
+            .map_err(|e| format!("{} is not a JSON schema: {}", path.display(), e))?;
+        Ok(Some(RegisteredSchema {
+            subject: subject.to_string(),
+            version,
+            schema,
+        }))
+    }
+
+    async fn append(&self, subject: &str, schema: Value) -> Result<RegisteredSchema, String> {
+        let _guard = self.lock.lock().await;
+        let version = self.latest_version(subject).await?.unwrap_or(0) + 1;
+        let dir = self.subject_dir(subject);
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        let path = dir.join(format!("v{}.json", version));
+        let content = serde_json::to_string_pretty(&schema)
+            .map_err(|e| format!("Failed to serialize schema: {}", e))?;
+        tokio::fs::write(&path, content)
+            .await
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(RegisteredSchema {
+            subject: subject.to_string(),
+            version,
+            schema,
+        })
+    }
+}
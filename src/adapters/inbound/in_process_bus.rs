@@ -0,0 +1,136 @@
+use crate::common::{BusMessage, EventEnvelope, MessageBus, MessageSubscription};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+type SharedReceiver = Arc<tokio::sync::Mutex<broadcast::Receiver<BusMessage>>>;
+
+struct Topic {
+    sender: broadcast::Sender<BusMessage>,
+    next_offset: i64,
+    // Members of a group take turns reading from one receiver, so each message
+    // reaches exactly one of them
+    groups: HashMap<String, SharedReceiver>,
+}
+
+/// In-process message bus on tokio broadcast channels, for tests and for running
+/// planner, worker and client in a single process.
+///
+/// Delivery is at-most-once: messages published before a group subscribes are
+/// not replayed, and a subscriber that falls more than `capacity` messages
+/// behind skips the oldest ones. Acknowledged offsets are recorded per group.
+#[derive(Clone)]
+pub struct InProcessMessageBus {
+    topics: Arc<Mutex<HashMap<String, Topic>>>,
+    committed: Arc<Mutex<HashMap<(String, String), i64>>>,
+    capacity: usize,
+}
+
+impl InProcessMessageBus {
+    pub fn new() -> Self {
+        Self::with_capacity(1024)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            topics: Arc::new(Mutex::new(HashMap::new())),
+            committed: Arc::new(Mutex::new(HashMap::new())),
+            capacity,
+        }
+    }
+
+    /// Offset of the last message `group_id` acknowledged on `topic`
+    pub fn committed_offset(&self, topic: &str, group_id: &str) -> Option<i64> {
+        self.committed
+            .lock()
+            .unwrap()
+            .get(&(topic.to_string(), group_id.to_string()))
+            .copied()
+    }
+}
+
+impl Default for InProcessMessageBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl MessageBus for InProcessMessageBus {
+    async fn publish(&self, topic: &str, envelope: &EventEnvelope) -> Result<(), String> {
+        let mut topics = self.topics.lock().unwrap();
+        let Some(entry) = topics.get_mut(topic) else {
+            // Nobody has subscribed yet, so nobody can receive it
+            return Ok(());
+        };
+        let message = BusMessage {
+            topic: topic.to_string(),
+            partition: 0,
+            offset: entry.next_offset,
+            envelope: envelope.clone(),
+        };
+        entry.next_offset += 1;
+        // Sending only fails when every receiver is gone, which is not an error here
+        let _ = entry.sender.send(message);
+        Ok(())
+    }
+
+    async fn subscribe(
+        &self,
+        topic: &str,
+        group_id: &str,
+    ) -> Result<Box<dyn MessageSubscription>, String> {
+        let mut topics = self.topics.lock().unwrap();
+        let entry = topics.entry(topic.to_string()).or_insert_with(|| Topic {
+            sender: broadcast::channel(self.capacity).0,
+            next_offset: 0,
+            groups: HashMap::new(),
+        });
+        let receiver = entry
+            .groups
+            .entry(group_id.to_string())
+            .or_insert_with(|| Arc::new(tokio::sync::Mutex::new(entry.sender.subscribe())))
+            .clone();
+        Ok(Box::new(InProcessSubscription {
+            group_id: group_id.to_string(),
+            receiver,
+            committed: self.committed.clone(),
+        }))
+    }
+}
+
+struct InProcessSubscription {
+    group_id: String,
+    receiver: SharedReceiver,
+    committed: Arc<Mutex<HashMap<(String, String), i64>>>,
+}
+
+#[async_trait]
+impl MessageSubscription for InProcessSubscription {
+    async fn next(&mut self) -> Result<Option<BusMessage>, String> {
+        let mut receiver = self.receiver.lock().await;
+        loop {
+            match receiver.recv().await {
+                Ok(message) => return Ok(Some(message)),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(
+                        "In-process subscriber in group {} skipped {} messages",
+                        self.group_id,
+                        skipped
+                    );
+                }
+                Err(broadcast::error::RecvError::Closed) => return Ok(None),
+            }
+        }
+    }
+
+    async fn ack(&mut self, message: &BusMessage) -> Result<(), String> {
+        let mut committed = self.committed.lock().unwrap();
+        let offset = committed
+            .entry((message.topic.clone(), self.group_id.clone()))
+            .or_insert(message.offset);
+        *offset = (*offset).max(message.offset);
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use crate::common::Inbox;
+use async_trait::async_trait;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Inbox kept in memory; it forgets everything when the process exits
+#[derive(Clone, Default)]
+pub struct InMemoryInbox {
+    processed: Arc<RwLock<HashSet<(String, Uuid)>>>,
+}
+
+impl InMemoryInbox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl Inbox for InMemoryInbox {
+    async fn is_processed(&self, consumer: &str, event_id: Uuid) -> Result<bool, String> {
+        Ok(self
+            .processed
+            .read()
+            .await
+            .contains(&(consumer.to_string(), event_id)))
+    }
+
+    async fn mark_processed(&self, consumer: &str, event_id: Uuid) -> Result<(), String> {
+        self.processed
+            .write()
+            .await
+            .insert((consumer.to_string(), event_id));
+        Ok(())
+    }
+}
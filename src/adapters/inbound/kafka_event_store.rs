@@ -1,22 +1,24 @@
-use crate::common::{EventEnvelope, EventStore};
+use crate::adapters::outbound::kafka_codec::codec_headers;
+use crate::adapters::outbound::kafka_replay::{KafkaReplayReader, ReplayFilter};
+use crate::common::{EnvelopeCodec, EventEnvelope, EventStore};
+use crate::config::PartitionStrategy;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use rdkafka::config::ClientConfig;
-use rdkafka::consumer::{Consumer, StreamConsumer};
 use rdkafka::producer::{FutureProducer, FutureRecord};
-use rdkafka::Message;
-use serde_json;
 use std::time::Duration;
-use uuid::Uuid;
 
 /// Kafka-based EventStore implementation for distributed event-driven architecture
 ///
 /// This implementation stores events in Kafka topics and allows for real-time
-/// event consumption across multiple processes
+/// event consumption across multiple processes. Reads replay the topic from
+/// the beginning, so it should be compacted or have long retention.
 pub struct KafkaEventStore {
     producer: FutureProducer,
     topic_name: String,
-    bootstrap_servers: String,
+    reader: KafkaReplayReader,
+    partitioning: PartitionStrategy,
+    codec: EnvelopeCodec,
 }
 
 impl KafkaEventStore {
@@ -29,15 +31,35 @@ impl KafkaEventStore {
             .set("bootstrap.servers", bootstrap_servers)
             .set("client.id", "gryphon-producer")
             .set("message.timeout.ms", "5000")
+            .set("enable.idempotence", "true")
             .create()
             .map_err(|e| format!("Failed to create Kafka producer: {}", e))?;
 
         Ok(Self {
             producer,
             topic_name: topic_name.to_string(),
-            bootstrap_servers: bootstrap_servers.to_string(),
+            reader: KafkaReplayReader::new(bootstrap_servers, "gryphon-replay"),
+            partitioning: PartitionStrategy::default(),
+            codec: EnvelopeCodec::default(),
         })
     }
+
+    pub fn with_partition_strategy(mut self, partitioning: PartitionStrategy) -> Self {
+        self.partitioning = partitioning;
+        self
+    }
+
+    /// Format new events are written in; reads accept every format
+    pub fn with_codec(mut self, codec: EnvelopeCodec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Upper bound for a single `load_events` / `load_events_by_type` replay
+    pub fn with_replay_timeout(mut self, timeout: Duration) -> Self {
+        self.reader = self.reader.with_timeout(timeout);
+        self
+    }
 }
 
 #[async_trait]
@@ -49,14 +71,14 @@ impl EventStore for KafkaEventStore {
         events: Vec<EventEnvelope>,
     ) -> Result<(), String> {
         for event in events {
-            let key = format!("{}:{}", event.aggregate_type, aggregate_id);
+            let key = self.partitioning.key(&event.aggregate_type, aggregate_id);
 
-            let payload = serde_json::to_string(&event)
-                .map_err(|e| format!("Failed to serialize event: {}", e))?;
+            let payload = self.codec.encode(&event)?;
 
             let record = FutureRecord::to(&self.topic_name)
                 .key(&key)
-                .payload(&payload);
+                .payload(&payload)
+                .headers(codec_headers(self.codec));
 
             self.producer
                 .send(record, Duration::from_secs(5))
@@ -70,108 +92,45 @@ impl EventStore for KafkaEventStore {
     async fn load_events(
         &self,
         aggregate_id: &str,
-        _from_version: u64,
+        from_version: u64,
     ) -> Result<Vec<EventEnvelope>, String> {
-        // Create a short-lived consumer with a unique group id so we read from the beginning
-        let temp_group = format!("temp-reader-{}", Uuid::new_v4());
-        let temp_consumer: StreamConsumer = ClientConfig::new()
-            .set("bootstrap.servers", &self.bootstrap_servers)
-            .set("group.id", &temp_group)
-            .set("client.id", "gryphon-temp-consumer")
-            .set("enable.partition.eof", "false")
-            .set("session.timeout.ms", "6000")
-            .set("enable.auto.commit", "false")
-            .set("auto.offset.reset", "earliest")
-            .create()
-            .map_err(|e| format!("Failed to create temp consumer: {}", e))?;
-
-        temp_consumer
-            .subscribe(&[&self.topic_name])
-            .map_err(|e| format!("Failed to subscribe to topic: {}", e))?;
-
-        let mut events = Vec::new();
-        let timeout = Duration::from_secs(2);
-
-        // Read all available messages within the timeout
-        let start_time = std::time::Instant::now();
-        while start_time.elapsed() < timeout {
-            match tokio::time::timeout(Duration::from_millis(200), temp_consumer.recv()).await {
-                Ok(Ok(message)) => {
-                    if let Some(key) = message.key() {
-                        let key_str = String::from_utf8_lossy(key);
-                        if key_str.contains(aggregate_id) {
-                            if let Some(payload) = message.payload() {
-                                let payload_str = String::from_utf8_lossy(payload);
-                                match serde_json::from_str::<EventEnvelope>(&payload_str) {
-                                    Ok(event) => events.push(event),
-                                    Err(e) => tracing::warn!("Failed to deserialize event: {}", e),
-                                }
-                            }
-                        }
-                    }
-                }
-                _ => break, // Timeout or error - stop reading
-            }
-        }
+        let filter = ReplayFilter {
+            aggregate_id: Some(aggregate_id.to_string()),
+            ..ReplayFilter::default()
+        };
+        let events = self.reader.replay(&self.topic_name, filter).await?;
 
-        Ok(events)
+        Ok(events.into_iter().skip(from_version as usize).collect())
     }
 
     async fn load_events_by_type(
         &self,
         event_type: &str,
-        _from_timestamp: Option<DateTime<Utc>>,
+        from_timestamp: Option<DateTime<Utc>>,
     ) -> Result<Vec<EventEnvelope>, String> {
-        // Create ephemeral consumer for polling new events. Use 'latest' so the
-        // ephemeral consumer starts at the end of the log and only receives
-        // messages produced after the request. Make the read window slightly
-        // longer to allow subscription and partition assignment to complete.
-        let temp_group = format!("temp-reader-{}", Uuid::new_v4());
-        let temp_consumer: StreamConsumer = ClientConfig::new()
-            .set("bootstrap.servers", &self.bootstrap_servers)
-            .set("group.id", &temp_group)
-            .set(
-                "client.id",
-                format!("gryphon-temp-consumer-{}", Uuid::new_v4()),
-            )
-            .set("enable.partition.eof", "false")
-            .set("session.timeout.ms", "6000")
-            .set("enable.auto.commit", "false")
-            .set("auto.offset.reset", "latest")
-            .create()
-            .map_err(|e| format!("Failed to create temp consumer: {}", e))?;
-
-        temp_consumer
-            .subscribe(&[&self.topic_name])
-            .map_err(|e| format!("Failed to subscribe to topic: {}", e))?;
-
-        let mut events = Vec::new();
-        // Increase the read window so subscription/assignment can settle and
-        // newly produced events are received.
-        let timeout = Duration::from_millis(1500);
-
-        // Read all available messages within the timeout
-        let start_time = std::time::Instant::now();
-        while start_time.elapsed() < timeout {
-            // Wait a bit longer per recv to tolerate scheduling delays
-            match tokio::time::timeout(Duration::from_millis(300), temp_consumer.recv()).await {
-                Ok(Ok(message)) => {
-                    if let Some(payload) = message.payload() {
-                        let payload_str = String::from_utf8_lossy(payload);
-                        match serde_json::from_str::<EventEnvelope>(&payload_str) {
-                            Ok(event) => {
-                                if event.event_type == event_type {
-                                    events.push(event);
-                                }
-                            }
-                            Err(e) => println!("⚠️  Failed to deserialize event: {}", e),
-                        }
-                    }
-                }
-                _ => break, // Timeout or error - stop
-            }
-        }
+        let filter = ReplayFilter {
+            event_type: Some(event_type.to_string()),
+            from_timestamp,
+            ..ReplayFilter::default()
+        };
+        let mut events = self.reader.replay(&self.topic_name, filter).await?;
+
+        // Partitions are only ordered internally
+        events.sort_by_key(|event| event.occurred_at);
+        Ok(events)
+    }
+
+    async fn load_all_events(
+        &self,
+        from_timestamp: Option<DateTime<Utc>>,
+    ) -> Result<Vec<EventEnvelope>, String> {
+        let filter = ReplayFilter {
+            from_timestamp,
+            ..ReplayFilter::default()
+        };
+        let mut events = self.reader.replay(&self.topic_name, filter).await?;
 
+        events.sort_by_key(|event| event.occurred_at);
         Ok(events)
     }
 }
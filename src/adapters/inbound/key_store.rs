@@ -0,0 +1,44 @@
+use crate::common::{KeyStore, SubjectKey};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Key store kept in memory, for tests and development. Its keys are gone
+/// when the process exits, and with them every field encrypted under them.
+#[derive(Default)]
+pub struct InMemoryKeyStore {
+    keys: RwLock<HashMap<String, SubjectKey>>,
+}
+
+impl InMemoryKeyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl KeyStore for InMemoryKeyStore {
+    async fn key_for_subject(&self, subject: &str) -> Result<SubjectKey, String> {
+        let mut keys = self.keys.write().await;
+        let key = keys
+            .entry(subject.to_string())
+            .or_insert_with(|| SubjectKey {
+                key_id: Uuid::new_v4(),
+                key: rand::random(),
+            });
+        Ok(key.clone())
+    }
+
+    async fn key_by_id(&self, key_id: Uuid) -> Result<Option<[u8; 32]>, String> {
+        let keys = self.keys.read().await;
+        Ok(keys
+            .values()
+            .find(|key| key.key_id == key_id)
+            .map(|key| key.key))
+    }
+
+    async fn forget_subject(&self, subject: &str) -> Result<bool, String> {
+        Ok(self.keys.write().await.remove(subject).is_some())
+    }
+}
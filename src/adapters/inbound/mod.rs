@@ -1,15 +1,39 @@
+pub mod dead_letter_queue;
+pub mod event_archive;
 pub mod event_store;
 pub mod file_event_store;
+pub mod file_schema_registry;
+pub mod in_process_bus;
+pub mod inbox;
 pub mod kafka_event_store;
+pub mod key_store;
+pub mod outbox;
+pub mod plan_cache;
 pub mod snapshot_store;
 
+pub use dead_letter_queue::*;
+pub use event_archive::*;
 pub use event_store::*;
 pub use file_event_store::*;
+pub use file_schema_registry::*;
+pub use in_process_bus::*;
+pub use inbox::*;
 pub use kafka_event_store::*;
+pub use key_store::*;
+pub use outbox::*;
+pub use plan_cache::*;
 pub use snapshot_store::*;
 
 // ESRS migration adapters
 #[cfg(feature = "esrs_migration")]
+pub mod esrs_history_migration;
+#[cfg(feature = "esrs_migration")]
+pub use esrs_history_migration::*;
+#[cfg(feature = "esrs_migration")]
 pub mod esrs_pg_store;
 #[cfg(feature = "esrs_migration")]
 pub use esrs_pg_store::*;
+#[cfg(feature = "esrs_migration")]
+pub mod esrs_read_models;
+#[cfg(feature = "esrs_migration")]
+pub use esrs_read_models::*;
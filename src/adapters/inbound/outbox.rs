@@ -0,0 +1,72 @@
+use crate::common::{EventEnvelope, Outbox, OutboxMessage};
+use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+#[derive(Default)]
+struct OutboxRows {
+    pending: Vec<OutboxMessage>,
+    sent: Vec<Uuid>,
+}
+
+/// In-memory stand-in for the Postgres outbox table. Clones share the same rows,
+/// so a relay can be restarted against the same outbox.
+#[derive(Clone, Default)]
+pub struct InMemoryOutbox {
+    rows: Arc<Mutex<OutboxRows>>,
+}
+
+impl InMemoryOutbox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue `envelopes` for `topic`. An envelope that was queued before is
+    /// ignored, like the unique event id on the outbox table.
+    pub async fn enqueue(&self, topic: &str, envelopes: Vec<EventEnvelope>) {
+        let mut rows = self.rows.lock().await;
+        for envelope in envelopes {
+            let id = envelope.event_id;
+            if rows.sent.contains(&id) || rows.pending.iter().any(|m| m.id == id) {
+                continue;
+            }
+            rows.pending.push(OutboxMessage::new(topic, envelope));
+        }
+    }
+
+    /// Ids of the messages marked sent, in the order they were marked
+    pub async fn sent(&self) -> Vec<Uuid> {
+        self.rows.lock().await.sent.clone()
+    }
+
+    pub async fn pending_count(&self) -> usize {
+        self.rows.lock().await.pending.len()
+    }
+}
+
+#[async_trait]
+impl Outbox for InMemoryOutbox {
+    async fn pending(&self, limit: usize) -> Result<Vec<OutboxMessage>, String> {
+        let rows = self.rows.lock().await;
+        Ok(rows.pending.iter().take(limit).cloned().collect())
+    }
+
+    async fn mark_sent(&self, id: Uuid) -> Result<(), String> {
+        let mut rows = self.rows.lock().await;
+        let before = rows.pending.len();
+        rows.pending.retain(|m| m.id != id);
+        if rows.pending.len() < before {
+            rows.sent.push(id);
+        }
+        Ok(())
+    }
+
+    async fn record_failure(&self, id: Uuid, _error: &str) -> Result<(), String> {
+        let mut rows = self.rows.lock().await;
+        if let Some(message) = rows.pending.iter_mut().find(|m| m.id == id) {
+            message.attempts += 1;
+        }
+        Ok(())
+    }
+}
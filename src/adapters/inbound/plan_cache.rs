@@ -0,0 +1,52 @@
+use crate::common::DomainResult;
+use crate::domains::path_planning::aggregate::Position2D;
+use crate::domains::path_planning::plan_cache::PlanCacheKey;
+use crate::domains::path_planning::ports::PlanCache;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+/// In-memory plan cache for a single worker process and for tests
+#[derive(Debug, Default)]
+pub struct InMemoryPlanCache {
+    entries: RwLock<HashMap<String, (PlanCacheKey, Vec<Position2D>)>>,
+}
+
+impl InMemoryPlanCache {
+    pub fn new() -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub async fn len(&self) -> usize {
+        self.entries.read().await.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.entries.read().await.is_empty()
+    }
+}
+
+#[async_trait]
+impl PlanCache for InMemoryPlanCache {
+    async fn get(&self, key: &PlanCacheKey) -> DomainResult<Option<Vec<Position2D>>> {
+        let entries = self.entries.read().await;
+        Ok(entries
+            .get(&key.digest())
+            .map(|(_, waypoints)| waypoints.clone()))
+    }
+
+    async fn put(&self, key: &PlanCacheKey, waypoints: &[Position2D]) -> DomainResult<()> {
+        let mut entries = self.entries.write().await;
+        entries.insert(key.digest(), (key.clone(), waypoints.to_vec()));
+        Ok(())
+    }
+
+    async fn invalidate_map(&self, map_name: &str) -> DomainResult<usize> {
+        let mut entries = self.entries.write().await;
+        let before = entries.len();
+        entries.retain(|_, (key, _)| key.map_name != map_name);
+        Ok(before - entries.len())
+    }
+}
@@ -69,4 +69,17 @@ impl SnapshotStore for InMemorySnapshotStore {
 
         Ok(())
     }
+
+    async fn load_all_snapshots(&self) -> Result<Vec<Snapshot>, String> {
+        let store = self.snapshots.read().await;
+
+        let mut all_snapshots: Vec<Snapshot> = store.values().flatten().cloned().collect();
+        all_snapshots.sort_by(|a, b| {
+            a.aggregate_id
+                .cmp(&b.aggregate_id)
+                .then(a.aggregate_version.cmp(&b.aggregate_version))
+        });
+
+        Ok(all_snapshots)
+    }
 }
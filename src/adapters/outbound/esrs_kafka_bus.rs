@@ -5,21 +5,34 @@ use rdkafka::producer::{FutureProducer, FutureRecord};
 use std::time::Duration;
 use std::sync::{Arc, Mutex};
 use std::marker::PhantomData;
+use crate::adapters::outbound::kafka_codec::store_event_headers;
+use crate::common::DomainEvent;
+use crate::config::PartitionStrategy;
 
 pub struct KafkaEventBus<A> {
     producer: FutureProducer,
     topic: String,
+    aggregate_type: String,
+    partitioning: PartitionStrategy,
     _marker: PhantomData<A>,
 }
 
 impl<A> KafkaEventBus<A> {
-    pub fn new(brokers: &str, topic: &str) -> Self {
+    /// `aggregate_type` is what the other producers of these streams put on
+    /// their envelopes (e.g. `PathPlanner::AGGREGATE_TYPE`), so partition keys agree.
+    pub fn new(brokers: &str, topic: &str, aggregate_type: &str) -> Self {
         let producer = ClientConfig::new()
             .set("bootstrap.servers", brokers)
             .set("client.id", "esrs-kafka-bus")
+            .set("enable.idempotence", "true")
             .create()
             .expect("Failed to create Kafka producer");
-        Self { producer, topic: topic.to_string(), _marker: PhantomData }
+        Self { producer, topic: topic.to_string(), aggregate_type: aggregate_type.to_string(), partitioning: PartitionStrategy::default(), _marker: PhantomData }
+    }
+
+    pub fn with_partition_strategy(mut self, partitioning: PartitionStrategy) -> Self {
+        self.partitioning = partitioning;
+        self
     }
 }
 
@@ -89,15 +102,28 @@ where
 impl<A> EventBus<A> for KafkaEventBus<A>
 where
     A: esrs::Aggregate + Sync,
-    A::Event: serde::Serialize + Send + Sync + 'static,
+    A::Event: DomainEvent + serde::Serialize + Send + Sync + 'static,
 {
+    // EventBus::publish cannot fail, so errors are only logged. Use OutboxWriter
+    // and OutboxRelay when every persisted event has to reach Kafka.
     async fn publish(&self, store_event: &StoreEvent<A::Event>) {
-        let payload = serde_json::to_string(store_event).unwrap_or_default();
-        // use string forms for payload and key
-        let key = store_event.aggregate_id.to_string();
+        let payload = match serde_json::to_string(store_event) {
+            Ok(payload) => payload,
+            Err(e) => {
+                tracing::error!("Not publishing esrs event {} to {}: failed to serialize: {}", store_event.id, self.topic, e);
+                return;
+            }
+        };
+        // Key by the domain aggregate id rather than the esrs UUID derived from it, so
+        // these records share partitions with the ones the message bus publishes
+        let key = self.partitioning.key(&self.aggregate_type, store_event.payload.aggregate_id());
+        // Marked so envelope consumers sharing the topic pass over it
         let record = FutureRecord::to(&self.topic)
             .payload(&payload)
-            .key(&key);
-        let _ = self.producer.send(record, Duration::from_secs(5)).await;
+            .key(&key)
+            .headers(store_event_headers());
+        if let Err((e, _)) = self.producer.send(record, Duration::from_secs(5)).await {
+            tracing::error!("Failed to publish esrs event {} to {}: {}", store_event.id, self.topic, e);
+        }
     }
 }
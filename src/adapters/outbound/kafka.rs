@@ -1,16 +1,18 @@
+use crate::adapters::outbound::kafka_admin::KafkaTopicAdmin;
+use crate::adapters::outbound::kafka_codec::codec_headers;
+use crate::adapters::outbound::kafka_replay::{KafkaReplayReader, ReplayFilter};
 use crate::common::{EventEnvelope, EventStore};
-use crate::config::KafkaConfig;
+use crate::config::{KafkaConfig, TopicStartup};
+use crate::domains::DynLogger;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use rdkafka::config::ClientConfig;
-use rdkafka::consumer::StreamConsumer;
 use rdkafka::producer::{FutureProducer, FutureRecord};
-use serde_json;
 use std::time::Duration;
 
 pub struct KafkaEventStore {
     producer: FutureProducer,
-    _consumer: StreamConsumer,
+    reader: KafkaReplayReader,
     config: KafkaConfig,
 }
 
@@ -20,26 +22,25 @@ impl KafkaEventStore {
             .set("bootstrap.servers", config.brokers.join(","))
             .set("client.id", &config.client_id)
             .set("message.timeout.ms", "5000")
+            .set("enable.idempotence", "true")
             .create()
             .map_err(|e| format!("Failed to create Kafka producer: {}", e))?;
 
-        let consumer: StreamConsumer = ClientConfig::new()
-            .set("bootstrap.servers", config.brokers.join(","))
-            .set("group.id", &config.group_id)
-            .set("client.id", &config.client_id)
-            .set("enable.partition.eof", "false")
-            .set("session.timeout.ms", "6000")
-            .set("enable.auto.commit", "true")
-            .create()
-            .map_err(|e| format!("Failed to create Kafka consumer: {}", e))?;
+        let reader = KafkaReplayReader::new(&config.brokers.join(","), &config.client_id);
 
         Ok(Self {
             producer,
-            _consumer: consumer,
+            reader,
             config,
         })
     }
 
+    /// Upper bound for a single `load_events` / `load_events_by_type` replay
+    pub fn with_replay_timeout(mut self, timeout: Duration) -> Self {
+        self.reader = self.reader.with_timeout(timeout);
+        self
+    }
+
     fn get_topic_for_aggregate(&self, aggregate_type: &str) -> &str {
         match aggregate_type.to_lowercase().as_str() {
             "logicalagent" => &self.config.topics.logical_agent_events,
@@ -51,6 +52,23 @@ impl KafkaEventStore {
             _ => "default-events",
         }
     }
+
+    /// Every topic `append_events` can write to
+    fn event_topics(&self) -> Vec<&str> {
+        let topics = &self.config.topics;
+        let mut all = vec![
+            topics.logical_agent_events.as_str(),
+            topics.technical_agent_events.as_str(),
+            topics.kinematic_agent_events.as_str(),
+            topics.path_planning_events.as_str(),
+            topics.dynamics_events.as_str(),
+            topics.gui_events.as_str(),
+            "default-events",
+        ];
+        all.sort_unstable();
+        all.dedup();
+        all
+    }
 }
 
 #[async_trait]
@@ -63,12 +81,17 @@ impl EventStore for KafkaEventStore {
     ) -> Result<(), String> {
         for event in events {
             let topic = self.get_topic_for_aggregate(&event.aggregate_type);
-            let key = format!("{}:{}", event.aggregate_type, aggregate_id);
+            let key = self
+                .config
+                .partitioning
+                .key(&event.aggregate_type, aggregate_id);
 
-            let payload = serde_json::to_string(&event)
-                .map_err(|e| format!("Failed to serialize event: {}", e))?;
+            let payload = self.config.codec.encode(&event)?;
 
-            let record = FutureRecord::to(topic).key(&key).payload(&payload);
+            let record = FutureRecord::to(topic)
+                .key(&key)
+                .payload(&payload)
+                .headers(codec_headers(self.config.codec));
 
             self.producer
                 .send(record, Duration::from_secs(5))
@@ -81,49 +104,83 @@ impl EventStore for KafkaEventStore {
 
     async fn load_events(
         &self,
-        _aggregate_id: &str,
-        _from_version: u64,
+        aggregate_id: &str,
+        from_version: u64,
     ) -> Result<Vec<EventEnvelope>, String> {
-        // Note: This is a simplified implementation
-        // In a real-world scenario, you'd need to implement proper event replay
-        // from Kafka, potentially using a separate topic for event storage
-        // or implementing compaction strategies
-
-        // For now, return empty as Kafka is primarily used for event publishing
-        // Consider using a separate event store (like EventStore DB) for event sourcing
-        // and Kafka for event broadcasting
+        // The aggregate type picks the topic but is not part of the query, so
+        // look in each topic; an aggregate's key only ever lands in one of them.
+        let mut events = Vec::new();
+        for topic in self.event_topics() {
+            let filter = ReplayFilter {
+                aggregate_id: Some(aggregate_id.to_string()),
+                ..ReplayFilter::default()
+            };
+            events.extend(self.reader.replay(topic, filter).await?);
+        }
 
-        Ok(Vec::new())
+        Ok(events.into_iter().skip(from_version as usize).collect())
     }
 
     async fn load_events_by_type(
         &self,
-        _event_type: &str,
-        _from_timestamp: Option<DateTime<Utc>>,
+        event_type: &str,
+        from_timestamp: Option<DateTime<Utc>>,
+    ) -> Result<Vec<EventEnvelope>, String> {
+        let mut events = Vec::new();
+        for topic in self.event_topics() {
+            let filter = ReplayFilter {
+                event_type: Some(event_type.to_string()),
+                from_timestamp,
+                ..ReplayFilter::default()
+            };
+            events.extend(self.reader.replay(topic, filter).await?);
+        }
+
+        // Partitions are only ordered internally
+        events.sort_by_key(|event| event.occurred_at);
+        Ok(events)
+    }
+
+    async fn load_all_events(
+        &self,
+        from_timestamp: Option<DateTime<Utc>>,
     ) -> Result<Vec<EventEnvelope>, String> {
-        // Similar to load_events, this would require implementing
-        // proper event querying capabilities
+        let mut events = Vec::new();
+        for topic in self.event_topics() {
+            let filter = ReplayFilter {
+                from_timestamp,
+                ..ReplayFilter::default()
+            };
+            events.extend(self.reader.replay(topic, filter).await?);
+        }
 
-        Ok(Vec::new())
+        events.sort_by_key(|event| event.occurred_at);
+        Ok(events)
     }
 }
 
-// Helper function to create all required Kafka topics
-pub async fn create_kafka_topics(config: &KafkaConfig) -> Result<(), String> {
-    // Note: In a real implementation, you would use Kafka Admin API
-    // to create topics programmatically
-
-    let topics = vec![
-        &config.topics.logical_agent_events,
-        &config.topics.technical_agent_events,
-        &config.topics.kinematic_agent_events,
-        &config.topics.path_planning_events,
-        &config.topics.dynamics_events,
-        &config.topics.gui_events,
-    ];
-
-    tracing::info!("Required Kafka topics: {:?}", topics);
-    tracing::warn!("Auto-creation of Kafka topics not implemented. Please ensure topics exist.");
+/// Create the topics in `config.topics` that don't exist yet and warn about
+/// existing ones whose settings differ from `config.topic_settings`. With
+/// `on_startup = "fail"` drift is an error instead; `"skip"` does nothing.
+pub async fn create_kafka_topics(config: &KafkaConfig, logger: &DynLogger) -> Result<(), String> {
+    let on_startup = config.topic_settings.on_startup;
+    if on_startup == TopicStartup::Skip {
+        return Ok(());
+    }
+    let report = KafkaTopicAdmin::new(config)?.ensure_topics().await?;
+
+    for topic in &report.created {
+        logger.info(&format!("Created Kafka topic {}", topic));
+    }
+    for drift in &report.drift {
+        logger.warn(&format!("Kafka topic drift: {}", drift));
+    }
+    if on_startup == TopicStartup::Fail && !report.drift.is_empty() {
+        return Err(format!(
+            "{} Kafka topic settings differ from the config",
+            report.drift.len()
+        ));
+    }
 
     Ok(())
 }
@@ -0,0 +1,250 @@
+use crate::config::{KafkaConfig, KafkaTopicSettings, TopicSettings};
+use rdkafka::admin::{AdminClient, AdminOptions, NewTopic, ResourceSpecifier, TopicReplication};
+use rdkafka::client::DefaultClientContext;
+use rdkafka::config::ClientConfig;
+use rdkafka::types::RDKafkaErrorCode;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+/// A topic as it exists on the cluster
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObservedTopic {
+    pub partitions: i32,
+    pub replication_factor: i32,
+    /// Topic configs by name, e.g. `cleanup.policy`
+    pub configs: HashMap<String, String>,
+}
+
+/// One setting that differs between `KafkaConfig` and the cluster
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicDrift {
+    pub topic: String,
+    pub setting: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl fmt::Display for TopicDrift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} is {} but configured as {}",
+            self.topic, self.setting, self.actual, self.expected
+        )
+    }
+}
+
+/// Outcome of `KafkaTopicAdmin::ensure_topics`
+#[derive(Debug, Clone, Default)]
+pub struct TopicReport {
+    pub created: Vec<String>,
+    pub missing: Vec<String>,
+    pub drift: Vec<TopicDrift>,
+}
+
+impl TopicSettings {
+    /// Settings of `observed` that don't match these
+    pub fn drift_from(&self, topic: &str, observed: &ObservedTopic) -> Vec<TopicDrift> {
+        let mut expected = vec![
+            (
+                "partitions",
+                self.partitions.to_string(),
+                Some(observed.partitions.to_string()),
+            ),
+            (
+                "replication.factor",
+                self.replication_factor.to_string(),
+                Some(observed.replication_factor.to_string()),
+            ),
+        ];
+        for (name, value) in self.topic_configs() {
+            expected.push((name, value, observed.configs.get(name).cloned()));
+        }
+
+        expected
+            .into_iter()
+            .filter(|(_, expected, actual)| actual.as_ref() != Some(expected))
+            .map(|(setting, expected, actual)| TopicDrift {
+                topic: topic.to_string(),
+                setting: setting.to_string(),
+                expected,
+                actual: actual.unwrap_or_else(|| "unset".to_string()),
+            })
+            .collect()
+    }
+
+    /// Topic-level configs set on creation
+    pub fn topic_configs(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (
+                "cleanup.policy",
+                self.cleanup_policy.as_kafka_str().to_string(),
+            ),
+            ("retention.ms", self.retention_ms.to_string()),
+        ]
+    }
+}
+
+/// Creates and validates the topics named in `KafkaTopics`
+pub struct KafkaTopicAdmin {
+    client: AdminClient<DefaultClientContext>,
+    topics: Vec<String>,
+    settings: KafkaTopicSettings,
+    timeout: Duration,
+}
+
+impl KafkaTopicAdmin {
+    pub fn new(config: &KafkaConfig) -> Result<Self, String> {
+        let client: AdminClient<DefaultClientContext> = ClientConfig::new()
+            .set("bootstrap.servers", config.brokers.join(","))
+            .set("client.id", &config.client_id)
+            .create()
+            .map_err(|e| format!("Failed to create Kafka admin client: {}", e))?;
+
+        Ok(Self {
+            client,
+            topics: config
+                .topics
+                .all()
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            settings: config.topic_settings.clone(),
+            timeout: Duration::from_secs(10),
+        })
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Create missing topics with their configured settings and report drift
+    /// on the ones that already exist. Existing topics are never altered.
+    pub async fn ensure_topics(&self) -> Result<TopicReport, String> {
+        let mut report = self.validate_topics().await?;
+        if report.missing.is_empty() {
+            return Ok(report);
+        }
+
+        let configs: Vec<_> = report
+            .missing
+            .iter()
+            .map(|topic| (topic, self.settings.for_topic(topic).clone()))
+            .collect();
+        let values: Vec<Vec<(&str, String)>> = configs
+            .iter()
+            .map(|(_, settings)| settings.topic_configs())
+            .collect();
+        let new_topics: Vec<NewTopic> = configs
+            .iter()
+            .zip(&values)
+            .map(|((topic, settings), values)| {
+                values.iter().fold(
+                    NewTopic::new(
+                        topic,
+                        settings.partitions,
+                        TopicReplication::Fixed(settings.replication_factor),
+                    ),
+                    |new_topic, (name, value)| new_topic.set(name, value),
+                )
+            })
+            .collect();
+
+        let results = self
+            .client
+            .create_topics(&new_topics, &self.options())
+            .await
+            .map_err(|e| format!("Failed to create topics: {}", e))?;
+        for result in results {
+            match result {
+                // Lost a race with another process; it is there now either way
+                Ok(topic) | Err((topic, RDKafkaErrorCode::TopicAlreadyExists)) => {
+                    report.created.push(topic)
+                }
+                Err((topic, code)) => {
+                    return Err(format!("Failed to create topic {}: {}", topic, code))
+                }
+            }
+        }
+        report
+            .missing
+            .retain(|topic| !report.created.contains(topic));
+        Ok(report)
+    }
+
+    /// Compare the configured topics with the cluster without changing anything
+    pub async fn validate_topics(&self) -> Result<TopicReport, String> {
+        let observed = self.observe_topics().await?;
+        let mut report = TopicReport::default();
+        for topic in &self.topics {
+            match observed.get(topic) {
+                Some(actual) => report
+                    .drift
+                    .extend(self.settings.for_topic(topic).drift_from(topic, actual)),
+                None => report.missing.push(topic.clone()),
+            }
+        }
+        Ok(report)
+    }
+
+    async fn observe_topics(&self) -> Result<HashMap<String, ObservedTopic>, String> {
+        let metadata = self
+            .client
+            .inner()
+            .fetch_metadata(None, self.timeout)
+            .map_err(|e| format!("Failed to fetch cluster metadata: {}", e))?;
+        let mut observed: HashMap<String, ObservedTopic> = metadata
+            .topics()
+            .iter()
+            .filter(|t| t.error().is_none() && self.topics.iter().any(|n| n == t.name()))
+            .map(|t| {
+                let replication_factor = t
+                    .partitions()
+                    .iter()
+                    .map(|p| p.replicas().len() as i32)
+                    .min()
+                    .unwrap_or(0);
+                (
+                    t.name().to_string(),
+                    ObservedTopic {
+                        partitions: t.partitions().len() as i32,
+                        replication_factor,
+                        configs: HashMap::new(),
+                    },
+                )
+            })
+            .collect();
+        if observed.is_empty() {
+            return Ok(observed);
+        }
+
+        let names: Vec<String> = observed.keys().cloned().collect();
+        let specifiers: Vec<_> = names
+            .iter()
+            .map(|name| ResourceSpecifier::Topic(name))
+            .collect();
+        let resources = self
+            .client
+            .describe_configs(&specifiers, &self.options())
+            .await
+            .map_err(|e| format!("Failed to describe topic configs: {}", e))?;
+        for (name, resource) in names.iter().zip(resources) {
+            let resource =
+                resource.map_err(|code| format!("Failed to describe topic {}: {}", name, code))?;
+            if let Some(topic) = observed.get_mut(name) {
+                topic.configs = resource
+                    .entries
+                    .into_iter()
+                    .filter_map(|entry| entry.value.map(|value| (entry.name, value)))
+                    .collect();
+            }
+        }
+        Ok(observed)
+    }
+
+    fn options(&self) -> AdminOptions {
+        AdminOptions::new().operation_timeout(Some(self.timeout))
+    }
+}
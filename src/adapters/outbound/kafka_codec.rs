@@ -0,0 +1,43 @@
+use crate::common::{EnvelopeCodec, EventEnvelope, CONTENT_TYPE_HEADER};
+use rdkafka::message::{Header, Headers, OwnedHeaders};
+use rdkafka::Message;
+
+/// Content type of the raw esrs `StoreEvent`s `KafkaEventBus` publishes. They
+/// are not event envelopes, so consumers of envelopes pass over them.
+pub const STORE_EVENT_CONTENT_TYPE: &str = "application/vnd.esrs.store-event+json";
+
+/// Headers announcing `codec` on a produced record
+pub fn codec_headers(codec: EnvelopeCodec) -> OwnedHeaders {
+    OwnedHeaders::new().insert(Header {
+        key: CONTENT_TYPE_HEADER,
+        value: Some(codec.content_type()),
+    })
+}
+
+/// The record's content type header, if it has one
+pub fn content_type<M: Message>(message: &M) -> Option<&str> {
+    message
+        .headers()?
+        .iter()
+        .find(|header| header.key == CONTENT_TYPE_HEADER)
+        .and_then(|header| header.value)
+        .and_then(|value| std::str::from_utf8(value).ok())
+}
+
+/// Headers marking a record as an esrs `StoreEvent`
+pub fn store_event_headers() -> OwnedHeaders {
+    OwnedHeaders::new().insert(Header {
+        key: CONTENT_TYPE_HEADER,
+        value: Some(STORE_EVENT_CONTENT_TYPE),
+    })
+}
+
+/// Whether the record is an esrs `StoreEvent` rather than an envelope
+pub fn is_store_event<M: Message>(message: &M) -> bool {
+    content_type(message) == Some(STORE_EVENT_CONTENT_TYPE)
+}
+
+/// Decode the envelope in a record written with any codec
+pub fn decode_envelope<M: Message>(message: &M, payload: &[u8]) -> Result<EventEnvelope, String> {
+    EnvelopeCodec::decode_any(content_type(message), payload)
+}
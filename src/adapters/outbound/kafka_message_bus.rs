@@ -0,0 +1,244 @@
+use crate::adapters::outbound::kafka_codec::{
+    codec_headers, content_type, decode_envelope, is_store_event,
+};
+use crate::common::{
+    BusMessage, DeadLetter, EnvelopeCodec, EventEnvelope, MessageBus, MessageSubscription,
+};
+use crate::config::{KafkaConfig, PartitionStrategy};
+use async_trait::async_trait;
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::{Message, Offset, TopicPartitionList};
+use std::time::{Duration, Instant};
+
+/// Kafka implementation of the `MessageBus` port. Offsets are committed when a
+/// message is acknowledged, so unacknowledged messages are redelivered to the
+/// group after a restart.
+///
+/// Records are keyed by the `PartitionStrategy` and the producer is idempotent,
+/// so retries never reorder the records of one key. Envelopes are written with
+/// the configured codec and read in whichever codec they were written in.
+pub struct KafkaMessageBus {
+    producer: FutureProducer,
+    bootstrap_servers: String,
+    client_id: String,
+    offset_reset: String,
+    dead_letter_topic: Option<String>,
+    partitioning: PartitionStrategy,
+    codec: EnvelopeCodec,
+}
+
+impl KafkaMessageBus {
+    pub fn new(bootstrap_servers: &str, client_id: &str) -> Result<Self, String> {
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", bootstrap_servers)
+            .set("client.id", client_id)
+            .set("message.timeout.ms", "5000")
+            .set("enable.idempotence", "true")
+            .create()
+            .map_err(|e| format!("Failed to create Kafka producer: {}", e))?;
+
+        Ok(Self {
+            producer,
+            bootstrap_servers: bootstrap_servers.to_string(),
+            client_id: client_id.to_string(),
+            offset_reset: "latest".to_string(),
+            dead_letter_topic: None,
+            partitioning: PartitionStrategy::default(),
+            codec: EnvelopeCodec::default(),
+        })
+    }
+
+    pub fn from_config(config: &KafkaConfig) -> Result<Self, String> {
+        Ok(Self::new(&config.brokers.join(","), &config.client_id)?
+            .with_partition_strategy(config.partitioning)
+            .with_codec(config.codec))
+    }
+
+    pub fn with_codec(mut self, codec: EnvelopeCodec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    pub fn with_partition_strategy(mut self, partitioning: PartitionStrategy) -> Self {
+        self.partitioning = partitioning;
+        self
+    }
+
+    /// Where a consumer group without committed offsets starts reading:
+    /// `"latest"` (the default) or `"earliest"`.
+    pub fn with_offset_reset(mut self, offset_reset: &str) -> Self {
+        self.offset_reset = offset_reset.to_string();
+        self
+    }
+
+    /// Send records that are not event envelopes to `topic` instead of
+    /// skipping them
+    pub fn with_dead_letter_topic(mut self, topic: &str) -> Self {
+        self.dead_letter_topic = Some(topic.to_string());
+        self
+    }
+}
+
+#[async_trait]
+impl MessageBus for KafkaMessageBus {
+    async fn publish(&self, topic: &str, envelope: &EventEnvelope) -> Result<(), String> {
+        let key = self.partitioning.key_for(envelope);
+        let payload = self.codec.encode(envelope)?;
+
+        let record = FutureRecord::to(topic)
+            .key(&key)
+            .payload(&payload)
+            .headers(codec_headers(self.codec));
+        self.producer
+            .send(record, Duration::from_secs(5))
+            .await
+            .map_err(|(e, _)| format!("Failed to send event to Kafka: {}", e))?;
+        Ok(())
+    }
+
+    async fn subscribe(
+        &self,
+        topic: &str,
+        group_id: &str,
+    ) -> Result<Box<dyn MessageSubscription>, String> {
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", &self.bootstrap_servers)
+            .set("group.id", group_id)
+            .set("client.id", &self.client_id)
+            .set("enable.partition.eof", "false")
+            .set("session.timeout.ms", "6000")
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", &self.offset_reset)
+            .create()
+            .map_err(|e| format!("Failed to create Kafka consumer: {}", e))?;
+
+        consumer
+            .subscribe(&[topic])
+            .map_err(|e| format!("Failed to subscribe to topic {}: {}", topic, e))?;
+
+        // Partitions are assigned in the background; wait for them so messages
+        // published right after subscribing are not missed
+        let assign_deadline = Instant::now() + Duration::from_secs(3);
+        while Instant::now() < assign_deadline {
+            match consumer.assignment() {
+                Ok(assignment) if assignment.count() > 0 => break,
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Failed to query consumer assignment: {}", e),
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        Ok(Box::new(KafkaSubscription {
+            consumer,
+            producer: self.producer.clone(),
+            dead_letter_topic: self.dead_letter_topic.clone(),
+        }))
+    }
+}
+
+struct KafkaSubscription {
+    consumer: StreamConsumer,
+    producer: FutureProducer,
+    dead_letter_topic: Option<String>,
+}
+
+impl KafkaSubscription {
+    /// Move a poison record to the dead-letter topic and commit past it, so it
+    /// neither blocks the partition nor comes back after a restart
+    async fn dead_letter(&self, letter: DeadLetter) -> Result<(), String> {
+        let Some(topic) = &self.dead_letter_topic else {
+            tracing::warn!(
+                "Skipping message that is not an event envelope ({}/{}@{}): {}",
+                letter.source_topic,
+                letter.partition,
+                letter.offset,
+                letter.error
+            );
+            return Ok(());
+        };
+
+        let payload = serde_json::to_string(&letter.to_envelope()?)
+            .map_err(|e| format!("Failed to serialize dead letter: {}", e))?;
+        let key = format!("DeadLetter:{}", letter.source_topic);
+        self.producer
+            .send(
+                FutureRecord::to(topic).key(&key).payload(&payload),
+                Duration::from_secs(5),
+            )
+            .await
+            .map_err(|(e, _)| format!("Failed to send dead letter to {}: {}", topic, e))?;
+
+        let mut offsets = TopicPartitionList::new();
+        offsets
+            .add_partition_offset(
+                &letter.source_topic,
+                letter.partition,
+                Offset::Offset(letter.offset + 1),
+            )
+            .map_err(|e| format!("Failed to build offset list: {}", e))?;
+        self.consumer
+            .commit(&offsets, CommitMode::Async)
+            .map_err(|e| format!("Failed to commit offset: {}", e))
+    }
+}
+
+#[async_trait]
+impl MessageSubscription for KafkaSubscription {
+    async fn next(&mut self) -> Result<Option<BusMessage>, String> {
+        loop {
+            let message = self
+                .consumer
+                .recv()
+                .await
+                .map_err(|e| format!("Kafka receive error: {}", e))?;
+            let Some(payload) = message.payload() else {
+                // Tombstone
+                continue;
+            };
+            if is_store_event(&message) {
+                // Published by the esrs bus onto a shared topic; not a message for this bus
+                continue;
+            }
+            match decode_envelope(&message, payload) {
+                Ok(envelope) => {
+                    return Ok(Some(BusMessage {
+                        topic: message.topic().to_string(),
+                        partition: message.partition(),
+                        offset: message.offset(),
+                        envelope,
+                    }))
+                }
+                Err(e) => {
+                    let letter = DeadLetter::poison(
+                        message.topic(),
+                        message.partition(),
+                        message.offset(),
+                        message
+                            .key()
+                            .map(|key| String::from_utf8_lossy(key).into_owned()),
+                        payload,
+                        content_type(&message),
+                        &e.to_string(),
+                    );
+                    self.dead_letter(letter).await?;
+                }
+            }
+        }
+    }
+
+    async fn ack(&mut self, message: &BusMessage) -> Result<(), String> {
+        let mut offsets = TopicPartitionList::new();
+        offsets
+            .add_partition_offset(
+                &message.topic,
+                message.partition,
+                Offset::Offset(message.offset + 1),
+            )
+            .map_err(|e| format!("Failed to build offset list: {}", e))?;
+        self.consumer
+            .commit(&offsets, CommitMode::Async)
+            .map_err(|e| format!("Failed to commit offset: {}", e))
+    }
+}
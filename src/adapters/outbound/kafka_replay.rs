@@ -0,0 +1,245 @@
+use crate::adapters::outbound::kafka_codec::{decode_envelope, is_store_event};
+use crate::common::EventEnvelope;
+use chrono::{DateTime, Utc};
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{BaseConsumer, Consumer};
+use rdkafka::{Message, Offset, TopicPartitionList};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Reads a topic from the start of the log up to its current high watermark.
+///
+/// Partitions are assigned directly rather than through a subscription, so a
+/// replay never joins a consumer group and never commits offsets. Records that
+/// were produced after the replay started are not returned.
+#[derive(Clone)]
+pub struct KafkaReplayReader {
+    bootstrap_servers: String,
+    client_id: String,
+    timeout: Duration,
+}
+
+/// Which records of the topic a replay returns
+#[derive(Debug, Clone, Default)]
+pub struct ReplayFilter {
+    /// Only records keyed for this aggregate id
+    pub aggregate_id: Option<String>,
+    /// Only records of this event type
+    pub event_type: Option<String>,
+    /// Skip records that occurred before this instant. Partitions are seeked
+    /// with the broker's time index, so older segments are not read at all.
+    pub from_timestamp: Option<DateTime<Utc>>,
+}
+
+impl KafkaReplayReader {
+    pub fn new(bootstrap_servers: &str, client_id: &str) -> Self {
+        Self {
+            bootstrap_servers: bootstrap_servers.to_string(),
+            client_id: client_id.to_string(),
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Upper bound for a whole replay, including metadata lookups
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Replay `topic` and return the matching events in offset order per
+    /// partition, partitions in ascending order. A topic that does not exist
+    /// replays as empty.
+    pub async fn replay(
+        &self,
+        topic: &str,
+        filter: ReplayFilter,
+    ) -> Result<Vec<EventEnvelope>, String> {
+        let reader = self.clone();
+        let topic = topic.to_string();
+        tokio::task::spawn_blocking(move || reader.replay_blocking(&topic, &filter))
+            .await
+            .map_err(|e| format!("Replay task failed: {}", e))?
+    }
+
+    fn replay_blocking(
+        &self,
+        topic: &str,
+        filter: &ReplayFilter,
+    ) -> Result<Vec<EventEnvelope>, String> {
+        let deadline = Instant::now() + self.timeout;
+        // librdkafka wants a group id even for assigned partitions; it is never
+        // joined because we neither subscribe nor commit.
+        let consumer: BaseConsumer = ClientConfig::new()
+            .set("bootstrap.servers", &self.bootstrap_servers)
+            .set("client.id", &self.client_id)
+            .set("group.id", format!("{}-replay", self.client_id))
+            .set("enable.auto.commit", "false")
+            .set("enable.auto.offset.store", "false")
+            .set("enable.partition.eof", "false")
+            .set("allow.auto.create.topics", "false")
+            .create()
+            .map_err(|e| format!("Failed to create Kafka replay consumer: {}", e))?;
+
+        let metadata = consumer
+            .fetch_metadata(Some(topic), self.timeout)
+            .map_err(|e| format!("Failed to fetch metadata for {}: {}", topic, e))?;
+        let partitions: Vec<i32> = metadata
+            .topics()
+            .iter()
+            .filter(|t| t.name() == topic && t.error().is_none())
+            .flat_map(|t| t.partitions().iter().map(|p| p.id()))
+            .collect();
+
+        // Fix the end of the replay before reading anything
+        let mut start = TopicPartitionList::new();
+        let mut high_watermarks = HashMap::new();
+        for partition in partitions {
+            let (low, high) = consumer
+                .fetch_watermarks(topic, partition, self.timeout)
+                .map_err(|e| {
+                    format!(
+                        "Failed to fetch watermarks for {}/{}: {}",
+                        topic, partition, e
+                    )
+                })?;
+            if high > low {
+                high_watermarks.insert(partition, high);
+                let offset = match filter.from_timestamp {
+                    Some(from) => Offset::Offset(from.timestamp_millis()),
+                    None => Offset::Offset(low),
+                };
+                start
+                    .add_partition_offset(topic, partition, offset)
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+        if high_watermarks.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if filter.from_timestamp.is_some() {
+            // offsets_for_times takes timestamps in place of offsets and
+            // answers with the first offset at or after each timestamp
+            let by_time = consumer
+                .offsets_for_times(start, self.timeout)
+                .map_err(|e| format!("Failed to look up offsets by time for {}: {}", topic, e))?;
+            start = TopicPartitionList::new();
+            for element in by_time.elements() {
+                if let Offset::Offset(offset) = element.offset() {
+                    start
+                        .add_partition_offset(topic, element.partition(), Offset::Offset(offset))
+                        .map_err(|e| e.to_string())?;
+                } else {
+                    // Nothing at or after the timestamp in this partition
+                    high_watermarks.remove(&element.partition());
+                }
+            }
+            if high_watermarks.is_empty() {
+                return Ok(Vec::new());
+            }
+        }
+
+        consumer
+            .assign(&start)
+            .map_err(|e| format!("Failed to assign partitions of {}: {}", topic, e))?;
+
+        let mut by_partition: HashMap<i32, Vec<EventEnvelope>> = HashMap::new();
+        while !high_watermarks.is_empty() {
+            if Instant::now() >= deadline {
+                return Err(format!(
+                    "Replay of {} timed out after {:?} with {} partitions unread",
+                    topic,
+                    self.timeout,
+                    high_watermarks.len()
+                ));
+            }
+
+            let Some(result) = consumer.poll(Duration::from_millis(100)) else {
+                // Control records and compacted-away offsets are never delivered,
+                // so a partition can be done without its last offset showing up
+                let position = consumer.position().map_err(|e| e.to_string())?;
+                for element in position.elements_for_topic(topic) {
+                    if let Offset::Offset(offset) = element.offset() {
+                        if high_watermarks
+                            .get(&element.partition())
+                            .is_some_and(|high| offset >= *high)
+                        {
+                            high_watermarks.remove(&element.partition());
+                        }
+                    }
+                }
+                continue;
+            };
+            let message = result.map_err(|e| format!("Failed to read {}: {}", topic, e))?;
+
+            let partition = message.partition();
+            let Some(high) = high_watermarks.get(&partition).copied() else {
+                continue;
+            };
+            if message.offset() + 1 >= high {
+                high_watermarks.remove(&partition);
+            }
+            if message.offset() >= high || !key_matches(message.key(), filter) {
+                continue;
+            }
+
+            let Some(payload) = message.payload() else {
+                // Tombstone left by compaction
+                continue;
+            };
+            if is_store_event(&message) {
+                continue;
+            }
+            let event: EventEnvelope = match decode_envelope(&message, payload) {
+                Ok(event) => event,
+                Err(e) => {
+                    tracing::warn!(
+                        "Skipping undecodable record {}/{}@{}: {}",
+                        topic,
+                        partition,
+                        message.offset(),
+                        e
+                    );
+                    continue;
+                }
+            };
+            if filter
+                .event_type
+                .as_ref()
+                .is_some_and(|event_type| event.event_type != *event_type)
+                || filter
+                    .aggregate_id
+                    .as_ref()
+                    .is_some_and(|id| event.aggregate_id != *id)
+                || filter
+                    .from_timestamp
+                    .is_some_and(|from| event.occurred_at < from)
+            {
+                continue;
+            }
+            by_partition.entry(partition).or_default().push(event);
+        }
+
+        let mut partitions: Vec<_> = by_partition.into_iter().collect();
+        partitions.sort_by_key(|(partition, _)| *partition);
+        Ok(partitions
+            .into_iter()
+            .flat_map(|(_, events)| events)
+            .collect())
+    }
+}
+
+/// Event store records are keyed `"<aggregate_id>"` or
+/// `"<aggregate_type>:<aggregate_id>"`, depending on the `PartitionStrategy`
+fn key_matches(key: Option<&[u8]>, filter: &ReplayFilter) -> bool {
+    let Some(aggregate_id) = &filter.aggregate_id else {
+        return true;
+    };
+    key.and_then(|key| std::str::from_utf8(key).ok())
+        .is_some_and(|key| {
+            key == aggregate_id
+                || key
+                    .split_once(':')
+                    .is_some_and(|(_, id)| id == aggregate_id)
+        })
+}
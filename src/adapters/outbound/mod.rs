@@ -2,21 +2,35 @@ pub mod buffered_logger;
 pub mod console_logger;
 pub mod file_logger;
 pub mod kafka;
+pub mod kafka_admin;
+pub mod kafka_codec;
+pub mod kafka_message_bus;
+pub mod kafka_replay;
 pub mod multi_logger;
 pub mod noop_logger;
 pub mod path_planning_data;
 pub mod postgres;
 pub mod postgres_graph_store;
+pub mod postgres_inbox;
+pub mod postgres_key_store;
+pub mod postgres_plan_cache;
 
 pub use buffered_logger::*;
 pub use console_logger::*;
 pub use file_logger::*;
 pub use kafka::*;
+pub use kafka_admin::*;
+pub use kafka_codec::*;
+pub use kafka_message_bus::*;
+pub use kafka_replay::*;
 pub use multi_logger::*;
 pub use noop_logger::*;
 pub use path_planning_data::*;
 pub use postgres::*;
 pub use postgres_graph_store::*;
+pub use postgres_inbox::*;
+pub use postgres_key_store::*;
+pub use postgres_plan_cache::*;
 
 // ESRS migration adapters
 #[cfg(feature = "esrs_migration")]
@@ -11,16 +11,18 @@ pub struct PostgresSnapshotStore {
 impl PostgresSnapshotStore {
     pub async fn new(config: PostgresConfig) -> Result<Self, String> {
         let mut pg_config = Config::new();
-        pg_config.host = Some(config.host);
+        pg_config.host = Some(config.host.clone());
         pg_config.port = Some(config.port);
-        pg_config.dbname = Some(config.database);
-        pg_config.user = Some(config.username);
-        pg_config.password = Some(config.password);
+        pg_config.dbname = Some(config.database.clone());
+        pg_config.user = Some(config.username.clone());
+        pg_config.password = Some(config.password.clone());
+        pg_config.options = config.connection_options();
 
         let pool = pg_config
             .create_pool(Some(Runtime::Tokio1), NoTls)
             .map_err(|e| format!("Failed to create PostgreSQL pool: {}", e))?;
 
+        create_tenant_schema(&pool, &config).await?;
         let store = Self { pool };
 
         // Initialize database schema
@@ -47,6 +49,9 @@ impl PostgresSnapshotStore {
                 UNIQUE(aggregate_id, aggregate_version)
             );
 
+            -- Tables from before hash chaining lack the column
+            ALTER TABLE snapshots ADD COLUMN IF NOT EXISTS chain_head TEXT;
+
             CREATE INDEX IF NOT EXISTS idx_snapshots_aggregate_id 
             ON snapshots(aggregate_id);
             
@@ -66,6 +71,22 @@ impl PostgresSnapshotStore {
     }
 }
 
+/// Create the schema `config` scopes connections to, if any. Run it once
+/// after creating a pool with `connection_options`, before creating tables.
+pub async fn create_tenant_schema(pool: &Pool, config: &PostgresConfig) -> Result<(), String> {
+    let Some(schema) = &config.schema else {
+        return Ok(());
+    };
+    let client = pool
+        .get()
+        .await
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    client
+        .batch_execute(&format!("CREATE SCHEMA IF NOT EXISTS \"{}\"", schema))
+        .await
+        .map_err(|e| format!("Failed to create schema {}: {}", schema, e))
+}
+
 #[async_trait]
 impl SnapshotStore for PostgresSnapshotStore {
     async fn save_snapshot(&self, snapshot: Snapshot) -> Result<(), String> {
@@ -76,11 +97,12 @@ impl SnapshotStore for PostgresSnapshotStore {
             .map_err(|e| format!("Failed to get database connection: {}", e))?;
 
         let stmt = client.prepare(
-            "INSERT INTO snapshots (snapshot_id, aggregate_id, aggregate_type, aggregate_version, snapshot_data, created_at) 
-             VALUES ($1, $2, $3, $4, $5, $6)
+            "INSERT INTO snapshots (snapshot_id, aggregate_id, aggregate_type, aggregate_version, snapshot_data, created_at, chain_head) 
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
              ON CONFLICT (aggregate_id, aggregate_version) DO UPDATE SET
              snapshot_data = EXCLUDED.snapshot_data,
-             created_at = EXCLUDED.created_at"
+             created_at = EXCLUDED.created_at,
+             chain_head = EXCLUDED.chain_head"
         ).await
         .map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
@@ -94,6 +116,7 @@ impl SnapshotStore for PostgresSnapshotStore {
                     &(snapshot.aggregate_version as i64),
                     &snapshot.snapshot_data,
                     &snapshot.created_at,
+                    &snapshot.chain_head,
                 ],
             )
             .await
@@ -114,9 +137,10 @@ impl SnapshotStore for PostgresSnapshotStore {
             .map_err(|e| format!("Failed to get database connection: {}", e))?;
 
         let row = if let Some(max_ver) = max_version {
-            let max_ver_i64 = max_ver as i64;
+            // Versions past what BIGINT holds cover every snapshot
+            let max_ver_i64 = i64::try_from(max_ver).unwrap_or(i64::MAX);
             client.query_opt(
-                "SELECT snapshot_id, aggregate_id, aggregate_type, aggregate_version, snapshot_data, created_at 
+                "SELECT snapshot_id, aggregate_id, aggregate_type, aggregate_version, snapshot_data, created_at, chain_head 
                  FROM snapshots 
                  WHERE aggregate_id = $1 AND aggregate_version <= $2 
                  ORDER BY aggregate_version DESC 
@@ -125,7 +149,7 @@ impl SnapshotStore for PostgresSnapshotStore {
             ).await
         } else {
             client.query_opt(
-                "SELECT snapshot_id, aggregate_id, aggregate_type, aggregate_version, snapshot_data, created_at 
+                "SELECT snapshot_id, aggregate_id, aggregate_type, aggregate_version, snapshot_data, created_at, chain_head 
                  FROM snapshots 
                  WHERE aggregate_id = $1 
                  ORDER BY aggregate_version DESC 
@@ -142,6 +166,7 @@ impl SnapshotStore for PostgresSnapshotStore {
                 aggregate_version: row.get::<_, i64>(3) as u64,
                 snapshot_data: row.get(4),
                 created_at: row.get(5),
+                chain_head: row.get(6),
             }))
         } else {
             Ok(None)
@@ -169,4 +194,35 @@ impl SnapshotStore for PostgresSnapshotStore {
 
         Ok(())
     }
+
+    async fn load_all_snapshots(&self) -> Result<Vec<Snapshot>, String> {
+        let client = self
+            .pool
+            .get()
+            .await
+            .map_err(|e| format!("Failed to get database connection: {}", e))?;
+
+        let rows = client
+            .query(
+                "SELECT snapshot_id, aggregate_id, aggregate_type, aggregate_version, snapshot_data, created_at, chain_head 
+                 FROM snapshots 
+                 ORDER BY aggregate_id, aggregate_version",
+                &[],
+            )
+            .await
+            .map_err(|e| format!("Failed to load snapshots: {}", e))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| Snapshot {
+                snapshot_id: row.get(0),
+                aggregate_id: row.get(1),
+                aggregate_type: row.get(2),
+                aggregate_version: row.get::<_, i64>(3) as u64,
+                snapshot_data: row.get(4),
+                created_at: row.get(5),
+                chain_head: row.get(6),
+            })
+            .collect())
+    }
 }
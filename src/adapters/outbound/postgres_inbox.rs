@@ -0,0 +1,87 @@
+use crate::adapters::outbound::create_tenant_schema;
+use crate::common::Inbox;
+use crate::config::PostgresConfig;
+use async_trait::async_trait;
+use deadpool_postgres::{Client, Config as DeadPoolConfig, Pool, Runtime};
+use tokio_postgres::NoTls;
+use uuid::Uuid;
+
+/// Inbox in the `processed_events` table, so it survives restarts
+pub struct PostgresInbox {
+    pool: Pool,
+}
+
+impl PostgresInbox {
+    /// Inbox in `pool`, creating its table if needed
+    pub async fn new(pool: Pool) -> Result<Self, String> {
+        let inbox = Self { pool };
+        inbox.initialize_schema().await?;
+        Ok(inbox)
+    }
+
+    /// Inbox of the configured database
+    pub async fn from_config(config: &PostgresConfig) -> Result<Self, String> {
+        let mut dp_cfg = DeadPoolConfig::new();
+        dp_cfg.host = Some(config.host.clone());
+        dp_cfg.port = Some(config.port);
+        dp_cfg.user = Some(config.username.clone());
+        dp_cfg.password = Some(config.password.clone());
+        dp_cfg.dbname = Some(config.database.clone());
+        dp_cfg.options = config.connection_options();
+        let pool = dp_cfg
+            .create_pool(Some(Runtime::Tokio1), NoTls)
+            .map_err(|e| format!("Failed to create Postgres pool: {}", e))?;
+        create_tenant_schema(&pool, config).await?;
+        Self::new(pool).await
+    }
+
+    async fn initialize_schema(&self) -> Result<(), String> {
+        self.get_client()
+            .await?
+            .batch_execute(
+                "CREATE TABLE IF NOT EXISTS processed_events (
+                consumer TEXT NOT NULL,
+                event_id UUID NOT NULL,
+                processed_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                PRIMARY KEY (consumer, event_id)
+            );",
+            )
+            .await
+            .map_err(|e| format!("pg create table: {}", e))
+    }
+
+    async fn get_client(&self) -> Result<Client, String> {
+        self.pool
+            .get()
+            .await
+            .map_err(|e| format!("deadpool get client: {}", e))
+    }
+}
+
+#[async_trait]
+impl Inbox for PostgresInbox {
+    async fn is_processed(&self, consumer: &str, event_id: Uuid) -> Result<bool, String> {
+        let client = self.get_client().await?;
+        let row = client
+            .query_opt(
+                "SELECT 1 FROM processed_events WHERE consumer = $1 AND event_id = $2",
+                &[&consumer, &event_id],
+            )
+            .await
+            .map_err(|e| format!("pg query: {}", e))?;
+        Ok(row.is_some())
+    }
+
+    async fn mark_processed(&self, consumer: &str, event_id: Uuid) -> Result<(), String> {
+        let client = self.get_client().await?;
+        client
+            .execute(
+                "INSERT INTO processed_events (consumer, event_id) VALUES ($1, $2)
+                 ON CONFLICT (consumer, event_id) DO NOTHING",
+                &[&consumer, &event_id],
+            )
+            .await
+            .map_err(|e| format!("pg insert: {}", e))?;
+        Ok(())
+    }
+}
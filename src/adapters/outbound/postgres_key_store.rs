@@ -0,0 +1,112 @@
+use crate::adapters::outbound::create_tenant_schema;
+use crate::common::{KeyStore, SubjectKey};
+use crate::config::PostgresConfig;
+use async_trait::async_trait;
+use deadpool_postgres::{Client, Config as DeadPoolConfig, Pool, Runtime};
+use tokio_postgres::NoTls;
+use uuid::Uuid;
+
+/// Key store in the `subject_keys` table. Keep it out of event backups, or
+/// a restored backup brings forgotten subjects back.
+pub struct PostgresKeyStore {
+    pool: Pool,
+}
+
+impl PostgresKeyStore {
+    /// Key store in `pool`, creating its table if needed
+    pub async fn new(pool: Pool) -> Result<Self, String> {
+        let key_store = Self { pool };
+        key_store.initialize_schema().await?;
+        Ok(key_store)
+    }
+
+    /// Key store of the configured database, shared by every process that
+    /// writes personal data and by `forget_subject`
+    pub async fn from_config(config: &PostgresConfig) -> Result<Self, String> {
+        let mut dp_cfg = DeadPoolConfig::new();
+        dp_cfg.host = Some(config.host.clone());
+        dp_cfg.port = Some(config.port);
+        dp_cfg.user = Some(config.username.clone());
+        dp_cfg.password = Some(config.password.clone());
+        dp_cfg.dbname = Some(config.database.clone());
+        dp_cfg.options = config.connection_options();
+        let pool = dp_cfg
+            .create_pool(Some(Runtime::Tokio1), NoTls)
+            .map_err(|e| format!("Failed to create Postgres pool: {}", e))?;
+        create_tenant_schema(&pool, config).await?;
+        Self::new(pool).await
+    }
+
+    async fn initialize_schema(&self) -> Result<(), String> {
+        self.get_client()
+            .await?
+            .batch_execute(
+                "CREATE TABLE IF NOT EXISTS subject_keys (
+                subject TEXT PRIMARY KEY,
+                key_id UUID NOT NULL UNIQUE,
+                key BYTEA NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+            );",
+            )
+            .await
+            .map_err(|e| format!("pg create table: {}", e))
+    }
+
+    async fn get_client(&self) -> Result<Client, String> {
+        self.pool
+            .get()
+            .await
+            .map_err(|e| format!("deadpool get client: {}", e))
+    }
+}
+
+fn key_bytes(key: Vec<u8>) -> Result<[u8; 32], String> {
+    key.try_into()
+        .map_err(|key: Vec<u8>| format!("subject key has {} bytes, expected 32", key.len()))
+}
+
+#[async_trait]
+impl KeyStore for PostgresKeyStore {
+    async fn key_for_subject(&self, subject: &str) -> Result<SubjectKey, String> {
+        let client = self.get_client().await?;
+        let key: [u8; 32] = rand::random();
+        // A concurrent writer may have created the key first; keep theirs
+        client
+            .execute(
+                "INSERT INTO subject_keys (subject, key_id, key) VALUES ($1, $2, $3)
+                 ON CONFLICT (subject) DO NOTHING",
+                &[&subject, &Uuid::new_v4(), &key.as_slice()],
+            )
+            .await
+            .map_err(|e| format!("pg insert: {}", e))?;
+        let row = client
+            .query_one(
+                "SELECT key_id, key FROM subject_keys WHERE subject = $1",
+                &[&subject],
+            )
+            .await
+            .map_err(|e| format!("pg query: {}", e))?;
+        Ok(SubjectKey {
+            key_id: row.get(0),
+            key: key_bytes(row.get(1))?,
+        })
+    }
+
+    async fn key_by_id(&self, key_id: Uuid) -> Result<Option<[u8; 32]>, String> {
+        let client = self.get_client().await?;
+        let row = client
+            .query_opt("SELECT key FROM subject_keys WHERE key_id = $1", &[&key_id])
+            .await
+            .map_err(|e| format!("pg query: {}", e))?;
+        row.map(|row| key_bytes(row.get(0))).transpose()
+    }
+
+    async fn forget_subject(&self, subject: &str) -> Result<bool, String> {
+        let client = self.get_client().await?;
+        let deleted = client
+            .execute("DELETE FROM subject_keys WHERE subject = $1", &[&subject])
+            .await
+            .map_err(|e| format!("pg delete: {}", e))?;
+        Ok(deleted > 0)
+    }
+}
@@ -0,0 +1,121 @@
+use crate::adapters::outbound::create_tenant_schema;
+use crate::common::DomainError;
+use crate::common::DomainResult;
+use crate::config::PostgresConfig;
+use crate::domains::path_planning::aggregate::Position2D;
+use crate::domains::path_planning::plan_cache::PlanCacheKey;
+use crate::domains::path_planning::ports::PlanCache;
+use async_trait::async_trait;
+use deadpool_postgres::{Client, Config as DeadPoolConfig, Pool, Runtime};
+use serde_json::Value as JsonValue;
+use tokio_postgres::NoTls;
+
+/// Plan cache shared by all workers through Postgres
+pub struct PostgresPlanCache {
+    pool: Pool,
+}
+
+impl PostgresPlanCache {
+    /// Plan cache in `pool`, creating its table if needed
+    pub async fn new(pool: Pool) -> Result<Self, String> {
+        let cache = Self { pool };
+        cache.initialize_schema().await?;
+        Ok(cache)
+    }
+
+    /// Plan cache of the configured database, shared by every worker; the app's
+    /// graph store clears it when a graph changes
+    pub async fn from_config(config: &PostgresConfig) -> Result<Self, String> {
+        let mut dp_cfg = DeadPoolConfig::new();
+        dp_cfg.host = Some(config.host.clone());
+        dp_cfg.port = Some(config.port);
+        dp_cfg.user = Some(config.username.clone());
+        dp_cfg.password = Some(config.password.clone());
+        dp_cfg.dbname = Some(config.database.clone());
+        dp_cfg.options = config.connection_options();
+        let pool = dp_cfg
+            .create_pool(Some(Runtime::Tokio1), NoTls)
+            .map_err(|e| format!("Failed to create Postgres pool: {}", e))?;
+        create_tenant_schema(&pool, config).await?;
+        Self::new(pool).await
+    }
+
+    async fn initialize_schema(&self) -> Result<(), String> {
+        self.pool
+            .get()
+            .await
+            .map_err(|e| format!("deadpool get client: {}", e))?
+            .batch_execute(
+                "CREATE TABLE IF NOT EXISTS plan_cache (
+                cache_key TEXT PRIMARY KEY,
+                map_name TEXT NOT NULL,
+                graph_version TEXT NOT NULL,
+                cache_entry JSONB NOT NULL,
+                waypoints JSONB NOT NULL,
+                created_at TIMESTAMPTZ DEFAULT now()
+            );
+            CREATE INDEX IF NOT EXISTS idx_plan_cache_map_name ON plan_cache(map_name);",
+            )
+            .await
+            .map_err(|e| format!("pg create table: {}", e))
+    }
+
+    async fn get_client(&self) -> Result<Client, DomainError> {
+        self.pool
+            .get()
+            .await
+            .map_err(|e| DomainError::InfrastructureError(format!("deadpool get client: {}", e)))
+    }
+}
+
+#[async_trait]
+impl PlanCache for PostgresPlanCache {
+    async fn get(&self, key: &PlanCacheKey) -> DomainResult<Option<Vec<Position2D>>> {
+        let client = self.get_client().await?;
+        let row = client
+            .query_opt(
+                "SELECT waypoints FROM plan_cache WHERE cache_key = $1",
+                &[&key.digest()],
+            )
+            .await
+            .map_err(|e| DomainError::InfrastructureError(format!("pg query: {}", e)))?;
+        match row {
+            Some(row) => {
+                let waypoints: JsonValue = row.get(0);
+                Ok(Some(serde_json::from_value(waypoints)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn put(&self, key: &PlanCacheKey, waypoints: &[Position2D]) -> DomainResult<()> {
+        let client = self.get_client().await?;
+        let entry = serde_json::to_value(key)?;
+        let waypoints = serde_json::to_value(waypoints)?;
+        client
+            .execute(
+                "INSERT INTO plan_cache (cache_key, map_name, graph_version, cache_entry, waypoints)
+                 VALUES ($1, $2, $3, $4, $5)
+                 ON CONFLICT (cache_key) DO UPDATE SET waypoints = EXCLUDED.waypoints, created_at = now()",
+                &[
+                    &key.digest(),
+                    &key.map_name,
+                    &key.graph_version,
+                    &entry,
+                    &waypoints,
+                ],
+            )
+            .await
+            .map_err(|e| DomainError::InfrastructureError(format!("pg insert: {}", e)))?;
+        Ok(())
+    }
+
+    async fn invalidate_map(&self, map_name: &str) -> DomainResult<usize> {
+        let client = self.get_client().await?;
+        let removed = client
+            .execute("DELETE FROM plan_cache WHERE map_name = $1", &[&map_name])
+            .await
+            .map_err(|e| DomainError::InfrastructureError(format!("pg delete: {}", e)))?;
+        Ok(removed as usize)
+    }
+}
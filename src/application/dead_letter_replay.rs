@@ -0,0 +1,50 @@
+use crate::common::{DeadLetter, MessageBus};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Outcome of `DeadLetterReplayer::replay`
+#[derive(Debug, Clone, Default)]
+pub struct DeadLetterReplayReport {
+    pub replayed: Vec<Uuid>,
+    /// Letters left alone, with the reason
+    pub rejected: Vec<(Uuid, String)>,
+}
+
+/// Re-injects dead letters into the topics they came from
+pub struct DeadLetterReplayer {
+    bus: Arc<dyn MessageBus>,
+    logger: crate::domains::DynLogger,
+}
+
+impl DeadLetterReplayer {
+    pub fn new(bus: Arc<dyn MessageBus>, logger: crate::domains::DynLogger) -> Self {
+        Self { bus, logger }
+    }
+
+    /// Publish each letter's payload to its source topic. Payloads that are
+    /// still not event envelopes are rejected rather than sent on as poison.
+    pub async fn replay(&self, letters: &[DeadLetter]) -> DeadLetterReplayReport {
+        let mut report = DeadLetterReplayReport::default();
+        for letter in letters {
+            let result = match letter.envelope() {
+                Ok(envelope) => self.bus.publish(&letter.source_topic, &envelope).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => {
+                    self.logger.info(&format!(
+                        "Replayed dead letter {} to {}",
+                        letter.id, letter.source_topic
+                    ));
+                    report.replayed.push(letter.id);
+                }
+                Err(e) => {
+                    self.logger
+                        .warn(&format!("Not replaying dead letter {}: {}", letter.id, e));
+                    report.rejected.push((letter.id, e));
+                }
+            }
+        }
+        report
+    }
+}
@@ -0,0 +1,179 @@
+use crate::common::{BusMessage, MessageSubscription};
+use crate::config::PartitionStrategy;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Handles the messages of a subscription in order per partition key, and
+/// messages with different keys in parallel.
+///
+/// Each key is pinned to one of `lanes` tasks that handle their messages one at
+/// a time. A message is acknowledged once it and every earlier message of its
+/// partition are handled, so a restart redelivers rather than skips work.
+/// Acknowledgements are sent between reads, so the last few handled messages
+/// stay unacknowledged until the next one arrives; pair handlers with an
+/// `Inbox` to recognise those redeliveries.
+pub struct KeyedDispatcher {
+    lanes: usize,
+    lane_capacity: usize,
+    partitioning: PartitionStrategy,
+    logger: crate::domains::DynLogger,
+}
+
+type Completion = (BusMessage, Result<(), String>);
+
+impl KeyedDispatcher {
+    pub fn new(lanes: usize, logger: crate::domains::DynLogger) -> Self {
+        Self {
+            lanes: lanes.max(1),
+            lane_capacity: 64,
+            partitioning: PartitionStrategy::default(),
+            logger,
+        }
+    }
+
+    /// Messages a lane buffers before reading from the subscription pauses
+    pub fn with_lane_capacity(mut self, lane_capacity: usize) -> Self {
+        self.lane_capacity = lane_capacity.max(1);
+        self
+    }
+
+    /// Which messages must stay in order; use the producers' strategy
+    pub fn with_partition_strategy(mut self, partitioning: PartitionStrategy) -> Self {
+        self.partitioning = partitioning;
+        self
+    }
+
+    fn lane_for(&self, message: &BusMessage) -> usize {
+        let mut hasher = DefaultHasher::new();
+        self.partitioning
+            .key_for(&message.envelope)
+            .hash(&mut hasher);
+        (hasher.finish() % self.lanes as u64) as usize
+    }
+
+    /// Dispatch until the subscription ends or a handler fails. A failed
+    /// message is not acknowledged; handle retries and dead-lettering inside
+    /// `handler`.
+    pub async fn run<H, Fut>(
+        &self,
+        mut subscription: Box<dyn MessageSubscription>,
+        handler: H,
+    ) -> Result<(), String>
+    where
+        H: Fn(BusMessage) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+        let (done_tx, mut done_rx) = mpsc::unbounded_channel::<Completion>();
+        let mut lanes = Vec::with_capacity(self.lanes);
+        let mut tasks = Vec::with_capacity(self.lanes);
+        for _ in 0..self.lanes {
+            let (tx, mut rx) = mpsc::channel::<BusMessage>(self.lane_capacity);
+            let handler = handler.clone();
+            let done_tx = done_tx.clone();
+            tasks.push(tokio::spawn(async move {
+                while let Some(message) = rx.recv().await {
+                    let result = handler(message.clone()).await;
+                    let failed = result.is_err();
+                    if done_tx.send((message, result)).is_err() || failed {
+                        break;
+                    }
+                }
+            }));
+            lanes.push(tx);
+        }
+        drop(done_tx);
+
+        let mut tracker = AckTracker::default();
+        let read = async {
+            loop {
+                while let Ok(completion) = done_rx.try_recv() {
+                    self.complete(&mut tracker, subscription.as_mut(), completion)
+                        .await?;
+                }
+                let Some(message) = subscription.next().await? else {
+                    return Ok(());
+                };
+                tracker.dispatched(&message);
+                let lane = self.lane_for(&message);
+                if lanes[lane].send(message).await.is_err() {
+                    // The lane stopped after a failure, which is reported below
+                    return Ok(());
+                }
+            }
+        }
+        .await;
+
+        // Let the lanes finish and acknowledge what they handled
+        drop(lanes);
+        let mut result = read;
+        while let Some(completion) = done_rx.recv().await {
+            if let Err(e) = self
+                .complete(&mut tracker, subscription.as_mut(), completion)
+                .await
+            {
+                match result {
+                    Ok(()) => result = Err(e),
+                    Err(_) => self.logger.warn(&e),
+                }
+            }
+        }
+        for task in tasks {
+            let _ = task.await;
+        }
+        result
+    }
+
+    async fn complete(
+        &self,
+        tracker: &mut AckTracker,
+        subscription: &mut dyn MessageSubscription,
+        (message, result): Completion,
+    ) -> Result<(), String> {
+        if let Err(e) = result {
+            return Err(format!(
+                "Failed to handle {}/{}@{}: {}",
+                message.topic, message.partition, message.offset, e
+            ));
+        }
+        if let Some(ackable) = tracker.handled(message) {
+            subscription.ack(&ackable).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Offsets dispatched per partition, with the handled ones filled in
+#[derive(Default)]
+struct AckTracker {
+    partitions: HashMap<(String, i32), BTreeMap<i64, Option<BusMessage>>>,
+}
+
+impl AckTracker {
+    fn dispatched(&mut self, message: &BusMessage) {
+        self.partitions
+            .entry((message.topic.clone(), message.partition))
+            .or_default()
+            .insert(message.offset, None);
+    }
+
+    /// The latest message that can be acknowledged now that `message` is handled
+    fn handled(&mut self, message: BusMessage) -> Option<BusMessage> {
+        let offsets = self
+            .partitions
+            .get_mut(&(message.topic.clone(), message.partition))?;
+        offsets.insert(message.offset, Some(message));
+        let mut ackable = None;
+        while let Some(entry) = offsets.first_entry() {
+            if entry.get().is_none() {
+                break;
+            }
+            ackable = entry.remove();
+        }
+        ackable
+    }
+}
@@ -1,16 +1,26 @@
 pub mod pathplan_client;
 
 pub use pathplan_client::*;
+pub mod dead_letter_replay;
 pub mod dynamics_service;
 pub mod gui_service;
+pub mod keyed_dispatcher;
 pub mod kinematic_agent_service;
 pub mod logical_agent_service;
+pub mod outbox_relay;
+pub mod path_planning_planner;
 pub mod path_planning_service;
+pub mod path_planning_worker;
 pub mod technical_agent_service;
 
+pub use dead_letter_replay::*;
 pub use dynamics_service::*;
 pub use gui_service::*;
+pub use keyed_dispatcher::*;
 pub use kinematic_agent_service::*;
 pub use logical_agent_service::*;
+pub use outbox_relay::*;
+pub use path_planning_planner::*;
 pub use path_planning_service::*;
+pub use path_planning_worker::*;
 pub use technical_agent_service::*;
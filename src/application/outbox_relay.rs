@@ -0,0 +1,99 @@
+use crate::common::{MessageBus, Outbox};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Publishes outbox messages to the bus and marks them sent.
+///
+/// Messages go out one at a time in outbox order and each is marked sent right
+/// after the bus accepts it, so stopping or crashing the relay loses nothing.
+/// Delivery is at least once: a crash between publishing and marking sends
+/// that one message again on restart. It keeps its `event_id`, so consumers
+/// with an inbox drop the repeat. Run a single relay per outbox.
+pub struct OutboxRelay {
+    outbox: Arc<dyn Outbox>,
+    bus: Arc<dyn MessageBus>,
+    logger: crate::domains::DynLogger,
+    batch_size: usize,
+    poll_interval: Duration,
+    max_backoff: Duration,
+}
+
+impl OutboxRelay {
+    pub fn new(
+        outbox: Arc<dyn Outbox>,
+        bus: Arc<dyn MessageBus>,
+        logger: crate::domains::DynLogger,
+    ) -> Self {
+        Self {
+            outbox,
+            bus,
+            logger,
+            batch_size: 100,
+            poll_interval: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// How long to wait when the outbox is empty, and the first retry delay
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Cap for the retry delay, which doubles after every failed attempt
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Publish one batch. Stops at the first failure so later messages never
+    /// overtake an earlier one; returns how many messages were sent.
+    pub async fn relay_batch(&self) -> Result<usize, String> {
+        let batch = self.outbox.pending(self.batch_size).await?;
+        let mut sent = 0;
+        for message in batch {
+            if let Err(e) = self.bus.publish(&message.topic, &message.envelope).await {
+                self.outbox.record_failure(message.id, &e).await?;
+                return Err(format!(
+                    "Failed to publish outbox message {} to {} (attempt {}): {}",
+                    message.id,
+                    message.topic,
+                    message.attempts + 1,
+                    e
+                ));
+            }
+            self.outbox.mark_sent(message.id).await?;
+            sent += 1;
+        }
+        Ok(sent)
+    }
+
+    /// Relay until the task is cancelled, backing off while publishing fails.
+    pub async fn run(&self) {
+        let mut delay = self.poll_interval;
+        loop {
+            match self.relay_batch().await {
+                Ok(sent) => {
+                    delay = self.poll_interval;
+                    if sent > 0 {
+                        self.logger
+                            .info(&format!("Relayed {} outbox messages", sent));
+                        continue;
+                    }
+                }
+                Err(e) => {
+                    self.logger.warn(&format!("{}; retrying in {:?}", e, delay));
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(self.max_backoff);
+                    continue;
+                }
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+}
@@ -0,0 +1,593 @@
+// Path planning planner service - assigns requested plans to registered workers
+use crate::adapters::inbound::inbox::InMemoryInbox;
+use crate::application::KeyedDispatcher;
+use crate::common::{
+    BusDeadLetterQueue, BusMessage, DeadLetter, DeadLetterQueue, DomainEvent, EventEnvelope,
+    EventMetadata, Inbox, MessageBus,
+};
+use crate::config::{KafkaTopics, PartitionStrategy};
+use crate::domains::path_planning::{
+    plan_legs, Orientation2D, PathPlanner, PathPlanningEvent, PlanningAlgorithm, Position2D,
+};
+use crate::domains::DynLogger;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{interval, Duration};
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct WorkerInfo {
+    pub worker_id: String,
+    pub capabilities: Vec<PlanningAlgorithm>,
+    pub status: WorkerAvailability,
+    pub last_heartbeat: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorkerAvailability {
+    Ready,
+    Busy { plan_id: String },
+    Offline,
+}
+
+/// Route that still has to be handed to a worker
+struct PlanRoute<'a> {
+    start_position: &'a Position2D,
+    destination_position: &'a Position2D,
+    start_orientation: &'a Orientation2D,
+    destination_orientation: &'a Orientation2D,
+}
+
+/// Consumes path planning events from the message bus, tracks worker
+/// availability and publishes `PlanAssigned` for every requested plan.
+pub struct PathPlanningPlannerService {
+    planner_id: String,
+    bus: Arc<dyn MessageBus>,
+    events_topic: String,
+    available_workers: HashMap<String, WorkerInfo>,
+    heartbeat_timeout: chrono::Duration,
+    logger: DynLogger,
+    dead_letters: Arc<dyn DeadLetterQueue>,
+    max_attempts: u32,
+    inbox: Arc<dyn Inbox>,
+    partitioning: PartitionStrategy,
+}
+
+/// A dispatched message and where its outcome goes
+type Dispatched = (BusMessage, oneshot::Sender<Result<(), String>>);
+
+impl PathPlanningPlannerService {
+    pub fn new(
+        planner_id: String,
+        bus: Arc<dyn MessageBus>,
+        topics: &KafkaTopics,
+        logger: DynLogger,
+    ) -> Self {
+        Self {
+            planner_id,
+            dead_letters: Arc::new(BusDeadLetterQueue::new(bus.clone(), &topics.dead_letters)),
+            max_attempts: 3,
+            inbox: Arc::new(InMemoryInbox::new()),
+            partitioning: PartitionStrategy::default(),
+            bus,
+            events_topic: topics.path_planning_events.clone(),
+            available_workers: HashMap::new(),
+            heartbeat_timeout: chrono::Duration::seconds(90), // 3 missed heartbeats
+            logger,
+        }
+    }
+
+    pub fn with_dead_letter_queue(mut self, dead_letters: Arc<dyn DeadLetterQueue>) -> Self {
+        self.dead_letters = dead_letters;
+        self
+    }
+
+    /// Where handled event ids are recorded. Use a durable inbox when the
+    /// planner keeps its id across restarts.
+    pub fn with_inbox(mut self, inbox: Arc<dyn Inbox>) -> Self {
+        self.inbox = inbox;
+        self
+    }
+
+    /// Processing attempts before an envelope is dead-lettered
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Which messages are handled in order; use the producers' strategy
+    pub fn with_partition_strategy(mut self, partitioning: PartitionStrategy) -> Self {
+        self.partitioning = partitioning;
+        self
+    }
+
+    pub fn workers(&self) -> &HashMap<String, WorkerInfo> {
+        &self.available_workers
+    }
+
+    /// Process events until the bus shuts down. Worker health is checked every minute.
+    ///
+    /// A `KeyedDispatcher` reads the subscription and acknowledges handled
+    /// messages; it hands each message to this loop, which owns the worker
+    /// table, so messages are handled one at a time.
+    pub async fn run(&mut self) -> Result<(), String> {
+        let subscription = self
+            .bus
+            .subscribe(&self.events_topic, &self.consumer_name())
+            .await?;
+        self.logger.info(&format!(
+            "🚀 Planner {} is consuming {}",
+            self.planner_id, self.events_topic
+        ));
+
+        let dispatcher =
+            KeyedDispatcher::new(1, self.logger.clone()).with_partition_strategy(self.partitioning);
+        let (dispatched_tx, mut dispatched) = mpsc::channel::<Dispatched>(1);
+        let dispatch = dispatcher.run(subscription, move |message| {
+            let dispatched_tx = dispatched_tx.clone();
+            async move {
+                let (done_tx, done) = oneshot::channel();
+                dispatched_tx
+                    .send((message, done_tx))
+                    .await
+                    .map_err(|_| "Planner stopped handling messages".to_string())?;
+                done.await
+                    .map_err(|_| "Planner dropped a message".to_string())?
+            }
+        });
+        tokio::pin!(dispatch);
+
+        let mut health_check_timer = interval(Duration::from_secs(60));
+        loop {
+            tokio::select! {
+                result = &mut dispatch => return result,
+                Some((message, done)) = dispatched.recv() => {
+                    let _ = done.send(self.process_message(&message).await);
+                }
+                _ = health_check_timer.tick() => {
+                    self.check_worker_health().await?;
+                    self.log_status();
+                }
+            }
+        }
+    }
+
+    fn consumer_name(&self) -> String {
+        format!("planner-{}", self.planner_id)
+    }
+
+    /// Handle `message` once: envelopes already in the inbox are skipped, and
+    /// an envelope is recorded there after it was handled. Dead-lettered
+    /// envelopes are not, so a replayed dead letter is handled again.
+    async fn process_message(&mut self, message: &BusMessage) -> Result<(), String> {
+        let consumer = self.consumer_name();
+        let event_id = message.envelope.event_id;
+        if self.inbox.is_processed(&consumer, event_id).await? {
+            self.logger.info(&format!(
+                "Skipping envelope {} that was already processed",
+                event_id
+            ));
+            return Ok(());
+        }
+        if !self.handle_with_retries(message).await? {
+            return Ok(());
+        }
+        self.inbox.mark_processed(&consumer, event_id).await
+    }
+
+    /// Handle `message`, retrying failures, and dead-letter it once
+    /// `max_attempts` are used up. `false` when it was dead-lettered.
+    async fn handle_with_retries(&mut self, message: &BusMessage) -> Result<bool, String> {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            match self.handle_envelope(&message.envelope).await {
+                Ok(()) => return Ok(true),
+                Err(e) if attempts < self.max_attempts => self.logger.warn(&format!(
+                    "Failed to process envelope {} (attempt {}): {}",
+                    message.envelope.event_id, attempts, e
+                )),
+                Err(e) => {
+                    self.logger.warn(&format!(
+                        "Dead-lettering envelope {} after {} attempts: {}",
+                        message.envelope.event_id, attempts, e
+                    ));
+                    self.dead_letters
+                        .send(DeadLetter::failed(message, &e, attempts))
+                        .await?;
+                    return Ok(false);
+                }
+            }
+        }
+    }
+
+    pub async fn handle_envelope(&mut self, envelope: &EventEnvelope) -> Result<(), String> {
+        let event = serde_json::from_value::<PathPlanningEvent>(envelope.event_data.clone())
+            .map_err(|e| format!("Undecodable {} event: {}", envelope.event_type, e))?;
+
+        match event {
+            PathPlanningEvent::PathPlanRequested {
+                planner_id,
+                request_id,
+                plan_id,
+                agent_id,
+                start_position,
+                destination_position,
+                start_orientation,
+                destination_orientation,
+                ..
+            } => {
+                self.logger.info(&format!(
+                    "🎯 Processing PathPlanRequested event: request_id={} plan_id={} agent={} from=({:.1},{:.1}) to=({:.1},{:.1})",
+                    request_id, plan_id, agent_id,
+                    start_position.x, start_position.y,
+                    destination_position.x, destination_position.y
+                ));
+
+                let route = PlanRoute {
+                    start_position: &start_position,
+                    destination_position: &destination_position,
+                    start_orientation: &start_orientation,
+                    destination_orientation: &destination_orientation,
+                };
+                self.assign_to_available_worker(
+                    &plan_id,
+                    &planner_id,
+                    &request_id,
+                    &agent_id,
+                    route,
+                    envelope,
+                    true,
+                )
+                .await?;
+            }
+
+            PathPlanningEvent::MultiGoalPlanRequested {
+                planner_id,
+                request_id,
+                plan_id,
+                agent_id,
+                start_position,
+                start_orientation,
+                goals,
+                leg_plan_ids,
+                order_optimized,
+                ..
+            } => {
+                self.logger.info(&format!(
+                    "🎯 Processing MultiGoalPlanRequested event: request_id={} plan_id={} agent={} goals={} order_optimized={}",
+                    request_id, plan_id, agent_id, goals.len(), order_optimized
+                ));
+
+                // Every leg is an ordinary plan for the workers
+                let legs = plan_legs(&start_position, &start_orientation, &goals);
+                for (leg_plan_id, leg) in leg_plan_ids.iter().zip(&legs) {
+                    let route = PlanRoute {
+                        start_position: &leg.start_position,
+                        destination_position: &leg.destination_position,
+                        start_orientation: &leg.start_orientation,
+                        destination_orientation: &leg.destination_orientation,
+                    };
+                    self.assign_to_available_worker(
+                        leg_plan_id,
+                        &planner_id,
+                        &request_id,
+                        &agent_id,
+                        route,
+                        envelope,
+                        false,
+                    )
+                    .await?;
+                }
+            }
+
+            PathPlanningEvent::PlanInvalidated {
+                planner_id,
+                plan_id,
+                agent_id,
+                obstacle_id,
+                start_position,
+                destination_position,
+                start_orientation,
+                destination_orientation,
+                ..
+            } => {
+                self.logger.warn(&format!(
+                    "Plan {} of agent {} invalidated by obstacle {}; re-queuing",
+                    plan_id, agent_id, obstacle_id
+                ));
+
+                // Replan exactly like a fresh request for the same route
+                let route = PlanRoute {
+                    start_position: &start_position,
+                    destination_position: &destination_position,
+                    start_orientation: &start_orientation,
+                    destination_orientation: &destination_orientation,
+                };
+                self.assign_to_available_worker(
+                    &plan_id,
+                    &planner_id,
+                    "",
+                    &agent_id,
+                    route,
+                    envelope,
+                    false,
+                )
+                .await?;
+            }
+
+            PathPlanningEvent::WorkerRegistered {
+                worker_id,
+                capabilities,
+                ..
+            } => {
+                self.logger.info(&format!(
+                    "👷 Worker registered: {} capabilities={:?}",
+                    worker_id, capabilities
+                ));
+                self.available_workers.insert(
+                    worker_id.clone(),
+                    WorkerInfo {
+                        worker_id,
+                        capabilities,
+                        status: WorkerAvailability::Ready,
+                        last_heartbeat: Utc::now(),
+                    },
+                );
+            }
+
+            PathPlanningEvent::WorkerReady { worker_id, .. } => {
+                if let Some(worker_info) = self.available_workers.get_mut(&worker_id) {
+                    worker_info.status = WorkerAvailability::Ready;
+                    worker_info.last_heartbeat = Utc::now();
+                    self.logger
+                        .info(&format!("✅ Updated worker {} status to Ready", worker_id));
+                } else {
+                    self.logger.warn(&format!(
+                        "Worker {} not found in available workers list",
+                        worker_id
+                    ));
+                }
+            }
+
+            PathPlanningEvent::WorkerHeartbeat {
+                worker_id,
+                timestamp,
+                ..
+            } => {
+                if let Some(worker_info) = self.available_workers.get_mut(&worker_id) {
+                    worker_info.last_heartbeat = timestamp;
+                    self.logger.info(&format!(
+                        "💓 Received heartbeat from worker {} at {}",
+                        worker_id,
+                        timestamp.format("%H:%M:%S")
+                    ));
+                } else {
+                    self.logger.warn(&format!(
+                        "Received heartbeat from unknown worker: {}",
+                        worker_id
+                    ));
+                }
+            }
+
+            PathPlanningEvent::WorkerOffline {
+                worker_id, reason, ..
+            } => {
+                self.logger
+                    .warn(&format!("❌ Worker {} went offline: {}", worker_id, reason));
+                if let Some(worker_info) = self.available_workers.get_mut(&worker_id) {
+                    worker_info.status = WorkerAvailability::Offline;
+                }
+            }
+
+            PathPlanningEvent::PlanCompleted {
+                plan_id, worker_id, ..
+            } => {
+                self.logger.info(&format!(
+                    "🎉 Plan completed: {} by worker {:?}",
+                    plan_id, worker_id
+                ));
+
+                // The worker is free for the next assignment
+                if let Some(worker_info) = worker_id
+                    .as_ref()
+                    .and_then(|worker_id| self.available_workers.get_mut(worker_id))
+                {
+                    worker_info.status = WorkerAvailability::Ready;
+                }
+            }
+
+            PathPlanningEvent::PlanFailed {
+                plan_id,
+                worker_id,
+                reason,
+                ..
+            } => {
+                self.logger.warn(&format!(
+                    "❌ Plan failed: {} by worker {:?}. Reason: {}",
+                    plan_id, worker_id, reason
+                ));
+
+                // A failed plan frees the worker just like a completed one
+                if let Some(worker_info) = worker_id
+                    .as_ref()
+                    .and_then(|worker_id| self.available_workers.get_mut(worker_id))
+                {
+                    worker_info.status = WorkerAvailability::Ready;
+                }
+            }
+
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn find_available_worker(&self) -> Option<String> {
+        self.available_workers
+            .values()
+            .find(|w| w.status == WorkerAvailability::Ready)
+            .map(|w| w.worker_id.clone())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn assign_to_available_worker(
+        &mut self,
+        plan_id: &str,
+        planner_id: &str,
+        request_id: &str,
+        agent_id: &str,
+        route: PlanRoute<'_>,
+        cause: &EventEnvelope,
+        forward_reply_to: bool,
+    ) -> Result<(), String> {
+        let Some(worker_id) = self.find_available_worker() else {
+            self.logger.warn(&format!(
+                "⚠️  No available workers for plan {}. Request queued.",
+                plan_id
+            ));
+            return Ok(());
+        };
+
+        let event = PathPlanningEvent::PlanAssigned {
+            planner_id: planner_id.to_string(),
+            plan_id: plan_id.to_string(),
+            worker_id: worker_id.clone(),
+            request_id: request_id.to_string(),
+            agent_id: agent_id.to_string(),
+            start_position: route.start_position.clone(),
+            destination_position: route.destination_position.clone(),
+            start_orientation: route.start_orientation.clone(),
+            destination_orientation: route.destination_orientation.clone(),
+            timeout_seconds: 300,
+            timestamp: Utc::now(),
+        };
+        // The worker answers the original requester, so it needs its reply address
+        let (correlation_id, reply_to) = if forward_reply_to {
+            (
+                cause.metadata.correlation_id,
+                cause.metadata.reply_to.clone(),
+            )
+        } else {
+            (None, None)
+        };
+        let envelope = self.envelope_for(
+            &event,
+            EventMetadata {
+                correlation_id: correlation_id.or(Some(Uuid::new_v4())),
+                causation_id: Some(cause.event_id),
+                user_id: Some(worker_id.clone()),
+                source: "path_planning_planner".to_string(),
+                reply_to,
+                tenant: cause.metadata.tenant.clone(),
+            },
+        )?;
+
+        self.bus.publish(&self.events_topic, &envelope).await?;
+
+        if let Some(worker_info) = self.available_workers.get_mut(&worker_id) {
+            worker_info.status = WorkerAvailability::Busy {
+                plan_id: plan_id.to_string(),
+            };
+        }
+        self.logger.info(&format!(
+            "📤 Assigned plan {} to worker {}",
+            plan_id, worker_id
+        ));
+        Ok(())
+    }
+
+    fn envelope_for(
+        &self,
+        event: &PathPlanningEvent,
+        metadata: EventMetadata,
+    ) -> Result<EventEnvelope, String> {
+        Ok(EventEnvelope {
+            event_id: Uuid::new_v4(),
+            aggregate_id: event.aggregate_id().to_string(),
+            aggregate_type: PathPlanner::AGGREGATE_TYPE.to_string(),
+            event_type: event.event_type().to_string(),
+            event_version: 1,
+            event_data: serde_json::to_value(event)
+                .map_err(|e| format!("Failed to serialize event: {}", e))?,
+            metadata,
+            occurred_at: Utc::now(),
+            chain: None,
+        })
+    }
+
+    /// Mark workers whose heartbeat is overdue as offline and publish `WorkerOffline`.
+    pub async fn check_worker_health(&mut self) -> Result<(), String> {
+        let now = Utc::now();
+        let stale: Vec<String> = self
+            .available_workers
+            .values()
+            .filter(|w| {
+                w.status != WorkerAvailability::Offline
+                    && now - w.last_heartbeat > self.heartbeat_timeout
+            })
+            .map(|w| w.worker_id.clone())
+            .collect();
+
+        for worker_id in stale {
+            self.logger.warn(&format!(
+                "⚠️  Worker {} missed its heartbeats, marking as offline",
+                worker_id
+            ));
+            if let Some(worker_info) = self.available_workers.get_mut(&worker_id) {
+                worker_info.status = WorkerAvailability::Offline;
+            }
+
+            let event = PathPlanningEvent::WorkerOffline {
+                planner_id: self.planner_id.clone(),
+                worker_id: worker_id.clone(),
+                reason: "Heartbeat timeout".to_string(),
+                timestamp: now,
+            };
+            let envelope = self.envelope_for(
+                &event,
+                EventMetadata {
+                    correlation_id: None,
+                    causation_id: None,
+                    user_id: Some("health_monitor".to_string()),
+                    source: "path_planning_planner".to_string(),
+                    reply_to: None,
+                    tenant: None,
+                },
+            )?;
+            self.bus.publish(&self.events_topic, &envelope).await?;
+        }
+        Ok(())
+    }
+
+    fn log_status(&self) {
+        let count = |status: fn(&WorkerAvailability) -> bool| {
+            self.available_workers
+                .values()
+                .filter(|w| status(&w.status))
+                .count()
+        };
+        self.logger.info(&format!(
+            "📊 Planner {} status: ready_workers={} busy_workers={}",
+            self.planner_id,
+            count(|s| *s == WorkerAvailability::Ready),
+            count(|s| matches!(s, WorkerAvailability::Busy { .. }))
+        ));
+
+        for (worker_id, worker_info) in &self.available_workers {
+            match &worker_info.status {
+                WorkerAvailability::Ready => {
+                    self.logger.info(&format!("     ✅ {}: Ready", worker_id))
+                }
+                WorkerAvailability::Busy { plan_id } => self
+                    .logger
+                    .info(&format!("     🔄 {}: Working on {}", worker_id, plan_id)),
+                WorkerAvailability::Offline => {
+                    self.logger.info(&format!("     ❌ {}: Offline", worker_id))
+                }
+            }
+        }
+    }
+}
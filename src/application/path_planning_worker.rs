@@ -0,0 +1,385 @@
+// Path planning worker service - computes the plans assigned to this worker
+use crate::adapters::inbound::inbox::InMemoryInbox;
+use crate::adapters::inbound::plan_cache::InMemoryPlanCache;
+use crate::application::KeyedDispatcher;
+use crate::common::{
+    send_reply, BusDeadLetterQueue, BusMessage, DeadLetter, DeadLetterQueue, DomainEvent,
+    EventEnvelope, EventMetadata, Inbox, MessageBus,
+};
+use crate::config::{KafkaTopics, PartitionStrategy};
+use crate::domains::path_planning::{
+    PathPlanner, PathPlanningEvent, PlanCache, PlanCacheKey, PlanningAlgorithm, Position2D,
+};
+use crate::domains::DynLogger;
+use chrono::Utc;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Registers with the planner over the message bus, then answers every
+/// `PlanAssigned` addressed to it with `PlanCompleted`.
+#[derive(Clone)]
+pub struct PathPlanningWorkerService {
+    pub worker_id: String,
+    pub planner_id: String,
+    pub capabilities: Vec<PlanningAlgorithm>,
+    pub logger: DynLogger,
+    pub plan_cache: Arc<dyn PlanCache>,
+    // The demo planner draws straight lines without a stored graph; a worker that
+    // plans on a graph sets its map name and the version of the loaded bytes.
+    pub map_name: String,
+    pub graph_version: String,
+    pub heartbeat_interval: Duration,
+    /// Processing attempts before an envelope is dead-lettered
+    pub max_attempts: u32,
+    /// Messages with different partition keys are handled on this many lanes
+    pub dispatch_lanes: usize,
+    bus: Arc<dyn MessageBus>,
+    events_topic: String,
+    dead_letters: Arc<dyn DeadLetterQueue>,
+    inbox: Arc<dyn Inbox>,
+    partitioning: PartitionStrategy,
+}
+
+impl PathPlanningWorkerService {
+    pub fn new(
+        worker_id: String,
+        planner_id: String,
+        bus: Arc<dyn MessageBus>,
+        topics: &KafkaTopics,
+        logger: DynLogger,
+    ) -> Self {
+        Self {
+            worker_id,
+            planner_id,
+            capabilities: vec![PlanningAlgorithm::AStar],
+            logger,
+            plan_cache: Arc::new(InMemoryPlanCache::new()),
+            map_name: "default".to_string(),
+            graph_version: PlanCacheKey::graph_version_of(&[]),
+            heartbeat_interval: Duration::from_secs(30),
+            max_attempts: 3,
+            dispatch_lanes: 4,
+            dead_letters: Arc::new(BusDeadLetterQueue::new(bus.clone(), &topics.dead_letters)),
+            inbox: Arc::new(InMemoryInbox::new()),
+            partitioning: PartitionStrategy::default(),
+            bus,
+            events_topic: topics.path_planning_events.clone(),
+        }
+    }
+
+    pub fn with_inbox(mut self, inbox: Arc<dyn Inbox>) -> Self {
+        self.inbox = inbox;
+        self
+    }
+
+    pub fn with_dead_letter_queue(mut self, dead_letters: Arc<dyn DeadLetterQueue>) -> Self {
+        self.dead_letters = dead_letters;
+        self
+    }
+
+    pub fn with_plan_cache(mut self, plan_cache: Arc<dyn PlanCache>) -> Self {
+        self.plan_cache = plan_cache;
+        self
+    }
+
+    /// Which messages are handled in order; use the producers' strategy
+    pub fn with_partition_strategy(mut self, partitioning: PartitionStrategy) -> Self {
+        self.partitioning = partitioning;
+        self
+    }
+
+    /// Register with the planner and process assignments until the bus shuts down.
+    /// Messages are handled through a `KeyedDispatcher`, in order per key.
+    pub async fn run(&self) -> Result<(), String> {
+        // Subscribe before registering so no assignment can slip past
+        let subscription = self
+            .bus
+            .subscribe(&self.events_topic, &self.consumer_name())
+            .await?;
+        self.register().await?;
+        self.logger.info(&format!(
+            "Worker {} is consuming {}",
+            self.worker_id, self.events_topic
+        ));
+
+        let dispatcher = KeyedDispatcher::new(self.dispatch_lanes, self.logger.clone())
+            .with_partition_strategy(self.partitioning);
+        let processed_plans = Arc::new(Mutex::new(HashSet::new()));
+        let worker = self.clone();
+        let dispatch = dispatcher.run(subscription, move |message| {
+            let worker = worker.clone();
+            let processed_plans = processed_plans.clone();
+            async move { worker.process_message(&message, &processed_plans).await }
+        });
+        let heartbeats = async {
+            let mut heartbeat_timer = tokio::time::interval(self.heartbeat_interval);
+            loop {
+                heartbeat_timer.tick().await;
+                if let Err(e) = self.send_heartbeat().await {
+                    self.logger
+                        .warn(&format!("Failed to send heartbeat: {}", e));
+                }
+            }
+        };
+        tokio::select! {
+            result = dispatch => result,
+            _ = heartbeats => Ok(()),
+        }
+    }
+
+    fn consumer_name(&self) -> String {
+        format!("worker-group-{}", self.worker_id)
+    }
+
+    /// Handle `message` once: envelopes already in the inbox are skipped, and
+    /// an envelope is recorded there after it was handled. Dead-lettered
+    /// envelopes are not, so a replayed dead letter is handled again.
+    async fn process_message(
+        &self,
+        message: &BusMessage,
+        processed_plans: &Mutex<HashSet<String>>,
+    ) -> Result<(), String> {
+        let consumer = self.consumer_name();
+        let event_id = message.envelope.event_id;
+        if self.inbox.is_processed(&consumer, event_id).await? {
+            return Ok(());
+        }
+        if !self.handle_with_retries(message, processed_plans).await? {
+            return Ok(());
+        }
+        self.inbox.mark_processed(&consumer, event_id).await
+    }
+
+    /// Handle `message`, retrying failures, and dead-letter it once
+    /// `max_attempts` are used up. `false` when it was dead-lettered.
+    async fn handle_with_retries(
+        &self,
+        message: &BusMessage,
+        processed_plans: &Mutex<HashSet<String>>,
+    ) -> Result<bool, String> {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            match self.handle_message(message, processed_plans).await {
+                Ok(()) => return Ok(true),
+                Err(e) if attempts < self.max_attempts => self.logger.warn(&format!(
+                    "Failed to process envelope {} (attempt {}): {}",
+                    message.envelope.event_id, attempts, e
+                )),
+                Err(e) => {
+                    self.logger.warn(&format!(
+                        "Dead-lettering envelope {} after {} attempts: {}",
+                        message.envelope.event_id, attempts, e
+                    ));
+                    self.dead_letters
+                        .send(DeadLetter::failed(message, &e, attempts))
+                        .await?;
+                    return Ok(false);
+                }
+            }
+        }
+    }
+
+    async fn handle_message(
+        &self,
+        message: &BusMessage,
+        processed_plans: &Mutex<HashSet<String>>,
+    ) -> Result<(), String> {
+        let envelope = &message.envelope;
+        match envelope.event_type.as_str() {
+            "PlanAssigned" => {}
+            // The workspace changed, so cached routes may cross an obstacle now
+            "ObstacleAdded" | "ObstacleMoved" | "ObstacleRemoved" | "PlanInvalidated" => {
+                let dropped = self
+                    .plan_cache
+                    .invalidate_map(&self.map_name)
+                    .await
+                    .map_err(|e| format!("Failed to invalidate the plan cache: {}", e))?;
+                self.logger.info(&format!(
+                    "Dropped {} cached plans after {}",
+                    dropped, envelope.event_type
+                ));
+                // An invalidated plan comes back under the same id
+                if let Ok(PathPlanningEvent::PlanInvalidated { plan_id, .. }) =
+                    serde_json::from_value::<PathPlanningEvent>(envelope.event_data.clone())
+                {
+                    processed_plans.lock().unwrap().remove(&plan_id);
+                }
+                return Ok(());
+            }
+            _ => return Ok(()),
+        }
+        let PathPlanningEvent::PlanAssigned {
+            plan_id,
+            worker_id,
+            start_position,
+            destination_position,
+            start_orientation,
+            destination_orientation,
+            ..
+        } = serde_json::from_value::<PathPlanningEvent>(envelope.event_data.clone())
+            .map_err(|e| format!("Undecodable PlanAssigned event: {}", e))?
+        else {
+            return Err("PlanAssigned envelope carries another event".to_string());
+        };
+        if worker_id != self.worker_id || processed_plans.lock().unwrap().contains(&plan_id) {
+            return Ok(());
+        }
+        self.logger
+            .info(&format!("Processing plan assignment: {}", plan_id));
+
+        let cache_key = PlanCacheKey::new(
+            &self.map_name,
+            &self.graph_version,
+            PlanningAlgorithm::AStar,
+            &start_position,
+            &destination_position,
+            &start_orientation,
+            &destination_orientation,
+        );
+        let cached = self.plan_cache.get(&cache_key).await.unwrap_or_else(|e| {
+            self.logger
+                .warn(&format!("Plan cache lookup failed: {}", e));
+            None
+        });
+        let cache_hit = cached.is_some();
+        let waypoints = match cached {
+            Some(waypoints) => {
+                self.logger
+                    .info(&format!("Plan cache hit for plan {}", plan_id));
+                waypoints
+            }
+            None => {
+                // Simulate path planning work
+                self.logger
+                    .info("Calculating optimal path using A* algorithm");
+                tokio::time::sleep(Duration::from_millis(500)).await;
+
+                // Generate a simple path (for demo)
+                let mut waypoints = Vec::new();
+                let steps = 4;
+                for i in 0..=steps {
+                    let t = i as f64 / steps as f64;
+                    let x = start_position.x + t * (destination_position.x - start_position.x);
+                    let y = start_position.y + t * (destination_position.y - start_position.y);
+                    waypoints.push(Position2D { x, y });
+                }
+
+                if let Err(e) = self.plan_cache.put(&cache_key, &waypoints).await {
+                    self.logger.warn(&format!("Plan cache store failed: {}", e));
+                }
+                waypoints
+            }
+        };
+        self.logger.info(&format!(
+            "Path calculated with {} waypoints",
+            waypoints.len()
+        ));
+
+        let completion_event = PathPlanningEvent::PlanCompleted {
+            planner_id: self.planner_id.clone(),
+            plan_id: plan_id.clone(),
+            worker_id: Some(self.worker_id.clone()),
+            waypoints,
+            cache_hit,
+            timestamp: Utc::now(),
+        };
+        let completion_envelope = EventEnvelope::new(
+            &completion_event,
+            PathPlanner::AGGREGATE_TYPE,
+            EventMetadata {
+                correlation_id: envelope.metadata.correlation_id,
+                causation_id: Some(envelope.event_id),
+                user_id: None,
+                source: "path_planning_worker".to_string(),
+                reply_to: None,
+                tenant: None,
+            },
+        )
+        .map_err(|e| format!("Failed to serialize event: {}", e))?;
+
+        self.bus
+            .publish(&self.events_topic, &completion_envelope)
+            .await?;
+        if send_reply(self.bus.as_ref(), envelope, completion_envelope).await? {
+            self.logger
+                .info(&format!("Replied to the requester of plan {}", plan_id));
+        }
+        self.logger
+            .info(&format!("Plan {} completed and published", plan_id));
+        processed_plans.lock().unwrap().insert(plan_id);
+        Ok(())
+    }
+
+    async fn publish_worker_event(&self, event: PathPlanningEvent) -> Result<(), String> {
+        let envelope = EventEnvelope {
+            event_id: Uuid::new_v4(),
+            aggregate_id: self.planner_id.clone(),
+            aggregate_type: PathPlanner::AGGREGATE_TYPE.to_string(),
+            event_type: event.event_type().to_string(),
+            event_version: 1,
+            event_data: serde_json::to_value(&event)
+                .map_err(|e| format!("Failed to serialize event: {}", e))?,
+            metadata: EventMetadata {
+                correlation_id: None,
+                causation_id: None,
+                user_id: None,
+                source: format!("worker-{}", self.worker_id),
+                reply_to: None,
+                tenant: None,
+            },
+            occurred_at: Utc::now(),
+            chain: None,
+        };
+        self.bus.publish(&self.events_topic, &envelope).await
+    }
+
+    /// Announce this worker and its capabilities, then mark it ready.
+    pub async fn register(&self) -> Result<(), String> {
+        self.publish_worker_event(PathPlanningEvent::WorkerRegistered {
+            planner_id: self.planner_id.clone(),
+            worker_id: self.worker_id.clone(),
+            capabilities: self.capabilities.clone(),
+            timestamp: Utc::now(),
+        })
+        .await?;
+        self.publish_worker_event(PathPlanningEvent::WorkerReady {
+            planner_id: self.planner_id.clone(),
+            worker_id: self.worker_id.clone(),
+            timestamp: Utc::now(),
+        })
+        .await?;
+        self.logger.info(&format!(
+            "Worker {} registered and marked as ready",
+            self.worker_id
+        ));
+        Ok(())
+    }
+
+    pub async fn send_heartbeat(&self) -> Result<(), String> {
+        self.publish_worker_event(PathPlanningEvent::WorkerHeartbeat {
+            planner_id: self.planner_id.clone(),
+            worker_id: self.worker_id.clone(),
+            timestamp: Utc::now(),
+        })
+        .await
+    }
+
+    /// Tell the planner this worker is leaving; called on graceful shutdown.
+    pub async fn unregister(&self) -> Result<(), String> {
+        self.publish_worker_event(PathPlanningEvent::WorkerOffline {
+            planner_id: self.planner_id.clone(),
+            worker_id: self.worker_id.clone(),
+            reason: "Graceful shutdown".to_string(),
+            timestamp: Utc::now(),
+        })
+        .await?;
+        self.logger.info(&format!(
+            "Sent unregistration event for worker {}",
+            self.worker_id
+        ));
+        Ok(())
+    }
+}
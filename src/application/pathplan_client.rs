@@ -1,29 +1,52 @@
 use crate::adapters::inbound::file_event_store::FileEventStore;
-use crate::common::EventStore;
-use crate::config::Config;
+use crate::common::{
+    DomainEvent, EventEnvelope, EventMetadata, EventStore, MessageBus, PersonalDataCipher,
+    PersonalDataEventStore, RequestReplyClient, RpcError,
+};
+use crate::config::{Config, KafkaTopics};
 use crate::domains::path_planning::aggregate::types::PlanningScenario;
 use crate::domains::path_planning::aggregate::types::{Orientation2D, Position2D};
+use crate::domains::path_planning::{
+    PathPlan, PathPlanRequest, PathPlanner, PathPlanningEvent, PlanStatus,
+};
+use chrono::Utc;
 use std::f64::consts::PI;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+use uuid::Uuid;
 
 pub struct PathPlanClient {
     pub scenarios: Vec<PlanningScenario>,
-    pub event_store: Arc<dyn EventStore>,
+    pub event_store: Arc<dyn EventStore + Send + Sync>,
     pub planner_id: String,
     pub logger: crate::domains::DynLogger,
+    pub bus: Option<Arc<dyn MessageBus>>,
+    pub topics: KafkaTopics,
+    pub request_timeout: Duration,
+    rpc: OnceCell<RequestReplyClient>,
 }
 
 impl PathPlanClient {
     pub async fn new(
         logger: crate::domains::DynLogger,
     ) -> Result<Self, Box<dyn std::error::Error>> {
-        let _config = Config::default();
+        let config = Config::default().with_tenant_from_env()?;
         logger.info("Using default configuration for demo");
+        Self::from_config(logger, config).await
+    }
 
-        let event_store: Arc<dyn EventStore> = Arc::new(FileEventStore::new("/tmp/gryphon-events"));
+    /// Client for the planner, event store and topics of `config`, which may
+    /// be scoped to a tenant
+    pub async fn from_config(
+        logger: crate::domains::DynLogger,
+        config: Config,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let event_store: Arc<dyn EventStore + Send + Sync> =
+            Arc::new(FileEventStore::new(&config.event_store.directory));
         logger.info("Using file-based event store for demo (shared between processes)");
 
-        let planner_id = "main-path-planner".to_string();
+        let planner_id = config.path_planning.planner_id.clone();
         let scenarios = vec![PlanningScenario {
             name: "Office Navigation".to_string(),
             description: "Robot navigating from office entrance to meeting room".to_string(),
@@ -41,6 +64,121 @@ impl PathPlanClient {
             event_store,
             planner_id,
             logger,
+            bus: None,
+            topics: config.kafka.topics,
+            request_timeout: Duration::from_secs(30),
+            rpc: OnceCell::new(),
         })
     }
+
+    pub fn with_message_bus(mut self, bus: Arc<dyn MessageBus>) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Encrypt personal data in the event store with `cipher`. Wrap the
+    /// message bus in a `PersonalDataBus` with the same cipher too.
+    pub fn with_personal_data(mut self, cipher: PersonalDataCipher) -> Self {
+        self.event_store = Arc::new(PersonalDataEventStore::new(self.event_store, cipher));
+        self
+    }
+
+    /// How long `plan` waits for a worker to finish a plan
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    async fn rpc(&self) -> Result<&RequestReplyClient, RpcError> {
+        let bus = self
+            .bus
+            .clone()
+            .ok_or_else(|| RpcError::Bus("PathPlanClient has no message bus".to_string()))?;
+        self.rpc
+            .get_or_try_init(|| RequestReplyClient::start(bus, &self.topics.path_planning_replies))
+            .await
+    }
+
+    /// Request a plan from the planner and wait for a worker to compute it.
+    pub async fn plan(&self, request: PathPlanRequest) -> Result<PathPlan, RpcError> {
+        let rpc = self.rpc().await?;
+        let plan_id = format!("plan-{}", Uuid::new_v4());
+        let event = PathPlanningEvent::PathPlanRequested {
+            planner_id: self.planner_id.clone(),
+            request_id: request.request_id.clone(),
+            plan_id: plan_id.clone(),
+            agent_id: request.agent_id.clone(),
+            start_position: request.start_position.clone(),
+            destination_position: request.destination_position.clone(),
+            start_orientation: request.start_orientation.clone(),
+            destination_orientation: request.destination_orientation.clone(),
+            timestamp: Utc::now(),
+        };
+        let envelope = EventEnvelope {
+            event_id: Uuid::new_v4(),
+            aggregate_id: self.planner_id.clone(),
+            aggregate_type: PathPlanner::AGGREGATE_TYPE.to_string(),
+            event_type: event.event_type().to_string(),
+            event_version: 1,
+            event_data: serde_json::to_value(&event).map_err(|e| RpcError::Bus(e.to_string()))?,
+            metadata: EventMetadata {
+                correlation_id: None,
+                causation_id: None,
+                user_id: Some(request.agent_id.clone()),
+                source: "pathplan_client".to_string(),
+                reply_to: None,
+                tenant: None,
+            },
+            occurred_at: Utc::now(),
+            chain: None,
+        };
+
+        self.logger.info(&format!(
+            "Requesting plan {} for request {} agent={}",
+            plan_id, request.request_id, request.agent_id
+        ));
+        let reply: PathPlanningEvent = rpc
+            .request(
+                &self.topics.path_planning_events,
+                envelope,
+                self.request_timeout,
+            )
+            .await?;
+
+        match reply {
+            PathPlanningEvent::PlanCompleted {
+                waypoints,
+                worker_id,
+                timestamp,
+                ..
+            } => {
+                self.logger.info(&format!(
+                    "Plan {} completed by worker {:?} with {} waypoints",
+                    plan_id,
+                    worker_id,
+                    waypoints.len()
+                ));
+                Ok(PathPlan {
+                    id: plan_id,
+                    agent_id: request.agent_id,
+                    start: request.start_position,
+                    goal: request.destination_position,
+                    start_orientation: request.start_orientation,
+                    destination_orientation: request.destination_orientation,
+                    waypoints,
+                    status: PlanStatus::Complete,
+                    created_at: request.created_at,
+                    finished_at: Some(timestamp),
+                    parent_plan_id: None,
+                    leg_plan_ids: Vec::new(),
+                })
+            }
+            PathPlanningEvent::PlanFailed { reason, .. } => Err(RpcError::Failed(reason)),
+            other => Err(RpcError::Decode(format!(
+                "Unexpected reply {} to plan {}",
+                other.event_type(),
+                plan_id
+            ))),
+        }
+    }
 }
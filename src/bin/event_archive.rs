@@ -0,0 +1,130 @@
+use chrono::{DateTime, Utc};
+use gryphon_app::adapters::inbound::{
+    export_archive, import_archive, ArchiveFilter, EventArchive, FileEventStore,
+};
+use gryphon_app::adapters::outbound::{KafkaEventStore, PostgresSnapshotStore};
+use gryphon_app::common::{EventStore, SnapshotStore};
+use gryphon_app::config::Config;
+
+const USAGE: &str = "Usage:
+  event_archive export <archive> [--dir <dir> | --kafka] [filters] [--snapshots]
+  event_archive import <archive> [--dir <dir> | --kafka] [--snapshots]
+
+Moves event history between stores as one NDJSON file with a manifest and an
+MD5 checksum. Events keep their position in their aggregate's stream. Import
+skips events and snapshots the store already has, so it can be run again, and
+refuses an archive that does not continue each stored stream without gaps.
+
+  --dir <dir>      file event store directory (default: the configured one)
+  --kafka          the configured Kafka event topics
+  --snapshots      also export or import the snapshots in the configured Postgres
+
+Filters (export only; --type and --id may be repeated):
+  --type <type>    aggregate type, e.g. PathPlanner
+  --id <id>        aggregate id
+  --since <time>   events that occurred at or after <time> (RFC 3339)
+  --until <time>   events that occurred at or before <time>
+
+To import into the esrs Postgres tables use `migrate_to_esrs archive <archive>`.";
+
+struct Options {
+    export: bool,
+    archive: String,
+    dir: Option<String>,
+    kafka: bool,
+    snapshots: bool,
+    filter: ArchiveFilter,
+}
+
+fn parse_time(time: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(time)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|e| format!("Invalid time {}: {}", time, e))
+}
+
+fn parse(args: &[String]) -> Result<Options, String> {
+    let [command, archive, rest @ ..] = args else {
+        return Err(USAGE.to_string());
+    };
+    let export = match command.as_str() {
+        "export" => true,
+        "import" => false,
+        _ => return Err(USAGE.to_string()),
+    };
+    let mut options = Options {
+        export,
+        archive: archive.clone(),
+        dir: None,
+        kafka: false,
+        snapshots: false,
+        filter: ArchiveFilter::default(),
+    };
+    let mut rest = rest.iter();
+    while let Some(flag) = rest.next() {
+        match flag.as_str() {
+            "--kafka" => options.kafka = true,
+            "--snapshots" => options.snapshots = true,
+            _ => {
+                let value = rest.next().ok_or_else(|| USAGE.to_string())?;
+                match flag.as_str() {
+                    "--dir" => options.dir = Some(value.clone()),
+                    "--type" if export => options.filter.aggregate_types.push(value.clone()),
+                    "--id" if export => options.filter.aggregate_ids.push(value.clone()),
+                    "--since" if export => options.filter.from = Some(parse_time(value)?),
+                    "--until" if export => options.filter.until = Some(parse_time(value)?),
+                    _ => return Err(USAGE.to_string()),
+                }
+            }
+        }
+    }
+    if options.kafka && options.dir.is_some() {
+        return Err(format!("--dir and --kafka exclude each other\n\n{}", USAGE));
+    }
+    Ok(options)
+}
+
+async fn run(args: &[String]) -> Result<(), String> {
+    let options = parse(args)?;
+    let config = Config::default().with_tenant_from_env()?;
+
+    let event_store: Box<dyn EventStore + Send + Sync> = if options.kafka {
+        Box::new(KafkaEventStore::new(config.kafka.clone()).await?)
+    } else {
+        let dir = options
+            .dir
+            .clone()
+            .unwrap_or_else(|| config.event_store.directory.clone());
+        Box::new(FileEventStore::new(dir))
+    };
+    let snapshot_store: Option<Box<dyn SnapshotStore + Send + Sync>> = if options.snapshots {
+        Some(Box::new(
+            PostgresSnapshotStore::new(config.postgres.clone()).await?,
+        ))
+    } else {
+        None
+    };
+
+    if options.export {
+        let archive =
+            export_archive(&*event_store, snapshot_store.as_deref(), options.filter).await?;
+        archive.write(&options.archive).await?;
+        println!(
+            "Exported {} events and {} snapshots to {}",
+            archive.manifest.event_count, archive.manifest.snapshot_count, options.archive
+        );
+    } else {
+        let archive = EventArchive::read(&options.archive).await?;
+        let report = import_archive(&archive, &*event_store, snapshot_store.as_deref()).await?;
+        println!("{}", report);
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args).await {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
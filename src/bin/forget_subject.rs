@@ -0,0 +1,37 @@
+use gryphon_app::adapters::outbound::PostgresKeyStore;
+use gryphon_app::common::KeyStore;
+use gryphon_app::config::Config;
+
+const USAGE: &str = "Usage:
+  forget_subject <subject>...
+
+Deletes each subject's key from the configured Postgres `subject_keys` table.
+Personal fields encrypted for them, e.g. the user id of their sessions, then
+read as \"<erased>\" in every stored copy of the events. This cannot be undone.";
+
+async fn run(args: &[String]) -> Result<(), String> {
+    if args.is_empty() || args.iter().any(|arg| arg.starts_with('-')) {
+        return Err(USAGE.to_string());
+    }
+    let config = Config::default().with_tenant_from_env()?;
+
+    let key_store = PostgresKeyStore::from_config(&config.postgres).await?;
+
+    for subject in args {
+        if key_store.forget_subject(subject).await? {
+            println!("Forgot {}", subject);
+        } else {
+            println!("{} had no key", subject);
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args).await {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,175 @@
+use chrono::{DateTime, Utc};
+use gryphon_app::adapters::inbound::FileEventStore;
+use gryphon_app::adapters::outbound::PostgresSnapshotStore;
+use gryphon_app::common::{AggregateRepository, AggregateRoot, AsOf, EventStore, SnapshotStore};
+use gryphon_app::config::Config;
+use gryphon_app::domains::dynamics::{DynamicsSimulator, PhysicsModel};
+use gryphon_app::domains::gui::GUIApplication;
+use gryphon_app::domains::logical_agent::LogicalAgent;
+use gryphon_app::domains::path_planning::{PathPlanner, PlanningAlgorithm};
+use gryphon_app::domains::technical_agent::{AgentType, TechnicalAgent};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::sync::Arc;
+
+const USAGE: &str = "Usage:
+  inspect_aggregate <type> <id> [--version <n> | --at <time>] [--dir <dir>] [--snapshots]
+
+Prints the state of an aggregate of the file event store after its first <n>
+events, or after the events that occurred at or before <time> (RFC 3339, e.g.
+2024-05-01T14:02:00Z). Without either it prints the current state.
+
+  --dir <dir>    event store directory (default: the configured one)
+  --snapshots    start from the best snapshot in the configured Postgres
+
+Types: path_planner, logical_agent, technical_agent, dynamics_simulator, gui_application";
+
+struct Options {
+    aggregate_type: String,
+    aggregate_id: String,
+    as_of: AsOf,
+    dir: Option<String>,
+    snapshots: bool,
+}
+
+fn parse(args: &[String]) -> Result<Options, String> {
+    let [aggregate_type, aggregate_id, rest @ ..] = args else {
+        return Err(USAGE.to_string());
+    };
+    let mut options = Options {
+        aggregate_type: aggregate_type.clone(),
+        aggregate_id: aggregate_id.clone(),
+        as_of: AsOf::Latest,
+        dir: None,
+        snapshots: false,
+    };
+    let mut rest = rest.iter();
+    while let Some(flag) = rest.next() {
+        // The only flag without a value
+        if flag == "--snapshots" {
+            options.snapshots = true;
+            continue;
+        }
+        match (flag.as_str(), rest.next()) {
+            ("--version", Some(version)) => {
+                let version = version
+                    .parse()
+                    .map_err(|_| format!("Invalid version {}", version))?;
+                options.as_of = AsOf::Version(version);
+            }
+            ("--at", Some(time)) => {
+                let time = DateTime::parse_from_rfc3339(time)
+                    .map_err(|e| format!("Invalid time {}: {}", time, e))?;
+                options.as_of = AsOf::Time(time.with_timezone(&Utc));
+            }
+            ("--dir", Some(dir)) => options.dir = Some(dir.clone()),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    Ok(options)
+}
+
+async fn inspect<T>(
+    options: &Options,
+    event_store: Arc<dyn EventStore + Send + Sync>,
+    snapshot_store: Option<Arc<dyn SnapshotStore + Send + Sync>>,
+    new_aggregate: impl Fn(&str) -> T + Send + Sync + 'static,
+) -> Result<(), String>
+where
+    T: AggregateRoot + Serialize + DeserializeOwned,
+{
+    let mut repository = AggregateRepository::new(event_store, new_aggregate);
+    if let Some(snapshot_store) = snapshot_store {
+        repository = repository.with_snapshots(snapshot_store);
+    }
+    let Some(state) = repository
+        .load_as_of(&options.aggregate_id, options.as_of)
+        .await?
+    else {
+        return Err(format!(
+            "{} {} has no events at that point",
+            options.aggregate_type, options.aggregate_id
+        ));
+    };
+
+    println!(
+        "{} {} at version {}{}{}",
+        options.aggregate_type,
+        options.aggregate_id,
+        state.version,
+        state
+            .snapshot_version
+            .map(|version| format!(", from snapshot {}", version))
+            .unwrap_or_default(),
+        state
+            .last_event_at
+            .map(|time| format!(", last event at {}", time.to_rfc3339()))
+            .unwrap_or_default(),
+    );
+    let json = serde_json::to_string_pretty(&state.aggregate)
+        .map_err(|e| format!("Failed to print the state: {}", e))?;
+    println!("{}", json);
+    Ok(())
+}
+
+async fn run(args: &[String]) -> Result<(), String> {
+    let options = parse(args)?;
+    let config = Config::default().with_tenant_from_env()?;
+
+    let dir = options
+        .dir
+        .clone()
+        .unwrap_or_else(|| config.event_store.directory.clone());
+    let event_store: Arc<dyn EventStore + Send + Sync> = Arc::new(FileEventStore::new(dir));
+    let snapshot_store: Option<Arc<dyn SnapshotStore + Send + Sync>> = if options.snapshots {
+        Some(Arc::new(
+            PostgresSnapshotStore::new(config.postgres.clone()).await?,
+        ))
+    } else {
+        None
+    };
+
+    // Replay overwrites what the constructors set from the creation event
+    match options.aggregate_type.as_str() {
+        "path_planner" => {
+            inspect(&options, event_store, snapshot_store, |id| {
+                PathPlanner::new(id.to_string(), PlanningAlgorithm::AStar)
+            })
+            .await
+        }
+        "logical_agent" => {
+            inspect(&options, event_store, snapshot_store, |id| {
+                LogicalAgent::new(id.to_string(), String::new())
+            })
+            .await
+        }
+        "technical_agent" => {
+            inspect(&options, event_store, snapshot_store, |id| {
+                TechnicalAgent::new(id.to_string(), String::new(), AgentType::Robot)
+            })
+            .await
+        }
+        "dynamics_simulator" => {
+            inspect(&options, event_store, snapshot_store, |id| {
+                DynamicsSimulator::new(id.to_string(), PhysicsModel::Newtonian)
+            })
+            .await
+        }
+        "gui_application" => {
+            inspect(&options, event_store, snapshot_store, |id| {
+                GUIApplication::new(id.to_string(), String::new())
+            })
+            .await
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args).await {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,69 @@
+use gryphon_app::adapters::inbound::esrs_history_migration::migrate_path_planner_history;
+use gryphon_app::adapters::inbound::esrs_pg_store::{
+    build_pg_store_without_bus, connect_pg_pool_in_schema,
+};
+use gryphon_app::adapters::inbound::{EventArchive, FileEventStore};
+use gryphon_app::adapters::outbound::kafka_replay::{KafkaReplayReader, ReplayFilter};
+use gryphon_app::common::{EventEnvelope, EventStore};
+use gryphon_app::config::Config;
+use gryphon_app::esrs::path_planning::PathPlanner;
+
+const USAGE: &str = "Usage:
+  migrate_to_esrs file <dir>       migrate the path planner events of a FileEventStore directory
+  migrate_to_esrs kafka [topic]    migrate them from a Kafka topic (default: the path planning topic)
+  migrate_to_esrs archive <file>   migrate them from an archive written by `event_archive export`
+
+Events already in esrs are skipped, so an interrupted migration can be run again.
+With GRYPHON_TENANT set, the default topic and the esrs tables are the tenant's.
+Exits with an error when a migrated aggregate does not replay to the same state.";
+
+async fn load_envelopes(args: &[String], config: &Config) -> Result<Vec<EventEnvelope>, String> {
+    match args {
+        [source, dir] if source == "file" => FileEventStore::new(dir).load_all_events(None).await,
+        [source, file] if source == "archive" => Ok(EventArchive::read(file).await?.envelopes()),
+        [source, topic @ ..] if source == "kafka" && topic.len() <= 1 => {
+            let brokers =
+                std::env::var("KAFKA_BROKERS").unwrap_or_else(|_| config.kafka.brokers.join(","));
+            let topic = topic
+                .first()
+                .cloned()
+                .unwrap_or_else(|| config.kafka.topics.path_planning_events.clone());
+            KafkaReplayReader::new(&brokers, "migrate-to-esrs")
+                .replay(&topic, ReplayFilter::default())
+                .await
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+async fn run(args: &[String]) -> Result<(), String> {
+    let config = Config::default().with_tenant_from_env()?;
+    let envelopes = load_envelopes(args, &config).await?;
+
+    let database_url = config.postgres.database_url_from_env();
+    let pool = connect_pg_pool_in_schema(&database_url, config.postgres.schema.as_deref())
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", database_url, e))?;
+    // History was published when it happened; migrating it must not publish again
+    let store = build_pg_store_without_bus::<PathPlanner>(pool.clone())
+        .await
+        .map_err(|e| format!("Failed to open the esrs store: {}", e))?;
+
+    let report = migrate_path_planner_history(&envelopes, &store, &pool)
+        .await
+        .map_err(|e| format!("Migration failed: {}", e))?;
+    println!("{}", report);
+    if !report.is_verified() {
+        return Err("Some aggregates were not migrated or do not replay alike".to_string());
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args).await {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,100 @@
+use gryphon_app::adapters::outbound::kafka_message_bus::KafkaMessageBus;
+use gryphon_app::adapters::outbound::kafka_replay::{KafkaReplayReader, ReplayFilter};
+use gryphon_app::application::DeadLetterReplayer;
+use gryphon_app::common::{DeadLetter, MessageBus};
+use gryphon_app::config::Config;
+use std::sync::Arc;
+
+const USAGE: &str = "Usage:
+  pathplan_dlq list                   show the dead letters on the dead-letter topic
+  pathplan_dlq export <file>          write them to <file>, one JSON object per line
+  pathplan_dlq replay <file> [id...]  re-inject the (edited) letters in <file>, or only the given ids";
+
+async fn load_letters(config: &Config) -> Result<Vec<DeadLetter>, String> {
+    let reader = KafkaReplayReader::new(&config.kafka.brokers.join(","), "pathplan-dlq");
+    reader
+        .replay(&config.kafka.topics.dead_letters, ReplayFilter::default())
+        .await?
+        .iter()
+        .map(DeadLetter::from_envelope)
+        .collect()
+}
+
+fn read_letters(path: &str) -> Result<Vec<DeadLetter>, String> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(idx, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| format!("{} line {} is not a dead letter: {}", path, idx + 1, e))
+        })
+        .collect()
+}
+
+async fn run(args: &[String]) -> Result<(), String> {
+    let logger = gryphon_app::adapters::outbound::init_combined_logger("./domain.log");
+
+    let mut config = Config::default();
+    if let Ok(brokers) = std::env::var("KAFKA_BROKERS") {
+        config.kafka.brokers = vec![brokers];
+    }
+    config.kafka.client_id = "pathplan-dlq".to_string();
+
+    match args {
+        [command] if command == "list" => {
+            for letter in load_letters(&config).await? {
+                println!(
+                    "{}  {}/{}@{}  attempts={}  {}",
+                    letter.id,
+                    letter.source_topic,
+                    letter.partition,
+                    letter.offset,
+                    letter.attempts,
+                    letter.error
+                );
+            }
+        }
+        [command, path] if command == "export" => {
+            let letters = load_letters(&config).await?;
+            let mut lines = String::new();
+            for letter in &letters {
+                lines.push_str(&serde_json::to_string(letter).map_err(|e| e.to_string())?);
+                lines.push('\n');
+            }
+            std::fs::write(path, lines).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+            println!("Exported {} dead letters to {}", letters.len(), path);
+        }
+        [command, path, ids @ ..] if command == "replay" => {
+            let letters: Vec<_> = read_letters(path)?
+                .into_iter()
+                .filter(|letter| ids.is_empty() || ids.contains(&letter.id.to_string()))
+                .collect();
+            let bus: Arc<dyn MessageBus> = Arc::new(KafkaMessageBus::from_config(&config.kafka)?);
+            let report = DeadLetterReplayer::new(bus, logger).replay(&letters).await;
+            println!("Replayed {} dead letters", report.replayed.len());
+            for (id, reason) in &report.rejected {
+                println!("Not replayed {}: {}", id, reason);
+            }
+            if !report.rejected.is_empty() {
+                return Err(format!(
+                    "{} dead letters not replayed",
+                    report.rejected.len()
+                ));
+            }
+        }
+        _ => return Err(USAGE.to_string()),
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args).await {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
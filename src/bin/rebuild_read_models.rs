@@ -0,0 +1,84 @@
+use esrs::store::postgres::{PgStore, PgStoreBuilder};
+use esrs::Aggregate;
+use gryphon_app::adapters::inbound::esrs_read_models::{
+    rebuild_read_model, LogicalAgentReadModel, PathPlanningReadModel, ReadModel,
+    TechnicalAgentReadModel,
+};
+use gryphon_app::config::Config;
+use gryphon_app::esrs::{LogicalAgent, PathPlanner, TechnicalAgent};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+
+const USAGE: &str = "Usage:
+  rebuild_read_models <read model>...  truncate the read models and replay them from the esrs event tables
+
+Read models: path_planning, logical_agent, technical_agent, all";
+
+const READ_MODELS: [&str; 3] = ["path_planning", "logical_agent", "technical_agent"];
+
+async fn rebuild<A, R>(pool: &PgPool, read_model: R) -> Result<(), String>
+where
+    A: Aggregate,
+    A::Event: serde::Serialize + serde::de::DeserializeOwned + Send + Sync + 'static + Clone,
+    R: ReadModel<A>,
+{
+    // Without handlers the store is only read from
+    let store: PgStore<A> = PgStoreBuilder::new(pool.clone())
+        .try_build()
+        .await
+        .map_err(|e| format!("Failed to open the {} events: {}", A::NAME, e))?;
+    let replayed = rebuild_read_model(pool, &store, &read_model)
+        .await
+        .map_err(|e| format!("Failed to rebuild {}: {}", R::TABLES.join(", "), e))?;
+    println!(
+        "Rebuilt {} from {} {} events",
+        R::TABLES.join(", "),
+        replayed,
+        A::NAME
+    );
+    Ok(())
+}
+
+async fn run(args: &[String]) -> Result<(), String> {
+    if args.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    let mut names: Vec<&str> = Vec::new();
+    for arg in args {
+        let requested = match arg.as_str() {
+            "all" => READ_MODELS.to_vec(),
+            name if READ_MODELS.contains(&name) => vec![name],
+            _ => return Err(USAGE.to_string()),
+        };
+        for name in requested {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+
+    let database_url = Config::default().postgres.database_url_from_env();
+    let pool = PgPoolOptions::new()
+        .max_connections(5)
+        .connect(&database_url)
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", database_url, e))?;
+
+    for name in names {
+        match name {
+            "path_planning" => rebuild::<PathPlanner, _>(&pool, PathPlanningReadModel).await?,
+            "logical_agent" => rebuild::<LogicalAgent, _>(&pool, LogicalAgentReadModel).await?,
+            _ => rebuild::<TechnicalAgent, _>(&pool, TechnicalAgentReadModel).await?,
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args).await {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,101 @@
+use esrs::Aggregate;
+use gryphon_app::adapters::inbound::esrs_pg_store::{
+    connect_pg_pool_in_schema, verify_esrs_chains,
+};
+use gryphon_app::adapters::inbound::FileEventStore;
+use gryphon_app::adapters::outbound::PostgresSnapshotStore;
+use gryphon_app::common::{verify_event_store, IntegrityReport, SnapshotStore};
+use gryphon_app::config::Config;
+use gryphon_app::esrs::{
+    DynamicsSimulator, GUIApplication, KinematicAgent, LogicalAgent, PathPlanner, TechnicalAgent,
+};
+
+const USAGE: &str = "Usage:
+  verify_history file [dir] [--snapshots]   walk the hash chains of a FileEventStore directory
+                                            (default: the configured one)
+  verify_history esrs                       walk the hash chains of the esrs events tables
+
+--snapshots also checks that each snapshot in the configured Postgres was built
+from the chain head the history has at its version. Events stored before hash
+chaining was introduced are counted but cannot be checked. Exits with an error
+at the first broken link.";
+
+const ESRS_AGGREGATES: [&str; 6] = [
+    PathPlanner::NAME,
+    LogicalAgent::NAME,
+    TechnicalAgent::NAME,
+    KinematicAgent::NAME,
+    DynamicsSimulator::NAME,
+    GUIApplication::NAME,
+];
+
+async fn verify_file(
+    dir: Option<&String>,
+    snapshots: bool,
+    config: &Config,
+) -> Result<IntegrityReport, String> {
+    let dir = dir
+        .cloned()
+        .unwrap_or_else(|| config.event_store.directory.clone());
+    let snapshot_store = if snapshots {
+        Some(PostgresSnapshotStore::new(config.postgres.clone()).await?)
+    } else {
+        None
+    };
+    let report = verify_event_store(
+        &FileEventStore::new(&dir),
+        snapshot_store
+            .as_ref()
+            .map(|store| store as &(dyn SnapshotStore + Send + Sync)),
+    )
+    .await?;
+    println!("{}: {}", dir, report);
+    Ok(report)
+}
+
+async fn verify_esrs(config: &Config) -> Result<IntegrityReport, String> {
+    let database_url = config.postgres.database_url_from_env();
+    let pool = connect_pg_pool_in_schema(&database_url, config.postgres.schema.as_deref())
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", database_url, e))?;
+
+    for name in ESRS_AGGREGATES {
+        let report = verify_esrs_chains(&pool, name)
+            .await
+            .map_err(|e| format!("Failed to verify the {} events: {}", name, e))?;
+        println!("{}: {}", name, report);
+        if !report.is_intact() {
+            return Ok(report);
+        }
+    }
+    Ok(IntegrityReport::default())
+}
+
+async fn run(args: &[String]) -> Result<(), String> {
+    let config = Config::default().with_tenant_from_env()?;
+    let report = match args {
+        [store, rest @ ..] if store == "file" => {
+            let snapshots = rest.iter().any(|arg| arg == "--snapshots");
+            let dirs: Vec<&String> = rest.iter().filter(|arg| *arg != "--snapshots").collect();
+            if dirs.len() > 1 {
+                return Err(USAGE.to_string());
+            }
+            verify_file(dirs.first().copied(), snapshots, &config).await?
+        }
+        [store] if store == "esrs" => verify_esrs(&config).await?,
+        _ => return Err(USAGE.to_string()),
+    };
+    match report.first_break {
+        Some(chain_break) => Err(format!("History is broken at {}", chain_break)),
+        None => Ok(()),
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args).await {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::common::EventEnvelope;
+use serde::{Deserialize, Serialize};
+
+/// Record header naming the codec a payload was written with
+pub const CONTENT_TYPE_HEADER: &str = "content-type";
+
+/// Wire format for event envelopes.
+///
+/// Consumers decode by the content-type header and fall back to sniffing the
+/// payload, so a topic can mix formats while producers switch over.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvelopeCodec {
+    #[default]
+    Json,
+    /// MessagePack with field names, so fields can still be added or reordered.
+    /// `event_data` is free-form JSON, which rules out non-self-describing
+    /// formats such as bincode.
+    MessagePack,
+}
+
+impl EnvelopeCodec {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            EnvelopeCodec::Json => "application/json",
+            EnvelopeCodec::MessagePack => "application/msgpack",
+        }
+    }
+
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        match content_type.split(';').next()?.trim() {
+            "application/json" => Some(EnvelopeCodec::Json),
+            "application/msgpack" | "application/x-msgpack" => Some(EnvelopeCodec::MessagePack),
+            _ => None,
+        }
+    }
+
+    /// Guess the codec of a payload without a header. An envelope is a map,
+    /// which JSON opens with `{` and MessagePack with a map marker.
+    pub fn sniff(payload: &[u8]) -> Option<Self> {
+        match payload.iter().find(|b| !b.is_ascii_whitespace())? {
+            b'{' => Some(EnvelopeCodec::Json),
+            0x80..=0x8f | 0xde | 0xdf => Some(EnvelopeCodec::MessagePack),
+            _ => None,
+        }
+    }
+
+    pub fn encode(&self, envelope: &EventEnvelope) -> Result<Vec<u8>, String> {
+        match self {
+            EnvelopeCodec::Json => serde_json::to_vec(envelope).map_err(|e| e.to_string()),
+            EnvelopeCodec::MessagePack => {
+                rmp_serde::to_vec_named(envelope).map_err(|e| e.to_string())
+            }
+        }
+        .map_err(|e| format!("Failed to serialize event: {}", e))
+    }
+
+    pub fn decode(&self, payload: &[u8]) -> Result<EventEnvelope, String> {
+        match self {
+            EnvelopeCodec::Json => serde_json::from_slice(payload).map_err(|e| e.to_string()),
+            EnvelopeCodec::MessagePack => rmp_serde::from_slice(payload).map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Decode a payload written by any codec: by its content type when the
+    /// record has one, else by sniffing
+    pub fn decode_any(content_type: Option<&str>, payload: &[u8]) -> Result<EventEnvelope, String> {
+        let codec = content_type
+            .and_then(Self::from_content_type)
+            .or_else(|| Self::sniff(payload))
+            .ok_or_else(|| match content_type {
+                Some(content_type) => format!("Unknown content type {}", content_type),
+                None => "Payload is neither JSON nor MessagePack".to_string(),
+            })?;
+        codec.decode(payload)
+    }
+}
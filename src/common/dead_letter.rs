@@ -0,0 +1,165 @@
+use crate::common::{BusMessage, EnvelopeCodec, EventEnvelope, EventMetadata, MessageBus};
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// A message that could not be decoded or kept failing, with where it came from
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DeadLetter {
+    pub id: Uuid,
+    pub source_topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    pub key: Option<String>,
+    /// The message as received. Edit it before replaying to fix the message.
+    pub payload: String,
+    /// How `payload` holds the received bytes
+    #[serde(default)]
+    pub payload_encoding: PayloadEncoding,
+    /// Content type header of the received record, used to decode it on replay
+    #[serde(default)]
+    pub content_type: Option<String>,
+    pub error: String,
+    /// Processing attempts before giving up; 0 if it never decoded
+    pub attempts: u32,
+    pub dead_lettered_at: DateTime<Utc>,
+}
+
+/// How a dead letter's payload is written
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadEncoding {
+    /// The received bytes were UTF-8 text and are kept as is
+    #[default]
+    Text,
+    /// The received bytes, such as MessagePack, in base64
+    Base64,
+}
+
+impl DeadLetter {
+    /// A payload that is not an event envelope. Bytes that are not UTF-8 are
+    /// kept in base64 so they can still be decoded with `content_type`.
+    pub fn poison(
+        source_topic: &str,
+        partition: i32,
+        offset: i64,
+        key: Option<String>,
+        payload: &[u8],
+        content_type: Option<&str>,
+        error: &str,
+    ) -> Self {
+        let (payload, payload_encoding) = match std::str::from_utf8(payload) {
+            Ok(text) => (text.to_string(), PayloadEncoding::Text),
+            Err(_) => (BASE64.encode(payload), PayloadEncoding::Base64),
+        };
+        Self {
+            id: Uuid::new_v4(),
+            source_topic: source_topic.to_string(),
+            partition,
+            offset,
+            key,
+            payload,
+            payload_encoding,
+            content_type: content_type.map(str::to_string),
+            error: error.to_string(),
+            attempts: 0,
+            dead_lettered_at: Utc::now(),
+        }
+    }
+
+    /// An envelope whose processing failed `attempts` times
+    pub fn failed(message: &BusMessage, error: &str, attempts: u32) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            source_topic: message.topic.clone(),
+            partition: message.partition,
+            offset: message.offset,
+            key: Some(format!(
+                "{}:{}",
+                message.envelope.aggregate_type, message.envelope.aggregate_id
+            )),
+            payload: serde_json::to_string(&message.envelope).unwrap_or_default(),
+            payload_encoding: PayloadEncoding::Text,
+            content_type: Some(EnvelopeCodec::Json.content_type().to_string()),
+            error: error.to_string(),
+            attempts,
+            dead_lettered_at: Utc::now(),
+        }
+    }
+
+    /// The bytes that were received, or their edited version
+    pub fn payload_bytes(&self) -> Result<Vec<u8>, String> {
+        match self.payload_encoding {
+            PayloadEncoding::Text => Ok(self.payload.clone().into_bytes()),
+            PayloadEncoding::Base64 => BASE64
+                .decode(&self.payload)
+                .map_err(|e| format!("Dead letter {} payload is not base64: {}", self.id, e)),
+        }
+    }
+
+    /// The envelope to re-inject, if the payload is one (again)
+    pub fn envelope(&self) -> Result<EventEnvelope, String> {
+        EnvelopeCodec::decode_any(self.content_type.as_deref(), &self.payload_bytes()?)
+            .map_err(|e| format!("Dead letter {} is not an event envelope: {}", self.id, e))
+    }
+
+    /// Wrap the letter so it can travel on a dead-letter topic
+    pub fn to_envelope(&self) -> Result<EventEnvelope, String> {
+        Ok(EventEnvelope {
+            event_id: self.id,
+            aggregate_id: self.source_topic.clone(),
+            aggregate_type: "DeadLetter".to_string(),
+            event_type: "MessageDeadLettered".to_string(),
+            event_version: 1,
+            event_data: serde_json::to_value(self)
+                .map_err(|e| format!("Failed to serialize dead letter: {}", e))?,
+            metadata: EventMetadata {
+                correlation_id: None,
+                causation_id: None,
+                user_id: None,
+                source: "dead_letter".to_string(),
+                reply_to: None,
+                tenant: None,
+            },
+            occurred_at: self.dead_lettered_at,
+            chain: None,
+        })
+    }
+
+    pub fn from_envelope(envelope: &EventEnvelope) -> Result<Self, String> {
+        serde_json::from_value(envelope.event_data.clone())
+            .map_err(|e| format!("Envelope {} is not a dead letter: {}", envelope.event_id, e))
+    }
+}
+
+/// Where consumers put messages they have given up on
+#[async_trait]
+pub trait DeadLetterQueue: Send + Sync {
+    async fn send(&self, letter: DeadLetter) -> Result<(), String>;
+}
+
+/// Dead-letter queue kept on a bus topic
+pub struct BusDeadLetterQueue {
+    bus: Arc<dyn MessageBus>,
+    topic: String,
+}
+
+impl BusDeadLetterQueue {
+    pub fn new(bus: Arc<dyn MessageBus>, topic: &str) -> Self {
+        Self {
+            bus,
+            topic: topic.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl DeadLetterQueue for BusDeadLetterQueue {
+    async fn send(&self, letter: DeadLetter) -> Result<(), String> {
+        self.bus.publish(&self.topic, &letter.to_envelope()?).await
+    }
+}
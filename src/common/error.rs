@@ -33,5 +33,23 @@ pub enum ApplicationError {
     Configuration(#[from] anyhow::Error),
 }
 
+#[derive(Error, Debug)]
+pub enum RpcError {
+    #[error("Message bus error: {0}")]
+    Bus(String),
+
+    #[error("No reply within {0:?}")]
+    Timeout(std::time::Duration),
+
+    #[error("Request was cancelled")]
+    Cancelled,
+
+    #[error("Reply could not be decoded: {0}")]
+    Decode(String),
+
+    #[error("Request failed: {0}")]
+    Failed(String),
+}
+
 pub type DomainResult<T> = Result<T, DomainError>;
 pub type ApplicationResult<T> = Result<T, ApplicationError>;
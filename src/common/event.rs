@@ -1,3 +1,4 @@
+use crate::common::ChainLink;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -19,6 +20,10 @@ pub struct EventEnvelope {
     pub event_data: serde_json::Value,
     pub metadata: EventMetadata,
     pub occurred_at: DateTime<Utc>,
+    /// Set by the event store on append; `None` before then, and in events
+    /// stored before hash chaining was introduced
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain: Option<ChainLink>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +32,12 @@ pub struct EventMetadata {
     pub causation_id: Option<Uuid>,
     pub user_id: Option<String>,
     pub source: String,
+    /// Topic the sender listens on for the reply to this message
+    #[serde(default)]
+    pub reply_to: Option<String>,
+    /// Tenant the event belongs to; `None` outside multi-tenant deployments
+    #[serde(default)]
+    pub tenant: Option<String>,
 }
 
 impl EventEnvelope {
@@ -44,6 +55,7 @@ impl EventEnvelope {
             event_data: serde_json::to_value(event)?,
             metadata,
             occurred_at: event.occurred_at(),
+            chain: None,
         })
     }
 }
@@ -68,4 +80,51 @@ pub trait EventStore {
         event_type: &str,
         from_timestamp: Option<DateTime<Utc>>,
     ) -> Result<Vec<EventEnvelope>, String>;
+
+    /// Every event of every aggregate, ordered by timestamp, skipping those
+    /// that occurred before `from_timestamp`
+    async fn load_all_events(
+        &self,
+        from_timestamp: Option<DateTime<Utc>>,
+    ) -> Result<Vec<EventEnvelope>, String>;
+}
+
+/// Append `events` at `*version`, the writer's own record of where the stream
+/// ends. When other writers appended in the meantime, only the events past
+/// `*version` are read, `*version` moves over them and the append is retried,
+/// at most `attempts` times. On success `*version` is the new end of the stream.
+pub async fn append_at_tracked_version<S>(
+    store: &S,
+    aggregate_id: &str,
+    version: &mut u64,
+    events: Vec<EventEnvelope>,
+    attempts: usize,
+) -> Result<(), String>
+where
+    S: EventStore + ?Sized,
+{
+    let count = events.len() as u64;
+    for _ in 0..attempts {
+        match store
+            .append_events(aggregate_id, *version, events.clone())
+            .await
+        {
+            Ok(()) => {
+                *version += count;
+                return Ok(());
+            }
+            Err(e) => {
+                let missed = store.load_events(aggregate_id, *version).await?.len() as u64;
+                if missed == 0 {
+                    // The stream did not move, so the append failed for another reason
+                    return Err(e);
+                }
+                *version += missed;
+            }
+        }
+    }
+    Err(format!(
+        "Stream {} kept moving; gave up appending after {} attempts",
+        aggregate_id, attempts
+    ))
 }
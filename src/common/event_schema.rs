@@ -0,0 +1,414 @@
+use crate::common::{EventEnvelope, MessageBus, MessageSubscription};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// JSON Schema of an event type, as published in the registry
+pub fn schema_of<E: schemars::JsonSchema>() -> Value {
+    serde_json::to_value(schemars::schema_for!(E)).unwrap_or(Value::Null)
+}
+
+/// A schema version stored under a subject, usually the event enum's name
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RegisteredSchema {
+    pub subject: String,
+    pub version: u32,
+    pub schema: Value,
+}
+
+/// Storage for event schemas. Use `register_schema` to add versions, so
+/// incompatible ones are rejected.
+#[async_trait]
+pub trait SchemaRegistry: Send + Sync {
+    async fn latest(&self, subject: &str) -> Result<Option<RegisteredSchema>, String>;
+
+    /// Store `schema` as the next version of `subject`
+    async fn append(&self, subject: &str, schema: Value) -> Result<RegisteredSchema, String>;
+}
+
+/// Register `schema` unless it breaks readers of the latest version. An
+/// unchanged schema returns the existing version.
+pub async fn register_schema(
+    registry: &dyn SchemaRegistry,
+    subject: &str,
+    schema: Value,
+) -> Result<RegisteredSchema, String> {
+    let Some(latest) = registry.latest(subject).await? else {
+        return registry.append(subject, schema).await;
+    };
+    if latest.schema == schema {
+        return Ok(latest);
+    }
+    check_backward_compatible(&latest.schema, &schema).map_err(|problems| {
+        format!(
+            "Schema for {} is incompatible with version {}: {}",
+            subject,
+            latest.version,
+            problems.join("; ")
+        )
+    })?;
+    registry.append(subject, schema).await
+}
+
+/// Check that everything valid under `old` is still valid under `new`, so
+/// events already published can be read with the new definition.
+///
+/// Adding variants and optional fields is fine. Removing or renaming a
+/// variant, adding a required field, making a field optional-to-required or
+/// changing its type are not. Returns the problems found.
+pub fn check_backward_compatible(old: &Value, new: &Value) -> Result<(), Vec<String>> {
+    let title = old.get("title").and_then(Value::as_str).unwrap_or("event");
+    let mut problems = Vec::new();
+    Compare { old, new }.check(old, new, title, &mut problems);
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems)
+    }
+}
+
+/// Variants of an event enum schema, as they appear in serialized events
+pub fn variant_names(schema: &Value) -> Vec<String> {
+    let mut names = Vec::new();
+    for alternative in alternatives(schema).unwrap_or_default() {
+        if let Some(values) = alternative.get("enum").and_then(Value::as_array) {
+            names.extend(values.iter().filter_map(Value::as_str).map(str::to_string));
+        } else if let Some(name) = variant_label(alternative) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// Variant name of externally tagged event data: its only key, or the string
+/// itself for a unit variant
+pub fn variant_of(event_data: &Value) -> Option<&str> {
+    match event_data {
+        Value::String(name) => Some(name),
+        Value::Object(map) if map.len() == 1 => map.keys().next().map(String::as_str),
+        _ => None,
+    }
+}
+
+fn alternatives(schema: &Value) -> Option<Vec<&Value>> {
+    schema
+        .get("oneOf")
+        .or_else(|| schema.get("anyOf"))
+        .and_then(Value::as_array)
+        .map(|alternatives| alternatives.iter().collect())
+}
+
+/// The variant an alternative stands for: the single key of an externally
+/// tagged variant object, or the single value of a string enum
+fn variant_label(schema: &Value) -> Option<&str> {
+    if let Some([value]) = schema
+        .get("enum")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+    {
+        return value.as_str();
+    }
+    if schema.get("additionalProperties") != Some(&Value::Bool(false)) {
+        return None;
+    }
+    match schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+    {
+        Some([key]) => key.as_str(),
+        _ => None,
+    }
+}
+
+fn types(schema: &Value) -> Option<Vec<&str>> {
+    match schema.get("type")? {
+        Value::String(t) => Some(vec![t.as_str()]),
+        Value::Array(ts) => Some(ts.iter().filter_map(Value::as_str).collect()),
+        _ => None,
+    }
+}
+
+fn required(schema: &Value) -> Vec<&str> {
+    schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|keys| keys.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+/// The two documents, for resolving `$ref`s on either side
+struct Compare<'a> {
+    old: &'a Value,
+    new: &'a Value,
+}
+
+impl<'a> Compare<'a> {
+    fn resolve(root: &'a Value, mut schema: &'a Value) -> &'a Value {
+        loop {
+            if let Some(name) = schema
+                .get("$ref")
+                .and_then(Value::as_str)
+                .and_then(|r| r.strip_prefix("#/definitions/"))
+            {
+                match root.get("definitions").and_then(|d| d.get(name)) {
+                    Some(target) => schema = target,
+                    None => return schema,
+                }
+            } else if let Some([only]) = schema
+                .get("allOf")
+                .and_then(Value::as_array)
+                .map(Vec::as_slice)
+            {
+                // schemars wraps documented references in a single-element allOf
+                schema = only;
+            } else {
+                return schema;
+            }
+        }
+    }
+
+    fn problems(&self, old: &'a Value, new: &'a Value, path: &str) -> Vec<String> {
+        let mut problems = Vec::new();
+        self.check(old, new, path, &mut problems);
+        problems
+    }
+
+    fn check(&self, old: &'a Value, new: &'a Value, path: &str, problems: &mut Vec<String>) {
+        let old = Self::resolve(self.old, old);
+        let new = Self::resolve(self.new, new);
+        if new == &Value::Bool(true) || new.as_object().is_some_and(|o| o.is_empty()) {
+            return;
+        }
+
+        if let Some(old_alternatives) = alternatives(old) {
+            let new_alternatives = alternatives(new).unwrap_or_else(|| vec![new]);
+            for old_alternative in old_alternatives {
+                self.check_alternative(old_alternative, &new_alternatives, path, problems);
+            }
+            return;
+        }
+        if let Some(new_alternatives) = alternatives(new) {
+            self.check_alternative(old, &new_alternatives, path, problems);
+            return;
+        }
+
+        let found = problems.len();
+        if let Some(new_types) = types(new) {
+            match types(old) {
+                Some(old_types) => {
+                    for t in old_types {
+                        let accepted = new_types.contains(&t)
+                            || (t == "integer" && new_types.contains(&"number"));
+                        if !accepted {
+                            problems.push(format!(
+                                "{} no longer accepts {} (now {})",
+                                path,
+                                t,
+                                new_types.join(" or ")
+                            ));
+                        }
+                    }
+                }
+                None => problems.push(format!(
+                    "{} is now restricted to {}",
+                    path,
+                    new_types.join(" or ")
+                )),
+            }
+        }
+        if problems.len() > found {
+            // A different type; its fields are not worth comparing
+            return;
+        }
+
+        if let Some(new_values) = new.get("enum").and_then(Value::as_array) {
+            match old.get("enum").and_then(Value::as_array) {
+                Some(old_values) => {
+                    for value in old_values.iter().filter(|v| !new_values.contains(v)) {
+                        problems.push(format!("{} no longer accepts {}", path, value));
+                    }
+                }
+                None => problems.push(format!("{} is now restricted to fixed values", path)),
+            }
+        }
+
+        let old_required = required(old);
+        for field in required(new) {
+            if !old_required.contains(&field) {
+                problems.push(format!("{}.{} is now required", path, field));
+            }
+        }
+        let new_properties = new.get("properties").and_then(Value::as_object);
+        if let Some(old_properties) = old.get("properties").and_then(Value::as_object) {
+            for (field, old_field) in old_properties {
+                match new_properties.and_then(|p| p.get(field)) {
+                    Some(new_field) => self.check(
+                        old_field,
+                        new_field,
+                        &format!("{}.{}", path, field),
+                        problems,
+                    ),
+                    None if new.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                        problems.push(format!(
+                            "{}.{} was removed and unknown fields are rejected",
+                            path, field
+                        ))
+                    }
+                    None => {}
+                }
+            }
+        }
+        if let (Some(old_values), Some(new_values)) = (
+            old.get("additionalProperties").filter(|v| v.is_object()),
+            new.get("additionalProperties").filter(|v| v.is_object()),
+        ) {
+            self.check(old_values, new_values, &format!("{}{{}}", path), problems);
+        }
+
+        match (old.get("items"), new.get("items")) {
+            (Some(Value::Array(old_items)), Some(Value::Array(new_items))) => {
+                if old_items.len() != new_items.len() {
+                    problems.push(format!("{} changed its number of elements", path));
+                }
+                for (i, (old_item, new_item)) in old_items.iter().zip(new_items).enumerate() {
+                    self.check(old_item, new_item, &format!("{}[{}]", path, i), problems);
+                }
+            }
+            (Some(old_items), Some(new_items)) => {
+                self.check(old_items, new_items, &format!("{}[]", path), problems)
+            }
+            _ => {}
+        }
+    }
+
+    /// `old` must be accepted by one of the new alternatives. A variant is
+    /// compared with the variant of the same name.
+    fn check_alternative(
+        &self,
+        old: &'a Value,
+        new_alternatives: &[&'a Value],
+        path: &str,
+        problems: &mut Vec<String>,
+    ) {
+        let old = Self::resolve(self.old, old);
+        if let Some(label) = variant_label(old) {
+            let path = format!("{}::{}", path, label);
+            let same_variant = new_alternatives.iter().find(|new| {
+                let new = Self::resolve(self.new, new);
+                variant_label(new) == Some(label)
+                    || new
+                        .get("enum")
+                        .and_then(Value::as_array)
+                        .is_some_and(|values| values.iter().any(|v| v.as_str() == Some(label)))
+            });
+            match same_variant {
+                // Compare the variant's fields rather than its wrapper object
+                Some(new) => match (
+                    old.get("properties").and_then(|p| p.get(label)),
+                    Self::resolve(self.new, new)
+                        .get("properties")
+                        .and_then(|p| p.get(label)),
+                ) {
+                    (Some(old_fields), Some(new_fields)) => {
+                        self.check(old_fields, new_fields, &path, problems)
+                    }
+                    _ => self.check(old, new, &path, problems),
+                },
+                None => problems.push(format!("{} was removed", path)),
+            }
+            return;
+        }
+
+        let mut closest: Option<Vec<String>> = None;
+        for new in new_alternatives {
+            let found = self.problems(old, new, path);
+            if found.is_empty() {
+                return;
+            }
+            if closest.as_ref().is_none_or(|c| found.len() < c.len()) {
+                closest = Some(found);
+            }
+        }
+        problems
+            .extend(closest.unwrap_or_else(|| vec![format!("{} has no alternatives left", path)]));
+    }
+}
+
+/// `MessageBus` decorator that registers the schema of each event enum the
+/// first time one of its events is published, and refuses to publish events
+/// whose schema would break readers of the registered version.
+///
+/// Events are matched to schemas by variant name; events of other types pass
+/// through unchecked.
+pub struct SchemaCheckedBus {
+    inner: Arc<dyn MessageBus>,
+    registry: Arc<dyn SchemaRegistry>,
+    schemas: HashMap<String, Value>,
+    subjects_by_variant: HashMap<String, String>,
+    registered: Mutex<HashMap<String, u32>>,
+}
+
+impl SchemaCheckedBus {
+    pub fn new(
+        inner: Arc<dyn MessageBus>,
+        registry: Arc<dyn SchemaRegistry>,
+        schemas: Vec<(&str, Value)>,
+    ) -> Self {
+        let mut subjects_by_variant = HashMap::new();
+        for (subject, schema) in &schemas {
+            for variant in variant_names(schema) {
+                subjects_by_variant.insert(variant, subject.to_string());
+            }
+        }
+        Self {
+            inner,
+            registry,
+            schemas: schemas
+                .into_iter()
+                .map(|(subject, schema)| (subject.to_string(), schema))
+                .collect(),
+            subjects_by_variant,
+            registered: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn ensure_registered(&self, subject: &str) -> Result<(), String> {
+        let mut registered = self.registered.lock().await;
+        if registered.contains_key(subject) {
+            return Ok(());
+        }
+        let schema = self.schemas[subject].clone();
+        let version = register_schema(self.registry.as_ref(), subject, schema).await?;
+        registered.insert(subject.to_string(), version.version);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MessageBus for SchemaCheckedBus {
+    async fn publish(&self, topic: &str, envelope: &EventEnvelope) -> Result<(), String> {
+        let subject = variant_of(&envelope.event_data)
+            .and_then(|variant| self.subjects_by_variant.get(variant));
+        if let Some(subject) = subject {
+            self.ensure_registered(subject).await.map_err(|e| {
+                format!(
+                    "Not publishing {} {}: {}",
+                    envelope.event_type, envelope.event_id, e
+                )
+            })?;
+        }
+        self.inner.publish(topic, envelope).await
+    }
+
+    async fn subscribe(
+        &self,
+        topic: &str,
+        group_id: &str,
+    ) -> Result<Box<dyn MessageSubscription>, String> {
+        self.inner.subscribe(topic, group_id).await
+    }
+}
@@ -0,0 +1,17 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+/// Consumer-side record of the events each consumer has already handled.
+///
+/// A consumer marks an event after handling it and before acknowledging it
+/// on the bus, and skips events it has marked. Redelivered messages, e.g.
+/// after a restart between handling and the offset commit, are then ignored.
+///
+/// Marking is not atomic with the handler's own writes: a crash between the
+/// two handles the event again, so handlers must tolerate a repeat.
+#[async_trait]
+pub trait Inbox: Send + Sync {
+    async fn is_processed(&self, consumer: &str, event_id: Uuid) -> Result<bool, String>;
+
+    async fn mark_processed(&self, consumer: &str, event_id: Uuid) -> Result<(), String>;
+}
@@ -0,0 +1,253 @@
+use crate::common::{EventEnvelope, EventStore, SnapshotStore};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Link of an event in its aggregate's hash chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainLink {
+    /// `hash` of the aggregate's previous event; `None` for the first event,
+    /// or the first one written after chaining was introduced
+    pub previous_hash: Option<String>,
+    /// SHA-256 over `previous_hash` and the rest of the event
+    pub hash: String,
+}
+
+/// JSON with object keys sorted, so a value hashes alike after a store
+/// reordered its keys, as Postgres `JSONB` does
+pub fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            let fields: Vec<String> = entries
+                .into_iter()
+                .map(|(key, value)| {
+                    format!("{}:{}", Value::String(key.clone()), canonical_json(value))
+                })
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+/// Hex SHA-256 of `previous_hash` followed by the canonical `content`
+pub fn chain_hash(previous_hash: Option<&str>, content: &Value) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(previous_hash.unwrap_or("").as_bytes());
+    hasher.update(b"\n");
+    hasher.update(canonical_json(content).as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// What an envelope's hash covers: everything but its chain link
+pub fn chained_content(envelope: &EventEnvelope) -> Result<Value, String> {
+    let mut content = serde_json::to_value(envelope)
+        .map_err(|e| format!("Failed to serialize event {}: {}", envelope.event_id, e))?;
+    if let Value::Object(fields) = &mut content {
+        fields.remove("chain");
+    }
+    Ok(content)
+}
+
+/// Link `events` to the aggregate's hash chain, after `previous`, its last
+/// stored event. Stores call this on append.
+pub fn chain_events(
+    previous: Option<&EventEnvelope>,
+    events: &mut [EventEnvelope],
+) -> Result<(), String> {
+    let mut previous_hash = previous
+        .and_then(|event| event.chain.as_ref())
+        .map(|link| link.hash.clone());
+    for event in events {
+        let hash = chain_hash(previous_hash.as_deref(), &chained_content(event)?);
+        event.chain = Some(ChainLink {
+            previous_hash: previous_hash.take(),
+            hash: hash.clone(),
+        });
+        previous_hash = Some(hash);
+    }
+    Ok(())
+}
+
+/// First point where stored history does not match its hash chain
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainBreak {
+    pub aggregate_id: String,
+    /// Position of the event in its aggregate's stream, from 1
+    pub version: u64,
+    pub reason: String,
+}
+
+impl fmt::Display for ChainBreak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} event {}: {}",
+            self.aggregate_id, self.version, self.reason
+        )
+    }
+}
+
+/// Result of walking a store's hash chains
+#[derive(Debug, Clone, Default)]
+pub struct IntegrityReport {
+    pub aggregates: usize,
+    pub events: usize,
+    /// Events written before chaining was introduced, which cannot be checked
+    pub unchained_events: usize,
+    /// Snapshots checked against the chain head they were built from
+    pub snapshots: usize,
+    /// The walk stops at the first break
+    pub first_break: Option<ChainBreak>,
+}
+
+impl IntegrityReport {
+    pub fn is_intact(&self) -> bool {
+        self.first_break.is_none()
+    }
+
+    /// Check one aggregate's stream, given each event's link and hashed
+    /// content in stream order. Returns the chain head of every version.
+    pub fn check_chain(
+        &mut self,
+        aggregate_id: &str,
+        records: &[(Option<ChainLink>, Value)],
+    ) -> Vec<Option<String>> {
+        self.aggregates += 1;
+        let mut heads = Vec::with_capacity(records.len());
+        let mut previous: Option<&ChainLink> = None;
+        for (index, (link, content)) in records.iter().enumerate() {
+            self.events += 1;
+            let broken = |reason: &str| ChainBreak {
+                aggregate_id: aggregate_id.to_string(),
+                version: index as u64 + 1,
+                reason: reason.to_string(),
+            };
+            let Some(link) = link else {
+                if previous.is_some() {
+                    self.first_break = Some(broken("has no hash although earlier events do"));
+                    break;
+                }
+                self.unchained_events += 1;
+                heads.push(None);
+                continue;
+            };
+            if link.previous_hash.as_deref() != previous.map(|link| link.hash.as_str()) {
+                self.first_break = Some(broken(
+                    "does not link to the previous event; events were removed, inserted or reordered",
+                ));
+                break;
+            }
+            if chain_hash(link.previous_hash.as_deref(), content) != link.hash {
+                self.first_break = Some(broken("content does not match its hash"));
+                break;
+            }
+            heads.push(Some(link.hash.clone()));
+            previous = Some(link);
+        }
+        heads
+    }
+
+    /// Check that a snapshot at `version` was built from the history whose
+    /// chain heads are `heads`
+    pub fn check_snapshot(
+        &mut self,
+        aggregate_id: &str,
+        version: u64,
+        chain_head: &str,
+        heads: &[Option<String>],
+    ) {
+        self.snapshots += 1;
+        let head = version
+            .checked_sub(1)
+            .and_then(|index| heads.get(index as usize))
+            .and_then(|head| head.as_deref());
+        if head != Some(chain_head) {
+            self.first_break = Some(ChainBreak {
+                aggregate_id: aggregate_id.to_string(),
+                version,
+                reason: format!(
+                    "snapshot was built from chain head {}, which the history does not have here",
+                    chain_head
+                ),
+            });
+        }
+    }
+}
+
+impl fmt::Display for IntegrityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} aggregates, {} events ({} from before chaining), {} snapshots: ",
+            self.aggregates, self.events, self.unchained_events, self.snapshots
+        )?;
+        match &self.first_break {
+            None => write!(f, "intact"),
+            Some(chain_break) => write!(f, "BROKEN at {}", chain_break),
+        }
+    }
+}
+
+/// Walk the hash chain of every aggregate in `event_store`, then check the
+/// chain heads recorded by the snapshots of `snapshot_store` when given
+pub async fn verify_event_store(
+    event_store: &(dyn EventStore + Send + Sync),
+    snapshot_store: Option<&(dyn SnapshotStore + Send + Sync)>,
+) -> Result<IntegrityReport, String> {
+    let mut aggregate_ids: Vec<String> = event_store
+        .load_all_events(None)
+        .await?
+        .into_iter()
+        .map(|event| event.aggregate_id)
+        .collect();
+    aggregate_ids.sort();
+    aggregate_ids.dedup();
+
+    let mut report = IntegrityReport::default();
+    let mut heads_by_aggregate = HashMap::new();
+    for aggregate_id in aggregate_ids {
+        // Stream order, which `load_all_events` does not keep
+        let events = event_store.load_events(&aggregate_id, 0).await?;
+        let records = events
+            .iter()
+            .map(|event| Ok((event.chain.clone(), chained_content(event)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        let heads = report.check_chain(&aggregate_id, &records);
+        if !report.is_intact() {
+            return Ok(report);
+        }
+        heads_by_aggregate.insert(aggregate_id, heads);
+    }
+
+    let Some(snapshot_store) = snapshot_store else {
+        return Ok(report);
+    };
+    for snapshot in snapshot_store.load_all_snapshots().await? {
+        let Some(chain_head) = &snapshot.chain_head else {
+            continue;
+        };
+        let heads = heads_by_aggregate
+            .get(&snapshot.aggregate_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        report.check_snapshot(
+            &snapshot.aggregate_id,
+            snapshot.aggregate_version,
+            chain_head,
+            heads,
+        );
+        if !report.is_intact() {
+            break;
+        }
+    }
+    Ok(report)
+}
@@ -0,0 +1,39 @@
+use crate::common::EventEnvelope;
+use async_trait::async_trait;
+
+/// An envelope delivered to a subscriber, with its position in the topic so it
+/// can be acknowledged.
+#[derive(Debug, Clone)]
+pub struct BusMessage {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    pub envelope: EventEnvelope,
+}
+
+/// Transport used by the planner, worker and client processes to exchange
+/// event envelopes.
+///
+/// Subscribers in the same consumer group share the messages of a topic, so
+/// each message reaches one of them. Every group receives every message.
+#[async_trait]
+pub trait MessageBus: Send + Sync {
+    async fn publish(&self, topic: &str, envelope: &EventEnvelope) -> Result<(), String>;
+
+    /// Subscribe to `topic` as a member of `group_id`. Messages published after
+    /// this call returns are delivered to the group.
+    async fn subscribe(
+        &self,
+        topic: &str,
+        group_id: &str,
+    ) -> Result<Box<dyn MessageSubscription>, String>;
+}
+
+#[async_trait]
+pub trait MessageSubscription: Send {
+    /// Wait for the next message. Returns `None` once the bus has shut down.
+    async fn next(&mut self) -> Result<Option<BusMessage>, String>;
+
+    /// Mark `message` as processed for this subscription's consumer group.
+    async fn ack(&mut self, message: &BusMessage) -> Result<(), String>;
+}
@@ -1,9 +1,31 @@
 pub mod aggregate;
+pub mod codec;
+pub mod dead_letter;
 pub mod error;
 pub mod event;
+pub mod event_schema;
+pub mod inbox;
+pub mod integrity;
+pub mod message_bus;
+pub mod outbox;
+pub mod personal_data;
+pub mod repository;
+pub mod request_reply;
 pub mod snapshot;
+pub mod tenant;
 
 pub use aggregate::*;
+pub use codec::*;
+pub use dead_letter::*;
 pub use error::*;
 pub use event::*;
+pub use event_schema::*;
+pub use inbox::*;
+pub use integrity::*;
+pub use message_bus::*;
+pub use outbox::*;
+pub use personal_data::*;
+pub use repository::*;
+pub use request_reply::*;
 pub use snapshot::*;
+pub use tenant::*;
@@ -0,0 +1,37 @@
+use crate::common::EventEnvelope;
+use async_trait::async_trait;
+use uuid::Uuid;
+
+/// An event waiting to be published. It is written in the same transaction as
+/// the event itself, so an event that was stored always ends up on the bus.
+#[derive(Debug, Clone)]
+pub struct OutboxMessage {
+    /// The envelope's `event_id`; consumers can use it to drop redeliveries
+    pub id: Uuid,
+    pub topic: String,
+    pub envelope: EventEnvelope,
+    /// Failed publish attempts so far
+    pub attempts: u32,
+}
+
+impl OutboxMessage {
+    pub fn new(topic: &str, envelope: EventEnvelope) -> Self {
+        Self {
+            id: envelope.event_id,
+            topic: topic.to_string(),
+            envelope,
+            attempts: 0,
+        }
+    }
+}
+
+/// Storage side of the transactional outbox, drained by `OutboxRelay`.
+#[async_trait]
+pub trait Outbox: Send + Sync {
+    /// Up to `limit` unsent messages, in the order they were written
+    async fn pending(&self, limit: usize) -> Result<Vec<OutboxMessage>, String>;
+
+    async fn mark_sent(&self, id: Uuid) -> Result<(), String>;
+
+    async fn record_failure(&self, id: Uuid, error: &str) -> Result<(), String>;
+}
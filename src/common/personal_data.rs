@@ -0,0 +1,407 @@
+use crate::common::{
+    BusMessage, DomainEvent, EventEnvelope, EventStore, MessageBus, MessageSubscription,
+};
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Prefix of an encrypted field: `enc:v1:<key id>:<nonce>:<ciphertext>`, the
+/// last two in base64. Encrypted fields stay strings, so `user_id: String`
+/// keeps deserializing and event schemas do not change.
+pub const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+/// What an encrypted field reads as once its subject's key is deleted
+pub const ERASED_PLACEHOLDER: &str = "<erased>";
+
+/// AES-256 key of one data subject
+#[derive(Clone)]
+pub struct SubjectKey {
+    /// Stored with every field encrypted under the key, in place of the
+    /// subject, who is personal data too
+    pub key_id: Uuid,
+    pub key: [u8; 32],
+}
+
+/// Keys of the data subjects that personal event fields are encrypted for.
+///
+/// Events are append-only, so they are never rewritten to remove personal
+/// data. Deleting a subject's key instead leaves every field encrypted for
+/// them unreadable, wherever the event was stored or copied.
+#[async_trait]
+pub trait KeyStore: Send + Sync {
+    /// The subject's key, created on first use
+    async fn key_for_subject(&self, subject: &str) -> Result<SubjectKey, String>;
+
+    /// `None` once the key's subject has been forgotten
+    async fn key_by_id(&self, key_id: Uuid) -> Result<Option<[u8; 32]>, String>;
+
+    /// Delete the subject's key; `false` when it had none
+    async fn forget_subject(&self, subject: &str) -> Result<bool, String>;
+}
+
+/// A payload field holding personal data
+#[derive(Debug, Clone)]
+pub struct PersonalField {
+    pub event_type: String,
+    /// JSON pointer into `event_data`
+    pub pointer: String,
+    /// JSON pointer to the field naming the data subject, `None` when the
+    /// field is itself the subject's identifier
+    pub subject_pointer: Option<String>,
+    /// Value substituted when the subject has been forgotten
+    pub placeholder: Value,
+}
+
+impl PersonalField {
+    /// An identifier field, encrypted for the subject it names
+    pub fn identifier(event_type: &str, pointer: &str) -> Self {
+        Self {
+            event_type: event_type.to_string(),
+            pointer: pointer.to_string(),
+            subject_pointer: None,
+            placeholder: Value::String(ERASED_PLACEHOLDER.to_string()),
+        }
+    }
+}
+
+/// Which parts of an envelope hold personal data
+#[derive(Debug, Clone)]
+pub struct PersonalDataPolicy {
+    pub fields: Vec<PersonalField>,
+    /// Encrypt `metadata.user_id` of every event for the user it names
+    pub metadata_user_id: bool,
+}
+
+impl Default for PersonalDataPolicy {
+    /// The personal data of the Gryphon domains: `EventMetadata.user_id` and
+    /// the user of `GUIEvent::UserSessionStarted`
+    fn default() -> Self {
+        Self {
+            fields: vec![PersonalField::identifier(
+                "UserSessionStarted",
+                "/UserSessionStarted/user_id",
+            )],
+            metadata_user_id: true,
+        }
+    }
+}
+
+/// Encrypts the personal fields of envelopes before they are stored and
+/// decrypts them on load, substituting placeholders for forgotten subjects
+#[derive(Clone)]
+pub struct PersonalDataCipher {
+    key_store: Arc<dyn KeyStore>,
+    policy: PersonalDataPolicy,
+}
+
+impl PersonalDataCipher {
+    pub fn new(key_store: Arc<dyn KeyStore>, policy: PersonalDataPolicy) -> Self {
+        Self { key_store, policy }
+    }
+
+    pub async fn encrypt(&self, mut envelope: EventEnvelope) -> Result<EventEnvelope, String> {
+        self.encrypt_payload(&envelope.event_type, &mut envelope.event_data)
+            .await?;
+
+        if self.policy.metadata_user_id {
+            if let Some(user_id) = &envelope.metadata.user_id {
+                if !user_id.starts_with(ENCRYPTED_PREFIX) {
+                    let sealed = self.seal(user_id, &Value::String(user_id.clone())).await?;
+                    envelope.metadata.user_id = Some(sealed);
+                }
+            }
+        }
+        Ok(envelope)
+    }
+
+    pub async fn decrypt(&self, mut envelope: EventEnvelope) -> Result<EventEnvelope, String> {
+        self.decrypt_payload(&envelope.event_type, &mut envelope.event_data)
+            .await?;
+
+        if let Some(user_id) = &envelope.metadata.user_id {
+            if user_id.starts_with(ENCRYPTED_PREFIX) {
+                let user_id = match self.open(user_id).await? {
+                    Some(Value::String(user_id)) => user_id,
+                    _ => ERASED_PLACEHOLDER.to_string(),
+                };
+                envelope.metadata.user_id = Some(user_id);
+            }
+        }
+        Ok(envelope)
+    }
+
+    /// Encrypt the personal fields of an event payload, as stored in
+    /// `EventEnvelope.event_data`
+    pub async fn encrypt_payload(
+        &self,
+        event_type: &str,
+        payload: &mut Value,
+    ) -> Result<(), String> {
+        // Subjects first, in case a subject field is encrypted too
+        let mut targets = Vec::new();
+        for field in self.fields_of(event_type) {
+            let Some(value) = payload.pointer(&field.pointer) else {
+                continue;
+            };
+            if is_encrypted(value) {
+                continue;
+            }
+            let subject = match &field.subject_pointer {
+                None => subject_name(value),
+                Some(pointer) => match payload.pointer(pointer) {
+                    Some(subject) => subject_name(subject),
+                    None => continue,
+                },
+            };
+            targets.push((field.pointer.clone(), subject));
+        }
+
+        for (pointer, subject) in targets {
+            let Some(value) = payload.pointer_mut(&pointer) else {
+                continue;
+            };
+            let sealed = self.seal(&subject, value).await?;
+            *value = Value::String(sealed);
+        }
+        Ok(())
+    }
+
+    pub async fn decrypt_payload(
+        &self,
+        event_type: &str,
+        payload: &mut Value,
+    ) -> Result<(), String> {
+        for field in self.fields_of(event_type) {
+            let Some(value) = payload.pointer_mut(&field.pointer) else {
+                continue;
+            };
+            let Some(token) = value
+                .as_str()
+                .filter(|token| token.starts_with(ENCRYPTED_PREFIX))
+            else {
+                continue;
+            };
+            let opened = self.open(token).await?;
+            *value = opened.unwrap_or_else(|| field.placeholder.clone());
+        }
+        Ok(())
+    }
+
+    /// A domain event with its personal fields encrypted, for stores that
+    /// persist events without an envelope, such as esrs. Encrypted fields
+    /// are strings, so only string fields may be personal.
+    pub async fn encrypt_event<E>(&self, event: &E) -> Result<E, String>
+    where
+        E: DomainEvent + Serialize + DeserializeOwned,
+    {
+        let mut payload = serde_json::to_value(event)
+            .map_err(|e| format!("Failed to serialize {}: {}", event.event_type(), e))?;
+        self.encrypt_payload(event.event_type(), &mut payload)
+            .await?;
+        serde_json::from_value(payload).map_err(|e| {
+            format!(
+                "Encrypted {} no longer deserializes: {}",
+                event.event_type(),
+                e
+            )
+        })
+    }
+
+    fn fields_of<'a>(&'a self, event_type: &'a str) -> impl Iterator<Item = &'a PersonalField> {
+        self.policy
+            .fields
+            .iter()
+            .filter(move |field| field.event_type == event_type)
+    }
+
+    async fn seal(&self, subject: &str, value: &Value) -> Result<String, String> {
+        let subject_key = self.key_store.key_for_subject(subject).await?;
+        let plaintext = serde_json::to_vec(value)
+            .map_err(|e| format!("Failed to serialize personal field: {}", e))?;
+        let nonce: [u8; 12] = rand::random();
+        let ciphertext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&subject_key.key))
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|_| "Failed to encrypt personal field".to_string())?;
+        Ok(format!(
+            "{}{}:{}:{}",
+            ENCRYPTED_PREFIX,
+            subject_key.key_id,
+            BASE64.encode(nonce),
+            BASE64.encode(ciphertext)
+        ))
+    }
+
+    /// The value sealed in `token`, `None` when its key has been deleted
+    async fn open(&self, token: &str) -> Result<Option<Value>, String> {
+        let malformed = || format!("Malformed encrypted field {}", token);
+        let mut parts = token
+            .strip_prefix(ENCRYPTED_PREFIX)
+            .ok_or_else(malformed)?
+            .splitn(3, ':');
+        let (Some(key_id), Some(nonce), Some(ciphertext)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(malformed());
+        };
+        let key_id = Uuid::parse_str(key_id).map_err(|_| malformed())?;
+        let nonce = BASE64.decode(nonce).map_err(|_| malformed())?;
+        let ciphertext = BASE64.decode(ciphertext).map_err(|_| malformed())?;
+        if nonce.len() != 12 {
+            return Err(malformed());
+        }
+
+        let Some(key) = self.key_store.key_by_id(key_id).await? else {
+            return Ok(None);
+        };
+        let plaintext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| format!("Failed to decrypt personal field with key {}", key_id))?;
+        serde_json::from_slice(&plaintext)
+            .map(Some)
+            .map_err(|e| format!("Failed to deserialize personal field: {}", e))
+    }
+}
+
+fn is_encrypted(value: &Value) -> bool {
+    value
+        .as_str()
+        .is_some_and(|value| value.starts_with(ENCRYPTED_PREFIX))
+}
+
+fn subject_name(value: &Value) -> String {
+    match value {
+        Value::String(subject) => subject.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Event store that encrypts personal fields on append and decrypts them on
+/// load, over any other store
+pub struct PersonalDataEventStore {
+    inner: Arc<dyn EventStore + Send + Sync>,
+    cipher: PersonalDataCipher,
+}
+
+impl PersonalDataEventStore {
+    pub fn new(inner: Arc<dyn EventStore + Send + Sync>, cipher: PersonalDataCipher) -> Self {
+        Self { inner, cipher }
+    }
+
+    async fn decrypt_all(&self, events: Vec<EventEnvelope>) -> Result<Vec<EventEnvelope>, String> {
+        let mut decrypted = Vec::with_capacity(events.len());
+        for event in events {
+            decrypted.push(self.cipher.decrypt(event).await?);
+        }
+        Ok(decrypted)
+    }
+}
+
+#[async_trait]
+impl EventStore for PersonalDataEventStore {
+    async fn append_events(
+        &self,
+        aggregate_id: &str,
+        expected_version: u64,
+        events: Vec<EventEnvelope>,
+    ) -> Result<(), String> {
+        let mut encrypted = Vec::with_capacity(events.len());
+        for event in events {
+            encrypted.push(self.cipher.encrypt(event).await?);
+        }
+        self.inner
+            .append_events(aggregate_id, expected_version, encrypted)
+            .await
+    }
+
+    async fn load_events(
+        &self,
+        aggregate_id: &str,
+        from_version: u64,
+    ) -> Result<Vec<EventEnvelope>, String> {
+        let events = self.inner.load_events(aggregate_id, from_version).await?;
+        self.decrypt_all(events).await
+    }
+
+    async fn load_events_by_type(
+        &self,
+        event_type: &str,
+        from_timestamp: Option<DateTime<Utc>>,
+    ) -> Result<Vec<EventEnvelope>, String> {
+        let events = self
+            .inner
+            .load_events_by_type(event_type, from_timestamp)
+            .await?;
+        self.decrypt_all(events).await
+    }
+
+    async fn load_all_events(
+        &self,
+        from_timestamp: Option<DateTime<Utc>>,
+    ) -> Result<Vec<EventEnvelope>, String> {
+        let events = self.inner.load_all_events(from_timestamp).await?;
+        self.decrypt_all(events).await
+    }
+}
+
+/// Message bus that encrypts personal fields of published envelopes and
+/// decrypts them for subscribers, over any other bus. Put it outermost, so
+/// that decorators mirroring published envelopes elsewhere, such as
+/// `EsrsMirroringBus`, only see them encrypted.
+pub struct PersonalDataBus {
+    inner: Arc<dyn MessageBus>,
+    cipher: PersonalDataCipher,
+}
+
+impl PersonalDataBus {
+    pub fn new(inner: Arc<dyn MessageBus>, cipher: PersonalDataCipher) -> Self {
+        Self { inner, cipher }
+    }
+}
+
+#[async_trait]
+impl MessageBus for PersonalDataBus {
+    async fn publish(&self, topic: &str, envelope: &EventEnvelope) -> Result<(), String> {
+        let envelope = self.cipher.encrypt(envelope.clone()).await?;
+        self.inner.publish(topic, &envelope).await
+    }
+
+    async fn subscribe(
+        &self,
+        topic: &str,
+        group_id: &str,
+    ) -> Result<Box<dyn MessageSubscription>, String> {
+        let inner = self.inner.subscribe(topic, group_id).await?;
+        Ok(Box::new(PersonalDataSubscription {
+            inner,
+            cipher: self.cipher.clone(),
+        }))
+    }
+}
+
+struct PersonalDataSubscription {
+    inner: Box<dyn MessageSubscription>,
+    cipher: PersonalDataCipher,
+}
+
+#[async_trait]
+impl MessageSubscription for PersonalDataSubscription {
+    async fn next(&mut self) -> Result<Option<BusMessage>, String> {
+        let Some(mut message) = self.inner.next().await? else {
+            return Ok(None);
+        };
+        message.envelope = self.cipher.decrypt(message.envelope).await?;
+        Ok(Some(message))
+    }
+
+    async fn ack(&mut self, message: &BusMessage) -> Result<(), String> {
+        self.inner.ack(message).await
+    }
+}
@@ -0,0 +1,196 @@
+use crate::common::{AggregateRoot, EventEnvelope, EventStore, Snapshot, SnapshotStore};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::sync::Arc;
+
+/// Point in an aggregate's history to load it at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AsOf {
+    /// After every stored event
+    Latest,
+    /// After this many events
+    Version(u64),
+    /// After every event that occurred at or before this time
+    Time(DateTime<Utc>),
+}
+
+/// An aggregate rebuilt at a point in its history
+#[derive(Debug, Clone)]
+pub struct HistoricalState<T> {
+    pub aggregate: T,
+    /// Number of events the state reflects
+    pub version: u64,
+    /// Version of the snapshot replay started from, if one was used
+    pub snapshot_version: Option<u64>,
+    /// When the last event the state reflects occurred, if it was replayed
+    pub last_event_at: Option<DateTime<Utc>>,
+    /// Hash of the last event the state reflects, when the history is chained
+    pub chain_head: Option<String>,
+}
+
+/// Loads aggregates from their events, starting from the best snapshot
+/// available when a snapshot store is attached
+pub struct AggregateRepository<T: AggregateRoot> {
+    event_store: Arc<dyn EventStore + Send + Sync>,
+    snapshot_store: Option<Arc<dyn SnapshotStore + Send + Sync>>,
+    new_aggregate: Arc<dyn Fn(&str) -> T + Send + Sync>,
+}
+
+impl<T: AggregateRoot + DeserializeOwned> AggregateRepository<T> {
+    /// `new_aggregate` builds the state events are replayed onto when there
+    /// is no snapshot
+    pub fn new(
+        event_store: Arc<dyn EventStore + Send + Sync>,
+        new_aggregate: impl Fn(&str) -> T + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            event_store,
+            snapshot_store: None,
+            new_aggregate: Arc::new(new_aggregate),
+        }
+    }
+
+    pub fn with_snapshots(mut self, snapshot_store: Arc<dyn SnapshotStore + Send + Sync>) -> Self {
+        self.snapshot_store = Some(snapshot_store);
+        self
+    }
+
+    /// Current state of the aggregate, or `None` if it has no history
+    pub async fn load(&self, aggregate_id: &str) -> Result<Option<HistoricalState<T>>, String> {
+        self.load_as_of(aggregate_id, AsOf::Latest).await
+    }
+
+    /// State of the aggregate at `as_of`, or `None` if it had no history yet.
+    /// A version past the end of the history gives the current state.
+    pub async fn load_as_of(
+        &self,
+        aggregate_id: &str,
+        as_of: AsOf,
+    ) -> Result<Option<HistoricalState<T>>, String> {
+        let (target, history) = match as_of {
+            AsOf::Latest => (u64::MAX, None),
+            AsOf::Version(version) => (version, None),
+            AsOf::Time(time) => {
+                // Event times are only known from the events, so the whole
+                // history is read to find the version at `time`
+                let events = self.event_store.load_events(aggregate_id, 0).await?;
+                let version = events
+                    .iter()
+                    .take_while(|event| event.occurred_at <= time)
+                    .count() as u64;
+                (version, Some(events))
+            }
+        };
+
+        let snapshot = match &self.snapshot_store {
+            // The latest snapshot is asked for as such, not as one at or
+            // before some version
+            Some(store) => {
+                let max_version = (as_of != AsOf::Latest).then_some(target);
+                store.load_snapshot(aggregate_id, max_version).await?
+            }
+            None => None,
+        };
+        let (mut aggregate, snapshot_version, mut chain_head) = match snapshot {
+            Some(snapshot) => {
+                let aggregate =
+                    serde_json::from_value::<T>(snapshot.snapshot_data).map_err(|e| {
+                        format!(
+                            "Failed to restore snapshot {} of {}: {}",
+                            snapshot.aggregate_version, aggregate_id, e
+                        )
+                    })?;
+                (
+                    aggregate,
+                    Some(snapshot.aggregate_version),
+                    snapshot.chain_head,
+                )
+            }
+            None => ((self.new_aggregate)(aggregate_id), None, None),
+        };
+        // Restored state has nothing left to persist
+        aggregate.mark_events_as_committed();
+
+        let from = snapshot_version.unwrap_or(0);
+        let events = match history {
+            Some(events) => events.into_iter().skip(from as usize).collect(),
+            None => self.event_store.load_events(aggregate_id, from).await?,
+        };
+        // A snapshot is only valid for the history it was built from
+        if let (Some(snapshot_head), Some(link)) = (
+            &chain_head,
+            events.first().and_then(|event| event.chain.as_ref()),
+        ) {
+            if link.previous_hash.as_ref() != Some(snapshot_head) {
+                return Err(format!(
+                    "Snapshot {} of {} was built from a different history than the stored one",
+                    from, aggregate_id
+                ));
+            }
+        }
+        let mut version = from;
+        let mut last_event_at = None;
+        for envelope in events.iter().take(target.saturating_sub(from) as usize) {
+            apply_envelope(&mut aggregate, envelope).map_err(|e| {
+                format!(
+                    "Failed to replay event {} of {}: {}",
+                    version + 1,
+                    aggregate_id,
+                    e
+                )
+            })?;
+            version += 1;
+            last_event_at = Some(envelope.occurred_at);
+            chain_head = envelope.chain.as_ref().map(|link| link.hash.clone());
+        }
+
+        if version == 0 {
+            return Ok(None);
+        }
+        Ok(Some(HistoricalState {
+            aggregate,
+            version,
+            snapshot_version,
+            last_event_at,
+            chain_head,
+        }))
+    }
+}
+
+impl<T: AggregateRoot + DeserializeOwned + Serialize> AggregateRepository<T> {
+    /// Snapshot the current state of the aggregate, recording the chain head
+    /// it was built from. `None` if it has no history or no snapshot store is
+    /// attached.
+    pub async fn save_snapshot(
+        &self,
+        aggregate_id: &str,
+        aggregate_type: &str,
+    ) -> Result<Option<Snapshot>, String> {
+        let Some(snapshot_store) = &self.snapshot_store else {
+            return Ok(None);
+        };
+        let Some(state) = self.load(aggregate_id).await? else {
+            return Ok(None);
+        };
+        let snapshot = Snapshot::new(
+            aggregate_id,
+            aggregate_type,
+            state.version,
+            &state.aggregate,
+        )
+        .map_err(|e| format!("Failed to snapshot {}: {}", aggregate_id, e))?
+        .with_chain_head(state.chain_head);
+        snapshot_store.save_snapshot(snapshot.clone()).await?;
+        Ok(Some(snapshot))
+    }
+}
+
+fn apply_envelope<T: AggregateRoot>(
+    aggregate: &mut T,
+    envelope: &EventEnvelope,
+) -> Result<(), String> {
+    let event = serde_json::from_value::<T::Event>(envelope.event_data.clone())
+        .map_err(|e| format!("unreadable {}: {}", envelope.event_type, e))?;
+    aggregate.apply(&event).map_err(|e| e.to_string())
+}
@@ -0,0 +1,169 @@
+use crate::common::{EventEnvelope, MessageBus, RpcError};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+type PendingReplies = Arc<Mutex<HashMap<Uuid, oneshot::Sender<EventEnvelope>>>>;
+
+/// Request/reply on top of a `MessageBus`.
+///
+/// Requests are stamped with a fresh correlation id and the client's reply topic
+/// as `reply_to`. A background task consumes the reply topic and hands each
+/// reply to the request with the same correlation id; replies nobody waits for
+/// are dropped, so many clients can share one reply topic.
+pub struct RequestReplyClient {
+    bus: Arc<dyn MessageBus>,
+    reply_topic: String,
+    pending: PendingReplies,
+    dispatcher: JoinHandle<()>,
+}
+
+impl RequestReplyClient {
+    /// Subscribe to `reply_topic` in a consumer group of its own and start routing replies.
+    pub async fn start(bus: Arc<dyn MessageBus>, reply_topic: &str) -> Result<Self, RpcError> {
+        let mut replies = bus
+            .subscribe(reply_topic, &format!("rpc-client-{}", Uuid::new_v4()))
+            .await
+            .map_err(RpcError::Bus)?;
+        let pending: PendingReplies = Arc::new(Mutex::new(HashMap::new()));
+
+        let routes = pending.clone();
+        let dispatcher = tokio::spawn(async move {
+            loop {
+                let message = match replies.next().await {
+                    Ok(Some(message)) => message,
+                    Ok(None) => break,
+                    Err(e) => {
+                        tracing::warn!("Reply consumer error: {}", e);
+                        tokio::time::sleep(Duration::from_millis(200)).await;
+                        continue;
+                    }
+                };
+                let waiter = message
+                    .envelope
+                    .metadata
+                    .correlation_id
+                    .and_then(|id| routes.lock().unwrap().remove(&id));
+                if let Some(waiter) = waiter {
+                    let _ = waiter.send(message.envelope.clone());
+                }
+                if let Err(e) = replies.ack(&message).await {
+                    tracing::warn!("Failed to acknowledge reply: {}", e);
+                }
+            }
+        });
+
+        Ok(Self {
+            bus,
+            reply_topic: reply_topic.to_string(),
+            pending,
+            dispatcher,
+        })
+    }
+
+    pub fn reply_topic(&self) -> &str {
+        &self.reply_topic
+    }
+
+    /// Requests still waiting for a reply
+    pub fn pending_requests(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    /// Publish `request` to `topic` and return a handle for its reply. The
+    /// envelope's correlation id and `reply_to` are overwritten.
+    pub async fn send(
+        &self,
+        topic: &str,
+        mut request: EventEnvelope,
+    ) -> Result<PendingReply, RpcError> {
+        let correlation_id = Uuid::new_v4();
+        request.metadata.correlation_id = Some(correlation_id);
+        request.metadata.reply_to = Some(self.reply_topic.clone());
+
+        // Register before publishing so a fast reply cannot arrive unclaimed
+        let (sender, receiver) = oneshot::channel();
+        self.pending.lock().unwrap().insert(correlation_id, sender);
+        let pending = PendingReply {
+            correlation_id,
+            receiver,
+            pending: self.pending.clone(),
+        };
+
+        self.bus
+            .publish(topic, &request)
+            .await
+            .map_err(RpcError::Bus)?;
+        Ok(pending)
+    }
+
+    /// Send `request` and decode the reply's event data as `R`.
+    pub async fn request<R: DeserializeOwned>(
+        &self,
+        topic: &str,
+        request: EventEnvelope,
+        timeout: Duration,
+    ) -> Result<R, RpcError> {
+        let reply = self.send(topic, request).await?.wait(timeout).await?;
+        serde_json::from_value(reply.event_data).map_err(|e| RpcError::Decode(e.to_string()))
+    }
+}
+
+impl Drop for RequestReplyClient {
+    fn drop(&mut self) {
+        self.dispatcher.abort();
+    }
+}
+
+/// A request waiting for its reply. Dropping it cancels the request: a reply
+/// that arrives afterwards is discarded.
+pub struct PendingReply {
+    correlation_id: Uuid,
+    receiver: oneshot::Receiver<EventEnvelope>,
+    pending: PendingReplies,
+}
+
+impl PendingReply {
+    pub fn correlation_id(&self) -> Uuid {
+        self.correlation_id
+    }
+
+    pub async fn wait(mut self, timeout: Duration) -> Result<EventEnvelope, RpcError> {
+        match tokio::time::timeout(timeout, &mut self.receiver).await {
+            Ok(Ok(reply)) => Ok(reply),
+            // The client shut down and dropped every waiter
+            Ok(Err(_)) => Err(RpcError::Cancelled),
+            Err(_) => Err(RpcError::Timeout(timeout)),
+        }
+    }
+
+    /// Stop waiting for the reply
+    pub fn cancel(self) {}
+}
+
+impl Drop for PendingReply {
+    fn drop(&mut self) {
+        self.pending.lock().unwrap().remove(&self.correlation_id);
+    }
+}
+
+/// Answer `request` with `reply` on the request's `reply_to` topic, carrying its
+/// correlation id. Returns `false` when the request did not ask for a reply.
+pub async fn send_reply(
+    bus: &dyn MessageBus,
+    request: &EventEnvelope,
+    mut reply: EventEnvelope,
+) -> Result<bool, String> {
+    let Some(reply_to) = &request.metadata.reply_to else {
+        return Ok(false);
+    };
+    reply.metadata.correlation_id = request.metadata.correlation_id;
+    reply.metadata.causation_id = Some(request.event_id);
+    reply.metadata.reply_to = None;
+    bus.publish(reply_to, &reply).await?;
+    Ok(true)
+}
@@ -10,6 +10,10 @@ pub struct Snapshot {
     pub aggregate_version: u64,
     pub snapshot_data: serde_json::Value,
     pub created_at: DateTime<Utc>,
+    /// Hash of the event at `aggregate_version` the state was built from,
+    /// when the history is hash chained
+    #[serde(default)]
+    pub chain_head: Option<String>,
 }
 
 impl Snapshot {
@@ -26,8 +30,14 @@ impl Snapshot {
             aggregate_version,
             snapshot_data: serde_json::to_value(aggregate_data)?,
             created_at: Utc::now(),
+            chain_head: None,
         })
     }
+
+    pub fn with_chain_head(mut self, chain_head: Option<String>) -> Self {
+        self.chain_head = chain_head;
+        self
+    }
 }
 
 #[async_trait::async_trait]
@@ -42,4 +52,7 @@ pub trait SnapshotStore {
 
     async fn delete_snapshots_before(&self, aggregate_id: &str, version: u64)
         -> Result<(), String>;
+
+    /// Every snapshot of every aggregate, ordered by aggregate id and version
+    async fn load_all_snapshots(&self) -> Result<Vec<Snapshot>, String>;
 }
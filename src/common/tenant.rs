@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::common::{BusMessage, EventEnvelope, MessageBus, MessageSubscription};
+
+/// Check that `tenant` can be used in topic names, directory names and
+/// Postgres schema names: lowercase letters, digits and `_`, starting with a
+/// letter. `-` is refused as Postgres identifiers cannot hold it unquoted.
+pub fn validate_tenant_id(tenant: &str) -> Result<(), String> {
+    let mut chars = tenant.chars();
+    let starts_with_letter = chars.next().is_some_and(|c| c.is_ascii_lowercase());
+    let rest_allowed = chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !starts_with_letter || !rest_allowed || tenant.len() > 48 {
+        return Err(format!(
+            "Invalid tenant id {:?}: use at most 48 lowercase letters, digits or '_', starting with a letter",
+            tenant
+        ));
+    }
+    Ok(())
+}
+
+/// Postgres schema holding a tenant's tables
+pub fn tenant_schema(tenant: &str) -> String {
+    format!("tenant_{}", tenant)
+}
+
+/// Bus decorator that keeps one tenant's envelopes apart from the others'
+/// when topics are shared: published envelopes are stamped with the tenant,
+/// and subscribers only see envelopes of their own tenant. Envelopes of other
+/// tenants are acknowledged and skipped, so tenants must not share consumer
+/// groups; `Config::for_tenant` gives each tenant its own.
+pub struct TenantScopedBus {
+    inner: Arc<dyn MessageBus>,
+    tenant: String,
+}
+
+impl TenantScopedBus {
+    pub fn new(inner: Arc<dyn MessageBus>, tenant: &str) -> Result<Self, String> {
+        validate_tenant_id(tenant)?;
+        Ok(Self {
+            inner,
+            tenant: tenant.to_string(),
+        })
+    }
+
+    pub fn tenant(&self) -> &str {
+        &self.tenant
+    }
+}
+
+#[async_trait]
+impl MessageBus for TenantScopedBus {
+    async fn publish(&self, topic: &str, envelope: &EventEnvelope) -> Result<(), String> {
+        match envelope.metadata.tenant.as_deref() {
+            Some(tenant) if tenant != self.tenant => Err(format!(
+                "Not publishing {} {} of tenant {} on the bus of tenant {}",
+                envelope.event_type, envelope.event_id, tenant, self.tenant
+            )),
+            Some(_) => self.inner.publish(topic, envelope).await,
+            None => {
+                let mut envelope = envelope.clone();
+                envelope.metadata.tenant = Some(self.tenant.clone());
+                self.inner.publish(topic, &envelope).await
+            }
+        }
+    }
+
+    async fn subscribe(
+        &self,
+        topic: &str,
+        group_id: &str,
+    ) -> Result<Box<dyn MessageSubscription>, String> {
+        let inner = self.inner.subscribe(topic, group_id).await?;
+        Ok(Box::new(TenantScopedSubscription {
+            inner,
+            tenant: self.tenant.clone(),
+        }))
+    }
+}
+
+struct TenantScopedSubscription {
+    inner: Box<dyn MessageSubscription>,
+    tenant: String,
+}
+
+#[async_trait]
+impl MessageSubscription for TenantScopedSubscription {
+    async fn next(&mut self) -> Result<Option<BusMessage>, String> {
+        loop {
+            let Some(message) = self.inner.next().await? else {
+                return Ok(None);
+            };
+            if message.envelope.metadata.tenant.as_deref() == Some(self.tenant.as_str()) {
+                return Ok(Some(message));
+            }
+            self.inner.ack(&message).await?;
+        }
+    }
+
+    async fn ack(&mut self, message: &BusMessage) -> Result<(), String> {
+        self.inner.ack(message).await
+    }
+}
@@ -1,12 +1,18 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Tenant this configuration is scoped to; see `Config::for_tenant`
+    #[serde(default)]
+    pub tenant: Option<String>,
     pub kafka: KafkaConfig,
     pub postgres: PostgresConfig,
     pub event_store: EventStoreConfig,
+    #[serde(default)]
+    pub path_planning: PathPlanningConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +21,15 @@ pub struct KafkaConfig {
     pub client_id: String,
     pub group_id: String,
     pub topics: KafkaTopics,
+    /// Settings used to create and validate the topics above
+    #[serde(default)]
+    pub topic_settings: KafkaTopicSettings,
+    /// How every producer picks the record key, and with it the partition
+    #[serde(default)]
+    pub partitioning: PartitionStrategy,
+    /// Wire format producers write envelopes in. Consumers read every format.
+    #[serde(default)]
+    pub codec: crate::common::EnvelopeCodec,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,8 +38,160 @@ pub struct KafkaTopics {
     pub technical_agent_events: String,
     pub kinematic_agent_events: String,
     pub path_planning_events: String,
+    /// Replies to path planning requests, matched by correlation id
+    #[serde(default = "default_path_planning_replies")]
+    pub path_planning_replies: String,
+    /// Path planning events as persisted in the esrs store, published by its
+    /// outbox relay. The services publish on `path_planning_events` first, so
+    /// the stored copies get a topic of their own rather than be handled twice.
+    #[serde(default = "default_path_planning_store_events")]
+    pub path_planning_store_events: String,
     pub dynamics_events: String,
     pub gui_events: String,
+    /// Messages consumers could not decode or process
+    #[serde(default = "default_dead_letters")]
+    pub dead_letters: String,
+}
+
+fn default_path_planning_replies() -> String {
+    "path-planning-replies".to_string()
+}
+
+fn default_path_planning_store_events() -> String {
+    "path-planning-store-events".to_string()
+}
+
+fn default_dead_letters() -> String {
+    "dead-letters".to_string()
+}
+
+impl KafkaTopics {
+    pub fn all(&self) -> Vec<&str> {
+        vec![
+            &self.logical_agent_events,
+            &self.technical_agent_events,
+            &self.kinematic_agent_events,
+            &self.path_planning_events,
+            &self.path_planning_replies,
+            &self.path_planning_store_events,
+            &self.dynamics_events,
+            &self.gui_events,
+            &self.dead_letters,
+        ]
+    }
+}
+
+/// Kafka `cleanup.policy`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanupPolicy {
+    Delete,
+    /// Keep the latest record per key; for topics that hold current state
+    Compact,
+    CompactDelete,
+}
+
+impl CleanupPolicy {
+    pub fn as_kafka_str(&self) -> &'static str {
+        match self {
+            CleanupPolicy::Delete => "delete",
+            CleanupPolicy::Compact => "compact",
+            CleanupPolicy::CompactDelete => "compact,delete",
+        }
+    }
+}
+
+/// Record key used by Kafka producers. Kafka only orders records within a
+/// partition, so events that must stay in order need the same key.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PartitionStrategy {
+    /// Key by aggregate id alone, so everything about one planner (planner,
+    /// worker and esrs events alike) shares a partition
+    #[default]
+    AggregateId,
+    /// Key by `"<aggregate_type>:<aggregate_id>"`. Planner events all carry
+    /// `PathPlanner::AGGREGATE_TYPE`, so they still share a partition.
+    AggregateTypeAndId,
+}
+
+impl PartitionStrategy {
+    pub fn key(&self, aggregate_type: &str, aggregate_id: &str) -> String {
+        match self {
+            PartitionStrategy::AggregateId => aggregate_id.to_string(),
+            PartitionStrategy::AggregateTypeAndId => format!("{}:{}", aggregate_type, aggregate_id),
+        }
+    }
+
+    pub fn key_for(&self, envelope: &crate::common::EventEnvelope) -> String {
+        self.key(&envelope.aggregate_type, &envelope.aggregate_id)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TopicSettings {
+    pub partitions: i32,
+    pub replication_factor: i32,
+    pub cleanup_policy: CleanupPolicy,
+    /// `retention.ms`; -1 keeps records forever
+    pub retention_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct KafkaTopicSettings {
+    /// Used for every topic without an override. Event topics double as the
+    /// event store and are replayed from the start, so they are never expired.
+    #[serde(default = "default_topic_settings")]
+    pub default: TopicSettings,
+    /// Per topic name
+    #[serde(default)]
+    pub overrides: HashMap<String, TopicSettings>,
+    /// What the services do about their topics when they start
+    #[serde(default)]
+    pub on_startup: TopicStartup,
+}
+
+/// Topic check the services run at startup, see `create_kafka_topics`
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TopicStartup {
+    /// Leave the topics alone
+    Skip,
+    /// Create missing topics and warn about existing ones that drifted
+    #[default]
+    Warn,
+    /// Create missing topics and refuse to start when existing ones drifted
+    Fail,
+}
+
+fn default_topic_settings() -> TopicSettings {
+    TopicSettings {
+        partitions: 3,
+        replication_factor: 1,
+        cleanup_policy: CleanupPolicy::Delete,
+        retention_ms: -1,
+    }
+}
+
+impl KafkaTopicSettings {
+    pub fn for_topic(&self, topic: &str) -> &TopicSettings {
+        self.overrides.get(topic).unwrap_or(&self.default)
+    }
+}
+
+impl Default for KafkaTopicSettings {
+    fn default() -> Self {
+        // Replies are only useful while a requester is still waiting
+        let replies = TopicSettings {
+            retention_ms: 60 * 60 * 1000,
+            ..default_topic_settings()
+        };
+        Self {
+            default: default_topic_settings(),
+            overrides: HashMap::from([(default_path_planning_replies(), replies)]),
+            on_startup: TopicStartup::default(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,25 +202,152 @@ pub struct PostgresConfig {
     pub username: String,
     pub password: String,
     pub max_connections: u32,
+    /// Schema every table is created in; `None` uses the server's search path
+    #[serde(default)]
+    pub schema: Option<String>,
+}
+
+impl PostgresConfig {
+    /// Connection options putting `schema` first on the search path, in the
+    /// form libpq and deadpool's `options` expect
+    pub fn connection_options(&self) -> Option<String> {
+        self.schema
+            .as_ref()
+            .map(|schema| format!("-c search_path={}", schema))
+    }
+
+    /// Connection URL of the configured database, for sqlx
+    pub fn database_url(&self) -> String {
+        format!(
+            "postgres://{}:{}@{}:{}/{}",
+            self.username, self.password, self.host, self.port, self.database
+        )
+    }
+
+    /// `DATABASE_URL` when set, the configured database otherwise
+    pub fn database_url_from_env(&self) -> String {
+        std::env::var("DATABASE_URL").unwrap_or_else(|_| self.database_url())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventStoreConfig {
     pub snapshot_frequency: u64,
     pub batch_size: usize,
+    /// Directory of the schema registry that published events are checked against
+    #[serde(default = "default_schema_registry_path")]
+    pub schema_registry_path: String,
+    /// Directory of the file event store shared by the demo processes
+    #[serde(default = "default_event_store_directory")]
+    pub directory: String,
+}
+
+fn default_event_store_directory() -> String {
+    "/tmp/gryphon-events".to_string()
+}
+
+fn default_schema_registry_path() -> String {
+    "schemas".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathPlanningConfig {
+    /// How long finished plans stay in the PathPlanner aggregate before being archived
+    pub plan_retention_seconds: u64,
+    /// Planner the services and clients work with
+    #[serde(default = "default_planner_id")]
+    pub planner_id: String,
+}
+
+fn default_planner_id() -> String {
+    "main-path-planner".to_string()
+}
+
+impl Default for PathPlanningConfig {
+    fn default() -> Self {
+        Self {
+            plan_retention_seconds: 3600,
+            planner_id: default_planner_id(),
+        }
+    }
 }
 
 impl Config {
     pub async fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
         let content = tokio::fs::read_to_string(path).await?;
-        let config: Config = toml::from_str(&content)?;
-        Ok(config)
+        let mut config: Config = toml::from_str(&content)?;
+        // A tenant named in the file scopes it like `for_tenant`
+        match config.tenant.take() {
+            Some(tenant) => config.for_tenant(&tenant).map_err(anyhow::Error::msg),
+            None => Ok(config),
+        }
+    }
+
+    /// Scope every section to `tenant`, so tenants sharing a broker, a
+    /// database or a machine never see each other's events:
+    /// - Kafka topics get a `<tenant>.` prefix, client and group ids a `<tenant>-` prefix
+    /// - Postgres tables live in a schema named after the tenant
+    /// - the file event store uses a `<tenant>` subdirectory
+    ///
+    /// Schemas in the schema registry describe the code, so they stay shared.
+    pub fn for_tenant(mut self, tenant: &str) -> Result<Self, String> {
+        crate::common::validate_tenant_id(tenant)?;
+        if let Some(current) = &self.tenant {
+            return Err(format!(
+                "Configuration is already scoped to tenant {}",
+                current
+            ));
+        }
+
+        let prefixed = |topic: &str| format!("{}.{}", tenant, topic);
+        let topics = &mut self.kafka.topics;
+        for topic in [
+            &mut topics.logical_agent_events,
+            &mut topics.technical_agent_events,
+            &mut topics.kinematic_agent_events,
+            &mut topics.path_planning_events,
+            &mut topics.path_planning_replies,
+            &mut topics.path_planning_store_events,
+            &mut topics.dynamics_events,
+            &mut topics.gui_events,
+            &mut topics.dead_letters,
+        ] {
+            *topic = prefixed(topic);
+        }
+        self.kafka.topic_settings.overrides = self
+            .kafka
+            .topic_settings
+            .overrides
+            .drain()
+            .map(|(topic, settings)| (prefixed(&topic), settings))
+            .collect();
+        self.kafka.client_id = format!("{}-{}", tenant, self.kafka.client_id);
+        self.kafka.group_id = format!("{}-{}", tenant, self.kafka.group_id);
+
+        self.postgres.schema = Some(crate::common::tenant_schema(tenant));
+        self.event_store.directory = Path::new(&self.event_store.directory)
+            .join(tenant)
+            .to_string_lossy()
+            .into_owned();
+        self.tenant = Some(tenant.to_string());
+        Ok(self)
+    }
+
+    /// `for_tenant` with the tenant named by `GRYPHON_TENANT`, if it is set
+    /// and the configuration is not already scoped to it
+    pub fn with_tenant_from_env(self) -> Result<Self, String> {
+        match std::env::var("GRYPHON_TENANT") {
+            Ok(tenant) if self.tenant.as_deref() == Some(tenant.as_str()) => Ok(self),
+            Ok(tenant) if !tenant.is_empty() => self.for_tenant(&tenant),
+            _ => Ok(self),
+        }
     }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            tenant: None,
             kafka: KafkaConfig {
                 brokers: vec!["localhost:9092".to_string()],
                 client_id: "gryphon-app".to_string(),
@@ -63,9 +357,15 @@ impl Default for Config {
                     technical_agent_events: "technical-agent-events".to_string(),
                     kinematic_agent_events: "kinematic-agent-events".to_string(),
                     path_planning_events: "path-planning-events".to_string(),
+                    path_planning_replies: default_path_planning_replies(),
+                    path_planning_store_events: default_path_planning_store_events(),
                     dynamics_events: "dynamics-events".to_string(),
                     gui_events: "gui-events".to_string(),
+                    dead_letters: default_dead_letters(),
                 },
+                topic_settings: KafkaTopicSettings::default(),
+                partitioning: PartitionStrategy::default(),
+                codec: crate::common::EnvelopeCodec::default(),
             },
             postgres: PostgresConfig {
                 host: "localhost".to_string(),
@@ -74,11 +374,15 @@ impl Default for Config {
                 username: "postgres".to_string(),
                 password: "password".to_string(),
                 max_connections: 10,
+                schema: None,
             },
             event_store: EventStoreConfig {
                 snapshot_frequency: 100,
                 batch_size: 50,
+                schema_registry_path: default_schema_registry_path(),
+                directory: default_event_store_directory(),
             },
+            path_planning: PathPlanningConfig::default(),
         }
     }
 }
@@ -1,5 +1,6 @@
 use super::events::DynamicsEvent;
 use crate::common::{AggregateRoot, DomainResult};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,7 +14,7 @@ pub struct DynamicsSimulator {
     uncommitted_events: Vec<DynamicsEvent>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum PhysicsModel {
     Newtonian,
     Relativistic,
@@ -21,7 +22,7 @@ pub enum PhysicsModel {
     Simplified,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum SimulationState {
     Stopped,
     Running,
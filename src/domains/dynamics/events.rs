@@ -1,9 +1,10 @@
 use super::aggregate::{PhysicsModel, SimulationState};
 use crate::common::DomainEvent;
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum DynamicsEvent {
     SimulatorCreated {
         simulator_id: String,
@@ -1,5 +1,6 @@
 use super::events::GUIEvent;
 use crate::common::{AggregateRoot, DomainResult};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -25,7 +26,7 @@ pub struct Window {
     pub components: Vec<UIComponent>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum WindowType {
     Main,
     Dialog,
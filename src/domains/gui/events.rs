@@ -1,10 +1,11 @@
 use super::aggregate::WindowType;
 use crate::common::DomainEvent;
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum GUIEvent {
     ApplicationCreated {
         app_id: String,
@@ -1,5 +1,6 @@
 use super::events::KinematicAgentEvent;
 use crate::common::{AggregateRoot, DomainError, DomainResult};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,7 +18,7 @@ pub struct KinematicAgent {
     uncommitted_events: Vec<KinematicAgentEvent>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Position3D {
     pub x: f64,
     pub y: f64,
@@ -25,21 +26,21 @@ pub struct Position3D {
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Velocity3D {
     pub x: f64,
     pub y: f64,
     pub z: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Acceleration3D {
     pub x: f64,
     pub y: f64,
     pub z: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Orientation {
     pub roll: f64,
     pub pitch: f64,
@@ -53,7 +54,7 @@ pub struct AngularVelocity {
     pub yaw_rate: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum KinematicsModel {
     PointMass,
     RigidBody,
@@ -81,6 +82,37 @@ pub struct WorkspaceBounds {
     pub max_z: f64,
 }
 
+impl Default for MovementConstraints {
+    fn default() -> Self {
+        Self {
+            max_velocity: 10.0,
+            max_acceleration: 5.0,
+            max_angular_velocity: std::f64::consts::PI,
+            min_turning_radius: 1.0,
+            workspace_bounds: WorkspaceBounds {
+                min_x: -100.0,
+                max_x: 100.0,
+                min_y: -100.0,
+                max_y: 100.0,
+                min_z: 0.0,
+                max_z: 50.0,
+            },
+        }
+    }
+}
+
+impl MovementConstraints {
+    pub fn contains(&self, position: &Position3D) -> bool {
+        let bounds = &self.workspace_bounds;
+        position.x >= bounds.min_x
+            && position.x <= bounds.max_x
+            && position.y >= bounds.min_y
+            && position.y <= bounds.max_y
+            && position.z >= bounds.min_z
+            && position.z <= bounds.max_z
+    }
+}
+
 impl KinematicAgent {
     pub fn new(
         id: String,
@@ -111,20 +143,7 @@ impl KinematicAgent {
                 yaw_rate: 0.0,
             },
             kinematics_model: kinematics_model.clone(),
-            constraints: MovementConstraints {
-                max_velocity: 10.0,
-                max_acceleration: 5.0,
-                max_angular_velocity: std::f64::consts::PI,
-                min_turning_radius: 1.0,
-                workspace_bounds: WorkspaceBounds {
-                    min_x: -100.0,
-                    max_x: 100.0,
-                    min_y: -100.0,
-                    max_y: 100.0,
-                    min_z: 0.0,
-                    max_z: 50.0,
-                },
-            },
+            constraints: MovementConstraints::default(),
             version: 0,
             uncommitted_events: Vec::new(),
         };
@@ -160,13 +179,7 @@ impl KinematicAgent {
     }
 
     fn is_position_valid(&self, position: &Position3D) -> bool {
-        let bounds = &self.constraints.workspace_bounds;
-        position.x >= bounds.min_x
-            && position.x <= bounds.max_x
-            && position.y >= bounds.min_y
-            && position.y <= bounds.max_y
-            && position.z >= bounds.min_z
-            && position.z <= bounds.max_z
+        self.constraints.contains(position)
     }
 }
 
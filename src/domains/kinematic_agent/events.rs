@@ -1,9 +1,10 @@
 use super::aggregate::{Acceleration3D, KinematicsModel, Orientation, Position3D, Velocity3D};
 use crate::common::DomainEvent;
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum KinematicAgentEvent {
     AgentCreated {
         agent_id: String,
@@ -40,6 +40,8 @@ impl LogicalAgentEventActor {
             causation_id: None,
             user_id: None,
             source: "LogicalAgentEventActor".to_string(),
+            reply_to: None,
+            tenant: None,
         };
 
         let envelope = EventEnvelope::new(&event, "LogicalAgent", metadata)
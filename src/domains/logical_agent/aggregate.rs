@@ -1,5 +1,6 @@
 use super::events::LogicalAgentEvent;
 use crate::common::{AggregateRoot, DomainError, DomainResult};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -16,7 +17,7 @@ pub struct LogicalAgent {
     uncommitted_events: Vec<LogicalAgentEvent>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum AgentStatus {
     Idle,
     Planning,
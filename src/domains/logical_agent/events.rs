@@ -1,10 +1,11 @@
 use super::aggregate::AgentStatus;
 use crate::common::DomainEvent;
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum LogicalAgentEvent {
     AgentCreated {
         agent_id: String,
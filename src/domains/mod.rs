@@ -13,3 +13,18 @@ pub use logger::*;
 pub use logical_agent::*;
 pub use path_planning::*;
 pub use technical_agent::*;
+
+/// JSON Schema of every domain event enum, keyed by enum name. These are the
+/// subjects in the schema registry.
+pub fn event_schemas() -> Vec<(&'static str, serde_json::Value)> {
+    use crate::common::schema_of;
+
+    vec![
+        ("DynamicsEvent", schema_of::<DynamicsEvent>()),
+        ("GUIEvent", schema_of::<GUIEvent>()),
+        ("KinematicAgentEvent", schema_of::<KinematicAgentEvent>()),
+        ("LogicalAgentEvent", schema_of::<LogicalAgentEvent>()),
+        ("PathPlanningEvent", schema_of::<PathPlanningEvent>()),
+        ("TechnicalAgentEvent", schema_of::<TechnicalAgentEvent>()),
+    ]
+}
@@ -5,7 +5,7 @@ pub mod worker;
 pub mod workspace;
 
 // Re-export all public types for convenience
-pub use path_planner::PathPlanner;
+pub use path_planner::{apply_transition, PathPlanner, PlannerDecisions, PlannerStateMut};
 pub use plan::*;
 pub use types::*;
 pub use worker::*;
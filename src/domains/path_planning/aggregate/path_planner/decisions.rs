@@ -0,0 +1,543 @@
+use super::super::plan::{PathPlan, PlanStatus};
+use super::super::types::{
+    BatchPlanRequest, MultiGoalPlanRequest, Orientation2D, PathPlanRequest, PlanningAlgorithm,
+    Position2D,
+};
+use super::super::worker::WorkerStatus;
+use super::super::workspace::{Obstacle, Workspace};
+use super::transitions::{apply_transition, PlannerStateMut};
+use crate::common::{DomainError, DomainResult};
+use crate::domains::path_planning::events::PathPlanningEvent;
+use crate::domains::path_planning::routing::{combined_waypoints, optimize_goal_order};
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+/// Decides which events a planner command emits. The native aggregate and the
+/// esrs `handle_command` both go through this, so a command emits the same
+/// events whichever of them handles it. Each event is applied as it is
+/// emitted, so follow-up decisions (assigning work, completing a multi-goal
+/// plan) see its effect; esrs decides on a copy of its state.
+pub struct PlannerDecisions<'a> {
+    state: PlannerStateMut<'a>,
+    events: Vec<PathPlanningEvent>,
+}
+
+impl<'a> PlannerDecisions<'a> {
+    pub fn new(state: PlannerStateMut<'a>) -> Self {
+        Self {
+            state,
+            events: Vec::new(),
+        }
+    }
+
+    /// The events decided so far, each already applied to the state
+    pub fn into_events(self) -> Vec<PathPlanningEvent> {
+        self.events
+    }
+
+    fn emit(&mut self, event: PathPlanningEvent) -> DomainResult<()> {
+        apply_transition(self.state.reborrow(), &event)?;
+        self.events.push(event);
+        Ok(())
+    }
+
+    fn planner_id(&self) -> String {
+        self.state.id.clone()
+    }
+
+    pub fn create_planner(
+        &mut self,
+        planner_id: String,
+        algorithm: PlanningAlgorithm,
+    ) -> DomainResult<()> {
+        self.emit(PathPlanningEvent::PlannerCreated {
+            planner_id,
+            algorithm,
+            timestamp: Utc::now(),
+        })
+    }
+
+    pub fn request_path_plan(&mut self, request: PathPlanRequest) -> DomainResult<String> {
+        validate_path_plan_request(self.state.workspace, &request)?;
+        self.submit_path_plan(request)
+    }
+
+    pub fn request_multi_goal_plan(
+        &mut self,
+        request: MultiGoalPlanRequest,
+    ) -> DomainResult<String> {
+        validate_multi_goal_plan_request(self.state.workspace, &request)?;
+        self.submit_multi_goal_plan(request)
+    }
+
+    /// Every request is validated before any event is emitted, so a batch is
+    /// either accepted whole or rejected whole.
+    pub fn request_plan_batch(&mut self, requests: Vec<BatchPlanRequest>) -> DomainResult<String> {
+        if requests.is_empty() {
+            return Err(DomainError::InvalidCommand {
+                reason: "Plan batch is empty".to_string(),
+            });
+        }
+        for request in &requests {
+            match request {
+                BatchPlanRequest::Single(request) => {
+                    validate_path_plan_request(self.state.workspace, request)?
+                }
+                BatchPlanRequest::MultiGoal(request) => {
+                    validate_multi_goal_plan_request(self.state.workspace, request)?
+                }
+            }
+        }
+        let mut plan_ids = Vec::with_capacity(requests.len());
+        for request in requests {
+            let plan_id = match request {
+                BatchPlanRequest::Single(request) => self.submit_path_plan(request)?,
+                BatchPlanRequest::MultiGoal(request) => self.submit_multi_goal_plan(request)?,
+            };
+            plan_ids.push(plan_id);
+        }
+        let batch_id = Uuid::new_v4().to_string();
+        self.emit(PathPlanningEvent::PlanBatchRequested {
+            planner_id: self.planner_id(),
+            batch_id: batch_id.clone(),
+            plan_ids,
+            timestamp: Utc::now(),
+        })?;
+        Ok(batch_id)
+    }
+
+    fn submit_path_plan(&mut self, request: PathPlanRequest) -> DomainResult<String> {
+        let plan_id = Uuid::new_v4().to_string();
+        self.emit(PathPlanningEvent::PathPlanRequested {
+            planner_id: self.planner_id(),
+            request_id: request.request_id,
+            plan_id: plan_id.clone(),
+            agent_id: request.agent_id,
+            start_position: request.start_position,
+            destination_position: request.destination_position,
+            start_orientation: request.start_orientation,
+            destination_orientation: request.destination_orientation,
+            timestamp: Utc::now(),
+        })?;
+        self.try_assign_plan(&plan_id)?;
+        Ok(plan_id)
+    }
+
+    fn submit_multi_goal_plan(&mut self, request: MultiGoalPlanRequest) -> DomainResult<String> {
+        let goals = if request.optimize_order {
+            optimize_goal_order(&request.start_position, &request.goals)
+                .into_iter()
+                .map(|index| request.goals[index].clone())
+                .collect()
+        } else {
+            request.goals
+        };
+        let plan_id = Uuid::new_v4().to_string();
+        let leg_plan_ids: Vec<String> = goals.iter().map(|_| Uuid::new_v4().to_string()).collect();
+        self.emit(PathPlanningEvent::MultiGoalPlanRequested {
+            planner_id: self.planner_id(),
+            request_id: request.request_id,
+            plan_id: plan_id.clone(),
+            agent_id: request.agent_id,
+            start_position: request.start_position,
+            start_orientation: request.start_orientation,
+            goals,
+            leg_plan_ids: leg_plan_ids.clone(),
+            order_optimized: request.optimize_order,
+            timestamp: Utc::now(),
+        })?;
+        for leg_plan_id in &leg_plan_ids {
+            self.try_assign_plan(leg_plan_id)?;
+        }
+        Ok(plan_id)
+    }
+
+    pub fn register_worker(
+        &mut self,
+        worker_id: String,
+        capabilities: Vec<PlanningAlgorithm>,
+    ) -> DomainResult<()> {
+        if self.is_registered(&worker_id) {
+            return Err(DomainError::InvalidCommand {
+                reason: format!("Worker {} is already registered", worker_id),
+            });
+        }
+        self.emit(PathPlanningEvent::WorkerRegistered {
+            planner_id: self.planner_id(),
+            worker_id,
+            capabilities,
+            timestamp: Utc::now(),
+        })
+    }
+
+    pub fn worker_ready(&mut self, worker_id: String) -> DomainResult<()> {
+        if !self.is_registered(&worker_id) {
+            return Err(DomainError::InvalidCommand {
+                reason: format!("Worker {} is not registered", worker_id),
+            });
+        }
+        self.ready_for_work(&worker_id)
+    }
+
+    pub fn plan_assignment_accepted(
+        &mut self,
+        worker_id: String,
+        plan_id: String,
+    ) -> DomainResult<()> {
+        self.ensure_live_plan(&plan_id)?;
+        self.emit(PathPlanningEvent::PlanAssignmentAccepted {
+            planner_id: self.planner_id(),
+            plan_id,
+            worker_id,
+            timestamp: Utc::now(),
+        })
+    }
+
+    /// Complete the plan, then its multi-goal plan once every leg is done, and
+    /// hand the worker its next plan.
+    pub fn plan_completed(
+        &mut self,
+        worker_id: String,
+        plan_id: String,
+        waypoints: Vec<Position2D>,
+    ) -> DomainResult<()> {
+        self.ensure_live_plan(&plan_id)?;
+        self.emit(PathPlanningEvent::PlanCompleted {
+            planner_id: self.planner_id(),
+            plan_id: plan_id.clone(),
+            worker_id: Some(worker_id.clone()),
+            waypoints,
+            cache_hit: false,
+            timestamp: Utc::now(),
+        })?;
+        self.complete_parent_plan_if_ready(&plan_id)?;
+        self.ready_for_work(&worker_id)
+    }
+
+    /// Fail the plan and its multi-goal plan, and hand the worker its next plan.
+    pub fn plan_failed(
+        &mut self,
+        worker_id: String,
+        plan_id: String,
+        reason: String,
+    ) -> DomainResult<()> {
+        self.ensure_live_plan(&plan_id)?;
+        self.emit(PathPlanningEvent::PlanFailed {
+            planner_id: self.planner_id(),
+            plan_id: plan_id.clone(),
+            worker_id: Some(worker_id.clone()),
+            reason: reason.clone(),
+            timestamp: Utc::now(),
+        })?;
+        self.fail_parent_plan(&plan_id, reason)?;
+        self.ready_for_work(&worker_id)
+    }
+
+    pub fn add_obstacle(&mut self, obstacle: Obstacle) -> DomainResult<()> {
+        if self
+            .state
+            .workspace
+            .obstacles
+            .iter()
+            .any(|o| o.id == obstacle.id)
+        {
+            return Err(DomainError::InvalidCommand {
+                reason: format!("Obstacle {} already exists", obstacle.id),
+            });
+        }
+        let obstacle_id = obstacle.id.clone();
+        self.emit(PathPlanningEvent::ObstacleAdded {
+            planner_id: self.planner_id(),
+            obstacle,
+            timestamp: Utc::now(),
+        })?;
+        self.invalidate_plans_crossing(&obstacle_id)
+    }
+
+    pub fn move_obstacle(&mut self, obstacle_id: String, position: Position2D) -> DomainResult<()> {
+        self.ensure_obstacle_exists(&obstacle_id)?;
+        self.emit(PathPlanningEvent::ObstacleMoved {
+            planner_id: self.planner_id(),
+            obstacle_id: obstacle_id.clone(),
+            position,
+            timestamp: Utc::now(),
+        })?;
+        self.invalidate_plans_crossing(&obstacle_id)
+    }
+
+    /// Removing an obstacle cannot block a route, so no plan is invalidated.
+    pub fn remove_obstacle(&mut self, obstacle_id: String) -> DomainResult<()> {
+        self.ensure_obstacle_exists(&obstacle_id)?;
+        self.emit(PathPlanningEvent::ObstacleRemoved {
+            planner_id: self.planner_id(),
+            obstacle_id,
+            timestamp: Utc::now(),
+        })
+    }
+
+    /// Archive finished plans whose retention window has elapsed; returns how
+    /// many were archived.
+    pub fn archive_finished_plans(
+        &mut self,
+        retention: Duration,
+        now: DateTime<Utc>,
+    ) -> DomainResult<usize> {
+        let expired: Vec<PathPlan> = self
+            .state
+            .active_plans
+            .iter()
+            .filter(|p| p.status.is_finished() && p.retention_anchor() + retention <= now)
+            // Legs stay until their combined plan is finished; it still needs their waypoints
+            .filter(|p| self.live_parent_of(&p.id).is_none())
+            .cloned()
+            .collect();
+        let archived = expired.len();
+        for plan in expired {
+            self.emit(PathPlanningEvent::PlanArchived {
+                planner_id: self.planner_id(),
+                plan,
+                timestamp: now,
+            })?;
+        }
+        Ok(archived)
+    }
+
+    fn is_registered(&self, worker_id: &str) -> bool {
+        self.state
+            .registered_workers
+            .iter()
+            .any(|w| w.worker_id == worker_id)
+    }
+
+    fn ensure_obstacle_exists(&self, obstacle_id: &str) -> DomainResult<()> {
+        if !self
+            .state
+            .workspace
+            .obstacles
+            .iter()
+            .any(|o| o.id == obstacle_id)
+        {
+            return Err(DomainError::InvalidCommand {
+                reason: format!("Obstacle {} does not exist", obstacle_id),
+            });
+        }
+        Ok(())
+    }
+
+    /// Plan invariants only hold for live plans: unknown, archived or already
+    /// finished plans cannot change state any more. Multi-goal plans only change
+    /// through their legs.
+    fn ensure_live_plan(&self, plan_id: &str) -> DomainResult<()> {
+        match self.state.active_plans.iter().find(|p| p.id == plan_id) {
+            Some(plan) if plan.status.is_finished() => Err(DomainError::InvalidCommand {
+                reason: format!("Plan {} is already finished", plan_id),
+            }),
+            Some(plan) if plan.is_multi_goal() => Err(DomainError::InvalidCommand {
+                reason: format!(
+                    "Plan {} is a multi-goal plan; only its legs are planned",
+                    plan_id
+                ),
+            }),
+            Some(_) => Ok(()),
+            None => Err(DomainError::InvalidCommand {
+                reason: format!("Plan {} is not active", plan_id),
+            }),
+        }
+    }
+
+    /// The unfinished multi-goal plan a leg belongs to, if any.
+    fn live_parent_of(&self, leg_plan_id: &str) -> Option<&PathPlan> {
+        let parent_id = self
+            .state
+            .active_plans
+            .iter()
+            .find(|p| p.id == leg_plan_id)?
+            .parent_plan_id
+            .as_ref()?;
+        self.state
+            .active_plans
+            .iter()
+            .find(|p| p.id == *parent_id && !p.status.is_finished())
+    }
+
+    /// Publish the combined route once every leg of the parent plan is complete.
+    fn complete_parent_plan_if_ready(&mut self, leg_plan_id: &str) -> DomainResult<()> {
+        let Some(parent) = self.live_parent_of(leg_plan_id) else {
+            return Ok(());
+        };
+        let legs: Option<Vec<&[Position2D]>> = parent
+            .leg_plan_ids
+            .iter()
+            .map(|id| {
+                self.state
+                    .active_plans
+                    .iter()
+                    .find(|p| p.id == *id && p.status == PlanStatus::Complete)
+                    .map(|p| p.waypoints.as_slice())
+            })
+            .collect();
+        let Some(legs) = legs else {
+            return Ok(());
+        };
+        let event = PathPlanningEvent::MultiGoalPlanCompleted {
+            planner_id: self.planner_id(),
+            plan_id: parent.id.clone(),
+            waypoints: combined_waypoints(legs),
+            timestamp: Utc::now(),
+        };
+        self.emit(event)
+    }
+
+    /// A single failed leg fails the whole multi-goal plan.
+    fn fail_parent_plan(&mut self, leg_plan_id: &str, reason: String) -> DomainResult<()> {
+        let Some(parent) = self.live_parent_of(leg_plan_id) else {
+            return Ok(());
+        };
+        let event = PathPlanningEvent::MultiGoalPlanFailed {
+            planner_id: self.planner_id(),
+            plan_id: parent.id.clone(),
+            leg_plan_id: leg_plan_id.to_string(),
+            reason,
+            timestamp: Utc::now(),
+        };
+        self.emit(event)
+    }
+
+    /// Mark the worker ready and give it a waiting plan, if there is one.
+    fn ready_for_work(&mut self, worker_id: &str) -> DomainResult<()> {
+        self.emit(PathPlanningEvent::WorkerReady {
+            planner_id: self.planner_id(),
+            worker_id: worker_id.to_string(),
+            timestamp: Utc::now(),
+        })?;
+        let Some(plan_id) = self
+            .state
+            .active_plans
+            .iter()
+            .find(|p| p.status == PlanStatus::Planning)
+            .map(|p| p.id.clone())
+        else {
+            return Ok(());
+        };
+        self.assign(plan_id, worker_id.to_string())
+    }
+
+    /// Invalidate and re-queue every plan whose route in use now crosses the obstacle.
+    fn invalidate_plans_crossing(&mut self, obstacle_id: &str) -> DomainResult<()> {
+        let Some(obstacle) = self
+            .state
+            .workspace
+            .obstacles
+            .iter()
+            .find(|o| o.id == obstacle_id)
+            .cloned()
+        else {
+            return Ok(());
+        };
+        let blocked: Vec<PathPlan> = self
+            .state
+            .active_plans
+            .iter()
+            .filter(|p| p.has_live_route() && p.path_intersects(&obstacle))
+            .cloned()
+            .collect();
+        for plan in blocked {
+            self.emit(PathPlanningEvent::PlanInvalidated {
+                planner_id: self.planner_id(),
+                plan_id: plan.id.clone(),
+                agent_id: plan.agent_id,
+                obstacle_id: obstacle_id.to_string(),
+                start_position: plan.start,
+                destination_position: plan.goal,
+                start_orientation: plan.start_orientation,
+                destination_orientation: plan.destination_orientation,
+                timestamp: Utc::now(),
+            })?;
+            self.try_assign_plan(&plan.id)?;
+        }
+        Ok(())
+    }
+
+    /// Hand `plan_id` to the first idle worker, if any.
+    fn try_assign_plan(&mut self, plan_id: &str) -> DomainResult<()> {
+        let Some(worker_id) = self
+            .state
+            .registered_workers
+            .iter()
+            .find(|w| w.status == WorkerStatus::Idle && w.current_plan_id.is_none())
+            .map(|w| w.worker_id.clone())
+        else {
+            return Ok(());
+        };
+        self.assign(plan_id.to_string(), worker_id)
+    }
+
+    fn assign(&mut self, plan_id: String, worker_id: String) -> DomainResult<()> {
+        // DEPRECATED: the aggregate doesn't have access to full request data
+        // Use event-driven assignment in pathplan_planner instead
+        self.emit(PathPlanningEvent::PlanAssigned {
+            planner_id: self.planner_id(),
+            plan_id,
+            worker_id,
+            request_id: "deprecated-aggregate-method".to_string(),
+            agent_id: "unknown".to_string(),
+            start_position: Position2D { x: 0.0, y: 0.0 },
+            destination_position: Position2D { x: 0.0, y: 0.0 },
+            start_orientation: Orientation2D { angle: 0.0 },
+            destination_orientation: Orientation2D { angle: 0.0 },
+            timeout_seconds: 300,
+            timestamp: Utc::now(),
+        })
+    }
+}
+
+pub(crate) fn is_position_in_workspace(workspace: &Workspace, position: &Position2D) -> bool {
+    let bounds = &workspace.bounds;
+    position.x >= bounds.min_x
+        && position.x <= bounds.max_x
+        && position.y >= bounds.min_y
+        && position.y <= bounds.max_y
+}
+
+pub(crate) fn validate_path_plan_request(
+    workspace: &Workspace,
+    request: &PathPlanRequest,
+) -> DomainResult<()> {
+    if !is_position_in_workspace(workspace, &request.start_position) {
+        return Err(DomainError::InvalidCommand {
+            reason: "Start position is outside workspace bounds".to_string(),
+        });
+    }
+    if !is_position_in_workspace(workspace, &request.destination_position) {
+        return Err(DomainError::InvalidCommand {
+            reason: "Destination position is outside workspace bounds".to_string(),
+        });
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_multi_goal_plan_request(
+    workspace: &Workspace,
+    request: &MultiGoalPlanRequest,
+) -> DomainResult<()> {
+    if request.goals.is_empty() {
+        return Err(DomainError::InvalidCommand {
+            reason: "Multi-goal plan request has no goals".to_string(),
+        });
+    }
+    if !is_position_in_workspace(workspace, &request.start_position) {
+        return Err(DomainError::InvalidCommand {
+            reason: "Start position is outside workspace bounds".to_string(),
+        });
+    }
+    if let Some(index) = request
+        .goals
+        .iter()
+        .position(|g| !is_position_in_workspace(workspace, &g.position))
+    {
+        return Err(DomainError::InvalidCommand {
+            reason: format!("Goal {} is outside workspace bounds", index),
+        });
+    }
+    Ok(())
+}
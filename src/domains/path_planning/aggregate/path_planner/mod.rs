@@ -1,5 +1,8 @@
 #![allow(clippy::module_inception)]
-pub mod assignment;
+pub mod decisions;
 pub mod event_apply;
 pub mod path_planner;
+pub mod transitions;
+pub use decisions::*;
 pub use path_planner::*;
+pub use transitions::*;
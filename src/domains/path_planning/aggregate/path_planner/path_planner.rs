@@ -1,13 +1,18 @@
-use crate::common::aggregate::AggregateRoot;
-use crate::common::{DomainError, DomainResult};
+use super::decisions::{is_position_in_workspace, validate_path_plan_request, PlannerDecisions};
+use super::transitions::PlannerStateMut;
+use crate::common::DomainResult;
 use crate::domains::path_planning::events::PathPlanningEvent;
+use crate::domains::path_planning::multi_agent::{
+    plan_conflict_free, MultiAgentPlanConfig, TimedPath,
+};
 use crate::domains::path_planning::plan::PathPlan;
-use crate::domains::path_planning::types::{PathPlanRequest, PlanningAlgorithm, Position2D};
+use crate::domains::path_planning::types::{
+    BatchPlanRequest, MultiGoalPlanRequest, PathPlanRequest, PlanningAlgorithm, Position2D,
+};
 use crate::domains::path_planning::worker::{PathPlanWorker, PlanAssignment};
-use crate::domains::path_planning::workspace::{Workspace, WorkspaceBounds};
-use chrono::Utc;
+use crate::domains::path_planning::workspace::{Obstacle, Workspace, WorkspaceBounds};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PathPlanner {
@@ -33,9 +38,23 @@ impl PathPlanner {
     pub fn add_event(&mut self, event: PathPlanningEvent) {
         self.uncommitted_events.push(event);
     }
+    pub fn state_mut(&mut self) -> PlannerStateMut<'_> {
+        PlannerStateMut {
+            id: &mut self.id,
+            algorithm: &mut self.algorithm,
+            workspace: &mut self.workspace,
+            active_plans: &mut self.active_plans,
+            registered_workers: &mut self.registered_workers,
+            plan_assignments: &mut self.plan_assignments,
+        }
+    }
 }
 
 impl PathPlanner {
+    /// Aggregate type of every envelope on a planner stream, whichever
+    /// service produces it, so partition keys agree across producers
+    pub const AGGREGATE_TYPE: &'static str = "PathPlanner";
+
     pub fn new(id: String, algorithm: PlanningAlgorithm) -> Self {
         let mut planner = Self {
             id: id.clone(),
@@ -66,79 +85,61 @@ impl PathPlanner {
         planner
     }
 
+    /// Run a decision against the aggregate and record the events it emitted,
+    /// which are already applied.
+    fn decide<T>(
+        &mut self,
+        decision: impl FnOnce(&mut PlannerDecisions<'_>) -> DomainResult<T>,
+    ) -> DomainResult<T> {
+        let mut decisions = PlannerDecisions::new(self.state_mut());
+        let result = decision(&mut decisions);
+        let events = decisions.into_events();
+        self.version += events.len() as u64;
+        self.uncommitted_events.extend(events);
+        result
+    }
+
     pub fn request_path_plan(&mut self, path_plan_request: PathPlanRequest) -> DomainResult<()> {
-        if !self.is_position_in_workspace(&path_plan_request.start_position) {
-            return Err(DomainError::InvalidCommand {
-                reason: "Start position is outside workspace bounds".to_string(),
-            });
-        }
-        if !self.is_position_in_workspace(&path_plan_request.destination_position) {
-            return Err(DomainError::InvalidCommand {
-                reason: "Destination position is outside workspace bounds".to_string(),
-            });
-        }
-        let plan_id = Uuid::new_v4().to_string();
-        let event = PathPlanningEvent::PathPlanRequested {
-            planner_id: self.id.clone(),
-            request_id: path_plan_request.request_id,
-            plan_id: plan_id.clone(),
-            agent_id: path_plan_request.agent_id,
-            start_position: path_plan_request.start_position,
-            destination_position: path_plan_request.destination_position,
-            start_orientation: path_plan_request.start_orientation,
-            destination_orientation: path_plan_request.destination_orientation,
-            timestamp: Utc::now(),
-        };
-        self.add_event(event.clone());
-        self.apply(&event)?;
-        self.try_assign_plan(&plan_id)?;
+        self.decide(|d| d.request_path_plan(path_plan_request))?;
+        Ok(())
+    }
+
+    /// Request a route through several goals. Each goal becomes a leg that workers
+    /// plan like a single plan; the combined plan completes when all legs have.
+    pub fn request_multi_goal_plan(&mut self, request: MultiGoalPlanRequest) -> DomainResult<()> {
+        self.decide(|d| d.request_multi_goal_plan(request))?;
         Ok(())
     }
 
+    /// Submit many requests in one command. Every request is validated before any
+    /// event is emitted, so a batch is either accepted whole or rejected whole.
+    pub fn request_plan_batch(&mut self, requests: Vec<BatchPlanRequest>) -> DomainResult<String> {
+        self.decide(|d| d.request_plan_batch(requests))
+    }
+
+    /// Plan several agents together over this planner's workspace so their paths
+    /// never share a cell or swap cells at the same time step.
+    pub fn plan_multi_agent(
+        &self,
+        requests: &[PathPlanRequest],
+        config: &MultiAgentPlanConfig,
+    ) -> DomainResult<Vec<TimedPath>> {
+        for request in requests {
+            validate_path_plan_request(&self.workspace, request)?;
+        }
+        plan_conflict_free(&self.workspace, requests, config)
+    }
+
     pub fn register_worker(
         &mut self,
         worker_id: String,
         algorithm_capabilities: Vec<PlanningAlgorithm>,
     ) -> DomainResult<()> {
-        if self
-            .registered_workers
-            .iter()
-            .any(|w| w.worker_id == worker_id)
-        {
-            return Err(DomainError::InvalidCommand {
-                reason: format!("Worker {} is already registered", worker_id),
-            });
-        }
-        let event = PathPlanningEvent::WorkerRegistered {
-            planner_id: self.id.clone(),
-            worker_id,
-            capabilities: algorithm_capabilities,
-            timestamp: Utc::now(),
-        };
-        self.add_event(event.clone());
-        self.apply(&event)?;
-        Ok(())
+        self.decide(|d| d.register_worker(worker_id, algorithm_capabilities))
     }
 
     pub fn handle_worker_ready(&mut self, worker_id: String) -> DomainResult<()> {
-        if !self
-            .registered_workers
-            .iter()
-            .any(|w| w.worker_id == worker_id)
-        {
-            return Err(DomainError::InvalidCommand {
-                reason: format!("Worker {} is not registered", worker_id),
-            });
-        }
-        let event = PathPlanningEvent::WorkerReady {
-            planner_id: self.id.clone(),
-            worker_id: worker_id.clone(),
-            timestamp: Utc::now(),
-        };
-        self.add_event(event.clone());
-        self.apply(&event)?;
-        self.try_assign_work_to_worker(&worker_id)?;
-        Ok(())
+        self.decide(|d| d.worker_ready(worker_id))
     }
 
     pub fn handle_plan_assignment_accepted(
@@ -146,15 +147,7 @@ impl PathPlanner {
         worker_id: String,
         plan_id: String,
     ) -> DomainResult<()> {
-        let event = PathPlanningEvent::PlanAssignmentAccepted {
-            planner_id: self.id.clone(),
-            plan_id,
-            worker_id,
-            timestamp: Utc::now(),
-        };
-        self.add_event(event.clone());
-        self.apply(&event)?;
-        Ok(())
+        self.decide(|d| d.plan_assignment_accepted(worker_id, plan_id))
     }
 
     pub fn handle_plan_completed(
@@ -163,24 +156,7 @@ impl PathPlanner {
         plan_id: String,
         waypoints: Vec<Position2D>,
     ) -> DomainResult<()> {
-        let event = PathPlanningEvent::PlanCompleted {
-            planner_id: self.id.clone(),
-            plan_id,
-            worker_id: Some(worker_id.clone()),
-            waypoints,
-            timestamp: Utc::now(),
-        };
-        self.add_event(event.clone());
-        self.apply(&event)?;
-        let ready_event = PathPlanningEvent::WorkerReady {
-            planner_id: self.id.clone(),
-            worker_id: worker_id.clone(),
-            timestamp: Utc::now(),
-        };
-        self.add_event(ready_event.clone());
-        self.apply(&ready_event)?;
-        self.try_assign_work_to_worker(&worker_id)?;
-        Ok(())
+        self.decide(|d| d.plan_completed(worker_id, plan_id, waypoints))
     }
 
     pub fn handle_plan_failed(
@@ -189,31 +165,33 @@ impl PathPlanner {
         plan_id: String,
         reason: String,
     ) -> DomainResult<()> {
-        let event = PathPlanningEvent::PlanFailed {
-            planner_id: self.id.clone(),
-            plan_id,
-            worker_id: Some(worker_id.clone()),
-            reason,
-            timestamp: Utc::now(),
-        };
-        self.add_event(event.clone());
-        self.apply(&event)?;
-        let ready_event = PathPlanningEvent::WorkerReady {
-            planner_id: self.id.clone(),
-            worker_id: worker_id.clone(),
-            timestamp: Utc::now(),
-        };
-        self.add_event(ready_event.clone());
-        self.apply(&ready_event)?;
-        self.try_assign_work_to_worker(&worker_id)?;
-        Ok(())
+        self.decide(|d| d.plan_failed(worker_id, plan_id, reason))
+    }
+
+    pub fn add_obstacle(&mut self, obstacle: Obstacle) -> DomainResult<()> {
+        self.decide(|d| d.add_obstacle(obstacle))
+    }
+
+    pub fn move_obstacle(&mut self, obstacle_id: String, position: Position2D) -> DomainResult<()> {
+        self.decide(|d| d.move_obstacle(obstacle_id, position))
+    }
+
+    /// Removing an obstacle cannot block a route, so no plan is invalidated.
+    pub fn remove_obstacle(&mut self, obstacle_id: String) -> DomainResult<()> {
+        self.decide(|d| d.remove_obstacle(obstacle_id))
+    }
+
+    /// Move finished plans whose retention window has elapsed out of the aggregate.
+    /// Emits one `PlanArchived` per plan and returns how many were archived.
+    pub fn archive_finished_plans(
+        &mut self,
+        retention: Duration,
+        now: DateTime<Utc>,
+    ) -> DomainResult<usize> {
+        self.decide(|d| d.archive_finished_plans(retention, now))
     }
 
     pub fn is_position_in_workspace(&self, position: &Position2D) -> bool {
-        let bounds = &self.workspace.bounds;
-        position.x >= bounds.min_x
-            && position.x <= bounds.max_x
-            && position.y >= bounds.min_y
-            && position.y <= bounds.max_y
+        is_position_in_workspace(&self.workspace, position)
     }
 }
@@ -0,0 +1,404 @@
+use super::super::plan::{PathPlan, PlanStatus};
+use super::super::types::PlanningAlgorithm;
+use super::super::worker::{PathPlanWorker, PlanAssignment, WorkerStatus};
+use super::super::workspace::Workspace;
+use crate::common::{DomainError, DomainResult};
+use crate::domains::path_planning::events::PathPlanningEvent;
+use crate::domains::path_planning::routing::plan_legs;
+
+/// The parts of a path planner that events change. Both the native aggregate
+/// and the esrs `PathPlannerState` lend their fields through this, so replaying
+/// a stream gives the same state whichever store it came from.
+pub struct PlannerStateMut<'a> {
+    pub id: &'a mut String,
+    pub algorithm: &'a mut PlanningAlgorithm,
+    pub workspace: &'a mut Workspace,
+    pub active_plans: &'a mut Vec<PathPlan>,
+    pub registered_workers: &'a mut Vec<PathPlanWorker>,
+    pub plan_assignments: &'a mut Vec<PlanAssignment>,
+}
+
+impl PlannerStateMut<'_> {
+    /// Lend the state again for one transition without giving it up.
+    pub fn reborrow(&mut self) -> PlannerStateMut<'_> {
+        PlannerStateMut {
+            id: self.id,
+            algorithm: self.algorithm,
+            workspace: self.workspace,
+            active_plans: self.active_plans,
+            registered_workers: self.registered_workers,
+            plan_assignments: self.plan_assignments,
+        }
+    }
+}
+
+/// Apply one event to the planner state. An event that cannot apply is
+/// rejected before anything changes; the caller decides whether that is an
+/// error (native) or is skipped (esrs, which cannot refuse a stored event).
+pub fn apply_transition(state: PlannerStateMut<'_>, event: &PathPlanningEvent) -> DomainResult<()> {
+    match event {
+        PathPlanningEvent::PlannerCreated {
+            planner_id,
+            algorithm,
+            ..
+        } => {
+            *state.id = planner_id.clone();
+            *state.algorithm = algorithm.clone();
+        }
+        PathPlanningEvent::PathPlanRequested {
+            plan_id,
+            agent_id,
+            start_position,
+            destination_position,
+            start_orientation,
+            destination_orientation,
+            timestamp,
+            ..
+        } => {
+            let path_plan = PathPlan {
+                id: plan_id.clone(),
+                agent_id: agent_id.clone(),
+                start: start_position.clone(),
+                goal: destination_position.clone(),
+                start_orientation: start_orientation.clone(),
+                destination_orientation: destination_orientation.clone(),
+                waypoints: Vec::new(),
+                status: PlanStatus::Planning,
+                created_at: *timestamp,
+                finished_at: None,
+                parent_plan_id: None,
+                leg_plan_ids: Vec::new(),
+            };
+            state.active_plans.push(path_plan);
+        }
+        PathPlanningEvent::WorkerRegistered {
+            worker_id,
+            capabilities,
+            timestamp,
+            ..
+        } => {
+            let worker = PathPlanWorker {
+                worker_id: worker_id.clone(),
+                status: WorkerStatus::Idle,
+                algorithm_capabilities: capabilities.clone(),
+                last_heartbeat: *timestamp,
+                current_plan_id: None,
+            };
+            state.registered_workers.push(worker);
+        }
+        PathPlanningEvent::WorkerReady {
+            worker_id,
+            timestamp,
+            ..
+        } => {
+            if let Some(worker) = state
+                .registered_workers
+                .iter_mut()
+                .find(|w| w.worker_id == *worker_id)
+            {
+                worker.status = WorkerStatus::Idle;
+                worker.last_heartbeat = *timestamp;
+                worker.current_plan_id = None;
+            }
+        }
+        PathPlanningEvent::WorkerBusy {
+            worker_id,
+            plan_id,
+            timestamp,
+            ..
+        } => {
+            if let Some(worker) = state
+                .registered_workers
+                .iter_mut()
+                .find(|w| w.worker_id == *worker_id)
+            {
+                worker.status = WorkerStatus::Busy;
+                worker.last_heartbeat = *timestamp;
+                worker.current_plan_id = Some(plan_id.clone());
+            }
+        }
+        PathPlanningEvent::WorkerProcessing {
+            worker_id,
+            plan_id,
+            timestamp,
+            ..
+        } => {
+            // Similar to WorkerBusy - indicates worker is processing a plan
+            if let Some(worker) = state
+                .registered_workers
+                .iter_mut()
+                .find(|w| w.worker_id == *worker_id)
+            {
+                worker.status = WorkerStatus::Busy;
+                worker.last_heartbeat = *timestamp;
+                worker.current_plan_id = Some(plan_id.clone());
+            }
+        }
+        PathPlanningEvent::WorkerOffline { worker_id, .. } => {
+            if let Some(worker) = state
+                .registered_workers
+                .iter_mut()
+                .find(|w| w.worker_id == *worker_id)
+            {
+                worker.status = WorkerStatus::Offline;
+                worker.current_plan_id = None;
+            }
+            state.plan_assignments.retain(|a| a.worker_id != *worker_id);
+        }
+        PathPlanningEvent::WorkerHeartbeat {
+            worker_id,
+            timestamp,
+            ..
+        } => {
+            if let Some(worker) = state
+                .registered_workers
+                .iter_mut()
+                .find(|w| w.worker_id == *worker_id)
+            {
+                worker.last_heartbeat = *timestamp;
+            }
+        }
+        PathPlanningEvent::PlanAssigned {
+            plan_id,
+            worker_id,
+            timeout_seconds,
+            timestamp,
+            ..
+        } => {
+            let assignment = PlanAssignment {
+                plan_id: plan_id.clone(),
+                worker_id: worker_id.clone(),
+                assigned_at: *timestamp,
+                timeout_at: *timestamp + chrono::Duration::seconds(*timeout_seconds as i64),
+            };
+            state.plan_assignments.push(assignment);
+            if let Some(plan) = state.active_plans.iter_mut().find(|p| p.id == *plan_id) {
+                plan.status = PlanStatus::Assigned;
+            }
+            if let Some(worker) = state
+                .registered_workers
+                .iter_mut()
+                .find(|w| w.worker_id == *worker_id)
+            {
+                worker.current_plan_id = Some(plan_id.clone());
+            }
+        }
+        PathPlanningEvent::PlanAssignmentAccepted {
+            plan_id, worker_id, ..
+        } => {
+            if let Some(plan) = state.active_plans.iter_mut().find(|p| p.id == *plan_id) {
+                plan.status = PlanStatus::InProgress;
+            }
+            if let Some(worker) = state
+                .registered_workers
+                .iter_mut()
+                .find(|w| w.worker_id == *worker_id)
+            {
+                worker.status = WorkerStatus::Busy;
+            }
+        }
+        PathPlanningEvent::PlanAssignmentRejected {
+            plan_id, worker_id, ..
+        } => {
+            state
+                .plan_assignments
+                .retain(|a| !(a.plan_id == *plan_id && a.worker_id == *worker_id));
+            if let Some(plan) = state.active_plans.iter_mut().find(|p| p.id == *plan_id) {
+                plan.status = PlanStatus::Planning;
+            }
+            if let Some(worker) = state
+                .registered_workers
+                .iter_mut()
+                .find(|w| w.worker_id == *worker_id)
+            {
+                worker.current_plan_id = None;
+                worker.status = WorkerStatus::Idle;
+            }
+        }
+        PathPlanningEvent::PlanAssignmentTimedOut {
+            plan_id, worker_id, ..
+        } => {
+            state
+                .plan_assignments
+                .retain(|a| !(a.plan_id == *plan_id && a.worker_id == *worker_id));
+            if let Some(plan) = state.active_plans.iter_mut().find(|p| p.id == *plan_id) {
+                plan.status = PlanStatus::Planning;
+            }
+            if let Some(worker) = state
+                .registered_workers
+                .iter_mut()
+                .find(|w| w.worker_id == *worker_id)
+            {
+                worker.status = WorkerStatus::Offline;
+                worker.current_plan_id = None;
+            }
+        }
+        PathPlanningEvent::PlanRequested { .. } => {}
+        PathPlanningEvent::PlanCompleted {
+            plan_id,
+            waypoints,
+            worker_id,
+            timestamp,
+            ..
+        } => {
+            if let Some(plan) = state.active_plans.iter_mut().find(|p| p.id == *plan_id) {
+                plan.status = PlanStatus::Complete;
+                plan.waypoints = waypoints.clone();
+                plan.finished_at = Some(*timestamp);
+            }
+            state.plan_assignments.retain(|a| a.plan_id != *plan_id);
+            if let Some(wid) = worker_id {
+                if let Some(worker) = state
+                    .registered_workers
+                    .iter_mut()
+                    .find(|w| w.worker_id == *wid)
+                {
+                    worker.status = WorkerStatus::Idle;
+                    worker.current_plan_id = None;
+                }
+            }
+        }
+        PathPlanningEvent::PlanFailed {
+            plan_id,
+            worker_id,
+            reason,
+            timestamp,
+            ..
+        } => {
+            if let Some(plan) = state.active_plans.iter_mut().find(|p| p.id == *plan_id) {
+                plan.status = PlanStatus::Failed(reason.clone());
+                plan.finished_at = Some(*timestamp);
+            }
+            state.plan_assignments.retain(|a| a.plan_id != *plan_id);
+            if let Some(wid) = worker_id {
+                if let Some(worker) = state
+                    .registered_workers
+                    .iter_mut()
+                    .find(|w| w.worker_id == *wid)
+                {
+                    worker.status = WorkerStatus::Idle;
+                    worker.current_plan_id = None;
+                }
+            }
+        }
+        PathPlanningEvent::MultiGoalPlanRequested {
+            plan_id,
+            agent_id,
+            start_position,
+            start_orientation,
+            goals,
+            leg_plan_ids,
+            timestamp,
+            ..
+        } => {
+            let Some(last_goal) = goals.last().filter(|_| goals.len() == leg_plan_ids.len()) else {
+                return Err(DomainError::InvalidCommand {
+                    reason: format!("Multi-goal plan {} has mismatched legs", plan_id),
+                });
+            };
+            // The combined plan is never assigned itself; workers plan its legs
+            state.active_plans.push(PathPlan {
+                id: plan_id.clone(),
+                agent_id: agent_id.clone(),
+                start: start_position.clone(),
+                goal: last_goal.position.clone(),
+                start_orientation: start_orientation.clone(),
+                destination_orientation: last_goal.orientation.clone(),
+                waypoints: Vec::new(),
+                status: PlanStatus::InProgress,
+                created_at: *timestamp,
+                finished_at: None,
+                parent_plan_id: None,
+                leg_plan_ids: leg_plan_ids.clone(),
+            });
+            let legs = plan_legs(start_position, start_orientation, goals);
+            for (leg_id, leg) in leg_plan_ids.iter().zip(legs) {
+                state.active_plans.push(PathPlan {
+                    id: leg_id.clone(),
+                    agent_id: agent_id.clone(),
+                    start: leg.start_position,
+                    goal: leg.destination_position,
+                    start_orientation: leg.start_orientation,
+                    destination_orientation: leg.destination_orientation,
+                    waypoints: Vec::new(),
+                    status: PlanStatus::Planning,
+                    created_at: *timestamp,
+                    finished_at: None,
+                    parent_plan_id: Some(plan_id.clone()),
+                    leg_plan_ids: Vec::new(),
+                });
+            }
+        }
+        PathPlanningEvent::MultiGoalPlanCompleted {
+            plan_id,
+            waypoints,
+            timestamp,
+            ..
+        } => {
+            if let Some(plan) = state.active_plans.iter_mut().find(|p| p.id == *plan_id) {
+                plan.status = PlanStatus::Complete;
+                plan.waypoints = waypoints.clone();
+                plan.finished_at = Some(*timestamp);
+            }
+        }
+        PathPlanningEvent::MultiGoalPlanFailed {
+            plan_id,
+            reason,
+            timestamp,
+            ..
+        } => {
+            if let Some(plan) = state.active_plans.iter_mut().find(|p| p.id == *plan_id) {
+                plan.status = PlanStatus::Failed(reason.clone());
+                plan.finished_at = Some(*timestamp);
+            }
+        }
+        PathPlanningEvent::PlanBatchRequested { .. } => {}
+        PathPlanningEvent::ObstacleAdded { obstacle, .. } => {
+            state.workspace.obstacles.push(obstacle.clone());
+        }
+        PathPlanningEvent::ObstacleMoved {
+            obstacle_id,
+            position,
+            ..
+        } => {
+            if let Some(obstacle) = state
+                .workspace
+                .obstacles
+                .iter_mut()
+                .find(|o| o.id == *obstacle_id)
+            {
+                obstacle.position = position.clone();
+            }
+        }
+        PathPlanningEvent::ObstacleRemoved { obstacle_id, .. } => {
+            state.workspace.obstacles.retain(|o| o.id != *obstacle_id);
+        }
+        PathPlanningEvent::PlanInvalidated { plan_id, .. } => {
+            // Back to the queue; the old route must not be used any more
+            let mut parent_id = None;
+            if let Some(plan) = state.active_plans.iter_mut().find(|p| p.id == *plan_id) {
+                plan.status = PlanStatus::Planning;
+                plan.waypoints.clear();
+                plan.finished_at = None;
+                parent_id = plan.parent_plan_id.clone();
+            }
+            state.plan_assignments.retain(|a| a.plan_id != *plan_id);
+            // A completed multi-goal plan is reopened until the leg is replanned
+            if let Some(parent) = state
+                .active_plans
+                .iter_mut()
+                .find(|p| Some(&p.id) == parent_id.as_ref() && p.status == PlanStatus::Complete)
+            {
+                parent.status = PlanStatus::InProgress;
+                parent.waypoints.clear();
+                parent.finished_at = None;
+            }
+        }
+        PathPlanningEvent::PlanArchived { plan, .. } => {
+            // Finished plans leave the hot state; the plan history projection keeps them
+            state.active_plans.retain(|p| p.id != plan.id);
+            state.plan_assignments.retain(|a| a.plan_id != plan.id);
+        }
+    }
+    Ok(())
+}
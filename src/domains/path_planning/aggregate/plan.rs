@@ -1,8 +1,10 @@
 use super::types::{Orientation2D, Position2D};
+use super::workspace::Obstacle;
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PathPlan {
     pub id: String,
     pub agent_id: String,
@@ -13,9 +15,47 @@ pub struct PathPlan {
     pub waypoints: Vec<Position2D>,
     pub status: PlanStatus,
     pub created_at: DateTime<Utc>,
+    /// When the plan reached a terminal status; drives archival of finished plans.
+    #[serde(default)]
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Set on the legs of a multi-goal plan; points at the combined plan.
+    #[serde(default)]
+    pub parent_plan_id: Option<String>,
+    /// Set on a multi-goal plan; its legs in visiting order.
+    #[serde(default)]
+    pub leg_plan_ids: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+impl PathPlan {
+    /// Time from which the retention window of a finished plan is measured.
+    /// Plans replayed from history without `finished_at` fall back to `created_at`.
+    pub fn retention_anchor(&self) -> DateTime<Utc> {
+        self.finished_at.unwrap_or(self.created_at)
+    }
+
+    pub fn is_multi_goal(&self) -> bool {
+        !self.leg_plan_ids.is_empty()
+    }
+
+    /// Whether the planned route (start followed by the waypoints) touches the
+    /// obstacle. Plans without waypoints have no route yet.
+    pub fn path_intersects(&self, obstacle: &Obstacle) -> bool {
+        if self.waypoints.is_empty() {
+            return false;
+        }
+        std::iter::once(&self.start)
+            .chain(&self.waypoints)
+            .zip(&self.waypoints)
+            .any(|(a, b)| obstacle.intersects_segment(a, b))
+    }
+
+    /// Plans whose route is in use: computed and waiting, or being executed.
+    pub fn has_live_route(&self) -> bool {
+        !self.is_multi_goal() && matches!(self.status, PlanStatus::Complete | PlanStatus::Executing)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub enum PlanStatus {
     Planning,       // Waiting for assignment
     Assigned,       // Assigned to a worker but not started
@@ -24,3 +64,10 @@ pub enum PlanStatus {
     Failed(String), // Failed with reason
     Executing,      // Being executed by agent
 }
+
+impl PlanStatus {
+    /// Complete and failed plans are finished; everything else is still live.
+    pub fn is_finished(&self) -> bool {
+        matches!(self, PlanStatus::Complete | PlanStatus::Failed(_))
+    }
+}
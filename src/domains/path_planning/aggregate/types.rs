@@ -1,13 +1,14 @@
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct Position2D {
     pub x: f64,
     pub y: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct Orientation2D {
     pub angle: f64, // Angle in radians
 }
@@ -23,7 +24,33 @@ pub struct PathPlanRequest {
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// One stop of a multi-goal route
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct PlanGoal {
+    pub label: Option<String>, // e.g. "pickup", "dropoff", "charging"
+    pub position: Position2D,
+    pub orientation: Orientation2D,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiGoalPlanRequest {
+    pub request_id: String,
+    pub agent_id: String,
+    pub start_position: Position2D,
+    pub start_orientation: Orientation2D,
+    pub goals: Vec<PlanGoal>,
+    pub optimize_order: bool, // Reorder goals with a TSP heuristic instead of visiting them as given
+    pub created_at: DateTime<Utc>,
+}
+
+/// Entry of a batch submission; batches may mix single and multi-goal requests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum BatchPlanRequest {
+    Single(PathPlanRequest),
+    MultiGoal(MultiGoalPlanRequest),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, JsonSchema)]
 pub enum PlanningAlgorithm {
     AStar,
     RRT,
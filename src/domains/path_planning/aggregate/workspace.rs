@@ -1,4 +1,5 @@
 use super::types::Position2D;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,16 +16,148 @@ pub struct WorkspaceBounds {
     pub max_y: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Obstacle {
     pub id: String,
     pub shape: ObstacleShape,
     pub position: Position2D,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum ObstacleShape {
     Circle { radius: f64 },
     Rectangle { width: f64, height: f64 },
     Polygon { vertices: Vec<Position2D> },
 }
+
+impl Workspace {
+    pub fn contains(&self, position: &Position2D) -> bool {
+        position.x >= self.bounds.min_x
+            && position.x <= self.bounds.max_x
+            && position.y >= self.bounds.min_y
+            && position.y <= self.bounds.max_y
+    }
+
+    /// Inside the bounds and not covered by any obstacle.
+    pub fn is_free(&self, position: &Position2D) -> bool {
+        self.contains(position) && !self.obstacles.iter().any(|o| o.covers(position))
+    }
+}
+
+impl Obstacle {
+    /// Circles and rectangles are centred on `position`; polygon vertices are
+    /// relative to it, so moving an obstacle moves every shape.
+    pub fn covers(&self, point: &Position2D) -> bool {
+        match &self.shape {
+            ObstacleShape::Circle { radius } => {
+                let dx = point.x - self.position.x;
+                let dy = point.y - self.position.y;
+                dx * dx + dy * dy <= radius * radius
+            }
+            ObstacleShape::Rectangle { width, height } => {
+                (point.x - self.position.x).abs() <= width / 2.0
+                    && (point.y - self.position.y).abs() <= height / 2.0
+            }
+            ObstacleShape::Polygon { vertices } => polygon_covers(vertices, &self.relative(point)),
+        }
+    }
+
+    /// `point` in the obstacle's own coordinates, with `position` as the origin
+    fn relative(&self, point: &Position2D) -> Position2D {
+        Position2D {
+            x: point.x - self.position.x,
+            y: point.y - self.position.y,
+        }
+    }
+
+    /// Whether the straight segment from `a` to `b` touches the obstacle.
+    pub fn intersects_segment(&self, a: &Position2D, b: &Position2D) -> bool {
+        match &self.shape {
+            ObstacleShape::Circle { .. } => {
+                // Closest point of the segment to the centre
+                let (dx, dy) = (b.x - a.x, b.y - a.y);
+                let length_sq = dx * dx + dy * dy;
+                let t = if length_sq == 0.0 {
+                    0.0
+                } else {
+                    (((self.position.x - a.x) * dx + (self.position.y - a.y) * dy) / length_sq)
+                        .clamp(0.0, 1.0)
+                };
+                let closest = Position2D {
+                    x: a.x + t * dx,
+                    y: a.y + t * dy,
+                };
+                self.covers(&closest)
+            }
+            ObstacleShape::Rectangle { width, height } => {
+                // Liang-Barsky clipping against the rectangle
+                let (min_x, max_x) = (self.position.x - width / 2.0, self.position.x + width / 2.0);
+                let (min_y, max_y) = (
+                    self.position.y - height / 2.0,
+                    self.position.y + height / 2.0,
+                );
+                let (dx, dy) = (b.x - a.x, b.y - a.y);
+                let mut t0: f64 = 0.0;
+                let mut t1: f64 = 1.0;
+                for (p, q) in [
+                    (-dx, a.x - min_x),
+                    (dx, max_x - a.x),
+                    (-dy, a.y - min_y),
+                    (dy, max_y - a.y),
+                ] {
+                    if p == 0.0 {
+                        if q < 0.0 {
+                            return false;
+                        }
+                    } else if p < 0.0 {
+                        t0 = t0.max(q / p);
+                    } else {
+                        t1 = t1.min(q / p);
+                    }
+                }
+                t0 <= t1
+            }
+            ObstacleShape::Polygon { vertices } => {
+                let (a, b) = (self.relative(a), self.relative(b));
+                polygon_covers(vertices, &a)
+                    || polygon_covers(vertices, &b)
+                    || vertices
+                        .iter()
+                        .zip(vertices.iter().cycle().skip(1))
+                        .any(|(p, q)| segments_intersect(&a, &b, p, q))
+            }
+        }
+    }
+}
+
+/// Even-odd ray casting
+fn polygon_covers(vertices: &[Position2D], point: &Position2D) -> bool {
+    let mut inside = false;
+    let mut j = vertices.len().wrapping_sub(1);
+    for (i, vi) in vertices.iter().enumerate() {
+        let vj = &vertices[j];
+        if (vi.y > point.y) != (vj.y > point.y)
+            && point.x < (vj.x - vi.x) * (point.y - vi.y) / (vj.y - vi.y) + vi.x
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+fn segments_intersect(a: &Position2D, b: &Position2D, c: &Position2D, d: &Position2D) -> bool {
+    let cross = |o: &Position2D, p: &Position2D, q: &Position2D| {
+        (p.x - o.x) * (q.y - o.y) - (p.y - o.y) * (q.x - o.x)
+    };
+    let (d1, d2) = (cross(c, d, a), cross(c, d, b));
+    let (d3, d4) = (cross(a, b, c), cross(a, b, d));
+    let on_segment = |o: &Position2D, p: &Position2D, q: &Position2D| {
+        q.x >= o.x.min(p.x) && q.x <= o.x.max(p.x) && q.y >= o.y.min(p.y) && q.y <= o.y.max(p.y)
+    };
+    ((d1 > 0.0) != (d2 > 0.0) && (d3 > 0.0) != (d4 > 0.0) && d1 != 0.0 && d2 != 0.0)
+        || (d1 == 0.0 && on_segment(c, d, a))
+        || (d2 == 0.0 && on_segment(c, d, b))
+        || (d3 == 0.0 && on_segment(a, b, c))
+        || (d4 == 0.0 && on_segment(a, b, d))
+}
@@ -1,9 +1,12 @@
-use super::aggregate::{Orientation2D, PlanningAlgorithm, Position2D};
+use super::aggregate::{
+    Obstacle, Orientation2D, PathPlan, PlanGoal, PlanningAlgorithm, Position2D,
+};
 use crate::common::DomainEvent;
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum PathPlanningEvent {
     PlannerCreated {
         planner_id: String,
@@ -106,6 +109,8 @@ pub enum PathPlanningEvent {
         plan_id: String,
         worker_id: Option<String>, // Add worker_id to track who completed it
         waypoints: Vec<Position2D>,
+        #[serde(default)]
+        cache_hit: bool, // Waypoints were served from the worker's plan cache
         timestamp: DateTime<Utc>,
     },
     PlanFailed {
@@ -115,6 +120,75 @@ pub enum PathPlanningEvent {
         reason: String,
         timestamp: DateTime<Utc>,
     },
+
+    // Multi-goal and batch events
+    MultiGoalPlanRequested {
+        planner_id: String,
+        request_id: String,
+        plan_id: String,
+        agent_id: String,
+        start_position: Position2D,
+        start_orientation: Orientation2D,
+        goals: Vec<PlanGoal>,      // In visiting order, after any reordering
+        leg_plan_ids: Vec<String>, // One leg per goal, planned like single plans
+        order_optimized: bool,
+        timestamp: DateTime<Utc>,
+    },
+    MultiGoalPlanCompleted {
+        planner_id: String,
+        plan_id: String,
+        waypoints: Vec<Position2D>, // Legs joined in visiting order
+        timestamp: DateTime<Utc>,
+    },
+    MultiGoalPlanFailed {
+        planner_id: String,
+        plan_id: String,
+        leg_plan_id: String,
+        reason: String,
+        timestamp: DateTime<Utc>,
+    },
+    PlanBatchRequested {
+        planner_id: String,
+        batch_id: String,
+        plan_ids: Vec<String>, // Plans created by the batch, in submission order
+        timestamp: DateTime<Utc>,
+    },
+
+    // Workspace events
+    ObstacleAdded {
+        planner_id: String,
+        obstacle: Obstacle,
+        timestamp: DateTime<Utc>,
+    },
+    ObstacleMoved {
+        planner_id: String,
+        obstacle_id: String,
+        position: Position2D,
+        timestamp: DateTime<Utc>,
+    },
+    ObstacleRemoved {
+        planner_id: String,
+        obstacle_id: String,
+        timestamp: DateTime<Utc>,
+    },
+    PlanInvalidated {
+        planner_id: String,
+        plan_id: String,
+        agent_id: String, // Agent following the plan; it must stop using the old route
+        obstacle_id: String, // Obstacle change that blocked the route
+        start_position: Position2D,
+        destination_position: Position2D,
+        start_orientation: Orientation2D,
+        destination_orientation: Orientation2D,
+        timestamp: DateTime<Utc>,
+    },
+
+    // Retention events
+    PlanArchived {
+        planner_id: String,
+        plan: PathPlan, // Final state of the plan as it leaves the aggregate
+        timestamp: DateTime<Utc>,
+    },
 }
 
 impl DomainEvent for PathPlanningEvent {
@@ -135,6 +209,15 @@ impl DomainEvent for PathPlanningEvent {
             PathPlanningEvent::PlanRequested { .. } => "PlanRequested",
             PathPlanningEvent::PlanCompleted { .. } => "PlanCompleted",
             PathPlanningEvent::PlanFailed { .. } => "PlanFailed",
+            PathPlanningEvent::MultiGoalPlanRequested { .. } => "MultiGoalPlanRequested",
+            PathPlanningEvent::MultiGoalPlanCompleted { .. } => "MultiGoalPlanCompleted",
+            PathPlanningEvent::MultiGoalPlanFailed { .. } => "MultiGoalPlanFailed",
+            PathPlanningEvent::PlanBatchRequested { .. } => "PlanBatchRequested",
+            PathPlanningEvent::ObstacleAdded { .. } => "ObstacleAdded",
+            PathPlanningEvent::ObstacleMoved { .. } => "ObstacleMoved",
+            PathPlanningEvent::ObstacleRemoved { .. } => "ObstacleRemoved",
+            PathPlanningEvent::PlanInvalidated { .. } => "PlanInvalidated",
+            PathPlanningEvent::PlanArchived { .. } => "PlanArchived",
         }
     }
 
@@ -155,6 +238,15 @@ impl DomainEvent for PathPlanningEvent {
             PathPlanningEvent::PlanRequested { planner_id, .. } => planner_id,
             PathPlanningEvent::PlanCompleted { planner_id, .. } => planner_id,
             PathPlanningEvent::PlanFailed { planner_id, .. } => planner_id,
+            PathPlanningEvent::MultiGoalPlanRequested { planner_id, .. } => planner_id,
+            PathPlanningEvent::MultiGoalPlanCompleted { planner_id, .. } => planner_id,
+            PathPlanningEvent::MultiGoalPlanFailed { planner_id, .. } => planner_id,
+            PathPlanningEvent::PlanBatchRequested { planner_id, .. } => planner_id,
+            PathPlanningEvent::ObstacleAdded { planner_id, .. } => planner_id,
+            PathPlanningEvent::ObstacleMoved { planner_id, .. } => planner_id,
+            PathPlanningEvent::ObstacleRemoved { planner_id, .. } => planner_id,
+            PathPlanningEvent::PlanInvalidated { planner_id, .. } => planner_id,
+            PathPlanningEvent::PlanArchived { planner_id, .. } => planner_id,
         }
     }
 
@@ -179,6 +271,15 @@ impl DomainEvent for PathPlanningEvent {
             PathPlanningEvent::PlanRequested { timestamp, .. } => *timestamp,
             PathPlanningEvent::PlanCompleted { timestamp, .. } => *timestamp,
             PathPlanningEvent::PlanFailed { timestamp, .. } => *timestamp,
+            PathPlanningEvent::MultiGoalPlanRequested { timestamp, .. } => *timestamp,
+            PathPlanningEvent::MultiGoalPlanCompleted { timestamp, .. } => *timestamp,
+            PathPlanningEvent::MultiGoalPlanFailed { timestamp, .. } => *timestamp,
+            PathPlanningEvent::PlanBatchRequested { timestamp, .. } => *timestamp,
+            PathPlanningEvent::ObstacleAdded { timestamp, .. } => *timestamp,
+            PathPlanningEvent::ObstacleMoved { timestamp, .. } => *timestamp,
+            PathPlanningEvent::ObstacleRemoved { timestamp, .. } => *timestamp,
+            PathPlanningEvent::PlanInvalidated { timestamp, .. } => *timestamp,
+            PathPlanningEvent::PlanArchived { timestamp, .. } => *timestamp,
         }
     }
 }
@@ -1,12 +1,18 @@
 pub mod actors;
 pub mod aggregate;
 pub mod events;
+pub mod multi_agent;
+pub mod plan_cache;
 pub mod projections;
+pub mod routing;
 
 pub use actors::*;
 pub use aggregate::*;
 pub use events::*;
+pub use multi_agent::*;
+pub use plan_cache::*;
 pub use projections::*;
+pub use routing::*;
 pub mod ports;
 
 pub use ports::*;
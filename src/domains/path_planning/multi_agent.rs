@@ -0,0 +1,316 @@
+// Multi-agent planning - prioritised planning with space-time reservations
+use super::aggregate::{PathPlanRequest, Position2D, Workspace};
+use crate::common::{DomainError, DomainResult};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiAgentPlanConfig {
+    pub grid_resolution: f64, // Cell size in workspace units
+    pub max_time_steps: u32,  // Search horizon per agent
+}
+
+impl Default for MultiAgentPlanConfig {
+    fn default() -> Self {
+        Self {
+            grid_resolution: 1.0,
+            max_time_steps: 1000,
+        }
+    }
+}
+
+/// Position of an agent at a discrete time step; one step moves at most one cell.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TimedWaypoint {
+    pub position: Position2D,
+    pub time_step: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimedPath {
+    pub request_id: String,
+    pub agent_id: String,
+    pub waypoints: Vec<TimedWaypoint>, // One entry per time step, starting at 0
+}
+
+impl TimedPath {
+    /// Where the agent is at `time_step`; agents wait at their goal once they arrive.
+    pub fn position_at(&self, time_step: u32) -> Option<&Position2D> {
+        self.waypoints
+            .get(time_step as usize)
+            .or(self.waypoints.last())
+            .map(|w| &w.position)
+    }
+
+    pub fn arrival_step(&self) -> u32 {
+        self.waypoints.last().map_or(0, |w| w.time_step)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathConflict {
+    /// Two agents occupy the same position at the same time step.
+    Vertex {
+        agents: (String, String),
+        position: Position2D,
+        time_step: u32,
+    },
+    /// Two agents swap positions between `time_step` and `time_step + 1`.
+    Edge {
+        agents: (String, String),
+        time_step: u32,
+    },
+}
+
+type Cell = (i64, i64);
+
+/// Grid laid over the workspace; cell centres are the positions agents move between.
+struct Grid<'a> {
+    workspace: &'a Workspace,
+    resolution: f64,
+    width: i64,
+    height: i64,
+}
+
+impl<'a> Grid<'a> {
+    fn new(workspace: &'a Workspace, resolution: f64) -> DomainResult<Self> {
+        if resolution <= 0.0 {
+            return Err(DomainError::InvalidCommand {
+                reason: "Grid resolution must be positive".to_string(),
+            });
+        }
+        let bounds = &workspace.bounds;
+        Ok(Self {
+            workspace,
+            resolution,
+            width: ((bounds.max_x - bounds.min_x) / resolution).floor() as i64 + 1,
+            height: ((bounds.max_y - bounds.min_y) / resolution).floor() as i64 + 1,
+        })
+    }
+
+    fn cell_of(&self, position: &Position2D) -> Option<Cell> {
+        let bounds = &self.workspace.bounds;
+        let cell = (
+            ((position.x - bounds.min_x) / self.resolution).round() as i64,
+            ((position.y - bounds.min_y) / self.resolution).round() as i64,
+        );
+        self.is_free(cell).then_some(cell)
+    }
+
+    fn position_of(&self, cell: Cell) -> Position2D {
+        Position2D {
+            x: self.workspace.bounds.min_x + cell.0 as f64 * self.resolution,
+            y: self.workspace.bounds.min_y + cell.1 as f64 * self.resolution,
+        }
+    }
+
+    fn is_free(&self, cell: Cell) -> bool {
+        cell.0 >= 0
+            && cell.1 >= 0
+            && cell.0 < self.width
+            && cell.1 < self.height
+            && self.workspace.is_free(&self.position_of(cell))
+    }
+}
+
+/// Space-time cells claimed by the agents planned so far.
+#[derive(Default)]
+struct ReservationTable {
+    vertices: HashSet<(Cell, u32)>,
+    edges: HashSet<(Cell, Cell, u32)>, // (from, to, t): moving from `from` at t to `to` at t + 1
+    parked: HashMap<Cell, u32>,        // Cell held from this step on by an agent at its goal
+    last_use: HashMap<Cell, u32>,
+}
+
+impl ReservationTable {
+    fn is_vertex_free(&self, cell: Cell, time_step: u32) -> bool {
+        !self.vertices.contains(&(cell, time_step))
+            && self.parked.get(&cell).is_none_or(|&from| time_step < from)
+    }
+
+    fn is_move_free(&self, from: Cell, to: Cell, time_step: u32) -> bool {
+        !self.edges.contains(&(to, from, time_step))
+    }
+
+    /// An agent may only stop at its goal if nobody passes through it afterwards.
+    fn can_park(&self, cell: Cell, time_step: u32) -> bool {
+        self.last_use
+            .get(&cell)
+            .is_none_or(|&last| last < time_step)
+    }
+
+    fn reserve_vertex(&mut self, cell: Cell, time_step: u32) {
+        self.vertices.insert((cell, time_step));
+        let last = self.last_use.entry(cell).or_insert(time_step);
+        *last = (*last).max(time_step);
+    }
+
+    fn reserve_path(&mut self, path: &[Cell]) {
+        for (t, &cell) in path.iter().enumerate() {
+            self.reserve_vertex(cell, t as u32);
+        }
+        for (t, step) in path.windows(2).enumerate() {
+            self.edges.insert((step[0], step[1], t as u32));
+        }
+        if let Some(&goal) = path.last() {
+            self.parked.insert(goal, path.len() as u32 - 1);
+        }
+    }
+}
+
+/// Prioritised planning: agents are planned in request order, each one around the
+/// space-time reservations of the agents before it. Returns one time-indexed path
+/// per request, in request order, with no vertex or edge conflicts between them.
+pub fn plan_conflict_free(
+    workspace: &Workspace,
+    requests: &[PathPlanRequest],
+    config: &MultiAgentPlanConfig,
+) -> DomainResult<Vec<TimedPath>> {
+    let grid = Grid::new(workspace, config.grid_resolution)?;
+    let mut endpoints = Vec::with_capacity(requests.len());
+    for request in requests {
+        let start =
+            grid.cell_of(&request.start_position)
+                .ok_or_else(|| DomainError::InvalidCommand {
+                    reason: format!(
+                        "Start position of agent {} is outside the workspace or blocked",
+                        request.agent_id
+                    ),
+                })?;
+        let goal = grid.cell_of(&request.destination_position).ok_or_else(|| {
+            DomainError::InvalidCommand {
+                reason: format!(
+                    "Destination of agent {} is outside the workspace or blocked",
+                    request.agent_id
+                ),
+            }
+        })?;
+        endpoints.push((start, goal));
+    }
+    let distinct_starts: HashSet<Cell> = endpoints.iter().map(|e| e.0).collect();
+    let distinct_goals: HashSet<Cell> = endpoints.iter().map(|e| e.1).collect();
+    if distinct_starts.len() != endpoints.len() || distinct_goals.len() != endpoints.len() {
+        return Err(DomainError::InvalidCommand {
+            reason: "Agents must start and finish in distinct grid cells".to_string(),
+        });
+    }
+
+    let mut reservations = ReservationTable::default();
+    // Agents are standing on their starts when planning begins
+    for &(start, _) in &endpoints {
+        reservations.reserve_vertex(start, 0);
+    }
+
+    let mut paths = Vec::with_capacity(requests.len());
+    for (request, &(start, goal)) in requests.iter().zip(&endpoints) {
+        let cells = space_time_astar(&grid, &reservations, start, goal, config.max_time_steps)
+            .ok_or_else(|| DomainError::InvalidCommand {
+                reason: format!(
+                    "No conflict-free path for agent {} within {} time steps",
+                    request.agent_id, config.max_time_steps
+                ),
+            })?;
+        reservations.reserve_path(&cells);
+        paths.push(TimedPath {
+            request_id: request.request_id.clone(),
+            agent_id: request.agent_id.clone(),
+            waypoints: cells
+                .iter()
+                .enumerate()
+                .map(|(t, &cell)| TimedWaypoint {
+                    position: grid.position_of(cell),
+                    time_step: t as u32,
+                })
+                .collect(),
+        });
+    }
+    Ok(paths)
+}
+
+/// A* over (cell, time) with four-connected moves and waiting in place. Every step
+/// costs one, so the cost of a state is its time step.
+fn space_time_astar(
+    grid: &Grid,
+    reservations: &ReservationTable,
+    start: Cell,
+    goal: Cell,
+    max_time_steps: u32,
+) -> Option<Vec<Cell>> {
+    let heuristic = |cell: Cell| ((cell.0 - goal.0).abs() + (cell.1 - goal.1).abs()) as u32;
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<(Cell, u32), (Cell, u32)> = HashMap::new();
+    let mut closed: HashSet<(Cell, u32)> = HashSet::new();
+    open.push(Reverse((heuristic(start), 0u32, start)));
+
+    while let Some(Reverse((_, t, cell))) = open.pop() {
+        if !closed.insert((cell, t)) {
+            continue;
+        }
+        if cell == goal && reservations.can_park(goal, t) {
+            let mut path = vec![cell];
+            let mut state = (cell, t);
+            while let Some(&previous) = came_from.get(&state) {
+                path.push(previous.0);
+                state = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        if t >= max_time_steps {
+            continue;
+        }
+        let next_t = t + 1;
+        for next in [
+            cell,
+            (cell.0 + 1, cell.1),
+            (cell.0 - 1, cell.1),
+            (cell.0, cell.1 + 1),
+            (cell.0, cell.1 - 1),
+        ] {
+            if closed.contains(&(next, next_t))
+                || !grid.is_free(next)
+                || !reservations.is_vertex_free(next, next_t)
+                || !reservations.is_move_free(cell, next, t)
+            {
+                continue;
+            }
+            came_from.entry((next, next_t)).or_insert((cell, t));
+            open.push(Reverse((next_t + heuristic(next), next_t, next)));
+        }
+    }
+    None
+}
+
+/// Check time-indexed paths pairwise for vertex and edge conflicts.
+pub fn find_conflicts(paths: &[TimedPath]) -> Vec<PathConflict> {
+    let horizon = paths.iter().map(|p| p.arrival_step()).max().unwrap_or(0);
+    let mut conflicts = Vec::new();
+    for (i, a) in paths.iter().enumerate() {
+        for b in &paths[i + 1..] {
+            let agents = (a.agent_id.clone(), b.agent_id.clone());
+            for t in 0..=horizon {
+                let (Some(a_now), Some(b_now)) = (a.position_at(t), b.position_at(t)) else {
+                    break;
+                };
+                if a_now == b_now {
+                    conflicts.push(PathConflict::Vertex {
+                        agents: agents.clone(),
+                        position: a_now.clone(),
+                        time_step: t,
+                    });
+                    continue;
+                }
+                if let (Some(a_next), Some(b_next)) = (a.position_at(t + 1), b.position_at(t + 1)) {
+                    if a_now == b_next && b_now == a_next {
+                        conflicts.push(PathConflict::Edge {
+                            agents: agents.clone(),
+                            time_step: t,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    conflicts
+}
@@ -0,0 +1,114 @@
+// Plan cache - content addressing for computed plans and graph-change invalidation
+use super::aggregate::{Orientation2D, PlanningAlgorithm, Position2D};
+use super::ports::{GraphStoreAsync, PlanCache};
+use crate::common::DomainResult;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::TAU;
+use std::sync::Arc;
+
+/// Content address of a computed plan. Positions are snapped to a grid and headings
+/// to fixed steps, so requests that only differ by noise share one entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PlanCacheKey {
+    pub map_name: String,
+    pub graph_version: String, // Content hash of the graph bytes, see `graph_version_of`
+    pub algorithm: PlanningAlgorithm,
+    pub start: (i64, i64),
+    pub goal: (i64, i64),
+    pub start_heading: i64,
+    pub goal_heading: i64,
+}
+
+impl PlanCacheKey {
+    /// Grid size used to snap start and goal, in workspace units
+    pub const POSITION_RESOLUTION: f64 = 0.1;
+    /// Number of heading buckets per full turn (5 degrees each)
+    pub const HEADING_STEPS: i64 = 72;
+
+    pub fn new(
+        map_name: &str,
+        graph_version: &str,
+        algorithm: PlanningAlgorithm,
+        start: &Position2D,
+        goal: &Position2D,
+        start_orientation: &Orientation2D,
+        goal_orientation: &Orientation2D,
+    ) -> Self {
+        Self {
+            map_name: map_name.to_string(),
+            graph_version: graph_version.to_string(),
+            algorithm,
+            start: Self::snap_position(start),
+            goal: Self::snap_position(goal),
+            start_heading: Self::snap_heading(start_orientation),
+            goal_heading: Self::snap_heading(goal_orientation),
+        }
+    }
+
+    /// Version of a graph as stored in a `GraphStoreAsync`: the md5 of its bytes.
+    pub fn graph_version_of(graph_bytes: &[u8]) -> String {
+        format!("{:x}", md5::compute(graph_bytes))
+    }
+
+    /// Stable storage key; equal keys always produce the same digest.
+    pub fn digest(&self) -> String {
+        let canonical = format!(
+            "{}|{}|{:?}|{},{}|{},{}|{}|{}",
+            self.map_name,
+            self.graph_version,
+            self.algorithm,
+            self.start.0,
+            self.start.1,
+            self.goal.0,
+            self.goal.1,
+            self.start_heading,
+            self.goal_heading
+        );
+        format!("{:x}", md5::compute(canonical.as_bytes()))
+    }
+
+    fn snap_position(position: &Position2D) -> (i64, i64) {
+        (
+            (position.x / Self::POSITION_RESOLUTION).round() as i64,
+            (position.y / Self::POSITION_RESOLUTION).round() as i64,
+        )
+    }
+
+    fn snap_heading(orientation: &Orientation2D) -> i64 {
+        let turns = orientation.angle.rem_euclid(TAU) / TAU;
+        (turns * Self::HEADING_STEPS as f64).round() as i64 % Self::HEADING_STEPS
+    }
+}
+
+/// `GraphStoreAsync` decorator that drops cached plans whenever a graph is saved
+/// or deleted, so no plan computed on an old graph outlives it.
+pub struct CacheInvalidatingGraphStore {
+    inner: Arc<dyn GraphStoreAsync>,
+    cache: Arc<dyn PlanCache>,
+}
+
+impl CacheInvalidatingGraphStore {
+    pub fn new(inner: Arc<dyn GraphStoreAsync>, cache: Arc<dyn PlanCache>) -> Self {
+        Self { inner, cache }
+    }
+}
+
+#[async_trait]
+impl GraphStoreAsync for CacheInvalidatingGraphStore {
+    async fn save_graph_bytes(&self, name: &str, bytes: &[u8]) -> DomainResult<()> {
+        self.inner.save_graph_bytes(name, bytes).await?;
+        self.cache.invalidate_map(name).await?;
+        Ok(())
+    }
+
+    async fn load_graph_bytes(&self, name: &str) -> DomainResult<Vec<u8>> {
+        self.inner.load_graph_bytes(name).await
+    }
+
+    async fn delete_graph(&self, name: &str) -> DomainResult<()> {
+        self.inner.delete_graph(name).await?;
+        self.cache.invalidate_map(name).await?;
+        Ok(())
+    }
+}
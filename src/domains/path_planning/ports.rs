@@ -1,3 +1,5 @@
+use super::aggregate::Position2D;
+use super::plan_cache::PlanCacheKey;
 use crate::common::DomainResult;
 use async_trait::async_trait;
 
@@ -25,3 +27,14 @@ pub trait GraphStoreAsync: Send + Sync {
     async fn load_graph_bytes(&self, name: &str) -> DomainResult<Vec<u8>>;
     async fn delete_graph(&self, name: &str) -> DomainResult<()>;
 }
+
+/// Port for reusing computed plans across identical requests on the same graph.
+#[async_trait]
+pub trait PlanCache: Send + Sync {
+    /// Waypoints previously stored under `key`, if any
+    async fn get(&self, key: &PlanCacheKey) -> DomainResult<Option<Vec<Position2D>>>;
+    /// Store the waypoints computed for `key`, replacing an existing entry
+    async fn put(&self, key: &PlanCacheKey, waypoints: &[Position2D]) -> DomainResult<()>;
+    /// Drop every entry computed on the named map; returns how many were removed
+    async fn invalidate_map(&self, map_name: &str) -> DomainResult<usize>;
+}
@@ -1,5 +1,9 @@
 // Path planning projections - simplified implementation
+use super::aggregate::PathPlan;
+use super::events::PathPlanningEvent;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PathPlanningProjection {
@@ -8,3 +12,59 @@ pub struct PathPlanningProjection {
     pub completed_plans_count: usize,
     pub failed_plans_count: usize,
 }
+
+/// Read-side history of finished plans archived out of the PathPlanner aggregate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanHistoryProjection {
+    pub planner_id: String,
+    pub archived_plans: HashMap<String, ArchivedPlan>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedPlan {
+    pub plan: PathPlan,
+    pub archived_at: DateTime<Utc>,
+}
+
+impl PlanHistoryProjection {
+    pub fn new(planner_id: String) -> Self {
+        Self {
+            planner_id,
+            archived_plans: HashMap::new(),
+        }
+    }
+
+    pub fn apply_event(&mut self, event: &PathPlanningEvent) {
+        if let PathPlanningEvent::PlanArchived {
+            planner_id,
+            plan,
+            timestamp,
+        } = event
+        {
+            if *planner_id != self.planner_id {
+                return;
+            }
+            self.archived_plans.insert(
+                plan.id.clone(),
+                ArchivedPlan {
+                    plan: plan.clone(),
+                    archived_at: *timestamp,
+                },
+            );
+        }
+    }
+
+    pub fn get_plan(&self, plan_id: &str) -> Option<&ArchivedPlan> {
+        self.archived_plans.get(plan_id)
+    }
+
+    pub fn plans_for_agent(&self, agent_id: &str) -> Vec<&ArchivedPlan> {
+        let mut plans: Vec<&ArchivedPlan> = self
+            .archived_plans
+            .values()
+            .filter(|a| a.plan.agent_id == agent_id)
+            .collect();
+        plans.sort_by_key(|a| a.plan.created_at);
+        plans
+    }
+}
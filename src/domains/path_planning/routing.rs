@@ -0,0 +1,117 @@
+// Multi-goal routing - goal ordering and leg construction for multi-goal plans
+use super::aggregate::{Orientation2D, PlanGoal, Position2D};
+
+/// One start-to-goal segment of a multi-goal route; each leg is planned by a
+/// worker exactly like a single plan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanLeg {
+    pub start_position: Position2D,
+    pub start_orientation: Orientation2D,
+    pub destination_position: Position2D,
+    pub destination_orientation: Orientation2D,
+}
+
+fn distance(a: &Position2D, b: &Position2D) -> f64 {
+    ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt()
+}
+
+/// Straight-line length of visiting `goals` in `order`, starting at `start`.
+/// The route is open: it ends at the last goal and does not return.
+pub fn route_length(start: &Position2D, goals: &[PlanGoal], order: &[usize]) -> f64 {
+    let mut length = 0.0;
+    let mut current = start;
+    for &index in order {
+        length += distance(current, &goals[index].position);
+        current = &goals[index].position;
+    }
+    length
+}
+
+/// Visiting order for `goals` using a nearest-neighbour tour improved by 2-opt.
+/// Returns indices into `goals`; this is a heuristic, not an exact TSP solution.
+pub fn optimize_goal_order(start: &Position2D, goals: &[PlanGoal]) -> Vec<usize> {
+    let mut order = nearest_neighbour_order(start, goals);
+    improve_with_two_opt(start, goals, &mut order);
+    order
+}
+
+fn nearest_neighbour_order(start: &Position2D, goals: &[PlanGoal]) -> Vec<usize> {
+    let mut remaining: Vec<usize> = (0..goals.len()).collect();
+    let mut order = Vec::with_capacity(goals.len());
+    let mut current = start.clone();
+    while !remaining.is_empty() {
+        let (slot, _) = remaining
+            .iter()
+            .enumerate()
+            .map(|(slot, &index)| (slot, distance(&current, &goals[index].position)))
+            .fold((0, f64::INFINITY), |best, candidate| {
+                if candidate.1 < best.1 {
+                    candidate
+                } else {
+                    best
+                }
+            });
+        let index = remaining.remove(slot);
+        current = goals[index].position.clone();
+        order.push(index);
+    }
+    order
+}
+
+fn improve_with_two_opt(start: &Position2D, goals: &[PlanGoal], order: &mut [usize]) {
+    let mut best = route_length(start, goals, order);
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for i in 0..order.len() {
+            for j in (i + 1)..order.len() {
+                order[i..=j].reverse();
+                let candidate = route_length(start, goals, order);
+                // Require a real gain so floating point noise cannot loop forever
+                if candidate + 1e-9 < best {
+                    best = candidate;
+                    improved = true;
+                } else {
+                    order[i..=j].reverse();
+                }
+            }
+        }
+    }
+}
+
+/// Split a route into legs: the first leg starts at the agent, every further leg
+/// starts where the previous one ended.
+pub fn plan_legs(
+    start_position: &Position2D,
+    start_orientation: &Orientation2D,
+    goals: &[PlanGoal],
+) -> Vec<PlanLeg> {
+    let mut legs = Vec::with_capacity(goals.len());
+    let mut position = start_position.clone();
+    let mut orientation = start_orientation.clone();
+    for goal in goals {
+        legs.push(PlanLeg {
+            start_position: position,
+            start_orientation: orientation,
+            destination_position: goal.position.clone(),
+            destination_orientation: goal.orientation.clone(),
+        });
+        position = goal.position.clone();
+        orientation = goal.orientation.clone();
+    }
+    legs
+}
+
+/// Join the waypoints of completed legs into one route. A leg usually starts with
+/// the point the previous leg ended on, so that joint is only kept once.
+pub fn combined_waypoints<'a>(legs: impl IntoIterator<Item = &'a [Position2D]>) -> Vec<Position2D> {
+    let mut waypoints: Vec<Position2D> = Vec::new();
+    for leg in legs {
+        for waypoint in leg {
+            if waypoints.last() != Some(waypoint) {
+                waypoints.push(waypoint.clone());
+            }
+        }
+    }
+    waypoints
+}
@@ -39,6 +39,8 @@ impl TechnicalAgentEventActor {
             causation_id: None,
             user_id: None,
             source: "TechnicalAgentEventActor".to_string(),
+            reply_to: None,
+            tenant: None,
         };
 
         let envelope = EventEnvelope::new(&event, "TechnicalAgent", metadata)
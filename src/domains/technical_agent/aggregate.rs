@@ -1,5 +1,6 @@
 use super::events::TechnicalAgentEvent;
 use crate::common::{AggregateRoot, DomainResult};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -18,7 +19,7 @@ pub struct TechnicalAgent {
     uncommitted_events: Vec<TechnicalAgentEvent>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum AgentType {
     Drone,
     Robot,
@@ -65,7 +66,7 @@ pub struct Actuator {
     pub status: ComponentStatus,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum ComponentStatus {
     Online,
     Offline,
@@ -82,7 +83,7 @@ pub struct SoftwareModule {
     pub dependencies: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum TechnicalStatus {
     Initializing,
     Ready,
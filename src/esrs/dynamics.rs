@@ -0,0 +1,155 @@
+use ::esrs::Aggregate;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::domains::dynamics::aggregate::{PhysicsModel, SimulationState};
+use crate::domains::dynamics::events::DynamicsEvent;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DynamicsSimulatorState {
+    pub id: String,
+    pub physics_model: Option<PhysicsModel>,
+    pub simulation_state: SimulationState,
+    pub version: u64,
+}
+
+impl Default for DynamicsSimulatorState {
+    fn default() -> Self {
+        DynamicsSimulatorState {
+            id: String::new(),
+            physics_model: None,
+            simulation_state: SimulationState::Stopped,
+            version: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum DynamicsSimulatorCommand {
+    CreateSimulator {
+        simulator_id: String,
+        physics_model: PhysicsModel,
+    },
+    /// Starts a stopped simulation or resumes a paused one
+    StartSimulation,
+    PauseSimulation,
+    StopSimulation,
+    ReportError {
+        message: String,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DynamicsSimulatorError {
+    #[error("invalid command: {0}")]
+    InvalidCommand(String),
+}
+
+fn invalid(reason: impl Into<String>) -> DynamicsSimulatorError {
+    DynamicsSimulatorError::InvalidCommand(reason.into())
+}
+
+pub struct DynamicsSimulator;
+
+impl Aggregate for DynamicsSimulator {
+    const NAME: &'static str = "dynamics_simulator";
+
+    type State = DynamicsSimulatorState;
+    type Command = DynamicsSimulatorCommand;
+    type Event = DynamicsEvent;
+    type Error = DynamicsSimulatorError;
+
+    fn handle_command(
+        state: &Self::State,
+        command: Self::Command,
+    ) -> Result<Vec<Self::Event>, Self::Error> {
+        let now = Utc::now();
+        if state.id.is_empty()
+            && !matches!(command, DynamicsSimulatorCommand::CreateSimulator { .. })
+        {
+            return Err(invalid("Simulator has not been created"));
+        }
+        let simulator_id = state.id.clone();
+        let event = match (command, &state.simulation_state) {
+            (
+                DynamicsSimulatorCommand::CreateSimulator {
+                    simulator_id,
+                    physics_model,
+                },
+                _,
+            ) => {
+                if !state.id.is_empty() {
+                    return Err(invalid(format!("Simulator {} already exists", state.id)));
+                }
+                if simulator_id.is_empty() {
+                    return Err(invalid("Simulator id is required"));
+                }
+                DynamicsEvent::SimulatorCreated {
+                    simulator_id,
+                    physics_model,
+                    timestamp: now,
+                }
+            }
+            (
+                DynamicsSimulatorCommand::StartSimulation,
+                SimulationState::Stopped | SimulationState::Paused,
+            ) => DynamicsEvent::SimulationStarted {
+                simulator_id,
+                timestamp: now,
+            },
+            (DynamicsSimulatorCommand::PauseSimulation, SimulationState::Running) => {
+                DynamicsEvent::StateUpdated {
+                    simulator_id,
+                    new_state: SimulationState::Paused,
+                    timestamp: now,
+                }
+            }
+            // A failed simulation can only be stopped and started again
+            (DynamicsSimulatorCommand::StopSimulation, current)
+                if !matches!(current, SimulationState::Stopped) =>
+            {
+                DynamicsEvent::SimulationStopped {
+                    simulator_id,
+                    timestamp: now,
+                }
+            }
+            (DynamicsSimulatorCommand::ReportError { message }, _) => DynamicsEvent::StateUpdated {
+                simulator_id,
+                new_state: SimulationState::Error(message),
+                timestamp: now,
+            },
+            (command, current) => {
+                return Err(invalid(format!(
+                    "{:?} is not allowed while the simulation is {:?}",
+                    command, current
+                )));
+            }
+        };
+        Ok(vec![event])
+    }
+
+    fn apply_event(mut state: Self::State, event: Self::Event) -> Self::State {
+        match event {
+            DynamicsEvent::SimulatorCreated {
+                simulator_id,
+                physics_model,
+                ..
+            } => {
+                state.id = simulator_id;
+                state.physics_model = Some(physics_model);
+                state.simulation_state = SimulationState::Stopped;
+            }
+            DynamicsEvent::SimulationStarted { .. } => {
+                state.simulation_state = SimulationState::Running;
+            }
+            DynamicsEvent::SimulationStopped { .. } => {
+                state.simulation_state = SimulationState::Stopped;
+            }
+            DynamicsEvent::StateUpdated { new_state, .. } => {
+                state.simulation_state = new_state;
+            }
+        }
+        state.version += 1;
+        state
+    }
+}
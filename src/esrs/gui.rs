@@ -0,0 +1,231 @@
+use ::esrs::Aggregate;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domains::gui::aggregate::{UserSession, Window, WindowType};
+use crate::domains::gui::events::GUIEvent;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GUIApplicationState {
+    pub id: String,
+    pub name: String,
+    pub windows: Vec<Window>,
+    pub active_sessions: Vec<UserSession>,
+    pub version: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum GUIApplicationCommand {
+    CreateApplication {
+        app_id: String,
+        name: String,
+    },
+    OpenWindow {
+        title: String,
+        window_type: WindowType,
+    },
+    CloseWindow {
+        window_id: Uuid,
+    },
+    StartSession {
+        user_id: String,
+    },
+    EndSession {
+        session_id: Uuid,
+    },
+    RecordInteraction {
+        session_id: Uuid,
+        window_id: Uuid,
+        component_id: Uuid,
+        interaction_type: String,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GUIApplicationError {
+    #[error("invalid command: {0}")]
+    InvalidCommand(String),
+}
+
+fn invalid(reason: impl Into<String>) -> GUIApplicationError {
+    GUIApplicationError::InvalidCommand(reason.into())
+}
+
+fn ensure_window(state: &GUIApplicationState, window_id: Uuid) -> Result<(), GUIApplicationError> {
+    if !state.windows.iter().any(|w| w.id == window_id) {
+        return Err(invalid(format!("Window {} is not open", window_id)));
+    }
+    Ok(())
+}
+
+fn ensure_session(state: &GUIApplicationState, session_id: Uuid) -> Result<(), GUIApplicationError> {
+    if !state.active_sessions.iter().any(|s| s.session_id == session_id) {
+        return Err(invalid(format!("Session {} is not active", session_id)));
+    }
+    Ok(())
+}
+
+pub struct GUIApplication;
+
+impl Aggregate for GUIApplication {
+    const NAME: &'static str = "gui_application";
+
+    type State = GUIApplicationState;
+    type Command = GUIApplicationCommand;
+    type Event = GUIEvent;
+    type Error = GUIApplicationError;
+
+    fn handle_command(
+        state: &Self::State,
+        command: Self::Command,
+    ) -> Result<Vec<Self::Event>, Self::Error> {
+        let now = Utc::now();
+        if state.id.is_empty()
+            && !matches!(command, GUIApplicationCommand::CreateApplication { .. })
+        {
+            return Err(invalid("Application has not been created"));
+        }
+        let app_id = state.id.clone();
+        let event = match command {
+            GUIApplicationCommand::CreateApplication { app_id, name } => {
+                if !state.id.is_empty() {
+                    return Err(invalid(format!("Application {} already exists", state.id)));
+                }
+                if app_id.is_empty() || name.is_empty() {
+                    return Err(invalid("Application id and name are required"));
+                }
+                GUIEvent::ApplicationCreated {
+                    app_id,
+                    name,
+                    timestamp: now,
+                }
+            }
+            GUIApplicationCommand::OpenWindow { title, window_type } => {
+                if title.trim().is_empty() {
+                    return Err(invalid("Window title is required"));
+                }
+                GUIEvent::WindowCreated {
+                    app_id,
+                    window_id: Uuid::new_v4(),
+                    title,
+                    window_type,
+                    timestamp: now,
+                }
+            }
+            GUIApplicationCommand::CloseWindow { window_id } => {
+                ensure_window(state, window_id)?;
+                GUIEvent::WindowClosed {
+                    app_id,
+                    window_id,
+                    timestamp: now,
+                }
+            }
+            GUIApplicationCommand::StartSession { user_id } => {
+                if user_id.is_empty() {
+                    return Err(invalid("User id is required"));
+                }
+                GUIEvent::UserSessionStarted {
+                    app_id,
+                    session_id: Uuid::new_v4(),
+                    user_id,
+                    timestamp: now,
+                }
+            }
+            GUIApplicationCommand::EndSession { session_id } => {
+                ensure_session(state, session_id)?;
+                GUIEvent::UserSessionEnded {
+                    app_id,
+                    session_id,
+                    timestamp: now,
+                }
+            }
+            GUIApplicationCommand::RecordInteraction {
+                session_id,
+                window_id,
+                component_id,
+                interaction_type,
+            } => {
+                ensure_session(state, session_id)?;
+                ensure_window(state, window_id)?;
+                GUIEvent::UserInteraction {
+                    app_id,
+                    session_id,
+                    window_id,
+                    component_id,
+                    interaction_type,
+                    timestamp: now,
+                }
+            }
+        };
+        Ok(vec![event])
+    }
+
+    fn apply_event(mut state: Self::State, event: Self::Event) -> Self::State {
+        match event {
+            GUIEvent::ApplicationCreated { app_id, name, .. } => {
+                state.id = app_id;
+                state.name = name;
+            }
+            GUIEvent::WindowCreated {
+                window_id,
+                title,
+                window_type,
+                ..
+            } => {
+                // Layout is not part of the event; the window opens at the origin
+                state.windows.push(Window {
+                    id: window_id,
+                    title,
+                    window_type,
+                    position: (0, 0),
+                    size: (0, 0),
+                    visible: true,
+                    components: Vec::new(),
+                });
+            }
+            GUIEvent::WindowClosed { window_id, .. } => {
+                state.windows.retain(|w| w.id != window_id);
+                for session in &mut state.active_sessions {
+                    session.active_windows.retain(|w| *w != window_id);
+                }
+            }
+            GUIEvent::UserSessionStarted {
+                session_id,
+                user_id,
+                timestamp,
+                ..
+            } => {
+                state.active_sessions.push(UserSession {
+                    session_id,
+                    user_id,
+                    start_time: timestamp,
+                    last_activity: timestamp,
+                    active_windows: Vec::new(),
+                });
+            }
+            GUIEvent::UserSessionEnded { session_id, .. } => {
+                state.active_sessions.retain(|s| s.session_id != session_id);
+            }
+            GUIEvent::UserInteraction {
+                session_id,
+                window_id,
+                timestamp,
+                ..
+            } => {
+                if let Some(session) = state
+                    .active_sessions
+                    .iter_mut()
+                    .find(|s| s.session_id == session_id)
+                {
+                    session.last_activity = timestamp;
+                    if !session.active_windows.contains(&window_id) {
+                        session.active_windows.push(window_id);
+                    }
+                }
+            }
+        }
+        state.version += 1;
+        state
+    }
+}
@@ -0,0 +1,293 @@
+use ::esrs::Aggregate;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::domains::kinematic_agent::aggregate::{
+    Acceleration3D, KinematicsModel, MovementConstraints, Orientation, Position3D, Velocity3D,
+};
+use crate::domains::kinematic_agent::events::KinematicAgentEvent;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KinematicAgentState {
+    pub id: String,
+    pub position: Option<Position3D>,
+    pub velocity: Velocity3D,
+    pub acceleration: Acceleration3D,
+    pub orientation: Orientation,
+    pub kinematics_model: Option<KinematicsModel>,
+    pub constraints: MovementConstraints,
+    /// Trajectory being followed, if any
+    pub active_trajectory: Option<String>,
+    pub collisions: u64,
+    pub version: u64,
+}
+
+impl Default for KinematicAgentState {
+    fn default() -> Self {
+        KinematicAgentState {
+            id: String::new(),
+            position: None,
+            velocity: Velocity3D {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            acceleration: Acceleration3D {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            orientation: Orientation {
+                roll: 0.0,
+                pitch: 0.0,
+                yaw: 0.0,
+            },
+            kinematics_model: None,
+            constraints: MovementConstraints::default(),
+            active_trajectory: None,
+            collisions: 0,
+            version: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum KinematicAgentCommand {
+    CreateAgent {
+        agent_id: String,
+        initial_position: Position3D,
+        kinematics_model: KinematicsModel,
+    },
+    UpdatePosition {
+        new_position: Position3D,
+    },
+    UpdateVelocity {
+        new_velocity: Velocity3D,
+    },
+    UpdateAcceleration {
+        new_acceleration: Acceleration3D,
+    },
+    UpdateOrientation {
+        new_orientation: Orientation,
+    },
+    StartTrajectory {
+        trajectory_id: String,
+        target_position: Position3D,
+    },
+    CompleteTrajectory {
+        trajectory_id: String,
+        final_position: Position3D,
+    },
+    ReportCollision {
+        collision_point: Position3D,
+        other_agent_id: Option<String>,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum KinematicAgentError {
+    #[error("invalid command: {0}")]
+    InvalidCommand(String),
+}
+
+fn invalid(reason: impl Into<String>) -> KinematicAgentError {
+    KinematicAgentError::InvalidCommand(reason.into())
+}
+
+fn ensure_in_workspace(
+    state: &KinematicAgentState,
+    position: &Position3D,
+) -> Result<(), KinematicAgentError> {
+    if !state.constraints.contains(position) {
+        return Err(invalid("Position violates workspace constraints"));
+    }
+    Ok(())
+}
+
+fn magnitude(x: f64, y: f64, z: f64) -> f64 {
+    (x * x + y * y + z * z).sqrt()
+}
+
+pub struct KinematicAgent;
+
+impl Aggregate for KinematicAgent {
+    const NAME: &'static str = "kinematic_agent";
+
+    type State = KinematicAgentState;
+    type Command = KinematicAgentCommand;
+    type Event = KinematicAgentEvent;
+    type Error = KinematicAgentError;
+
+    fn handle_command(
+        state: &Self::State,
+        command: Self::Command,
+    ) -> Result<Vec<Self::Event>, Self::Error> {
+        let now = Utc::now();
+        // Every command but CreateAgent needs an existing agent
+        if state.position.is_none() && !matches!(command, KinematicAgentCommand::CreateAgent { .. })
+        {
+            return Err(invalid("Agent has not been created"));
+        }
+        let agent_id = state.id.clone();
+        let event = match command {
+            KinematicAgentCommand::CreateAgent {
+                agent_id,
+                initial_position,
+                kinematics_model,
+            } => {
+                if state.position.is_some() {
+                    return Err(invalid(format!("Agent {} already exists", state.id)));
+                }
+                if agent_id.is_empty() {
+                    return Err(invalid("Agent id is required"));
+                }
+                ensure_in_workspace(state, &initial_position)?;
+                KinematicAgentEvent::AgentCreated {
+                    agent_id,
+                    initial_position,
+                    kinematics_model,
+                    timestamp: now,
+                }
+            }
+            KinematicAgentCommand::UpdatePosition { new_position } => {
+                ensure_in_workspace(state, &new_position)?;
+                KinematicAgentEvent::PositionUpdated {
+                    agent_id,
+                    new_position,
+                    timestamp: now,
+                }
+            }
+            KinematicAgentCommand::UpdateVelocity { new_velocity } => {
+                let speed = magnitude(new_velocity.x, new_velocity.y, new_velocity.z);
+                if speed > state.constraints.max_velocity {
+                    return Err(invalid(format!(
+                        "Speed {:.2} exceeds the maximum of {:.2}",
+                        speed, state.constraints.max_velocity
+                    )));
+                }
+                KinematicAgentEvent::VelocityUpdated {
+                    agent_id,
+                    new_velocity,
+                    timestamp: now,
+                }
+            }
+            KinematicAgentCommand::UpdateAcceleration { new_acceleration } => {
+                let acceleration =
+                    magnitude(new_acceleration.x, new_acceleration.y, new_acceleration.z);
+                if acceleration > state.constraints.max_acceleration {
+                    return Err(invalid(format!(
+                        "Acceleration {:.2} exceeds the maximum of {:.2}",
+                        acceleration, state.constraints.max_acceleration
+                    )));
+                }
+                KinematicAgentEvent::AccelerationUpdated {
+                    agent_id,
+                    new_acceleration,
+                    timestamp: now,
+                }
+            }
+            KinematicAgentCommand::UpdateOrientation { new_orientation } => {
+                KinematicAgentEvent::OrientationUpdated {
+                    agent_id,
+                    new_orientation,
+                    timestamp: now,
+                }
+            }
+            KinematicAgentCommand::StartTrajectory {
+                trajectory_id,
+                target_position,
+            } => {
+                if let Some(active) = &state.active_trajectory {
+                    return Err(invalid(format!("Trajectory {} is still active", active)));
+                }
+                ensure_in_workspace(state, &target_position)?;
+                let Some(start_position) = state.position.clone() else {
+                    return Err(invalid("Agent has not been created"));
+                };
+                KinematicAgentEvent::TrajectoryStarted {
+                    agent_id,
+                    trajectory_id,
+                    start_position,
+                    target_position,
+                    timestamp: now,
+                }
+            }
+            KinematicAgentCommand::CompleteTrajectory {
+                trajectory_id,
+                final_position,
+            } => {
+                if state.active_trajectory.as_ref() != Some(&trajectory_id) {
+                    return Err(invalid(format!(
+                        "Trajectory {} is not active",
+                        trajectory_id
+                    )));
+                }
+                ensure_in_workspace(state, &final_position)?;
+                KinematicAgentEvent::TrajectoryCompleted {
+                    agent_id,
+                    trajectory_id,
+                    final_position,
+                    timestamp: now,
+                }
+            }
+            KinematicAgentCommand::ReportCollision {
+                collision_point,
+                other_agent_id,
+            } => {
+                if other_agent_id.as_ref() == Some(&state.id) {
+                    return Err(invalid("An agent cannot collide with itself"));
+                }
+                KinematicAgentEvent::CollisionDetected {
+                    agent_id,
+                    collision_point,
+                    other_agent_id,
+                    timestamp: now,
+                }
+            }
+        };
+        Ok(vec![event])
+    }
+
+    fn apply_event(mut state: Self::State, event: Self::Event) -> Self::State {
+        match event {
+            KinematicAgentEvent::AgentCreated {
+                agent_id,
+                initial_position,
+                kinematics_model,
+                ..
+            } => {
+                state.id = agent_id;
+                state.position = Some(initial_position);
+                state.kinematics_model = Some(kinematics_model);
+            }
+            KinematicAgentEvent::PositionUpdated { new_position, .. } => {
+                state.position = Some(new_position);
+            }
+            KinematicAgentEvent::VelocityUpdated { new_velocity, .. } => {
+                state.velocity = new_velocity;
+            }
+            KinematicAgentEvent::AccelerationUpdated {
+                new_acceleration, ..
+            } => {
+                state.acceleration = new_acceleration;
+            }
+            KinematicAgentEvent::OrientationUpdated {
+                new_orientation, ..
+            } => {
+                state.orientation = new_orientation;
+            }
+            KinematicAgentEvent::TrajectoryStarted { trajectory_id, .. } => {
+                state.active_trajectory = Some(trajectory_id);
+            }
+            KinematicAgentEvent::TrajectoryCompleted { final_position, .. } => {
+                state.active_trajectory = None;
+                state.position = Some(final_position);
+            }
+            KinematicAgentEvent::CollisionDetected { .. } => {
+                state.collisions += 1;
+            }
+        }
+        state.version += 1;
+        state
+    }
+}
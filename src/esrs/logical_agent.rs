@@ -0,0 +1,337 @@
+use ::esrs::Aggregate;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domains::logical_agent::aggregate::{
+    AgentStatus, Fact, Objective, ObjectiveStatus, Rule,
+};
+use crate::domains::logical_agent::events::LogicalAgentEvent;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogicalAgentState {
+    pub id: String,
+    pub name: String,
+    pub status: AgentStatus,
+    pub objectives: Vec<Objective>,
+    pub facts: Vec<Fact>,
+    pub rules: Vec<Rule>,
+    pub version: u64,
+}
+
+impl Default for LogicalAgentState {
+    fn default() -> Self {
+        LogicalAgentState {
+            id: String::new(),
+            name: String::new(),
+            status: AgentStatus::Idle,
+            objectives: Vec::new(),
+            facts: Vec::new(),
+            rules: Vec::new(),
+            version: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LogicalAgentCommand {
+    CreateAgent {
+        agent_id: String,
+        name: String,
+    },
+    AddObjective {
+        description: String,
+        priority: u8,
+        constraints: Vec<String>,
+    },
+    CompleteObjective {
+        objective_id: Uuid,
+    },
+    FailObjective {
+        objective_id: Uuid,
+        reason: String,
+    },
+    ChangeStatus {
+        new_status: AgentStatus,
+    },
+    AddFact {
+        statement: String,
+        confidence: f64,
+        source: String,
+    },
+    AddRule {
+        condition: String,
+        action: String,
+        priority: u8,
+    },
+    RecordDecision {
+        context: String,
+        decision: String,
+        confidence: f64,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LogicalAgentError {
+    #[error("invalid command: {0}")]
+    InvalidCommand(String),
+}
+
+fn invalid(reason: impl Into<String>) -> LogicalAgentError {
+    LogicalAgentError::InvalidCommand(reason.into())
+}
+
+/// Every command but `CreateAgent` needs an existing agent; returns its id.
+fn ensure_created(state: &LogicalAgentState) -> Result<String, LogicalAgentError> {
+    if state.id.is_empty() {
+        return Err(invalid("Agent has not been created"));
+    }
+    Ok(state.id.clone())
+}
+
+fn ensure_priority(priority: u8) -> Result<(), LogicalAgentError> {
+    if priority > 10 {
+        return Err(invalid("Priority must be between 0 and 10"));
+    }
+    Ok(())
+}
+
+fn ensure_confidence(confidence: f64) -> Result<(), LogicalAgentError> {
+    if !(0.0..=1.0).contains(&confidence) {
+        return Err(invalid("Confidence must be between 0.0 and 1.0"));
+    }
+    Ok(())
+}
+
+/// Objectives can only be closed once.
+fn ensure_open_objective(
+    state: &LogicalAgentState,
+    objective_id: Uuid,
+) -> Result<(), LogicalAgentError> {
+    match state.objectives.iter().find(|o| o.id == objective_id) {
+        Some(objective) => match objective.status {
+            ObjectiveStatus::Pending | ObjectiveStatus::InProgress => Ok(()),
+            _ => Err(invalid(format!(
+                "Objective {} is already closed",
+                objective_id
+            ))),
+        },
+        None => Err(invalid(format!(
+            "Objective {} does not exist",
+            objective_id
+        ))),
+    }
+}
+
+pub struct LogicalAgent;
+
+impl Aggregate for LogicalAgent {
+    const NAME: &'static str = "logical_agent";
+
+    type State = LogicalAgentState;
+    type Command = LogicalAgentCommand;
+    type Event = LogicalAgentEvent;
+    type Error = LogicalAgentError;
+
+    fn handle_command(
+        state: &Self::State,
+        command: Self::Command,
+    ) -> Result<Vec<Self::Event>, Self::Error> {
+        let now = Utc::now();
+        match command {
+            LogicalAgentCommand::CreateAgent { agent_id, name } => {
+                if !state.id.is_empty() {
+                    return Err(invalid(format!("Agent {} already exists", state.id)));
+                }
+                if agent_id.is_empty() || name.is_empty() {
+                    return Err(invalid("Agent id and name are required"));
+                }
+                Ok(vec![LogicalAgentEvent::AgentCreated {
+                    agent_id,
+                    name,
+                    timestamp: now,
+                }])
+            }
+            LogicalAgentCommand::AddObjective {
+                description,
+                priority,
+                constraints,
+            } => {
+                let agent_id = ensure_created(state)?;
+                ensure_priority(priority)?;
+                if description.trim().is_empty() {
+                    return Err(invalid("Objective description is required"));
+                }
+                Ok(vec![LogicalAgentEvent::ObjectiveAdded {
+                    agent_id,
+                    objective_id: Uuid::new_v4(),
+                    description,
+                    priority,
+                    constraints,
+                    timestamp: now,
+                }])
+            }
+            LogicalAgentCommand::CompleteObjective { objective_id } => {
+                let agent_id = ensure_created(state)?;
+                ensure_open_objective(state, objective_id)?;
+                Ok(vec![LogicalAgentEvent::ObjectiveCompleted {
+                    agent_id,
+                    objective_id,
+                    timestamp: now,
+                }])
+            }
+            LogicalAgentCommand::FailObjective {
+                objective_id,
+                reason,
+            } => {
+                let agent_id = ensure_created(state)?;
+                ensure_open_objective(state, objective_id)?;
+                Ok(vec![LogicalAgentEvent::ObjectiveFailed {
+                    agent_id,
+                    objective_id,
+                    reason,
+                    timestamp: now,
+                }])
+            }
+            LogicalAgentCommand::ChangeStatus { new_status } => {
+                let agent_id = ensure_created(state)?;
+                Ok(vec![LogicalAgentEvent::StatusChanged {
+                    agent_id,
+                    new_status,
+                    timestamp: now,
+                }])
+            }
+            LogicalAgentCommand::AddFact {
+                statement,
+                confidence,
+                source,
+            } => {
+                let agent_id = ensure_created(state)?;
+                ensure_confidence(confidence)?;
+                if statement.trim().is_empty() {
+                    return Err(invalid("Fact statement is required"));
+                }
+                Ok(vec![LogicalAgentEvent::FactAdded {
+                    agent_id,
+                    fact_id: Uuid::new_v4(),
+                    statement,
+                    confidence,
+                    source,
+                    timestamp: now,
+                }])
+            }
+            LogicalAgentCommand::AddRule {
+                condition,
+                action,
+                priority,
+            } => {
+                let agent_id = ensure_created(state)?;
+                ensure_priority(priority)?;
+                if condition.trim().is_empty() || action.trim().is_empty() {
+                    return Err(invalid("Rule condition and action are required"));
+                }
+                Ok(vec![LogicalAgentEvent::RuleAdded {
+                    agent_id,
+                    rule_id: Uuid::new_v4(),
+                    condition,
+                    action,
+                    priority,
+                    timestamp: now,
+                }])
+            }
+            LogicalAgentCommand::RecordDecision {
+                context,
+                decision,
+                confidence,
+            } => {
+                let agent_id = ensure_created(state)?;
+                ensure_confidence(confidence)?;
+                Ok(vec![LogicalAgentEvent::DecisionMade {
+                    agent_id,
+                    decision_id: Uuid::new_v4(),
+                    context,
+                    decision,
+                    confidence,
+                    timestamp: now,
+                }])
+            }
+        }
+    }
+
+    fn apply_event(mut state: Self::State, event: Self::Event) -> Self::State {
+        match event {
+            LogicalAgentEvent::AgentCreated { agent_id, name, .. } => {
+                state.id = agent_id;
+                state.name = name;
+                state.status = AgentStatus::Idle;
+            }
+            LogicalAgentEvent::ObjectiveAdded {
+                objective_id,
+                description,
+                priority,
+                constraints,
+                ..
+            } => {
+                state.objectives.push(Objective {
+                    id: objective_id,
+                    description,
+                    priority,
+                    status: ObjectiveStatus::Pending,
+                    constraints,
+                });
+            }
+            LogicalAgentEvent::ObjectiveCompleted { objective_id, .. } => {
+                if let Some(objective) = state.objectives.iter_mut().find(|o| o.id == objective_id)
+                {
+                    objective.status = ObjectiveStatus::Completed;
+                }
+            }
+            LogicalAgentEvent::ObjectiveFailed {
+                objective_id,
+                reason,
+                ..
+            } => {
+                if let Some(objective) = state.objectives.iter_mut().find(|o| o.id == objective_id)
+                {
+                    objective.status = ObjectiveStatus::Failed(reason);
+                }
+            }
+            LogicalAgentEvent::StatusChanged { new_status, .. } => {
+                state.status = new_status;
+            }
+            LogicalAgentEvent::FactAdded {
+                fact_id,
+                statement,
+                confidence,
+                source,
+                ..
+            } => {
+                state.facts.push(Fact {
+                    id: fact_id,
+                    statement,
+                    confidence,
+                    source,
+                });
+            }
+            LogicalAgentEvent::RuleAdded {
+                rule_id,
+                condition,
+                action,
+                priority,
+                ..
+            } => {
+                state.rules.push(Rule {
+                    id: rule_id,
+                    condition,
+                    action,
+                    priority,
+                });
+            }
+            // Recorded for projections; they do not change what the agent may do next
+            LogicalAgentEvent::DecisionMade { .. }
+            | LogicalAgentEvent::KnowledgeBaseUpdated { .. } => {}
+        }
+        state.version += 1;
+        state
+    }
+}
@@ -1,5 +1,25 @@
 #[cfg(feature = "esrs_migration")]
+pub mod dynamics;
+#[cfg(feature = "esrs_migration")]
+pub mod gui;
+#[cfg(feature = "esrs_migration")]
+pub mod kinematic_agent;
+#[cfg(feature = "esrs_migration")]
+pub mod logical_agent;
+#[cfg(feature = "esrs_migration")]
 pub mod path_planning;
+#[cfg(feature = "esrs_migration")]
+pub mod technical_agent;
 
+#[cfg(feature = "esrs_migration")]
+pub use dynamics::*;
+#[cfg(feature = "esrs_migration")]
+pub use gui::*;
+#[cfg(feature = "esrs_migration")]
+pub use kinematic_agent::*;
+#[cfg(feature = "esrs_migration")]
+pub use logical_agent::*;
 #[cfg(feature = "esrs_migration")]
 pub use path_planning::*;
+#[cfg(feature = "esrs_migration")]
+pub use technical_agent::*;
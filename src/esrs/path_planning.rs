@@ -3,10 +3,12 @@ use chrono::Utc;
 use serde::{Deserialize, Serialize};
 
 use crate::domains::path_planning::events::PathPlanningEvent;
-use crate::domains::path_planning::aggregate::types::PlanningAlgorithm;
+use crate::common::DomainError;
+use crate::domains::path_planning::aggregate::types::{MultiGoalPlanRequest, PathPlanRequest, PlanningAlgorithm};
 use crate::domains::path_planning::aggregate::plan::PathPlan;
 use crate::domains::path_planning::aggregate::worker::{PathPlanWorker, PlanAssignment};
-use crate::domains::path_planning::aggregate::workspace::Workspace;
+use crate::domains::path_planning::aggregate::workspace::{Obstacle, Workspace};
+use crate::domains::path_planning::aggregate::path_planner::{apply_transition, PlannerDecisions, PlannerStateMut};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PathPlannerState {
@@ -19,6 +21,12 @@ pub struct PathPlannerState {
     pub version: u64,
 }
 
+impl PathPlannerState {
+    pub fn state_mut(&mut self) -> PlannerStateMut<'_> {
+        PlannerStateMut { id: &mut self.id, algorithm: &mut self.algorithm, workspace: &mut self.workspace, active_plans: &mut self.active_plans, registered_workers: &mut self.registered_workers, plan_assignments: &mut self.plan_assignments }
+    }
+}
+
 impl Default for PathPlannerState {
     fn default() -> Self {
         PathPlannerState {
@@ -42,6 +50,11 @@ pub enum PathPlannerCommand {
     PlanAssignmentAccepted { worker_id: String, plan_id: String },
     PlanCompleted { worker_id: String, plan_id: String, waypoints: Vec<crate::domains::path_planning::aggregate::types::Position2D> },
     PlanFailed { worker_id: String, plan_id: String, reason: String },
+    RequestMultiGoalPlan { request_id: String, agent_id: String, start_position: crate::domains::path_planning::aggregate::types::Position2D, start_orientation: crate::domains::path_planning::aggregate::types::Orientation2D, goals: Vec<crate::domains::path_planning::aggregate::types::PlanGoal>, optimize_order: bool },
+    ArchiveFinishedPlans { retention_seconds: u64 },
+    AddObstacle { obstacle: Obstacle },
+    MoveObstacle { obstacle_id: String, position: crate::domains::path_planning::aggregate::types::Position2D },
+    RemoveObstacle { obstacle_id: String },
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -50,6 +63,15 @@ pub enum PathPlannerError {
     InvalidCommand(String),
 }
 
+impl From<DomainError> for PathPlannerError {
+    fn from(error: DomainError) -> Self {
+        match error {
+            DomainError::InvalidCommand { reason } => PathPlannerError::InvalidCommand(reason),
+            other => PathPlannerError::InvalidCommand(other.to_string()),
+        }
+    }
+}
+
 pub struct PathPlanner;
 
 impl Aggregate for PathPlanner {
@@ -61,171 +83,31 @@ impl Aggregate for PathPlanner {
     type Error = PathPlannerError;
 
     fn handle_command(state: &Self::State, command: Self::Command) -> Result<Vec<Self::Event>, Self::Error> {
+        // Decide on a copy: the native aggregate's decisions apply each event as they go, esrs applies them once stored
+        let mut scratch = state.clone();
+        let mut decisions = PlannerDecisions::new(scratch.state_mut());
         match command {
-            PathPlannerCommand::CreatePlanner { planner_id, algorithm } => Ok(vec![PathPlanningEvent::PlannerCreated { planner_id, algorithm, timestamp: Utc::now() }]),
-            PathPlannerCommand::RequestPathPlan { request_id, agent_id, start_position, destination_position, start_orientation, destination_orientation } => {
-                // validate positions within workspace
-                if start_position.x < state.workspace.bounds.min_x || start_position.x > state.workspace.bounds.max_x || start_position.y < state.workspace.bounds.min_y || start_position.y > state.workspace.bounds.max_y {
-                    return Err(PathPlannerError::InvalidCommand("Start position outside workspace bounds".to_string()));
-                }
-                if destination_position.x < state.workspace.bounds.min_x || destination_position.x > state.workspace.bounds.max_x || destination_position.y < state.workspace.bounds.min_y || destination_position.y > state.workspace.bounds.max_y {
-                    return Err(PathPlannerError::InvalidCommand("Destination position outside workspace bounds".to_string()));
-                }
-                let plan_id = uuid::Uuid::new_v4().to_string();
-                Ok(vec![PathPlanningEvent::PathPlanRequested { planner_id: state.id.clone(), request_id, plan_id, agent_id, start_position, destination_position, start_orientation, destination_orientation, timestamp: Utc::now() }])
-            }
-            PathPlannerCommand::RegisterWorker { worker_id, capabilities } => {
-                if state.registered_workers.iter().any(|w| w.worker_id == worker_id) {
-                    return Err(PathPlannerError::InvalidCommand(format!("Worker {} is already registered", worker_id)));
-                }
-                Ok(vec![PathPlanningEvent::WorkerRegistered { planner_id: state.id.clone(), worker_id, capabilities, timestamp: Utc::now() }])
-            }
-            PathPlannerCommand::WorkerReady { worker_id } => {
-                if !state.registered_workers.iter().any(|w| w.worker_id == worker_id) {
-                    return Err(PathPlannerError::InvalidCommand(format!("Worker {} is not registered", worker_id)));
-                }
-                Ok(vec![PathPlanningEvent::WorkerReady { planner_id: state.id.clone(), worker_id, timestamp: Utc::now() }])
-            }
-            PathPlannerCommand::PlanAssignmentAccepted { worker_id, plan_id } => Ok(vec![PathPlanningEvent::PlanAssignmentAccepted { planner_id: state.id.clone(), plan_id, worker_id, timestamp: Utc::now() }]),
-            PathPlannerCommand::PlanCompleted { worker_id, plan_id, waypoints } => Ok(vec![PathPlanningEvent::PlanCompleted { planner_id: state.id.clone(), plan_id, worker_id: Some(worker_id), waypoints, timestamp: Utc::now() }]),
-            PathPlannerCommand::PlanFailed { worker_id, plan_id, reason } => Ok(vec![PathPlanningEvent::PlanFailed { planner_id: state.id.clone(), plan_id, worker_id: Some(worker_id), reason, timestamp: Utc::now() }]),
-        }
+            PathPlannerCommand::CreatePlanner { planner_id, algorithm } => decisions.create_planner(planner_id, algorithm),
+            PathPlannerCommand::RequestPathPlan { request_id, agent_id, start_position, destination_position, start_orientation, destination_orientation } => decisions.request_path_plan(PathPlanRequest { request_id, agent_id, start_position, destination_position, start_orientation, destination_orientation, created_at: Utc::now() }).map(drop),
+            PathPlannerCommand::RegisterWorker { worker_id, capabilities } => decisions.register_worker(worker_id, capabilities),
+            PathPlannerCommand::WorkerReady { worker_id } => decisions.worker_ready(worker_id),
+            PathPlannerCommand::PlanAssignmentAccepted { worker_id, plan_id } => decisions.plan_assignment_accepted(worker_id, plan_id),
+            PathPlannerCommand::PlanCompleted { worker_id, plan_id, waypoints } => decisions.plan_completed(worker_id, plan_id, waypoints),
+            PathPlannerCommand::PlanFailed { worker_id, plan_id, reason } => decisions.plan_failed(worker_id, plan_id, reason),
+            PathPlannerCommand::RequestMultiGoalPlan { request_id, agent_id, start_position, start_orientation, goals, optimize_order } => decisions.request_multi_goal_plan(MultiGoalPlanRequest { request_id, agent_id, start_position, start_orientation, goals, optimize_order, created_at: Utc::now() }).map(drop),
+            PathPlannerCommand::ArchiveFinishedPlans { retention_seconds } => decisions.archive_finished_plans(chrono::Duration::seconds(retention_seconds as i64), Utc::now()).map(drop),
+            PathPlannerCommand::AddObstacle { obstacle } => decisions.add_obstacle(obstacle),
+            PathPlannerCommand::MoveObstacle { obstacle_id, position } => decisions.move_obstacle(obstacle_id, position),
+            PathPlannerCommand::RemoveObstacle { obstacle_id } => decisions.remove_obstacle(obstacle_id),
+        }?;
+        Ok(decisions.into_events())
     }
 
     fn apply_event(mut state: Self::State, event: Self::Event) -> Self::State {
-        match event {
-            PathPlanningEvent::PlannerCreated { planner_id, algorithm, .. } => {
-                state.id = planner_id;
-                state.algorithm = algorithm;
-            }
-            PathPlanningEvent::PathPlanRequested { plan_id, agent_id, start_position, destination_position, start_orientation, destination_orientation, timestamp, .. } => {
-                let path_plan = PathPlan {
-                    id: plan_id.clone(),
-                    agent_id: agent_id.clone(),
-                    start: start_position.clone(),
-                    goal: destination_position.clone(),
-                    start_orientation: start_orientation.clone(),
-                    destination_orientation: destination_orientation.clone(),
-                    waypoints: Vec::new(),
-                    status: crate::domains::path_planning::aggregate::plan::PlanStatus::Planning,
-                    created_at: timestamp,
-                };
-                state.active_plans.push(path_plan);
-            }
-            PathPlanningEvent::WorkerRegistered { worker_id, capabilities, timestamp, .. } => {
-                let worker = PathPlanWorker {
-                    worker_id: worker_id.clone(),
-                    status: crate::domains::path_planning::aggregate::worker::WorkerStatus::Idle,
-                    algorithm_capabilities: capabilities.clone(),
-                    last_heartbeat: timestamp,
-                    current_plan_id: None,
-                };
-                state.registered_workers.push(worker);
-            }
-            PathPlanningEvent::WorkerReady { worker_id, timestamp, .. } => {
-                if let Some(worker) = state.registered_workers.iter_mut().find(|w| w.worker_id == worker_id) {
-                    worker.status = crate::domains::path_planning::aggregate::worker::WorkerStatus::Idle;
-                    worker.last_heartbeat = timestamp;
-                    worker.current_plan_id = None;
-                }
-            }
-            PathPlanningEvent::WorkerBusy { worker_id, plan_id, timestamp, .. } => {
-                if let Some(worker) = state.registered_workers.iter_mut().find(|w| w.worker_id == worker_id) {
-                    worker.status = crate::domains::path_planning::aggregate::worker::WorkerStatus::Busy;
-                    worker.last_heartbeat = timestamp;
-                    worker.current_plan_id = Some(plan_id.clone());
-                }
-            }
-            PathPlanningEvent::WorkerProcessing { worker_id, plan_id, timestamp, .. } => {
-                if let Some(worker) = state.registered_workers.iter_mut().find(|w| w.worker_id == worker_id) {
-                    worker.status = crate::domains::path_planning::aggregate::worker::WorkerStatus::Busy;
-                    worker.last_heartbeat = timestamp;
-                    worker.current_plan_id = Some(plan_id.clone());
-                }
-            }
-            PathPlanningEvent::WorkerOffline { worker_id, .. } => {
-                if let Some(worker) = state.registered_workers.iter_mut().find(|w| w.worker_id == worker_id) {
-                    worker.status = crate::domains::path_planning::aggregate::worker::WorkerStatus::Offline;
-                    worker.current_plan_id = None;
-                }
-                state.plan_assignments.retain(|a| a.worker_id != worker_id);
-            }
-            PathPlanningEvent::WorkerHeartbeat { worker_id, timestamp, .. } => {
-                if let Some(worker) = state.registered_workers.iter_mut().find(|w| w.worker_id == worker_id) {
-                    worker.last_heartbeat = timestamp;
-                }
-            }
-            PathPlanningEvent::PlanAssigned { plan_id, worker_id, timeout_seconds, timestamp, .. } => {
-                let assignment = PlanAssignment {
-                    plan_id: plan_id.clone(),
-                    worker_id: worker_id.clone(),
-                    assigned_at: timestamp,
-                    timeout_at: timestamp + chrono::Duration::seconds(timeout_seconds as i64),
-                };
-                state.plan_assignments.push(assignment);
-                if let Some(plan) = state.active_plans.iter_mut().find(|p| p.id == plan_id) {
-                    plan.status = crate::domains::path_planning::aggregate::plan::PlanStatus::Assigned;
-                }
-                if let Some(worker) = state.registered_workers.iter_mut().find(|w| w.worker_id == worker_id) {
-                    worker.current_plan_id = Some(plan_id.clone());
-                }
-            }
-            PathPlanningEvent::PlanAssignmentAccepted { plan_id, worker_id, .. } => {
-                if let Some(plan) = state.active_plans.iter_mut().find(|p| p.id == plan_id) {
-                    plan.status = crate::domains::path_planning::aggregate::plan::PlanStatus::InProgress;
-                }
-                if let Some(worker) = state.registered_workers.iter_mut().find(|w| w.worker_id == worker_id) {
-                    worker.status = crate::domains::path_planning::aggregate::worker::WorkerStatus::Busy;
-                }
-            }
-            PathPlanningEvent::PlanAssignmentRejected { plan_id, worker_id, .. } => {
-                state.plan_assignments.retain(|a| !(a.plan_id == plan_id && a.worker_id == worker_id));
-                if let Some(plan) = state.active_plans.iter_mut().find(|p| p.id == plan_id) {
-                    plan.status = crate::domains::path_planning::aggregate::plan::PlanStatus::Planning;
-                }
-                if let Some(worker) = state.registered_workers.iter_mut().find(|w| w.worker_id == worker_id) {
-                    worker.current_plan_id = None;
-                    worker.status = crate::domains::path_planning::aggregate::worker::WorkerStatus::Idle;
-                }
-            }
-            PathPlanningEvent::PlanAssignmentTimedOut { plan_id, worker_id, .. } => {
-                state.plan_assignments.retain(|a| !(a.plan_id == plan_id && a.worker_id == worker_id));
-                if let Some(plan) = state.active_plans.iter_mut().find(|p| p.id == plan_id) {
-                    plan.status = crate::domains::path_planning::aggregate::plan::PlanStatus::Planning;
-                }
-                if let Some(worker) = state.registered_workers.iter_mut().find(|w| w.worker_id == worker_id) {
-                    worker.status = crate::domains::path_planning::aggregate::worker::WorkerStatus::Offline;
-                    worker.current_plan_id = None;
-                }
-            }
-            PathPlanningEvent::PlanRequested { .. } => {}
-            PathPlanningEvent::PlanCompleted { plan_id, waypoints, worker_id, .. } => {
-                if let Some(plan) = state.active_plans.iter_mut().find(|p| p.id == plan_id) {
-                    plan.status = crate::domains::path_planning::aggregate::plan::PlanStatus::Complete;
-                    plan.waypoints = waypoints.clone();
-                }
-                state.plan_assignments.retain(|a| a.plan_id != plan_id);
-                if let Some(wid) = worker_id {
-                    if let Some(worker) = state.registered_workers.iter_mut().find(|w| w.worker_id == wid) {
-                        worker.status = crate::domains::path_planning::aggregate::worker::WorkerStatus::Idle;
-                        worker.current_plan_id = None;
-                    }
-                }
-            }
-            PathPlanningEvent::PlanFailed { plan_id, worker_id, .. } => {
-                if let Some(plan) = state.active_plans.iter_mut().find(|p| p.id == plan_id) {
-                    plan.status = crate::domains::path_planning::aggregate::plan::PlanStatus::Failed("Planning failed".to_string());
-                }
-                state.plan_assignments.retain(|a| a.plan_id != plan_id);
-                if let Some(wid) = worker_id {
-                    if let Some(worker) = state.registered_workers.iter_mut().find(|w| w.worker_id == wid) {
-                        worker.status = crate::domains::path_planning::aggregate::worker::WorkerStatus::Idle;
-                        worker.current_plan_id = None;
-                    }
-                }
-            }
+        // A stored event cannot be refused; one the native aggregate would reject leaves the state as it was
+        if apply_transition(state.state_mut(), &event).is_ok() {
+            state.version += 1;
         }
-        state.version += 1;
         state
     }
 }
@@ -0,0 +1,419 @@
+use ::esrs::Aggregate;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::domains::technical_agent::aggregate::{
+    Actuator, AgentType, Capability, ComponentStatus, Sensor, SoftwareModule, TechnicalStatus,
+};
+use crate::domains::technical_agent::events::TechnicalAgentEvent;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TechnicalAgentState {
+    pub id: String,
+    pub name: String,
+    pub agent_type: Option<AgentType>,
+    pub status: TechnicalStatus,
+    pub capabilities: Vec<Capability>,
+    pub sensors: Vec<Sensor>,
+    pub actuators: Vec<Actuator>,
+    pub software_modules: Vec<SoftwareModule>,
+    pub parameters: HashMap<String, String>,
+    pub version: u64,
+}
+
+impl Default for TechnicalAgentState {
+    fn default() -> Self {
+        TechnicalAgentState {
+            id: String::new(),
+            name: String::new(),
+            agent_type: None,
+            status: TechnicalStatus::Initializing,
+            capabilities: Vec::new(),
+            sensors: Vec::new(),
+            actuators: Vec::new(),
+            software_modules: Vec::new(),
+            parameters: HashMap::new(),
+            version: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TechnicalAgentCommand {
+    CreateAgent {
+        agent_id: String,
+        name: String,
+        agent_type: AgentType,
+    },
+    AddCapability {
+        name: String,
+        description: String,
+    },
+    EnableCapability {
+        capability_id: Uuid,
+    },
+    DisableCapability {
+        capability_id: Uuid,
+    },
+    ChangeStatus {
+        new_status: TechnicalStatus,
+    },
+    AddSensor {
+        sensor_type: String,
+        model: String,
+    },
+    ChangeSensorStatus {
+        sensor_id: Uuid,
+        new_status: ComponentStatus,
+    },
+    AddActuator {
+        actuator_type: String,
+        model: String,
+    },
+    ChangeActuatorStatus {
+        actuator_id: Uuid,
+        new_status: ComponentStatus,
+    },
+    InstallSoftwareModule {
+        name: String,
+        version: String,
+    },
+    UpdateConfiguration {
+        parameter: String,
+        value: String,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TechnicalAgentError {
+    #[error("invalid command: {0}")]
+    InvalidCommand(String),
+}
+
+fn invalid(reason: impl Into<String>) -> TechnicalAgentError {
+    TechnicalAgentError::InvalidCommand(reason.into())
+}
+
+/// Every command but `CreateAgent` needs an existing agent that has not been
+/// shut down; only a status change can bring a shut down agent back.
+fn ensure_operable(
+    state: &TechnicalAgentState,
+    command: &TechnicalAgentCommand,
+) -> Result<String, TechnicalAgentError> {
+    if state.id.is_empty() {
+        return Err(invalid("Agent has not been created"));
+    }
+    if matches!(state.status, TechnicalStatus::Shutdown)
+        && !matches!(command, TechnicalAgentCommand::ChangeStatus { .. })
+    {
+        return Err(invalid(format!("Agent {} is shut down", state.id)));
+    }
+    Ok(state.id.clone())
+}
+
+fn capability(
+    state: &TechnicalAgentState,
+    capability_id: Uuid,
+) -> Result<&Capability, TechnicalAgentError> {
+    state
+        .capabilities
+        .iter()
+        .find(|c| c.id == capability_id)
+        .ok_or_else(|| invalid(format!("Capability {} does not exist", capability_id)))
+}
+
+pub struct TechnicalAgent;
+
+impl Aggregate for TechnicalAgent {
+    const NAME: &'static str = "technical_agent";
+
+    type State = TechnicalAgentState;
+    type Command = TechnicalAgentCommand;
+    type Event = TechnicalAgentEvent;
+    type Error = TechnicalAgentError;
+
+    fn handle_command(
+        state: &Self::State,
+        command: Self::Command,
+    ) -> Result<Vec<Self::Event>, Self::Error> {
+        let now = Utc::now();
+        let agent_id = match command {
+            TechnicalAgentCommand::CreateAgent { .. } => String::new(),
+            _ => ensure_operable(state, &command)?,
+        };
+        let event = match command {
+            TechnicalAgentCommand::CreateAgent {
+                agent_id,
+                name,
+                agent_type,
+            } => {
+                if !state.id.is_empty() {
+                    return Err(invalid(format!("Agent {} already exists", state.id)));
+                }
+                if agent_id.is_empty() || name.is_empty() {
+                    return Err(invalid("Agent id and name are required"));
+                }
+                TechnicalAgentEvent::AgentCreated {
+                    agent_id,
+                    name,
+                    agent_type,
+                    timestamp: now,
+                }
+            }
+            TechnicalAgentCommand::AddCapability { name, description } => {
+                if state.capabilities.iter().any(|c| c.name == name) {
+                    return Err(invalid(format!("Capability {} already exists", name)));
+                }
+                TechnicalAgentEvent::CapabilityAdded {
+                    agent_id,
+                    capability_id: Uuid::new_v4(),
+                    name,
+                    description,
+                    timestamp: now,
+                }
+            }
+            TechnicalAgentCommand::EnableCapability { capability_id } => {
+                if capability(state, capability_id)?.enabled {
+                    return Err(invalid(format!(
+                        "Capability {} is already enabled",
+                        capability_id
+                    )));
+                }
+                TechnicalAgentEvent::CapabilityEnabled {
+                    agent_id,
+                    capability_id,
+                    timestamp: now,
+                }
+            }
+            TechnicalAgentCommand::DisableCapability { capability_id } => {
+                if !capability(state, capability_id)?.enabled {
+                    return Err(invalid(format!(
+                        "Capability {} is already disabled",
+                        capability_id
+                    )));
+                }
+                TechnicalAgentEvent::CapabilityDisabled {
+                    agent_id,
+                    capability_id,
+                    timestamp: now,
+                }
+            }
+            TechnicalAgentCommand::ChangeStatus { new_status } => {
+                TechnicalAgentEvent::StatusChanged {
+                    agent_id,
+                    new_status,
+                    timestamp: now,
+                }
+            }
+            TechnicalAgentCommand::AddSensor { sensor_type, model } => {
+                TechnicalAgentEvent::SensorAdded {
+                    agent_id,
+                    sensor_id: Uuid::new_v4(),
+                    sensor_type,
+                    model,
+                    timestamp: now,
+                }
+            }
+            TechnicalAgentCommand::ChangeSensorStatus {
+                sensor_id,
+                new_status,
+            } => {
+                if !state.sensors.iter().any(|s| s.id == sensor_id) {
+                    return Err(invalid(format!("Sensor {} does not exist", sensor_id)));
+                }
+                TechnicalAgentEvent::SensorStatusChanged {
+                    agent_id,
+                    sensor_id,
+                    new_status,
+                    timestamp: now,
+                }
+            }
+            TechnicalAgentCommand::AddActuator {
+                actuator_type,
+                model,
+            } => TechnicalAgentEvent::ActuatorAdded {
+                agent_id,
+                actuator_id: Uuid::new_v4(),
+                actuator_type,
+                model,
+                timestamp: now,
+            },
+            TechnicalAgentCommand::ChangeActuatorStatus {
+                actuator_id,
+                new_status,
+            } => {
+                if !state.actuators.iter().any(|a| a.id == actuator_id) {
+                    return Err(invalid(format!("Actuator {} does not exist", actuator_id)));
+                }
+                TechnicalAgentEvent::ActuatorStatusChanged {
+                    agent_id,
+                    actuator_id,
+                    new_status,
+                    timestamp: now,
+                }
+            }
+            TechnicalAgentCommand::InstallSoftwareModule { name, version } => {
+                if state
+                    .software_modules
+                    .iter()
+                    .any(|m| m.name == name && m.version == version)
+                {
+                    return Err(invalid(format!(
+                        "{} {} is already installed",
+                        name, version
+                    )));
+                }
+                TechnicalAgentEvent::SoftwareModuleInstalled {
+                    agent_id,
+                    module_id: Uuid::new_v4(),
+                    name,
+                    version,
+                    timestamp: now,
+                }
+            }
+            TechnicalAgentCommand::UpdateConfiguration { parameter, value } => {
+                let old_value = state
+                    .parameters
+                    .get(&parameter)
+                    .cloned()
+                    .unwrap_or_default();
+                if old_value == value {
+                    return Ok(Vec::new());
+                }
+                TechnicalAgentEvent::ConfigurationUpdated {
+                    agent_id,
+                    parameter,
+                    old_value,
+                    new_value: value,
+                    timestamp: now,
+                }
+            }
+        };
+        Ok(vec![event])
+    }
+
+    fn apply_event(mut state: Self::State, event: Self::Event) -> Self::State {
+        match event {
+            TechnicalAgentEvent::AgentCreated {
+                agent_id,
+                name,
+                agent_type,
+                ..
+            } => {
+                state.id = agent_id;
+                state.name = name;
+                state.agent_type = Some(agent_type);
+                state.status = TechnicalStatus::Initializing;
+            }
+            TechnicalAgentEvent::CapabilityAdded {
+                capability_id,
+                name,
+                description,
+                ..
+            } => {
+                state.capabilities.push(Capability {
+                    id: capability_id,
+                    name,
+                    description,
+                    enabled: true,
+                    parameters: HashMap::new(),
+                });
+            }
+            TechnicalAgentEvent::CapabilityEnabled { capability_id, .. } => {
+                if let Some(c) = state
+                    .capabilities
+                    .iter_mut()
+                    .find(|c| c.id == capability_id)
+                {
+                    c.enabled = true;
+                }
+            }
+            TechnicalAgentEvent::CapabilityDisabled { capability_id, .. } => {
+                if let Some(c) = state
+                    .capabilities
+                    .iter_mut()
+                    .find(|c| c.id == capability_id)
+                {
+                    c.enabled = false;
+                }
+            }
+            TechnicalAgentEvent::StatusChanged { new_status, .. } => {
+                state.status = new_status;
+            }
+            TechnicalAgentEvent::SensorAdded {
+                sensor_id,
+                sensor_type,
+                model,
+                ..
+            } => {
+                // Accuracy and range are not part of the event
+                state.sensors.push(Sensor {
+                    id: sensor_id,
+                    sensor_type,
+                    model,
+                    accuracy: 0.0,
+                    range: 0.0,
+                    status: ComponentStatus::Online,
+                });
+            }
+            TechnicalAgentEvent::SensorStatusChanged {
+                sensor_id,
+                new_status,
+                ..
+            } => {
+                if let Some(s) = state.sensors.iter_mut().find(|s| s.id == sensor_id) {
+                    s.status = new_status;
+                }
+            }
+            TechnicalAgentEvent::ActuatorAdded {
+                actuator_id,
+                actuator_type,
+                model,
+                ..
+            } => {
+                state.actuators.push(Actuator {
+                    id: actuator_id,
+                    actuator_type,
+                    model,
+                    max_force: 0.0,
+                    precision: 0.0,
+                    status: ComponentStatus::Online,
+                });
+            }
+            TechnicalAgentEvent::ActuatorStatusChanged {
+                actuator_id,
+                new_status,
+                ..
+            } => {
+                if let Some(a) = state.actuators.iter_mut().find(|a| a.id == actuator_id) {
+                    a.status = new_status;
+                }
+            }
+            TechnicalAgentEvent::SoftwareModuleInstalled {
+                module_id,
+                name,
+                version,
+                ..
+            } => {
+                state.software_modules.push(SoftwareModule {
+                    id: module_id,
+                    name,
+                    version,
+                    enabled: true,
+                    dependencies: Vec::new(),
+                });
+            }
+            TechnicalAgentEvent::ConfigurationUpdated {
+                parameter,
+                new_value,
+                ..
+            } => {
+                state.parameters.insert(parameter, new_value);
+            }
+        }
+        state.version += 1;
+        state
+    }
+}
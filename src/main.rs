@@ -1,3 +1,4 @@
+use gryphon_app::config::TopicStartup;
 use gryphon_app::Config;
 use std::error::Error;
 use std::sync::Arc;
@@ -5,10 +6,15 @@ use tokio::sync::mpsc;
 use tracing::{error, info};
 
 use deadpool_postgres::{Config as DeadPoolConfig, Pool};
+use gryphon_app::adapters::outbound::kafka::create_kafka_topics;
 use gryphon_app::adapters::outbound::path_planning_data::FilesystemDataSource;
+use gryphon_app::adapters::outbound::postgres::create_tenant_schema;
 use gryphon_app::adapters::outbound::postgres_graph_store::PostgresGraphStore;
+use gryphon_app::adapters::outbound::postgres_plan_cache::PostgresPlanCache;
 use gryphon_app::application::PathPlanningService;
-use gryphon_app::domains::path_planning::PathPlanningCommandActor;
+use gryphon_app::domains::path_planning::{
+    CacheInvalidatingGraphStore, PathPlanningCommandActor, PlanCache,
+};
 use tokio_postgres::NoTls;
 
 #[tokio::main]
@@ -19,12 +25,24 @@ async fn main() -> Result<(), Box<dyn Error>> {
     info!("Starting Gryphon App");
 
     // Load configuration
-    let config = Config::from_file("config.toml").await?;
+    let config = Config::from_file("config.toml")
+        .await?
+        .with_tenant_from_env()?;
 
     info!("Configuration loaded successfully");
     info!("Kafka brokers: {:?}", config.kafka.brokers);
     info!("PostgreSQL host: {}", config.postgres.host);
 
+    // Create missing topics and check the others against [kafka.topic_settings];
+    // the app runs without Kafka unless drift is configured to stop it
+    let console = gryphon_app::adapters::outbound::init_console_logger();
+    if let Err(e) = create_kafka_topics(&config.kafka, &console).await {
+        if config.kafka.topic_settings.on_startup == TopicStartup::Fail {
+            return Err(e.into());
+        }
+        error!("Kafka topics not checked: {}", e);
+    }
+
     // Initialize event channel and command actor
     let (event_sender, _event_receiver) = mpsc::channel(100);
     let command_actor = PathPlanningCommandActor::new(event_sender);
@@ -42,6 +60,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     dp_cfg.user = Some(config.postgres.username.clone());
     dp_cfg.password = Some(config.postgres.password.clone());
     dp_cfg.dbname = Some(config.postgres.database.clone());
+    dp_cfg.options = config.postgres.connection_options();
     // deadpool-postgres Config does not expose max_size directly on this struct in all versions;
     // if you need to tune max connections, set it via environment or update this code to match the
     // specific deadpool version in use. For now, use defaults.
@@ -49,8 +68,25 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let pool: Pool = dp_cfg
         .create_pool(None, NoTls)
         .expect("failed to create pg pool");
-    let pg_store = PostgresGraphStore::new(pool);
-    let pg_arc: Arc<dyn gryphon_app::domains::path_planning::GraphStoreAsync> = Arc::new(pg_store);
+    if let Err(e) = create_tenant_schema(&pool, &config.postgres).await {
+        error!("{}", e);
+    }
+    let pg_store = PostgresGraphStore::new(pool.clone());
+    // Saving or deleting a graph drops the plans workers cached for it
+    let pg_arc: Arc<dyn gryphon_app::domains::path_planning::GraphStoreAsync> =
+        match PostgresPlanCache::new(pool).await {
+            Ok(plan_cache) => {
+                let plan_cache: Arc<dyn PlanCache> = Arc::new(plan_cache);
+                Arc::new(CacheInvalidatingGraphStore::new(
+                    Arc::new(pg_store),
+                    plan_cache,
+                ))
+            }
+            Err(e) => {
+                error!("{}, saved graphs will not clear cached plans", e);
+                Arc::new(pg_store)
+            }
+        };
 
     // Construct application service
     let path_planning_service =
@@ -35,3 +35,69 @@ fn test_filesystem_datasource_save_and_load() {
     assert_eq!(graph.node_count(), loaded.node_count());
     assert_eq!(graph.edge_count(), loaded.edge_count());
 }
+
+#[test]
+fn test_topic_settings_report_drift() {
+    use gryphon_app::adapters::outbound::kafka_admin::ObservedTopic;
+    use gryphon_app::config::{CleanupPolicy, Config};
+    use std::collections::HashMap;
+
+    let kafka = Config::default().kafka;
+    let events = kafka
+        .topic_settings
+        .for_topic(&kafka.topics.path_planning_events);
+    let replies = kafka
+        .topic_settings
+        .for_topic(&kafka.topics.path_planning_replies);
+    assert_eq!(events.retention_ms, -1);
+    assert!(replies.retention_ms > 0);
+
+    let matching = ObservedTopic {
+        partitions: events.partitions,
+        replication_factor: events.replication_factor,
+        configs: HashMap::from([
+            ("cleanup.policy".to_string(), "delete".to_string()),
+            ("retention.ms".to_string(), "-1".to_string()),
+            ("segment.ms".to_string(), "604800000".to_string()),
+        ]),
+    };
+    assert!(events
+        .drift_from("path-planning-events", &matching)
+        .is_empty());
+
+    let mut compacted = events.clone();
+    compacted.cleanup_policy = CleanupPolicy::Compact;
+    let observed = ObservedTopic {
+        partitions: 1,
+        configs: HashMap::from([("cleanup.policy".to_string(), "delete".to_string())]),
+        ..matching
+    };
+    let drift = compacted.drift_from("agent-state", &observed);
+    let settings: Vec<_> = drift.iter().map(|d| d.setting.as_str()).collect();
+    assert_eq!(
+        settings,
+        vec!["partitions", "cleanup.policy", "retention.ms"]
+    );
+    assert_eq!(drift[1].expected, "compact");
+    assert_eq!(drift[2].actual, "unset");
+}
+
+#[tokio::test]
+async fn test_sample_config_topic_settings() {
+    use gryphon_app::config::Config;
+
+    let config = Config::from_file("config.toml").await.unwrap();
+    let settings = &config.kafka.topic_settings;
+    assert_eq!(settings, &Config::default().kafka.topic_settings);
+}
+
+#[test]
+fn test_topic_startup_check_defaults_to_warn() {
+    use gryphon_app::config::{KafkaTopicSettings, TopicStartup};
+
+    let settings: KafkaTopicSettings = toml::from_str("").unwrap();
+    assert_eq!(settings.on_startup, TopicStartup::Warn);
+
+    let settings: KafkaTopicSettings = toml::from_str("on_startup = \"fail\"").unwrap();
+    assert_eq!(settings.on_startup, TopicStartup::Fail);
+}
@@ -0,0 +1,210 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use gryphon_app::adapters::inbound::{
+    export_archive, import_archive, ArchiveFilter, EventArchive, InMemoryEventStore,
+    InMemorySnapshotStore,
+};
+use gryphon_app::common::*;
+use uuid::Uuid;
+
+fn start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 5, 1, 14, 0, 0).unwrap()
+}
+
+fn envelope(aggregate_type: &str, aggregate_id: &str, version: u64) -> EventEnvelope {
+    EventEnvelope {
+        event_id: Uuid::new_v4(),
+        aggregate_id: aggregate_id.to_string(),
+        aggregate_type: aggregate_type.to_string(),
+        event_type: "TestEvent".to_string(),
+        event_version: version,
+        event_data: serde_json::json!({ "version": version }),
+        metadata: EventMetadata {
+            correlation_id: None,
+            causation_id: None,
+            user_id: None,
+            source: "test".to_string(),
+            reply_to: None,
+            tenant: None,
+        },
+        occurred_at: start() + Duration::minutes(version as i64),
+        chain: None,
+    }
+}
+
+/// Two planners and an agent with three events each, one minute apart, and a
+/// snapshot of the first planner
+async fn source_stores() -> (InMemoryEventStore, InMemorySnapshotStore) {
+    let events = InMemoryEventStore::new();
+    for (aggregate_type, aggregate_id) in [
+        ("PathPlanner", "planner-1"),
+        ("PathPlanner", "planner-2"),
+        ("LogicalAgent", "agent-1"),
+    ] {
+        let history = (1..=3)
+            .map(|version| envelope(aggregate_type, aggregate_id, version))
+            .collect();
+        events
+            .append_events(aggregate_id, 0, history)
+            .await
+            .unwrap();
+    }
+    let snapshots = InMemorySnapshotStore::new();
+    let mut snapshot =
+        Snapshot::new("planner-1", "PathPlanner", 2, &serde_json::json!({})).unwrap();
+    snapshot.created_at = start() + Duration::minutes(2);
+    snapshots.save_snapshot(snapshot).await.unwrap();
+    (events, snapshots)
+}
+
+#[tokio::test]
+async fn test_export_filters_by_type_id_and_time() {
+    let (events, snapshots) = source_stores().await;
+
+    let by_type = ArchiveFilter {
+        aggregate_types: vec!["PathPlanner".to_string()],
+        ..ArchiveFilter::default()
+    };
+    let archive = export_archive(&events, Some(&snapshots), by_type)
+        .await
+        .unwrap();
+    assert_eq!(archive.events.len(), 6);
+    assert_eq!(archive.snapshots.len(), 1);
+    let planner_1: Vec<_> = archive
+        .events
+        .iter()
+        .filter(|archived| archived.event.aggregate_id == "planner-1")
+        .map(|archived| archived.stream_version)
+        .collect();
+    assert_eq!(planner_1, vec![1, 2, 3]);
+
+    let by_id_and_time = ArchiveFilter {
+        aggregate_ids: vec!["agent-1".to_string()],
+        from: Some(start() + Duration::minutes(2)),
+        until: Some(start() + Duration::minutes(2)),
+        ..ArchiveFilter::default()
+    };
+    let archive = export_archive(&events, Some(&snapshots), by_id_and_time)
+        .await
+        .unwrap();
+    let versions: Vec<_> = archive.events.iter().map(|e| e.stream_version).collect();
+    assert_eq!(versions, vec![2]);
+    assert!(archive.snapshots.is_empty());
+}
+
+#[tokio::test]
+async fn test_import_round_trips_and_skips_present_events() {
+    let (events, snapshots) = source_stores().await;
+    let archive = export_archive(&events, Some(&snapshots), ArchiveFilter::default())
+        .await
+        .unwrap();
+    let read_back = EventArchive::from_ndjson(&archive.to_ndjson().unwrap()).unwrap();
+    assert_eq!(read_back.manifest, archive.manifest);
+
+    let target_events = InMemoryEventStore::new();
+    let target_snapshots = InMemorySnapshotStore::new();
+    let report = import_archive(&read_back, &target_events, Some(&target_snapshots))
+        .await
+        .unwrap();
+    assert_eq!(report.events_imported, 9);
+    assert_eq!(report.snapshots_imported, 1);
+
+    let source: Vec<_> = events
+        .load_events("planner-2", 0)
+        .await
+        .unwrap()
+        .iter()
+        .map(|e| e.event_id)
+        .collect();
+    let imported: Vec<_> = target_events
+        .load_events("planner-2", 0)
+        .await
+        .unwrap()
+        .iter()
+        .map(|e| e.event_id)
+        .collect();
+    assert_eq!(imported, source);
+
+    // Running it again adds nothing
+    let report = import_archive(&read_back, &target_events, Some(&target_snapshots))
+        .await
+        .unwrap();
+    assert_eq!(report.events_imported, 0);
+    assert_eq!(report.events_skipped, 9);
+    assert_eq!(report.snapshots_skipped, 1);
+    assert_eq!(target_events.load_all_events(None).await.unwrap().len(), 9);
+}
+
+#[tokio::test]
+async fn test_changed_archive_fails_checksum() {
+    let (events, snapshots) = source_stores().await;
+    let archive = export_archive(&events, Some(&snapshots), ArchiveFilter::default())
+        .await
+        .unwrap();
+    let ndjson = archive.to_ndjson().unwrap();
+
+    let tampered = ndjson.replacen("\"version\":2", "\"version\":7", 1);
+    assert_ne!(tampered, ndjson);
+    let error = EventArchive::from_ndjson(&tampered).unwrap_err();
+    assert!(error.contains("checksum"), "{}", error);
+
+    let truncated: String = ndjson
+        .lines()
+        .take(4)
+        .map(|line| format!("{}\n", line))
+        .collect();
+    assert!(EventArchive::from_ndjson(&truncated).is_err());
+}
+
+#[tokio::test]
+async fn test_stream_order_survives_skewed_clocks() {
+    // The second event was stamped by a clock running behind
+    let source = InMemoryEventStore::new();
+    let mut history: Vec<_> = (1..=3)
+        .map(|version| envelope("PathPlanner", "planner-1", version))
+        .collect();
+    history[1].occurred_at = start() - Duration::minutes(10);
+    history[2].occurred_at = history[0].occurred_at;
+    source.append_events("planner-1", 0, history).await.unwrap();
+
+    let archive = export_archive(&source, None, ArchiveFilter::default())
+        .await
+        .unwrap();
+    let target = InMemoryEventStore::new();
+    import_archive(&archive, &target, None).await.unwrap();
+
+    let ids =
+        |events: Vec<EventEnvelope>| -> Vec<_> { events.iter().map(|e| e.event_id).collect() };
+    assert_eq!(
+        ids(target.load_events("planner-1", 0).await.unwrap()),
+        ids(source.load_events("planner-1", 0).await.unwrap())
+    );
+    let report = verify_event_store(&target, None).await.unwrap();
+    assert!(report.is_intact(), "{}", report);
+}
+
+#[tokio::test]
+async fn test_import_rejects_gaps_in_a_stream() {
+    let (events, _snapshots) = source_stores().await;
+    let archive = export_archive(&events, None, ArchiveFilter::default())
+        .await
+        .unwrap();
+
+    let mut gapped = archive.events.clone();
+    gapped.retain(|archived| {
+        !(archived.event.aggregate_id == "planner-2" && archived.stream_version == 2)
+    });
+    let gapped = EventArchive::new(ArchiveFilter::default(), gapped, Vec::new()).unwrap();
+    let target = InMemoryEventStore::new();
+    let error = import_archive(&gapped, &target, None).await.unwrap_err();
+    assert!(error.contains("not contiguous"), "{}", error);
+    assert!(target.load_all_events(None).await.unwrap().is_empty());
+
+    // The tail of a stream needs the stored start
+    let tail = ArchiveFilter {
+        from: Some(start() + Duration::minutes(2)),
+        ..ArchiveFilter::default()
+    };
+    let tail = export_archive(&events, None, tail).await.unwrap();
+    let error = import_archive(&tail, &target, None).await.unwrap_err();
+    assert!(error.contains("the store has 0 events"), "{}", error);
+}
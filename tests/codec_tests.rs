@@ -0,0 +1,72 @@
+use gryphon_app::common::*;
+use gryphon_app::domains::path_planning::PathPlanningEvent;
+
+fn heartbeat() -> EventEnvelope {
+    EventEnvelope::new(
+        &PathPlanningEvent::WorkerHeartbeat {
+            planner_id: "main-path-planner".to_string(),
+            worker_id: "worker-1".to_string(),
+            timestamp: chrono::Utc::now(),
+        },
+        "PathPlanner",
+        EventMetadata {
+            correlation_id: Some(uuid::Uuid::new_v4()),
+            causation_id: None,
+            user_id: None,
+            source: "worker-1".to_string(),
+            reply_to: None,
+            tenant: None,
+        },
+    )
+    .unwrap()
+}
+
+fn assert_same(decoded: &EventEnvelope, original: &EventEnvelope) {
+    assert_eq!(decoded.event_id, original.event_id);
+    assert_eq!(decoded.aggregate_id, original.aggregate_id);
+    assert_eq!(decoded.event_type, original.event_type);
+    assert_eq!(decoded.event_data, original.event_data);
+    assert_eq!(
+        decoded.metadata.correlation_id,
+        original.metadata.correlation_id
+    );
+    assert_eq!(decoded.occurred_at, original.occurred_at);
+}
+
+#[test]
+fn test_codecs_roundtrip_and_message_pack_is_smaller() {
+    let envelope = heartbeat();
+    let json = EnvelopeCodec::Json.encode(&envelope).unwrap();
+    let msgpack = EnvelopeCodec::MessagePack.encode(&envelope).unwrap();
+
+    assert_same(&EnvelopeCodec::Json.decode(&json).unwrap(), &envelope);
+    assert_same(
+        &EnvelopeCodec::MessagePack.decode(&msgpack).unwrap(),
+        &envelope,
+    );
+    assert!(msgpack.len() < json.len());
+}
+
+#[test]
+fn test_decode_any_uses_the_header_and_falls_back_to_sniffing() {
+    let envelope = heartbeat();
+    for codec in [EnvelopeCodec::Json, EnvelopeCodec::MessagePack] {
+        let payload = codec.encode(&envelope).unwrap();
+        assert_eq!(
+            EnvelopeCodec::from_content_type(codec.content_type()),
+            Some(codec)
+        );
+        assert_same(
+            &EnvelopeCodec::decode_any(Some(codec.content_type()), &payload).unwrap(),
+            &envelope,
+        );
+        // Records written before the header existed
+        assert_same(
+            &EnvelopeCodec::decode_any(None, &payload).unwrap(),
+            &envelope,
+        );
+    }
+
+    assert!(EnvelopeCodec::decode_any(None, b"not an envelope").is_err());
+    assert!(EnvelopeCodec::decode_any(Some("application/json"), b"{\"half\":").is_err());
+}
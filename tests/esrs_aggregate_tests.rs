@@ -0,0 +1,278 @@
+#![cfg(feature = "esrs_migration")]
+
+use chrono::Utc;
+use esrs::Aggregate;
+use gryphon_app::domains::dynamics::{PhysicsModel, SimulationState};
+use gryphon_app::domains::gui::WindowType;
+use gryphon_app::domains::kinematic_agent::{KinematicsModel, Position3D, Velocity3D};
+use gryphon_app::domains::logical_agent::ObjectiveStatus;
+use gryphon_app::domains::technical_agent::{AgentType, TechnicalStatus};
+use gryphon_app::esrs::*;
+
+/// Handle a command against `state` and fold the resulting events into it
+fn execute<A: Aggregate>(state: A::State, command: A::Command) -> Result<A::State, A::Error> {
+    let events = A::handle_command(&state, command)?;
+    Ok(events.into_iter().fold(state, A::apply_event))
+}
+
+fn position(x: f64, y: f64, z: f64) -> Position3D {
+    Position3D {
+        x,
+        y,
+        z,
+        timestamp: Utc::now(),
+    }
+}
+
+#[test]
+fn test_logical_agent_closes_objectives_once() {
+    let state = execute::<LogicalAgent>(
+        LogicalAgentState::default(),
+        LogicalAgentCommand::CreateAgent {
+            agent_id: "agent-1".to_string(),
+            name: "Scout".to_string(),
+        },
+    )
+    .unwrap();
+    assert!(execute::<LogicalAgent>(
+        state.clone(),
+        LogicalAgentCommand::AddObjective {
+            description: "Survey".to_string(),
+            priority: 11,
+            constraints: Vec::new(),
+        },
+    )
+    .is_err());
+
+    let state = execute::<LogicalAgent>(
+        state,
+        LogicalAgentCommand::AddObjective {
+            description: "Survey".to_string(),
+            priority: 5,
+            constraints: Vec::new(),
+        },
+    )
+    .unwrap();
+    let objective_id = state.objectives[0].id;
+    let state = execute::<LogicalAgent>(
+        state,
+        LogicalAgentCommand::CompleteObjective { objective_id },
+    )
+    .unwrap();
+    assert!(matches!(
+        state.objectives[0].status,
+        ObjectiveStatus::Completed
+    ));
+    assert!(execute::<LogicalAgent>(
+        state.clone(),
+        LogicalAgentCommand::FailObjective {
+            objective_id,
+            reason: "late".to_string(),
+        },
+    )
+    .is_err());
+    assert_eq!(state.version, 3);
+}
+
+#[test]
+fn test_technical_agent_rejects_commands_before_creation_and_after_shutdown() {
+    let add_sensor = || TechnicalAgentCommand::AddSensor {
+        sensor_type: "lidar".to_string(),
+        model: "L1".to_string(),
+    };
+    assert!(execute::<TechnicalAgent>(TechnicalAgentState::default(), add_sensor()).is_err());
+
+    let state = execute::<TechnicalAgent>(
+        TechnicalAgentState::default(),
+        TechnicalAgentCommand::CreateAgent {
+            agent_id: "robot-1".to_string(),
+            name: "Rover".to_string(),
+            agent_type: AgentType::Robot,
+        },
+    )
+    .unwrap();
+    let state = execute::<TechnicalAgent>(state, add_sensor()).unwrap();
+    assert_eq!(state.sensors.len(), 1);
+
+    // Setting a parameter to its current value records nothing
+    let set_rate = || TechnicalAgentCommand::UpdateConfiguration {
+        parameter: "rate".to_string(),
+        value: "10".to_string(),
+    };
+    let state = execute::<TechnicalAgent>(state, set_rate()).unwrap();
+    let events = TechnicalAgent::handle_command(&state, set_rate()).unwrap();
+    assert!(events.is_empty());
+
+    let state = execute::<TechnicalAgent>(
+        state,
+        TechnicalAgentCommand::ChangeStatus {
+            new_status: TechnicalStatus::Shutdown,
+        },
+    )
+    .unwrap();
+    assert!(execute::<TechnicalAgent>(state.clone(), add_sensor()).is_err());
+    let state = execute::<TechnicalAgent>(
+        state,
+        TechnicalAgentCommand::ChangeStatus {
+            new_status: TechnicalStatus::Ready,
+        },
+    )
+    .unwrap();
+    assert!(execute::<TechnicalAgent>(state, add_sensor()).is_ok());
+}
+
+#[test]
+fn test_kinematic_agent_enforces_constraints_and_trajectory_order() {
+    let state = execute::<KinematicAgent>(
+        KinematicAgentState::default(),
+        KinematicAgentCommand::CreateAgent {
+            agent_id: "drone-1".to_string(),
+            initial_position: position(0.0, 0.0, 10.0),
+            kinematics_model: KinematicsModel::PointMass,
+        },
+    )
+    .unwrap();
+    assert!(execute::<KinematicAgent>(
+        state.clone(),
+        KinematicAgentCommand::UpdatePosition {
+            new_position: position(500.0, 0.0, 10.0),
+        },
+    )
+    .is_err());
+    assert!(execute::<KinematicAgent>(
+        state.clone(),
+        KinematicAgentCommand::UpdateVelocity {
+            new_velocity: Velocity3D {
+                x: 30.0,
+                y: 0.0,
+                z: 0.0,
+            },
+        },
+    )
+    .is_err());
+
+    let state = execute::<KinematicAgent>(
+        state,
+        KinematicAgentCommand::StartTrajectory {
+            trajectory_id: "t1".to_string(),
+            target_position: position(20.0, 20.0, 10.0),
+        },
+    )
+    .unwrap();
+    assert!(execute::<KinematicAgent>(
+        state.clone(),
+        KinematicAgentCommand::StartTrajectory {
+            trajectory_id: "t2".to_string(),
+            target_position: position(5.0, 5.0, 10.0),
+        },
+    )
+    .is_err());
+    let state = execute::<KinematicAgent>(
+        state,
+        KinematicAgentCommand::CompleteTrajectory {
+            trajectory_id: "t1".to_string(),
+            final_position: position(20.0, 20.0, 10.0),
+        },
+    )
+    .unwrap();
+    assert_eq!(state.active_trajectory, None);
+    assert_eq!(state.position.unwrap().x, 20.0);
+}
+
+#[test]
+fn test_dynamics_simulator_follows_its_lifecycle() {
+    let state = execute::<DynamicsSimulator>(
+        DynamicsSimulatorState::default(),
+        DynamicsSimulatorCommand::CreateSimulator {
+            simulator_id: "sim-1".to_string(),
+            physics_model: PhysicsModel::Newtonian,
+        },
+    )
+    .unwrap();
+    assert!(execute::<DynamicsSimulator>(
+        state.clone(),
+        DynamicsSimulatorCommand::PauseSimulation
+    )
+    .is_err());
+
+    let state =
+        execute::<DynamicsSimulator>(state, DynamicsSimulatorCommand::StartSimulation).unwrap();
+    let state =
+        execute::<DynamicsSimulator>(state, DynamicsSimulatorCommand::PauseSimulation).unwrap();
+    assert!(matches!(state.simulation_state, SimulationState::Paused));
+    let state =
+        execute::<DynamicsSimulator>(state, DynamicsSimulatorCommand::StartSimulation).unwrap();
+    assert!(matches!(state.simulation_state, SimulationState::Running));
+
+    let state = execute::<DynamicsSimulator>(
+        state,
+        DynamicsSimulatorCommand::ReportError {
+            message: "diverged".to_string(),
+        },
+    )
+    .unwrap();
+    assert!(execute::<DynamicsSimulator>(
+        state.clone(),
+        DynamicsSimulatorCommand::StartSimulation
+    )
+    .is_err());
+    let state =
+        execute::<DynamicsSimulator>(state, DynamicsSimulatorCommand::StopSimulation).unwrap();
+    assert!(matches!(state.simulation_state, SimulationState::Stopped));
+}
+
+#[test]
+fn test_gui_application_tracks_sessions_and_windows() {
+    let state = execute::<GUIApplication>(
+        GUIApplicationState::default(),
+        GUIApplicationCommand::CreateApplication {
+            app_id: "app-1".to_string(),
+            name: "Control".to_string(),
+        },
+    )
+    .unwrap();
+    let state = execute::<GUIApplication>(
+        state,
+        GUIApplicationCommand::OpenWindow {
+            title: "Map".to_string(),
+            window_type: WindowType::Visualization,
+        },
+    )
+    .unwrap();
+    let state = execute::<GUIApplication>(
+        state,
+        GUIApplicationCommand::StartSession {
+            user_id: "operator".to_string(),
+        },
+    )
+    .unwrap();
+    let window_id = state.windows[0].id;
+    let session_id = state.active_sessions[0].session_id;
+
+    assert!(execute::<GUIApplication>(
+        state.clone(),
+        GUIApplicationCommand::RecordInteraction {
+            session_id,
+            window_id: uuid::Uuid::new_v4(),
+            component_id: uuid::Uuid::new_v4(),
+            interaction_type: "click".to_string(),
+        },
+    )
+    .is_err());
+    let state = execute::<GUIApplication>(
+        state,
+        GUIApplicationCommand::RecordInteraction {
+            session_id,
+            window_id,
+            component_id: uuid::Uuid::new_v4(),
+            interaction_type: "click".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(state.active_sessions[0].active_windows, vec![window_id]);
+
+    let state =
+        execute::<GUIApplication>(state, GUIApplicationCommand::CloseWindow { window_id }).unwrap();
+    assert!(state.windows.is_empty());
+    assert!(state.active_sessions[0].active_windows.is_empty());
+}
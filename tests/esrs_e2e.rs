@@ -23,7 +23,7 @@ async fn esrs_pgstore_and_bus_end_to_end() {
     let bus_clone = bus.clone();
 
     // Build PgStore wired to in-memory bus
-    let store = build_pg_store_with_bus::<PathPlanner, _>(&url, bus_clone)
+    let (store, _pool) = build_pg_store_with_bus::<PathPlanner, _>(&url, bus_clone)
         .await
         .expect("store built");
 
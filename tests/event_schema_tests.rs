@@ -0,0 +1,198 @@
+use gryphon_app::adapters::inbound::{FileSchemaRegistry, InProcessMessageBus};
+use gryphon_app::common::*;
+use gryphon_app::domains::path_planning::PathPlanningEvent;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+mod v1 {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    pub enum RobotEvent {
+        Moved { robot_id: String, x: i64 },
+        Stopped { robot_id: String },
+        Reset,
+    }
+}
+
+mod compatible {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    pub enum RobotEvent {
+        Moved {
+            robot_id: String,
+            x: f64,
+            #[serde(default)]
+            y: f64,
+        },
+        Stopped {
+            robot_id: String,
+            reason: Option<String>,
+        },
+        Reset,
+        Docked {
+            robot_id: String,
+        },
+    }
+}
+
+mod breaking {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    pub enum RobotEvent {
+        Moved { robot_id: String, x: String },
+        Halted { robot_id: String },
+        Reset { at: i64 },
+    }
+}
+
+#[test]
+fn test_added_variants_and_optional_fields_are_compatible() {
+    let old = schema_of::<v1::RobotEvent>();
+    let new = schema_of::<compatible::RobotEvent>();
+    assert_eq!(check_backward_compatible(&old, &new), Ok(()));
+}
+
+#[test]
+fn test_removed_variants_and_changed_fields_are_breaking() {
+    let old = schema_of::<v1::RobotEvent>();
+    let new = schema_of::<breaking::RobotEvent>();
+    let problems = check_backward_compatible(&old, &new).unwrap_err();
+    let report = problems.join("\n");
+
+    assert!(
+        report.contains("Moved.x no longer accepts integer"),
+        "{}",
+        report
+    );
+    assert!(
+        report.contains("RobotEvent::Stopped was removed"),
+        "{}",
+        report
+    );
+    assert!(report.contains("Reset"), "{}", report);
+}
+
+#[tokio::test]
+async fn test_registry_versions_compatible_schemas_and_rejects_breaking_ones() {
+    let dir = tempfile::tempdir().unwrap();
+    let registry = FileSchemaRegistry::new(dir.path());
+
+    let first = register_schema(&registry, "RobotEvent", schema_of::<v1::RobotEvent>())
+        .await
+        .unwrap();
+    assert_eq!(first.version, 1);
+    let again = register_schema(&registry, "RobotEvent", schema_of::<v1::RobotEvent>())
+        .await
+        .unwrap();
+    assert_eq!(again.version, 1);
+
+    let second = register_schema(
+        &registry,
+        "RobotEvent",
+        schema_of::<compatible::RobotEvent>(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(second.version, 2);
+
+    let rejected = register_schema(&registry, "RobotEvent", schema_of::<breaking::RobotEvent>())
+        .await
+        .unwrap_err();
+    assert!(
+        rejected.contains("incompatible with version 2"),
+        "{}",
+        rejected
+    );
+    assert_eq!(
+        registry
+            .latest("RobotEvent")
+            .await
+            .unwrap()
+            .unwrap()
+            .version,
+        2
+    );
+}
+
+#[tokio::test]
+async fn test_checked_bus_refuses_events_whose_schema_breaks_the_registered_one() {
+    let dir = tempfile::tempdir().unwrap();
+    let registry = Arc::new(FileSchemaRegistry::new(dir.path()));
+    // The registry already knows a variant this binary no longer has
+    registry
+        .append("RobotEvent", schema_of::<compatible::RobotEvent>())
+        .await
+        .unwrap();
+
+    let inner = InProcessMessageBus::new();
+    let mut received = inner.subscribe("events", "audit").await.unwrap();
+    let bus = SchemaCheckedBus::new(
+        Arc::new(inner),
+        registry,
+        vec![("RobotEvent", schema_of::<v1::RobotEvent>())],
+    );
+
+    let mut envelope = EventEnvelope::new(
+        &PathPlanningEvent::WorkerHeartbeat {
+            planner_id: "planner-1".to_string(),
+            worker_id: "worker-1".to_string(),
+            timestamp: chrono::Utc::now(),
+        },
+        "PathPlanner",
+        EventMetadata {
+            correlation_id: None,
+            causation_id: None,
+            user_id: None,
+            source: "test".to_string(),
+            reply_to: None,
+            tenant: None,
+        },
+    )
+    .unwrap();
+    // Not a RobotEvent, so it passes unchecked
+    bus.publish("events", &envelope).await.unwrap();
+    assert!(received.next().await.unwrap().is_some());
+
+    envelope.event_data = serde_json::to_value(v1::RobotEvent::Reset).unwrap();
+    let refused = bus.publish("events", &envelope).await.unwrap_err();
+    assert!(refused.contains("Docked was removed"), "{}", refused);
+}
+
+/// The registry committed in `schemas/` records the published shape of every
+/// domain event. Changing an event so that recorded events no longer decode
+/// fails here; compatible changes are registered with
+/// `UPDATE_EVENT_SCHEMAS=1 cargo test --test event_schema_tests`.
+#[tokio::test]
+async fn test_domain_events_stay_compatible_with_the_registry() {
+    let registry = FileSchemaRegistry::new(concat!(env!("CARGO_MANIFEST_DIR"), "/schemas"));
+    let update = std::env::var("UPDATE_EVENT_SCHEMAS").is_ok();
+
+    for (subject, schema) in gryphon_app::domains::event_schemas() {
+        if update {
+            register_schema(&registry, subject, schema).await.unwrap();
+            continue;
+        }
+        let latest = registry
+            .latest(subject)
+            .await
+            .unwrap()
+            .unwrap_or_else(|| panic!("{} is not registered", subject));
+        if let Err(problems) = check_backward_compatible(&latest.schema, &schema) {
+            panic!(
+                "{} breaks version {} of its schema:\n{}",
+                subject,
+                latest.version,
+                problems.join("\n")
+            );
+        }
+        assert!(
+            latest.schema == schema,
+            "{} changed compatibly; register it with UPDATE_EVENT_SCHEMAS=1",
+            subject
+        );
+    }
+}
@@ -11,6 +11,8 @@ async fn test_in_memory_event_store() {
         causation_id: None,
         user_id: Some("test-user".to_string()),
         source: "test".to_string(),
+        reply_to: None,
+        tenant: None,
     };
 
     let event_envelope = EventEnvelope {
@@ -22,6 +24,7 @@ async fn test_in_memory_event_store() {
         event_data: serde_json::json!({"test": "data"}),
         metadata,
         occurred_at: chrono::Utc::now(),
+        chain: None,
     };
 
     // Test append
@@ -47,6 +50,7 @@ async fn test_in_memory_snapshot_store() {
         aggregate_version: 1,
         snapshot_data: serde_json::json!({"state": "test"}),
         created_at: chrono::Utc::now(),
+        chain_head: None,
     };
 
     // Test save
@@ -0,0 +1,263 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use gryphon_app::adapters::inbound::{FileEventStore, InMemorySnapshotStore};
+use gryphon_app::common::*;
+use gryphon_app::domains::path_planning::{PathPlanner, PathPlanningEvent, PlanningAlgorithm};
+use std::sync::Arc;
+use tempfile::TempDir;
+
+const PLANNER: &str = "planner-1";
+
+fn start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 5, 1, 14, 0, 0).unwrap()
+}
+
+fn envelope(event: &PathPlanningEvent) -> EventEnvelope {
+    let metadata = EventMetadata {
+        correlation_id: None,
+        causation_id: None,
+        user_id: None,
+        source: "test".to_string(),
+        reply_to: None,
+        tenant: None,
+    };
+    EventEnvelope::new(event, "PathPlanner", metadata).unwrap()
+}
+
+fn created() -> EventEnvelope {
+    envelope(&PathPlanningEvent::PlannerCreated {
+        planner_id: PLANNER.to_string(),
+        algorithm: PlanningAlgorithm::AStar,
+        timestamp: start(),
+    })
+}
+
+fn worker_registered(minute: i64) -> EventEnvelope {
+    envelope(&PathPlanningEvent::WorkerRegistered {
+        planner_id: PLANNER.to_string(),
+        worker_id: format!("worker-{}", minute),
+        capabilities: vec![PlanningAlgorithm::AStar],
+        timestamp: start() + Duration::minutes(minute),
+    })
+}
+
+/// A file store holding a created planner and three workers, appended in
+/// two batches
+async fn file_history() -> (TempDir, FileEventStore) {
+    let dir = TempDir::new().unwrap();
+    let store = FileEventStore::new(dir.path().join("events"));
+    store
+        .append_events(PLANNER, 0, vec![created(), worker_registered(1)])
+        .await
+        .unwrap();
+    store
+        .append_events(PLANNER, 2, vec![worker_registered(2), worker_registered(3)])
+        .await
+        .unwrap();
+    (dir, store)
+}
+
+fn event_file(dir: &TempDir) -> std::path::PathBuf {
+    dir.path().join("events").join(format!("{}.jsonl", PLANNER))
+}
+
+fn rewrite_lines(dir: &TempDir, edit: impl FnOnce(&mut Vec<String>)) {
+    let path = event_file(dir);
+    let mut lines: Vec<String> = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(str::to_string)
+        .collect();
+    edit(&mut lines);
+    std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+}
+
+#[tokio::test]
+async fn test_appended_events_are_chained_across_batches() {
+    let (_dir, store) = file_history().await;
+
+    let events = store.load_events(PLANNER, 0).await.unwrap();
+    let links: Vec<_> = events.iter().map(|e| e.chain.clone().unwrap()).collect();
+    assert_eq!(links[0].previous_hash, None);
+    for pair in links.windows(2) {
+        assert_eq!(pair[1].previous_hash.as_ref(), Some(&pair[0].hash));
+    }
+
+    let report = verify_event_store(&store, None).await.unwrap();
+    assert!(report.is_intact(), "{}", report);
+    assert_eq!(report.events, 4);
+    assert_eq!(report.unchained_events, 0);
+}
+
+#[tokio::test]
+async fn test_verify_reports_the_first_changed_event() {
+    let (dir, store) = file_history().await;
+    rewrite_lines(&dir, |lines| {
+        lines[2] = lines[2].replace("worker-2", "worker-9");
+    });
+
+    let report = verify_event_store(&store, None).await.unwrap();
+    let chain_break = report.first_break.unwrap();
+    assert_eq!(chain_break.aggregate_id, PLANNER);
+    assert_eq!(chain_break.version, 3);
+    assert!(chain_break.reason.contains("content"), "{}", chain_break);
+}
+
+#[tokio::test]
+async fn test_verify_reports_a_removed_event() {
+    let (dir, store) = file_history().await;
+    rewrite_lines(&dir, |lines| {
+        lines.remove(1);
+    });
+
+    let report = verify_event_store(&store, None).await.unwrap();
+    let chain_break = report.first_break.unwrap();
+    assert_eq!(chain_break.version, 2);
+    assert!(chain_break.reason.contains("link"), "{}", chain_break);
+}
+
+#[tokio::test]
+async fn test_history_from_before_chaining_is_counted_not_checked() {
+    let (dir, store) = file_history().await;
+    // Strip the links, as if the events were written by an older version
+    rewrite_lines(&dir, |lines| {
+        for line in lines.iter_mut() {
+            let mut event: EventEnvelope = serde_json::from_str(line).unwrap();
+            event.chain = None;
+            *line = serde_json::to_string(&event).unwrap();
+        }
+    });
+
+    let report = verify_event_store(&store, None).await.unwrap();
+    assert!(report.is_intact(), "{}", report);
+    assert_eq!(report.unchained_events, 4);
+
+    // New events start a chain of their own
+    store
+        .append_events(PLANNER, 4, vec![worker_registered(4)])
+        .await
+        .unwrap();
+    let last = store.load_events(PLANNER, 4).await.unwrap().pop().unwrap();
+    assert_eq!(last.chain.unwrap().previous_hash, None);
+    let report = verify_event_store(&store, None).await.unwrap();
+    assert!(report.is_intact(), "{}", report);
+    assert_eq!(report.events, 5);
+    assert_eq!(report.unchained_events, 4);
+
+    // Unlinking an earlier event once the chain has started is a break
+    rewrite_lines(&dir, |lines| lines.swap(3, 4));
+    let report = verify_event_store(&store, None).await.unwrap();
+    assert_eq!(report.first_break.unwrap().version, 5);
+}
+
+#[tokio::test]
+async fn test_snapshots_record_and_check_their_chain_head() {
+    let (_dir, store) = file_history().await;
+    let store = Arc::new(store);
+    let snapshots = Arc::new(InMemorySnapshotStore::new());
+    let repository = AggregateRepository::new(store.clone(), |id| {
+        PathPlanner::new(id.to_string(), PlanningAlgorithm::AStar)
+    })
+    .with_snapshots(snapshots.clone());
+
+    let snapshot = repository
+        .save_snapshot(PLANNER, "PathPlanner")
+        .await
+        .unwrap()
+        .unwrap();
+    let last = store.load_events(PLANNER, 0).await.unwrap().pop().unwrap();
+    assert_eq!(snapshot.aggregate_version, 4);
+    assert_eq!(snapshot.chain_head, last.chain.map(|link| link.hash));
+
+    let report = verify_event_store(store.as_ref(), Some(snapshots.as_ref()))
+        .await
+        .unwrap();
+    assert!(report.is_intact(), "{}", report);
+    assert_eq!(report.snapshots, 1);
+
+    // A snapshot of some other history at version 2
+    let stray = Snapshot::new(PLANNER, "PathPlanner", 2, &snapshot.snapshot_data)
+        .unwrap()
+        .with_chain_head(Some("0".repeat(64)));
+    snapshots.save_snapshot(stray).await.unwrap();
+    let report = verify_event_store(store.as_ref(), Some(snapshots.as_ref()))
+        .await
+        .unwrap();
+    assert_eq!(report.first_break.unwrap().version, 2);
+
+    let error = repository
+        .load_as_of(PLANNER, AsOf::Version(3))
+        .await
+        .unwrap_err();
+    assert!(error.contains("different history"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_file_store_rejects_a_stale_expected_version() {
+    let (_dir, store) = file_history().await;
+
+    let error = store
+        .append_events(PLANNER, 2, vec![worker_registered(4)])
+        .await
+        .unwrap_err();
+    assert!(error.contains("Version mismatch"), "{}", error);
+    assert_eq!(store.load_events(PLANNER, 0).await.unwrap().len(), 4);
+}
+
+#[tokio::test]
+async fn test_tracked_append_catches_up_with_other_writers() {
+    let (dir, store) = file_history().await;
+
+    // This writer last saw two events; another process appended two more since
+    let mut version = 2;
+    append_at_tracked_version(&store, PLANNER, &mut version, vec![worker_registered(4)], 3)
+        .await
+        .unwrap();
+    assert_eq!(version, 5);
+
+    let other = FileEventStore::new(dir.path().join("events"));
+    other
+        .append_events(PLANNER, 5, vec![worker_registered(5)])
+        .await
+        .unwrap();
+    append_at_tracked_version(&store, PLANNER, &mut version, vec![worker_registered(6)], 3)
+        .await
+        .unwrap();
+    assert_eq!(version, 7);
+
+    let report = verify_event_store(&store, None).await.unwrap();
+    assert!(report.is_intact(), "{}", report);
+    assert_eq!(report.events, 7);
+}
+
+#[tokio::test]
+async fn test_concurrent_appenders_keep_one_chain() {
+    let (dir, _store) = file_history().await;
+
+    // Separate stores over one directory, as separate processes would have
+    let appenders: Vec<_> = (0..4)
+        .map(|appender| {
+            let store = FileEventStore::new(dir.path().join("events"));
+            tokio::spawn(async move {
+                for round in 0..5 {
+                    loop {
+                        let version = store.load_events(PLANNER, 0).await.unwrap().len() as u64;
+                        let event = worker_registered(10 + appender * 5 + round);
+                        match store.append_events(PLANNER, version, vec![event]).await {
+                            Ok(()) => break,
+                            Err(e) if e.contains("Version mismatch") => continue,
+                            Err(e) => panic!("{}", e),
+                        }
+                    }
+                }
+            })
+        })
+        .collect();
+    for appender in appenders {
+        appender.await.unwrap();
+    }
+
+    let store = FileEventStore::new(dir.path().join("events"));
+    let report = verify_event_store(&store, None).await.unwrap();
+    assert!(report.is_intact(), "{}", report);
+    assert_eq!(report.events, 24);
+}
@@ -0,0 +1,594 @@
+use gryphon_app::adapters::inbound::{
+    InMemoryDeadLetterQueue, InMemoryInbox, InMemoryPlanCache, InProcessMessageBus,
+};
+use gryphon_app::adapters::outbound::init_noop_logger;
+use gryphon_app::application::{
+    DeadLetterReplayer, PathPlanClient, PathPlanningPlannerService, PathPlanningWorkerService,
+};
+use gryphon_app::common::*;
+use gryphon_app::config::Config;
+use gryphon_app::domains::path_planning::{
+    Orientation2D, PathPlanRequest, PathPlanningEvent, PlanStatus, PlanningAlgorithm, Position2D,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn envelope(aggregate_id: &str) -> EventEnvelope {
+    EventEnvelope {
+        event_id: uuid::Uuid::new_v4(),
+        aggregate_id: aggregate_id.to_string(),
+        aggregate_type: "TestAggregate".to_string(),
+        event_type: "TestEvent".to_string(),
+        event_version: 1,
+        event_data: serde_json::json!({"test": "data"}),
+        metadata: EventMetadata {
+            correlation_id: None,
+            causation_id: None,
+            user_id: None,
+            source: "test".to_string(),
+            reply_to: None,
+            tenant: None,
+        },
+        occurred_at: chrono::Utc::now(),
+        chain: None,
+    }
+}
+
+async fn next_within(subscription: &mut Box<dyn MessageSubscription>) -> Option<BusMessage> {
+    tokio::time::timeout(Duration::from_millis(100), subscription.next())
+        .await
+        .ok()
+        .and_then(|message| message.unwrap())
+}
+
+#[tokio::test]
+async fn test_every_group_receives_every_message() {
+    let bus = InProcessMessageBus::new();
+    let mut planner = bus.subscribe("events", "planner").await.unwrap();
+    let mut audit = bus.subscribe("events", "audit").await.unwrap();
+
+    bus.publish("events", &envelope("a")).await.unwrap();
+
+    assert_eq!(
+        next_within(&mut planner)
+            .await
+            .unwrap()
+            .envelope
+            .aggregate_id,
+        "a"
+    );
+    assert_eq!(
+        next_within(&mut audit).await.unwrap().envelope.aggregate_id,
+        "a"
+    );
+}
+
+#[tokio::test]
+async fn test_group_members_share_messages() {
+    let bus = InProcessMessageBus::new();
+    let mut first = bus.subscribe("events", "workers").await.unwrap();
+    let mut second = bus.subscribe("events", "workers").await.unwrap();
+
+    bus.publish("events", &envelope("a")).await.unwrap();
+    bus.publish("events", &envelope("b")).await.unwrap();
+
+    let mut received = vec![
+        next_within(&mut first).await.unwrap().envelope.aggregate_id,
+        next_within(&mut second)
+            .await
+            .unwrap()
+            .envelope
+            .aggregate_id,
+    ];
+    received.sort();
+    assert_eq!(received, vec!["a", "b"]);
+    assert!(next_within(&mut first).await.is_none());
+}
+
+#[tokio::test]
+async fn test_ack_records_committed_offset() {
+    let bus = InProcessMessageBus::new();
+    let mut subscription = bus.subscribe("events", "planner").await.unwrap();
+    bus.publish("events", &envelope("a")).await.unwrap();
+    bus.publish("events", &envelope("b")).await.unwrap();
+
+    assert_eq!(bus.committed_offset("events", "planner"), None);
+    let first = next_within(&mut subscription).await.unwrap();
+    subscription.ack(&first).await.unwrap();
+    let second = next_within(&mut subscription).await.unwrap();
+    subscription.ack(&second).await.unwrap();
+    assert_eq!(bus.committed_offset("events", "planner"), Some(1));
+}
+
+#[tokio::test]
+async fn test_request_assign_complete_flow_over_in_process_bus() {
+    let logger = init_noop_logger();
+    let topics = Config::default().kafka.topics;
+    let bus: Arc<dyn MessageBus> = Arc::new(InProcessMessageBus::new());
+
+    let mut planner = PathPlanningPlannerService::new(
+        "main-path-planner".to_string(),
+        bus.clone(),
+        &topics,
+        logger.clone(),
+    );
+    let worker = PathPlanningWorkerService::new(
+        "worker-1".to_string(),
+        "main-path-planner".to_string(),
+        bus.clone(),
+        &topics,
+        logger.clone(),
+    );
+
+    // The planner has to be listening before the worker registers
+    let planner_task = tokio::spawn(async move { planner.run().await });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let worker_task = tokio::spawn(async move { worker.run().await });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let client = PathPlanClient::new(logger)
+        .await
+        .unwrap()
+        .with_message_bus(bus)
+        .with_request_timeout(Duration::from_secs(5));
+    let scenario = client.scenarios[0].clone();
+    let request = PathPlanRequest {
+        request_id: "req-1".to_string(),
+        agent_id: scenario.agent_id.clone(),
+        start_position: scenario.start_position.clone(),
+        destination_position: scenario.destination_position.clone(),
+        start_orientation: scenario.start_orientation.clone(),
+        destination_orientation: scenario.destination_orientation.clone(),
+        created_at: chrono::Utc::now(),
+    };
+    let plan = client
+        .plan(request.clone())
+        .await
+        .expect("plan should complete");
+
+    assert_eq!(plan.status, PlanStatus::Complete);
+    assert_eq!(plan.agent_id, scenario.agent_id);
+    assert_eq!(plan.waypoints.first(), Some(&scenario.start_position));
+    assert_eq!(plan.waypoints.last(), Some(&scenario.destination_position));
+
+    // Asking again gets a fresh plan for the same route
+    let again = client
+        .plan(request)
+        .await
+        .expect("second plan should complete");
+    assert_ne!(again.id, plan.id);
+    assert_eq!(again.waypoints, plan.waypoints);
+
+    planner_task.abort();
+    worker_task.abort();
+}
+
+#[tokio::test]
+async fn test_request_times_out_without_reply() {
+    let bus: Arc<dyn MessageBus> = Arc::new(InProcessMessageBus::new());
+    let client = RequestReplyClient::start(bus, "replies").await.unwrap();
+
+    let result = client
+        .request::<serde_json::Value>("requests", envelope("a"), Duration::from_millis(50))
+        .await;
+
+    assert!(matches!(result, Err(RpcError::Timeout(_))));
+    assert_eq!(client.pending_requests(), 0);
+}
+
+#[tokio::test]
+async fn test_reply_is_routed_by_correlation_id() {
+    let bus = Arc::new(InProcessMessageBus::new());
+    let mut server = bus.subscribe("requests", "server").await.unwrap();
+    let client = RequestReplyClient::start(bus.clone(), "replies")
+        .await
+        .unwrap();
+
+    let first = client.send("requests", envelope("a")).await.unwrap();
+    let second = client.send("requests", envelope("b")).await.unwrap();
+    assert_eq!(client.pending_requests(), 2);
+
+    // Answer in reverse order
+    let request_a = next_within(&mut server).await.unwrap().envelope;
+    let request_b = next_within(&mut server).await.unwrap().envelope;
+    assert_eq!(request_a.metadata.reply_to.as_deref(), Some("replies"));
+    assert!(send_reply(bus.as_ref(), &request_b, envelope("reply-b"))
+        .await
+        .unwrap());
+    assert!(send_reply(bus.as_ref(), &request_a, envelope("reply-a"))
+        .await
+        .unwrap());
+
+    let reply_a = first.wait(Duration::from_secs(1)).await.unwrap();
+    let reply_b = second.wait(Duration::from_secs(1)).await.unwrap();
+    assert_eq!(reply_a.aggregate_id, "reply-a");
+    assert_eq!(reply_b.aggregate_id, "reply-b");
+    assert_eq!(
+        reply_a.metadata.correlation_id,
+        request_a.metadata.correlation_id
+    );
+    assert_eq!(reply_a.metadata.causation_id, Some(request_a.event_id));
+    assert_eq!(client.pending_requests(), 0);
+}
+
+#[tokio::test]
+async fn test_cancelled_request_is_forgotten() {
+    let bus: Arc<dyn MessageBus> = Arc::new(InProcessMessageBus::new());
+    let client = RequestReplyClient::start(bus.clone(), "replies")
+        .await
+        .unwrap();
+
+    let pending = client.send("requests", envelope("a")).await.unwrap();
+    assert_eq!(client.pending_requests(), 1);
+    pending.cancel();
+    assert_eq!(client.pending_requests(), 0);
+
+    // A request without reply_to gets no answer
+    assert!(!send_reply(bus.as_ref(), &envelope("a"), envelope("b"))
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn test_failing_envelope_is_dead_lettered_and_replayed() {
+    let logger = init_noop_logger();
+    let topics = Config::default().kafka.topics;
+    let bus: Arc<dyn MessageBus> = Arc::new(InProcessMessageBus::new());
+    let dead_letters = InMemoryDeadLetterQueue::new();
+
+    let mut planner = PathPlanningPlannerService::new(
+        "main-path-planner".to_string(),
+        bus.clone(),
+        &topics,
+        logger.clone(),
+    )
+    .with_dead_letter_queue(Arc::new(dead_letters.clone()))
+    .with_max_attempts(2);
+    // Hand the planner back once it has had time to see the replay
+    let planner_task = tokio::spawn(async move {
+        let _ = tokio::time::timeout(Duration::from_millis(500), planner.run()).await;
+        planner
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    // Not a path planning event, so the planner can never process it
+    let mut broken = envelope("main-path-planner");
+    broken.event_type = "WorkerRegistered".to_string();
+    bus.publish(&topics.path_planning_events, &broken)
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let letters = dead_letters.letters().await;
+    assert_eq!(letters.len(), 1);
+    assert_eq!(letters[0].source_topic, topics.path_planning_events);
+    assert_eq!(letters[0].attempts, 2);
+    assert_eq!(letters[0].offset, 0);
+    assert!(letters[0].error.contains("Undecodable"));
+    assert_eq!(letters[0].envelope().unwrap().event_id, broken.event_id);
+
+    // Fix the payload and replay it; this time the planner accepts it
+    let mut fixed = letters[0].clone();
+    let mut envelope = fixed.envelope().unwrap();
+    envelope.event_data = serde_json::to_value(PathPlanningEvent::WorkerRegistered {
+        planner_id: "main-path-planner".to_string(),
+        worker_id: "worker-9".to_string(),
+        capabilities: vec![PlanningAlgorithm::AStar],
+        timestamp: chrono::Utc::now(),
+    })
+    .unwrap();
+    fixed.payload = serde_json::to_string(&envelope).unwrap();
+    let mut poison = fixed.clone();
+    poison.id = uuid::Uuid::new_v4();
+    poison.payload = "not json".to_string();
+
+    let mut audit = bus
+        .subscribe(&topics.path_planning_events, "audit")
+        .await
+        .unwrap();
+    let report = DeadLetterReplayer::new(bus.clone(), logger)
+        .replay(&[fixed.clone(), poison.clone()])
+        .await;
+    assert_eq!(report.replayed, vec![fixed.id]);
+    assert_eq!(report.rejected.len(), 1);
+    assert_eq!(report.rejected[0].0, poison.id);
+    assert_eq!(
+        next_within(&mut audit).await.unwrap().envelope.event_id,
+        broken.event_id
+    );
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(dead_letters.letters().await.len(), 1);
+
+    // The replay has the dead-lettered event id, and is still handled
+    let planner = planner_task.await.unwrap();
+    assert!(planner.workers().contains_key("worker-9"));
+}
+
+#[tokio::test]
+async fn test_bus_dead_letter_queue_roundtrip() {
+    let bus: Arc<dyn MessageBus> = Arc::new(InProcessMessageBus::new());
+    let mut dlq = bus.subscribe("dead-letters", "ops").await.unwrap();
+    let letter = DeadLetter::poison(
+        "path-planning-events",
+        2,
+        41,
+        Some("PathPlanner:main".to_string()),
+        b"{not an envelope",
+        None,
+        "expected value",
+    );
+
+    BusDeadLetterQueue::new(bus.clone(), "dead-letters")
+        .send(letter.clone())
+        .await
+        .unwrap();
+
+    let received = next_within(&mut dlq).await.unwrap();
+    assert_eq!(
+        DeadLetter::from_envelope(&received.envelope).unwrap(),
+        letter
+    );
+    assert!(letter.envelope().is_err());
+}
+
+#[test]
+fn test_poison_message_pack_payload_keeps_its_bytes() {
+    let sent = envelope("planner-1");
+    let bytes = EnvelopeCodec::MessagePack.encode(&sent).unwrap();
+    let letter = DeadLetter::poison(
+        "path-planning-events",
+        0,
+        7,
+        None,
+        &bytes,
+        Some(EnvelopeCodec::MessagePack.content_type()),
+        "handler rejected it",
+    );
+
+    assert_eq!(letter.payload_encoding, PayloadEncoding::Base64);
+    assert_eq!(letter.payload_bytes().unwrap(), bytes);
+    assert_eq!(letter.envelope().unwrap().event_id, sent.event_id);
+
+    // The letter survives the trip through the dead-letter topic unchanged
+    let roundtrip = DeadLetter::from_envelope(&letter.to_envelope().unwrap()).unwrap();
+    assert_eq!(roundtrip, letter);
+}
+
+#[tokio::test]
+async fn test_redelivered_request_is_assigned_once() {
+    let logger = init_noop_logger();
+    let topics = Config::default().kafka.topics;
+    let bus: Arc<dyn MessageBus> = Arc::new(InProcessMessageBus::new());
+    let inbox = InMemoryInbox::new();
+    let mut assignments = bus
+        .subscribe(&topics.path_planning_events, "audit")
+        .await
+        .unwrap();
+
+    let mut planner = PathPlanningPlannerService::new(
+        "main-path-planner".to_string(),
+        bus.clone(),
+        &topics,
+        logger.clone(),
+    )
+    .with_inbox(Arc::new(inbox.clone()));
+    let planner_task = tokio::spawn(async move { planner.run().await });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let registration = EventEnvelope::new(
+        &PathPlanningEvent::WorkerRegistered {
+            planner_id: "main-path-planner".to_string(),
+            worker_id: "worker-1".to_string(),
+            capabilities: vec![PlanningAlgorithm::AStar],
+            timestamp: chrono::Utc::now(),
+        },
+        "PathPlanner",
+        envelope("main-path-planner").metadata,
+    )
+    .unwrap();
+    let request = EventEnvelope::new(
+        &PathPlanningEvent::PathPlanRequested {
+            planner_id: "main-path-planner".to_string(),
+            request_id: "req-1".to_string(),
+            plan_id: "plan-1".to_string(),
+            agent_id: "agent-1".to_string(),
+            start_position: Position2D { x: 0.0, y: 0.0 },
+            destination_position: Position2D { x: 1.0, y: 1.0 },
+            start_orientation: Orientation2D { angle: 0.0 },
+            destination_orientation: Orientation2D { angle: 0.0 },
+            timestamp: chrono::Utc::now(),
+        },
+        "PathPlanner",
+        envelope("main-path-planner").metadata,
+    )
+    .unwrap();
+
+    // The registration and the request are both delivered twice
+    for message in [&registration, &request, &registration, &request] {
+        bus.publish(&topics.path_planning_events, message)
+            .await
+            .unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let mut assigned = 0;
+    while let Some(message) = next_within(&mut assignments).await {
+        if message.envelope.event_type == "PlanAssigned" {
+            assigned += 1;
+        }
+    }
+    assert_eq!(assigned, 1);
+    assert!(inbox
+        .is_processed("planner-main-path-planner", request.event_id)
+        .await
+        .unwrap());
+
+    planner_task.abort();
+}
+
+#[tokio::test]
+async fn test_worker_is_assigned_again_after_a_failed_plan() {
+    let logger = init_noop_logger();
+    let topics = Config::default().kafka.topics;
+    let bus: Arc<dyn MessageBus> = Arc::new(InProcessMessageBus::new());
+    let mut assignments = bus
+        .subscribe(&topics.path_planning_events, "audit")
+        .await
+        .unwrap();
+
+    let mut planner = PathPlanningPlannerService::new(
+        "main-path-planner".to_string(),
+        bus.clone(),
+        &topics,
+        logger,
+    );
+    let planner_task = tokio::spawn(async move { planner.run().await });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let event = |event: PathPlanningEvent| {
+        EventEnvelope::new(
+            &event,
+            "PathPlanner",
+            envelope("main-path-planner").metadata,
+        )
+        .unwrap()
+    };
+    let request = |plan_id: &str| {
+        event(PathPlanningEvent::PathPlanRequested {
+            planner_id: "main-path-planner".to_string(),
+            request_id: format!("req-{}", plan_id),
+            plan_id: plan_id.to_string(),
+            agent_id: "agent-1".to_string(),
+            start_position: Position2D { x: 0.0, y: 0.0 },
+            destination_position: Position2D { x: 1.0, y: 1.0 },
+            start_orientation: Orientation2D { angle: 0.0 },
+            destination_orientation: Orientation2D { angle: 0.0 },
+            timestamp: chrono::Utc::now(),
+        })
+    };
+
+    // The only worker fails its first plan
+    for message in [
+        event(PathPlanningEvent::WorkerRegistered {
+            planner_id: "main-path-planner".to_string(),
+            worker_id: "worker-1".to_string(),
+            capabilities: vec![PlanningAlgorithm::AStar],
+            timestamp: chrono::Utc::now(),
+        }),
+        request("plan-1"),
+        event(PathPlanningEvent::PlanFailed {
+            planner_id: "main-path-planner".to_string(),
+            plan_id: "plan-1".to_string(),
+            worker_id: Some("worker-1".to_string()),
+            reason: "no route".to_string(),
+            timestamp: chrono::Utc::now(),
+        }),
+        request("plan-2"),
+    ] {
+        bus.publish(&topics.path_planning_events, &message)
+            .await
+            .unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let mut assigned = Vec::new();
+    while let Some(message) = next_within(&mut assignments).await {
+        if let Ok(PathPlanningEvent::PlanAssigned {
+            plan_id, worker_id, ..
+        }) = serde_json::from_value(message.envelope.event_data)
+        {
+            assigned.push((plan_id, worker_id));
+        }
+    }
+    assert_eq!(
+        assigned,
+        vec![
+            ("plan-1".to_string(), "worker-1".to_string()),
+            ("plan-2".to_string(), "worker-1".to_string()),
+        ]
+    );
+
+    planner_task.abort();
+}
+
+#[tokio::test]
+async fn test_invalidated_plan_is_replanned_without_the_cache() {
+    let logger = init_noop_logger();
+    let topics = Config::default().kafka.topics;
+    let bus: Arc<dyn MessageBus> = Arc::new(InProcessMessageBus::new());
+    let mut completions = bus
+        .subscribe(&topics.path_planning_events, "audit")
+        .await
+        .unwrap();
+
+    let worker = PathPlanningWorkerService::new(
+        "worker-1".to_string(),
+        "main-path-planner".to_string(),
+        bus.clone(),
+        &topics,
+        logger,
+    )
+    .with_plan_cache(Arc::new(InMemoryPlanCache::new()));
+    let worker_task = tokio::spawn(async move { worker.run().await });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let event = |event: PathPlanningEvent| {
+        EventEnvelope::new(
+            &event,
+            "PathPlanner",
+            envelope("main-path-planner").metadata,
+        )
+        .unwrap()
+    };
+    let route = (
+        Position2D { x: 0.0, y: 0.0 },
+        Position2D { x: 4.0, y: 0.0 },
+        Orientation2D { angle: 0.0 },
+    );
+    let assigned = || {
+        event(PathPlanningEvent::PlanAssigned {
+            planner_id: "main-path-planner".to_string(),
+            plan_id: "plan-1".to_string(),
+            worker_id: "worker-1".to_string(),
+            request_id: "req-1".to_string(),
+            agent_id: "agent-1".to_string(),
+            start_position: route.0.clone(),
+            destination_position: route.1.clone(),
+            start_orientation: route.2.clone(),
+            destination_orientation: route.2.clone(),
+            timeout_seconds: 300,
+            timestamp: chrono::Utc::now(),
+        })
+    };
+    let invalidated = event(PathPlanningEvent::PlanInvalidated {
+        planner_id: "main-path-planner".to_string(),
+        plan_id: "plan-1".to_string(),
+        agent_id: "agent-1".to_string(),
+        obstacle_id: "crate-1".to_string(),
+        start_position: route.0.clone(),
+        destination_position: route.1.clone(),
+        start_orientation: route.2.clone(),
+        destination_orientation: route.2.clone(),
+        timestamp: chrono::Utc::now(),
+    });
+
+    // Planned, invalidated by an obstacle, then assigned again under the same id
+    for message in [assigned(), invalidated, assigned()] {
+        bus.publish(&topics.path_planning_events, &message)
+            .await
+            .unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+
+    let mut cache_hits = Vec::new();
+    while let Some(message) = next_within(&mut completions).await {
+        if let Ok(PathPlanningEvent::PlanCompleted { cache_hit, .. }) =
+            serde_json::from_value(message.envelope.event_data)
+        {
+            cache_hits.push(cache_hit);
+        }
+    }
+    assert_eq!(cache_hits, vec![false, false]);
+
+    worker_task.abort();
+}
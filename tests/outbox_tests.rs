@@ -0,0 +1,131 @@
+use async_trait::async_trait;
+use gryphon_app::adapters::inbound::{InMemoryOutbox, InProcessMessageBus};
+use gryphon_app::adapters::outbound::init_noop_logger;
+use gryphon_app::application::OutboxRelay;
+use gryphon_app::common::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn envelope(sequence: u64) -> EventEnvelope {
+    EventEnvelope {
+        event_id: uuid::Uuid::new_v4(),
+        aggregate_id: "planner-1".to_string(),
+        aggregate_type: "PathPlanner".to_string(),
+        event_type: "TestEvent".to_string(),
+        event_version: sequence,
+        event_data: serde_json::json!({ "sequence": sequence }),
+        metadata: EventMetadata {
+            correlation_id: None,
+            causation_id: None,
+            user_id: None,
+            source: "test".to_string(),
+            reply_to: None,
+            tenant: None,
+        },
+        occurred_at: chrono::Utc::now(),
+        chain: None,
+    }
+}
+
+/// Bus that rejects the first `failures` publishes
+struct FlakyBus {
+    inner: InProcessMessageBus,
+    failures: AtomicUsize,
+}
+
+#[async_trait]
+impl MessageBus for FlakyBus {
+    async fn publish(&self, topic: &str, envelope: &EventEnvelope) -> Result<(), String> {
+        let remaining = self.failures.load(Ordering::SeqCst);
+        if remaining > 0 {
+            self.failures.store(remaining - 1, Ordering::SeqCst);
+            return Err("broker unavailable".to_string());
+        }
+        self.inner.publish(topic, envelope).await
+    }
+
+    async fn subscribe(
+        &self,
+        topic: &str,
+        group_id: &str,
+    ) -> Result<Box<dyn MessageSubscription>, String> {
+        self.inner.subscribe(topic, group_id).await
+    }
+}
+
+async fn drain(subscription: &mut Box<dyn MessageSubscription>) -> Vec<u64> {
+    let mut received = Vec::new();
+    while let Ok(Some(message)) =
+        tokio::time::timeout(Duration::from_millis(100), subscription.next())
+            .await
+            .map(|m| m.unwrap())
+    {
+        received.push(message.envelope.event_version);
+    }
+    received
+}
+
+#[tokio::test]
+async fn test_relay_restarts_lose_and_duplicate_nothing() {
+    let outbox = InMemoryOutbox::new();
+    let bus = Arc::new(FlakyBus {
+        inner: InProcessMessageBus::new(),
+        failures: AtomicUsize::new(2),
+    });
+    let mut consumer = bus.subscribe("events", "consumer").await.unwrap();
+
+    let events: Vec<_> = (1..=7).map(envelope).collect();
+    outbox.enqueue("events", events.clone()).await;
+    // Writing the same event again is a no-op
+    outbox.enqueue("events", events[..2].to_vec()).await;
+
+    let relay = |outbox: &InMemoryOutbox| {
+        OutboxRelay::new(Arc::new(outbox.clone()), bus.clone(), init_noop_logger())
+            .with_batch_size(3)
+    };
+
+    // First relay hits the outage, then gets one batch out before it is stopped
+    let first = relay(&outbox);
+    assert!(first.relay_batch().await.is_err());
+    assert!(first.relay_batch().await.is_err());
+    assert_eq!(first.relay_batch().await.unwrap(), 3);
+    drop(first);
+    assert_eq!(outbox.pending_count().await, 4);
+
+    // A new relay picks up where the old one stopped
+    let second = relay(&outbox);
+    let task = tokio::spawn(async move { second.run().await });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    task.abort();
+
+    assert_eq!(drain(&mut consumer).await, vec![1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!(outbox.pending_count().await, 0);
+    assert_eq!(
+        outbox.sent().await,
+        events.iter().map(|e| e.event_id).collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn test_failed_publish_is_counted_and_blocks_later_messages() {
+    let outbox = InMemoryOutbox::new();
+    let bus = Arc::new(FlakyBus {
+        inner: InProcessMessageBus::new(),
+        failures: AtomicUsize::new(1),
+    });
+    let mut consumer = bus.subscribe("events", "consumer").await.unwrap();
+    outbox
+        .enqueue("events", vec![envelope(1), envelope(2)])
+        .await;
+
+    let relay = OutboxRelay::new(Arc::new(outbox.clone()), bus, init_noop_logger());
+    assert!(relay.relay_batch().await.is_err());
+    assert!(drain(&mut consumer).await.is_empty());
+    let pending = outbox.pending(10).await.unwrap();
+    assert_eq!(pending[0].attempts, 1);
+    assert_eq!(pending[1].attempts, 0);
+
+    assert_eq!(relay.relay_batch().await.unwrap(), 2);
+    assert_eq!(drain(&mut consumer).await, vec![1, 2]);
+}
@@ -0,0 +1,187 @@
+use gryphon_app::adapters::inbound::InProcessMessageBus;
+use gryphon_app::adapters::outbound::init_noop_logger;
+use gryphon_app::application::KeyedDispatcher;
+use gryphon_app::common::*;
+use gryphon_app::config::PartitionStrategy;
+use gryphon_app::domains::path_planning::PathPlanner;
+use rand::Rng;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn envelope(aggregate_type: &str, aggregate_id: &str, sequence: u64) -> EventEnvelope {
+    EventEnvelope {
+        event_id: uuid::Uuid::new_v4(),
+        aggregate_id: aggregate_id.to_string(),
+        aggregate_type: aggregate_type.to_string(),
+        event_type: "TestEvent".to_string(),
+        event_version: sequence,
+        event_data: serde_json::json!({ "sequence": sequence }),
+        metadata: EventMetadata {
+            correlation_id: None,
+            causation_id: None,
+            user_id: None,
+            source: "test".to_string(),
+            reply_to: None,
+            tenant: None,
+        },
+        occurred_at: chrono::Utc::now(),
+        chain: None,
+    }
+}
+
+#[test]
+fn test_every_strategy_keys_one_planner_alike_across_producers() {
+    // The planner, the workers, the client and the esrs bus all publish planner
+    // events under PathPlanner::AGGREGATE_TYPE
+    for strategy in [
+        PartitionStrategy::AggregateId,
+        PartitionStrategy::AggregateTypeAndId,
+    ] {
+        let planner = strategy.key_for(&envelope(PathPlanner::AGGREGATE_TYPE, "planner-1", 1));
+        assert_eq!(
+            planner,
+            strategy.key(PathPlanner::AGGREGATE_TYPE, "planner-1")
+        );
+        assert_ne!(
+            planner,
+            strategy.key_for(&envelope(PathPlanner::AGGREGATE_TYPE, "planner-2", 1))
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_dispatcher_keeps_per_aggregate_order_under_concurrent_producers() {
+    const PRODUCERS: usize = 4;
+    const AGGREGATES_PER_PRODUCER: usize = 5;
+    const EVENTS_PER_AGGREGATE: u64 = 20;
+    let total = PRODUCERS * AGGREGATES_PER_PRODUCER * EVENTS_PER_AGGREGATE as usize;
+
+    let bus = Arc::new(InProcessMessageBus::new());
+    let subscription = bus.subscribe("events", "consumer").await.unwrap();
+
+    let seen: Arc<Mutex<HashMap<String, Vec<u64>>>> = Arc::default();
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let max_in_flight = Arc::new(AtomicUsize::new(0));
+    let handled = Arc::new(AtomicUsize::new(0));
+    let dispatcher = KeyedDispatcher::new(4, init_noop_logger());
+    let handler = {
+        let seen = seen.clone();
+        let in_flight = in_flight.clone();
+        let max_in_flight = max_in_flight.clone();
+        let handled = handled.clone();
+        move |message: BusMessage| {
+            let seen = seen.clone();
+            let in_flight = in_flight.clone();
+            let max_in_flight = max_in_flight.clone();
+            let handled = handled.clone();
+            async move {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(now, Ordering::SeqCst);
+                let pause = rand::thread_rng().gen_range(0..500);
+                tokio::time::sleep(Duration::from_micros(pause)).await;
+                seen.lock()
+                    .unwrap()
+                    .entry(message.envelope.aggregate_id.clone())
+                    .or_default()
+                    .push(message.envelope.event_version);
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                handled.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        }
+    };
+    let consumer = tokio::spawn(async move { dispatcher.run(subscription, handler).await });
+
+    let producers: Vec<_> = (0..PRODUCERS)
+        .map(|producer| {
+            let bus = bus.clone();
+            tokio::spawn(async move {
+                for sequence in 0..EVENTS_PER_AGGREGATE {
+                    for aggregate in 0..AGGREGATES_PER_PRODUCER {
+                        let id = format!("planner-{}-{}", producer, aggregate);
+                        bus.publish("events", &envelope("PathPlanner", &id, sequence))
+                            .await
+                            .unwrap();
+                    }
+                    tokio::task::yield_now().await;
+                }
+            })
+        })
+        .collect();
+    for producer in producers {
+        producer.await.unwrap();
+    }
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while handled.load(Ordering::SeqCst) < total {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .expect("every event is handled");
+    consumer.abort();
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), PRODUCERS * AGGREGATES_PER_PRODUCER);
+    for (aggregate, sequences) in seen.iter() {
+        let expected: Vec<u64> = (0..EVENTS_PER_AGGREGATE).collect();
+        assert_eq!(sequences, &expected, "events of {} out of order", aggregate);
+    }
+    assert!(
+        max_in_flight.load(Ordering::SeqCst) > 1,
+        "different aggregates are handled in parallel"
+    );
+}
+
+#[tokio::test]
+async fn test_dispatcher_acknowledges_only_contiguous_handled_offsets() {
+    let bus = InProcessMessageBus::new();
+    let subscription = bus.subscribe("events", "consumer").await.unwrap();
+    let dispatcher = KeyedDispatcher::new(2, init_noop_logger());
+
+    // "slow" blocks until released, so "fast" finishes first even though it
+    // was published later
+    let release = Arc::new(tokio::sync::Notify::new());
+    let handler = {
+        let release = release.clone();
+        move |message: BusMessage| {
+            let release = release.clone();
+            async move {
+                if message.envelope.aggregate_id == "slow" {
+                    release.notified().await;
+                }
+                Ok(())
+            }
+        }
+    };
+    let consumer = tokio::spawn(async move { dispatcher.run(subscription, handler).await });
+
+    // Offset 0 stays unhandled while most of offsets 1..=8 finish on the other lane
+    bus.publish("events", &envelope("A", "slow", 0))
+        .await
+        .unwrap();
+    for sequence in 1..=8 {
+        bus.publish("events", &envelope("A", &format!("fast-{}", sequence), 0))
+            .await
+            .unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    // Acknowledgements go out between reads
+    bus.publish("events", &envelope("A", "fast-last", 0))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(bus.committed_offset("events", "consumer"), None);
+
+    release.notify_one();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    bus.publish("events", &envelope("A", "fast-after", 0))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(bus.committed_offset("events", "consumer").unwrap() > 8);
+
+    consumer.abort();
+}
@@ -413,7 +413,7 @@ mod path_planner_tests {
         // Check plan status changed to Failed
         match &planner.active_plans[0].status {
             PlanStatus::Failed(reason) => {
-                assert_eq!(*reason, "Planning failed");
+                assert_eq!(*reason, failure_reason);
             }
             _ => panic!("Expected plan status to be Failed"),
         }
@@ -547,6 +547,858 @@ mod path_planner_tests {
         assert!(result.is_ok());
         assert_eq!(planner.version(), 1);
     }
+
+    fn planner_with_completed_plan() -> (PathPlanner, String) {
+        let mut planner = PathPlanner::new("planner-1".to_string(), PlanningAlgorithm::AStar);
+        let worker_id = "worker-1".to_string();
+        planner
+            .register_worker(worker_id.clone(), vec![PlanningAlgorithm::AStar])
+            .unwrap();
+
+        let request = PathPlanRequest {
+            request_id: "req-123".to_string(),
+            agent_id: "agent-1".to_string(),
+            start_position: Position2D { x: 10.0, y: 20.0 },
+            destination_position: Position2D { x: 50.0, y: 80.0 },
+            start_orientation: Orientation2D { angle: 0.0 },
+            destination_orientation: Orientation2D { angle: 1.57 },
+            created_at: Utc::now(),
+        };
+        planner.request_path_plan(request).unwrap();
+        let plan_id = planner.active_plans[0].id.clone();
+        planner
+            .handle_plan_completed(worker_id, plan_id.clone(), vec![])
+            .unwrap();
+        (planner, plan_id)
+    }
+
+    #[test]
+    fn test_archive_finished_plans_respects_retention() {
+        let (mut planner, plan_id) = planner_with_completed_plan();
+        let finished_at = planner.active_plans[0].finished_at.unwrap();
+        let retention = chrono::Duration::seconds(60);
+
+        // Still inside the retention window: nothing is archived
+        let archived = planner
+            .archive_finished_plans(retention, finished_at + chrono::Duration::seconds(30))
+            .unwrap();
+        assert_eq!(archived, 0);
+        assert_eq!(planner.active_plans.len(), 1);
+
+        // Past the window the plan leaves the aggregate
+        let archived = planner
+            .archive_finished_plans(retention, finished_at + retention)
+            .unwrap();
+        assert_eq!(archived, 1);
+        assert!(planner.active_plans.is_empty());
+        assert!(planner.plan_assignments.is_empty());
+
+        match planner.uncommitted_events().last() {
+            Some(PathPlanningEvent::PlanArchived { plan, .. }) => {
+                assert_eq!(plan.id, plan_id);
+                assert_eq!(plan.status, PlanStatus::Complete);
+            }
+            _ => panic!("Expected PlanArchived event"),
+        }
+    }
+
+    #[test]
+    fn test_archive_keeps_live_plans() {
+        let mut planner = PathPlanner::new("planner-1".to_string(), PlanningAlgorithm::AStar);
+        let request = PathPlanRequest {
+            request_id: "req-123".to_string(),
+            agent_id: "agent-1".to_string(),
+            start_position: Position2D { x: 10.0, y: 20.0 },
+            destination_position: Position2D { x: 50.0, y: 80.0 },
+            start_orientation: Orientation2D { angle: 0.0 },
+            destination_orientation: Orientation2D { angle: 1.57 },
+            created_at: Utc::now(),
+        };
+        planner.request_path_plan(request).unwrap();
+
+        let archived = planner
+            .archive_finished_plans(
+                chrono::Duration::zero(),
+                Utc::now() + chrono::Duration::days(1),
+            )
+            .unwrap();
+        assert_eq!(archived, 0);
+        assert_eq!(planner.active_plans.len(), 1);
+    }
+
+    #[test]
+    fn test_finished_and_archived_plans_reject_updates() {
+        let (mut planner, plan_id) = planner_with_completed_plan();
+
+        let result = planner.handle_plan_failed(
+            "worker-1".to_string(),
+            plan_id.clone(),
+            "late failure".to_string(),
+        );
+        assert!(matches!(result, Err(DomainError::InvalidCommand { .. })));
+
+        planner
+            .archive_finished_plans(chrono::Duration::zero(), Utc::now())
+            .unwrap();
+        let result = planner.handle_plan_completed("worker-1".to_string(), plan_id, vec![]);
+        match result {
+            Err(DomainError::InvalidCommand { reason }) => assert!(reason.contains("not active")),
+            _ => panic!("Expected InvalidCommand error"),
+        }
+    }
+
+    #[test]
+    fn test_plan_history_projection_records_archived_plans() {
+        let (mut planner, plan_id) = planner_with_completed_plan();
+        planner
+            .archive_finished_plans(chrono::Duration::zero(), Utc::now())
+            .unwrap();
+
+        let mut history = PlanHistoryProjection::new("planner-1".to_string());
+        for event in planner.uncommitted_events() {
+            history.apply_event(event);
+        }
+
+        let archived = history.get_plan(&plan_id).expect("plan should be archived");
+        assert_eq!(archived.plan.status, PlanStatus::Complete);
+        assert_eq!(history.plans_for_agent("agent-1").len(), 1);
+        assert!(history.plans_for_agent("agent-2").is_empty());
+    }
+
+    fn goal(x: f64, y: f64) -> PlanGoal {
+        PlanGoal {
+            label: None,
+            position: Position2D { x, y },
+            orientation: Orientation2D { angle: 0.0 },
+        }
+    }
+
+    fn multi_goal_request(goals: Vec<PlanGoal>, optimize_order: bool) -> MultiGoalPlanRequest {
+        MultiGoalPlanRequest {
+            request_id: "req-multi".to_string(),
+            agent_id: "agent-1".to_string(),
+            start_position: Position2D { x: 0.0, y: 0.0 },
+            start_orientation: Orientation2D { angle: 0.0 },
+            goals,
+            optimize_order,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_multi_goal_plan_creates_chained_legs() {
+        let mut planner = PathPlanner::new("planner-1".to_string(), PlanningAlgorithm::AStar);
+        let request = multi_goal_request(vec![goal(10.0, 0.0), goal(10.0, 10.0)], false);
+        planner.request_multi_goal_plan(request).unwrap();
+
+        let parent = planner
+            .active_plans
+            .iter()
+            .find(|p| p.is_multi_goal())
+            .unwrap();
+        assert_eq!(parent.status, PlanStatus::InProgress);
+        assert_eq!(parent.goal, Position2D { x: 10.0, y: 10.0 });
+        assert_eq!(parent.leg_plan_ids.len(), 2);
+
+        let first = planner
+            .active_plans
+            .iter()
+            .find(|p| p.id == parent.leg_plan_ids[0])
+            .unwrap();
+        let second = planner
+            .active_plans
+            .iter()
+            .find(|p| p.id == parent.leg_plan_ids[1])
+            .unwrap();
+        assert_eq!(first.start, Position2D { x: 0.0, y: 0.0 });
+        assert_eq!(second.start, first.goal);
+        assert_eq!(second.parent_plan_id.as_ref(), Some(&parent.id));
+        assert_eq!(second.status, PlanStatus::Planning);
+    }
+
+    #[test]
+    fn test_multi_goal_plan_rejects_empty_and_out_of_bounds_goals() {
+        let mut planner = PathPlanner::new("planner-1".to_string(), PlanningAlgorithm::AStar);
+
+        let result = planner.request_multi_goal_plan(multi_goal_request(vec![], false));
+        assert!(matches!(result, Err(DomainError::InvalidCommand { .. })));
+
+        let result = planner.request_multi_goal_plan(multi_goal_request(
+            vec![goal(1.0, 1.0), goal(500.0, 0.0)],
+            false,
+        ));
+        match result {
+            Err(DomainError::InvalidCommand { reason }) => assert!(reason.contains("Goal 1")),
+            _ => panic!("Expected InvalidCommand error"),
+        }
+        assert!(planner.active_plans.is_empty());
+    }
+
+    #[test]
+    fn test_multi_goal_plan_completes_with_combined_waypoints() {
+        let mut planner = PathPlanner::new("planner-1".to_string(), PlanningAlgorithm::AStar);
+        let request = multi_goal_request(vec![goal(10.0, 0.0), goal(10.0, 10.0)], false);
+        planner.request_multi_goal_plan(request).unwrap();
+        let parent_id = planner.active_plans[0].id.clone();
+        let legs = planner.active_plans[0].leg_plan_ids.clone();
+
+        // Legs may finish out of order; the route is still joined in visiting order
+        planner
+            .handle_plan_completed(
+                "worker-2".to_string(),
+                legs[1].clone(),
+                vec![
+                    Position2D { x: 10.0, y: 0.0 },
+                    Position2D { x: 10.0, y: 10.0 },
+                ],
+            )
+            .unwrap();
+        let parent = planner
+            .active_plans
+            .iter()
+            .find(|p| p.id == parent_id)
+            .unwrap();
+        assert_eq!(parent.status, PlanStatus::InProgress);
+
+        planner
+            .handle_plan_completed(
+                "worker-1".to_string(),
+                legs[0].clone(),
+                vec![
+                    Position2D { x: 0.0, y: 0.0 },
+                    Position2D { x: 10.0, y: 0.0 },
+                ],
+            )
+            .unwrap();
+        let parent = planner
+            .active_plans
+            .iter()
+            .find(|p| p.id == parent_id)
+            .unwrap();
+        assert_eq!(parent.status, PlanStatus::Complete);
+        assert_eq!(
+            parent.waypoints,
+            vec![
+                Position2D { x: 0.0, y: 0.0 },
+                Position2D { x: 10.0, y: 0.0 },
+                Position2D { x: 10.0, y: 10.0 },
+            ]
+        );
+        assert!(planner
+            .uncommitted_events()
+            .iter()
+            .any(|e| matches!(e, PathPlanningEvent::MultiGoalPlanCompleted { plan_id, .. } if *plan_id == parent_id)));
+    }
+
+    #[test]
+    fn test_failed_leg_fails_multi_goal_plan() {
+        let mut planner = PathPlanner::new("planner-1".to_string(), PlanningAlgorithm::AStar);
+        let request = multi_goal_request(vec![goal(10.0, 0.0), goal(10.0, 10.0)], false);
+        planner.request_multi_goal_plan(request).unwrap();
+        let parent_id = planner.active_plans[0].id.clone();
+        let legs = planner.active_plans[0].leg_plan_ids.clone();
+
+        planner
+            .handle_plan_failed(
+                "worker-1".to_string(),
+                legs[0].clone(),
+                "blocked".to_string(),
+            )
+            .unwrap();
+        let parent = planner
+            .active_plans
+            .iter()
+            .find(|p| p.id == parent_id)
+            .unwrap();
+        assert_eq!(parent.status, PlanStatus::Failed("blocked".to_string()));
+
+        // The combined plan itself is never driven directly by workers
+        let result = planner.handle_plan_completed("worker-1".to_string(), parent_id, vec![]);
+        assert!(matches!(result, Err(DomainError::InvalidCommand { .. })));
+    }
+
+    #[test]
+    fn test_plan_batch_is_all_or_nothing() {
+        let mut planner = PathPlanner::new("planner-1".to_string(), PlanningAlgorithm::AStar);
+        let single = PathPlanRequest {
+            request_id: "req-123".to_string(),
+            agent_id: "agent-1".to_string(),
+            start_position: Position2D { x: 10.0, y: 20.0 },
+            destination_position: Position2D { x: 50.0, y: 80.0 },
+            start_orientation: Orientation2D { angle: 0.0 },
+            destination_orientation: Orientation2D { angle: 1.57 },
+            created_at: Utc::now(),
+        };
+        let invalid = multi_goal_request(vec![goal(500.0, 0.0)], false);
+        let result = planner.request_plan_batch(vec![
+            BatchPlanRequest::Single(single.clone()),
+            BatchPlanRequest::MultiGoal(invalid),
+        ]);
+        assert!(result.is_err());
+        assert!(planner.active_plans.is_empty());
+        assert_eq!(planner.uncommitted_events().len(), 1);
+
+        let valid = multi_goal_request(vec![goal(10.0, 0.0), goal(20.0, 0.0)], true);
+        let batch_id = planner
+            .request_plan_batch(vec![
+                BatchPlanRequest::Single(single),
+                BatchPlanRequest::MultiGoal(valid),
+            ])
+            .unwrap();
+        // One single plan, one combined plan and its two legs
+        assert_eq!(planner.active_plans.len(), 4);
+        match planner.uncommitted_events().last() {
+            Some(PathPlanningEvent::PlanBatchRequested {
+                batch_id: event_batch_id,
+                plan_ids,
+                ..
+            }) => {
+                assert_eq!(*event_batch_id, batch_id);
+                assert_eq!(plan_ids.len(), 2);
+            }
+            _ => panic!("Expected PlanBatchRequested event"),
+        }
+    }
+
+    #[test]
+    fn test_archive_keeps_legs_of_live_multi_goal_plan() {
+        let mut planner = PathPlanner::new("planner-1".to_string(), PlanningAlgorithm::AStar);
+        let request = multi_goal_request(vec![goal(10.0, 0.0), goal(10.0, 10.0)], false);
+        planner.request_multi_goal_plan(request).unwrap();
+        let legs = planner.active_plans[0].leg_plan_ids.clone();
+        planner
+            .handle_plan_completed("worker-1".to_string(), legs[0].clone(), vec![])
+            .unwrap();
+
+        let archived = planner
+            .archive_finished_plans(
+                chrono::Duration::zero(),
+                Utc::now() + chrono::Duration::days(1),
+            )
+            .unwrap();
+        assert_eq!(archived, 0);
+        assert_eq!(planner.active_plans.len(), 3);
+    }
+}
+
+#[cfg(test)]
+mod workspace_change_tests {
+    use super::*;
+
+    fn box_obstacle(id: &str, x: f64, y: f64) -> Obstacle {
+        Obstacle {
+            id: id.to_string(),
+            shape: ObstacleShape::Rectangle {
+                width: 2.0,
+                height: 2.0,
+            },
+            position: Position2D { x, y },
+        }
+    }
+
+    /// Planner with one completed plan along the x axis from (0, 0) to (20, 0).
+    fn planner_with_route() -> (PathPlanner, String) {
+        let mut planner = PathPlanner::new("planner-1".to_string(), PlanningAlgorithm::AStar);
+        let request = PathPlanRequest {
+            request_id: "req-123".to_string(),
+            agent_id: "agent-1".to_string(),
+            start_position: Position2D { x: 0.0, y: 0.0 },
+            destination_position: Position2D { x: 20.0, y: 0.0 },
+            start_orientation: Orientation2D { angle: 0.0 },
+            destination_orientation: Orientation2D { angle: 0.0 },
+            created_at: Utc::now(),
+        };
+        planner.request_path_plan(request).unwrap();
+        let plan_id = planner.active_plans[0].id.clone();
+        planner
+            .handle_plan_completed(
+                "worker-1".to_string(),
+                plan_id.clone(),
+                vec![
+                    Position2D { x: 10.0, y: 0.0 },
+                    Position2D { x: 20.0, y: 0.0 },
+                ],
+            )
+            .unwrap();
+        planner.mark_events_as_committed();
+        (planner, plan_id)
+    }
+
+    #[test]
+    fn test_obstacle_on_route_invalidates_and_requeues_plan() {
+        let (mut planner, plan_id) = planner_with_route();
+
+        planner
+            .add_obstacle(box_obstacle("crate-1", 5.0, 0.0))
+            .unwrap();
+
+        assert_eq!(planner.workspace.obstacles.len(), 1);
+        let plan = &planner.active_plans[0];
+        assert_eq!(plan.status, PlanStatus::Planning);
+        assert!(plan.waypoints.is_empty());
+        assert!(plan.finished_at.is_none());
+        match &planner.uncommitted_events()[1] {
+            PathPlanningEvent::PlanInvalidated {
+                plan_id: event_plan_id,
+                agent_id,
+                obstacle_id,
+                ..
+            } => {
+                assert_eq!(*event_plan_id, plan_id);
+                assert_eq!(agent_id, "agent-1");
+                assert_eq!(obstacle_id, "crate-1");
+            }
+            _ => panic!("Expected PlanInvalidated event"),
+        }
+    }
+
+    #[test]
+    fn test_obstacle_off_route_keeps_plan() {
+        let (mut planner, _) = planner_with_route();
+
+        planner
+            .add_obstacle(box_obstacle("crate-1", 5.0, 10.0))
+            .unwrap();
+        assert_eq!(planner.active_plans[0].status, PlanStatus::Complete);
+        assert_eq!(planner.uncommitted_events().len(), 1);
+
+        // Moving it onto the route invalidates the plan; removing it never does
+        planner
+            .move_obstacle("crate-1".to_string(), Position2D { x: 15.0, y: 0.5 })
+            .unwrap();
+        assert_eq!(planner.active_plans[0].status, PlanStatus::Planning);
+        assert!(matches!(
+            planner.uncommitted_events().last(),
+            Some(PathPlanningEvent::PlanInvalidated { .. })
+        ));
+
+        planner.remove_obstacle("crate-1".to_string()).unwrap();
+        assert!(planner.workspace.obstacles.is_empty());
+        assert!(matches!(
+            planner.uncommitted_events().last(),
+            Some(PathPlanningEvent::ObstacleRemoved { .. })
+        ));
+    }
+
+    #[test]
+    fn test_moved_polygon_invalidates_plan_and_frees_old_spot() {
+        let (mut planner, plan_id) = planner_with_route();
+        let triangle = Obstacle {
+            id: "triangle".to_string(),
+            shape: ObstacleShape::Polygon {
+                vertices: vec![
+                    Position2D { x: -1.0, y: -1.0 },
+                    Position2D { x: 1.0, y: -1.0 },
+                    Position2D { x: 0.0, y: 1.0 },
+                ],
+            },
+            position: Position2D { x: 5.0, y: 10.0 },
+        };
+        planner.add_obstacle(triangle).unwrap();
+        assert_eq!(planner.active_plans[0].status, PlanStatus::Complete);
+        assert!(!planner.workspace.is_free(&Position2D { x: 5.0, y: 10.0 }));
+
+        // Its vertices move with it, across the route
+        planner
+            .move_obstacle("triangle".to_string(), Position2D { x: 15.0, y: 0.0 })
+            .unwrap();
+        assert_eq!(planner.active_plans[0].status, PlanStatus::Planning);
+        match planner.uncommitted_events().last() {
+            Some(PathPlanningEvent::PlanInvalidated {
+                plan_id: event_plan_id,
+                obstacle_id,
+                ..
+            }) => {
+                assert_eq!(*event_plan_id, plan_id);
+                assert_eq!(obstacle_id, "triangle");
+            }
+            other => panic!("Expected PlanInvalidated, got {:?}", other),
+        }
+        assert!(planner.workspace.is_free(&Position2D { x: 5.0, y: 10.0 }));
+        assert!(!planner.workspace.is_free(&Position2D { x: 15.0, y: 0.0 }));
+    }
+
+    #[test]
+    fn test_obstacle_commands_validate_ids() {
+        let (mut planner, _) = planner_with_route();
+        planner
+            .add_obstacle(box_obstacle("crate-1", 5.0, 10.0))
+            .unwrap();
+
+        let result = planner.add_obstacle(box_obstacle("crate-1", 0.0, 10.0));
+        assert!(matches!(result, Err(DomainError::InvalidCommand { .. })));
+        let result = planner.move_obstacle("missing".to_string(), Position2D { x: 0.0, y: 0.0 });
+        assert!(matches!(result, Err(DomainError::InvalidCommand { .. })));
+        let result = planner.remove_obstacle("missing".to_string());
+        assert!(matches!(result, Err(DomainError::InvalidCommand { .. })));
+    }
+
+    #[test]
+    fn test_obstacle_segment_intersection() {
+        let a = Position2D { x: 0.0, y: 0.0 };
+        let b = Position2D { x: 10.0, y: 0.0 };
+
+        let circle = Obstacle {
+            id: "circle".to_string(),
+            shape: ObstacleShape::Circle { radius: 1.0 },
+            position: Position2D { x: 5.0, y: 0.5 },
+        };
+        assert!(circle.intersects_segment(&a, &b));
+        let far_circle = Obstacle {
+            position: Position2D { x: 5.0, y: 2.0 },
+            ..circle.clone()
+        };
+        assert!(!far_circle.intersects_segment(&a, &b));
+
+        assert!(box_obstacle("box", 10.5, 0.0).intersects_segment(&a, &b));
+        assert!(!box_obstacle("box", 12.0, 0.0).intersects_segment(&a, &b));
+
+        let triangle = Obstacle {
+            id: "triangle".to_string(),
+            shape: ObstacleShape::Polygon {
+                vertices: vec![
+                    Position2D { x: 4.0, y: -1.0 },
+                    Position2D { x: 6.0, y: -1.0 },
+                    Position2D { x: 5.0, y: 1.0 },
+                ],
+            },
+            position: Position2D { x: 0.0, y: 0.0 },
+        };
+        assert!(triangle.intersects_segment(&a, &b));
+        assert!(!triangle.intersects_segment(
+            &Position2D { x: 0.0, y: 3.0 },
+            &Position2D { x: 10.0, y: 3.0 }
+        ));
+    }
+}
+
+#[cfg(test)]
+mod multi_agent_tests {
+    use super::*;
+
+    fn small_planner(obstacles: Vec<Obstacle>) -> PathPlanner {
+        let mut planner = PathPlanner::new("planner-1".to_string(), PlanningAlgorithm::AStar);
+        planner.workspace = Workspace {
+            bounds: WorkspaceBounds {
+                min_x: 0.0,
+                max_x: 10.0,
+                min_y: 0.0,
+                max_y: 10.0,
+            },
+            obstacles,
+        };
+        planner
+    }
+
+    fn request(agent_id: &str, from: (f64, f64), to: (f64, f64)) -> PathPlanRequest {
+        PathPlanRequest {
+            request_id: format!("req-{}", agent_id),
+            agent_id: agent_id.to_string(),
+            start_position: Position2D {
+                x: from.0,
+                y: from.1,
+            },
+            destination_position: Position2D { x: to.0, y: to.1 },
+            start_orientation: Orientation2D { angle: 0.0 },
+            destination_orientation: Orientation2D { angle: 0.0 },
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_head_on_agents_get_conflict_free_paths() {
+        let planner = small_planner(vec![]);
+        let requests = vec![
+            request("agent-1", (0.0, 5.0), (10.0, 5.0)),
+            request("agent-2", (10.0, 5.0), (0.0, 5.0)),
+        ];
+
+        let paths = planner
+            .plan_multi_agent(&requests, &MultiAgentPlanConfig::default())
+            .unwrap();
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0].agent_id, "agent-1");
+        assert_eq!(
+            paths[0].waypoints.last().unwrap().position,
+            Position2D { x: 10.0, y: 5.0 }
+        );
+        assert_eq!(
+            paths[1].waypoints.last().unwrap().position,
+            Position2D { x: 0.0, y: 5.0 }
+        );
+        assert!(find_conflicts(&paths).is_empty());
+    }
+
+    #[test]
+    fn test_crossing_agents_and_obstacles() {
+        let wall = Obstacle {
+            id: "wall".to_string(),
+            shape: ObstacleShape::Rectangle {
+                width: 1.0,
+                height: 6.0,
+            },
+            position: Position2D { x: 5.0, y: 3.0 },
+        };
+        let planner = small_planner(vec![wall.clone()]);
+        let requests = vec![
+            request("agent-1", (0.0, 0.0), (10.0, 0.0)),
+            request("agent-2", (10.0, 2.0), (0.0, 2.0)),
+            request("agent-3", (5.0, 10.0), (5.0, 7.0)),
+        ];
+
+        let paths = planner
+            .plan_multi_agent(&requests, &MultiAgentPlanConfig::default())
+            .unwrap();
+        assert!(find_conflicts(&paths).is_empty());
+        for path in &paths {
+            assert!(path.waypoints.iter().all(|w| !wall.covers(&w.position)));
+            // Consecutive steps move at most one cell
+            for step in path.waypoints.windows(2) {
+                let dx = (step[1].position.x - step[0].position.x).abs();
+                let dy = (step[1].position.y - step[0].position.y).abs();
+                assert!(dx + dy <= 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_conflicts_detects_vertex_and_edge_conflicts() {
+        let timed = |agent_id: &str, cells: &[(f64, f64)]| TimedPath {
+            request_id: format!("req-{}", agent_id),
+            agent_id: agent_id.to_string(),
+            waypoints: cells
+                .iter()
+                .enumerate()
+                .map(|(t, &(x, y))| TimedWaypoint {
+                    position: Position2D { x, y },
+                    time_step: t as u32,
+                })
+                .collect(),
+        };
+        let paths = vec![
+            timed("agent-1", &[(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)]),
+            timed("agent-2", &[(2.0, 0.0), (1.0, 0.0), (0.0, 0.0)]),
+            timed("agent-3", &[(5.0, 5.0), (4.0, 5.0)]),
+            timed("agent-4", &[(4.0, 5.0), (5.0, 5.0)]),
+        ];
+
+        let conflicts = find_conflicts(&paths);
+        assert!(conflicts.contains(&PathConflict::Vertex {
+            agents: ("agent-1".to_string(), "agent-2".to_string()),
+            position: Position2D { x: 1.0, y: 0.0 },
+            time_step: 1,
+        }));
+        assert!(conflicts.contains(&PathConflict::Edge {
+            agents: ("agent-3".to_string(), "agent-4".to_string()),
+            time_step: 0,
+        }));
+    }
+
+    #[test]
+    fn test_multi_agent_rejects_shared_or_blocked_goals() {
+        let pillar = Obstacle {
+            id: "pillar".to_string(),
+            shape: ObstacleShape::Circle { radius: 1.0 },
+            position: Position2D { x: 8.0, y: 8.0 },
+        };
+        let planner = small_planner(vec![pillar]);
+
+        let shared = vec![
+            request("agent-1", (0.0, 0.0), (5.0, 5.0)),
+            request("agent-2", (1.0, 0.0), (5.0, 5.0)),
+        ];
+        let result = planner.plan_multi_agent(&shared, &MultiAgentPlanConfig::default());
+        assert!(matches!(result, Err(DomainError::InvalidCommand { .. })));
+
+        let blocked = vec![request("agent-1", (0.0, 0.0), (8.0, 8.0))];
+        match planner.plan_multi_agent(&blocked, &MultiAgentPlanConfig::default()) {
+            Err(DomainError::InvalidCommand { reason }) => assert!(reason.contains("blocked")),
+            _ => panic!("Expected InvalidCommand error"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod routing_tests {
+    use super::*;
+
+    fn goal(x: f64, y: f64) -> PlanGoal {
+        PlanGoal {
+            label: None,
+            position: Position2D { x, y },
+            orientation: Orientation2D { angle: 0.0 },
+        }
+    }
+
+    #[test]
+    fn test_optimize_goal_order_shortens_route() {
+        let start = Position2D { x: 0.0, y: 0.0 };
+        // Given order zig-zags along the x axis
+        let goals = vec![
+            goal(30.0, 0.0),
+            goal(10.0, 0.0),
+            goal(40.0, 0.0),
+            goal(20.0, 0.0),
+        ];
+        let given: Vec<usize> = (0..goals.len()).collect();
+
+        let order = optimize_goal_order(&start, &goals);
+        assert_eq!(order, vec![1, 3, 0, 2]);
+        assert!(route_length(&start, &goals, &order) < route_length(&start, &goals, &given));
+    }
+
+    #[test]
+    fn test_optimize_goal_order_visits_every_goal_once() {
+        let start = Position2D { x: 5.0, y: 5.0 };
+        let goals: Vec<PlanGoal> = (0..8)
+            .map(|i| goal((i * 37 % 11) as f64, (i * 53 % 7) as f64))
+            .collect();
+
+        let mut order = optimize_goal_order(&start, &goals);
+        order.sort();
+        assert_eq!(order, (0..goals.len()).collect::<Vec<_>>());
+        assert!(optimize_goal_order(&start, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_combined_waypoints_keeps_joints_once() {
+        let first = vec![Position2D { x: 0.0, y: 0.0 }, Position2D { x: 1.0, y: 0.0 }];
+        let second = vec![Position2D { x: 1.0, y: 0.0 }, Position2D { x: 1.0, y: 1.0 }];
+
+        let waypoints = combined_waypoints([first.as_slice(), second.as_slice()]);
+        assert_eq!(waypoints.len(), 3);
+        assert_eq!(waypoints[2], Position2D { x: 1.0, y: 1.0 });
+    }
+}
+
+#[cfg(test)]
+mod plan_cache_tests {
+    use super::*;
+    use async_trait::async_trait;
+    use gryphon_app::adapters::inbound::plan_cache::InMemoryPlanCache;
+    use gryphon_app::common::DomainResult;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    fn key(map_name: &str, start: (f64, f64), goal: (f64, f64), heading: f64) -> PlanCacheKey {
+        PlanCacheKey::new(
+            map_name,
+            &PlanCacheKey::graph_version_of(b"graph"),
+            PlanningAlgorithm::AStar,
+            &Position2D {
+                x: start.0,
+                y: start.1,
+            },
+            &Position2D {
+                x: goal.0,
+                y: goal.1,
+            },
+            &Orientation2D { angle: heading },
+            &Orientation2D { angle: 0.0 },
+        )
+    }
+
+    #[derive(Default)]
+    struct MemoryGraphStore {
+        graphs: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    #[async_trait]
+    impl GraphStoreAsync for MemoryGraphStore {
+        async fn save_graph_bytes(&self, name: &str, bytes: &[u8]) -> DomainResult<()> {
+            self.graphs
+                .lock()
+                .unwrap()
+                .insert(name.to_string(), bytes.to_vec());
+            Ok(())
+        }
+
+        async fn load_graph_bytes(&self, name: &str) -> DomainResult<Vec<u8>> {
+            self.graphs
+                .lock()
+                .unwrap()
+                .get(name)
+                .cloned()
+                .ok_or_else(|| DomainError::InfrastructureError(format!("No graph {}", name)))
+        }
+
+        async fn delete_graph(&self, name: &str) -> DomainResult<()> {
+            self.graphs.lock().unwrap().remove(name);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_key_snaps_positions_and_headings() {
+        let a = key("warehouse", (1.01, 2.02), (5.0, 5.0), 0.01);
+        let b = key("warehouse", (0.99, 1.98), (5.04, 4.96), -0.01);
+        assert_eq!(a, b);
+        assert_eq!(a.digest(), b.digest());
+
+        // A full turn lands in the same heading bucket
+        let turned = key("warehouse", (1.0, 2.0), (5.0, 5.0), std::f64::consts::TAU);
+        assert_eq!(turned.start_heading, 0);
+        assert_eq!(turned.digest(), a.digest());
+    }
+
+    #[test]
+    fn test_key_separates_maps_versions_and_goals() {
+        let base = key("warehouse", (0.0, 0.0), (5.0, 5.0), 0.0);
+        assert_ne!(
+            base.digest(),
+            key("dock", (0.0, 0.0), (5.0, 5.0), 0.0).digest()
+        );
+        assert_ne!(
+            base.digest(),
+            key("warehouse", (0.0, 0.0), (5.5, 5.0), 0.0).digest()
+        );
+
+        let mut newer = base.clone();
+        newer.graph_version = PlanCacheKey::graph_version_of(b"graph v2");
+        assert_ne!(base.digest(), newer.digest());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_cache_roundtrip_and_invalidation() {
+        let cache = InMemoryPlanCache::new();
+        let waypoints = vec![Position2D { x: 0.0, y: 0.0 }, Position2D { x: 5.0, y: 5.0 }];
+        let warehouse = key("warehouse", (0.0, 0.0), (5.0, 5.0), 0.0);
+        let dock = key("dock", (0.0, 0.0), (5.0, 5.0), 0.0);
+
+        assert!(cache.get(&warehouse).await.unwrap().is_none());
+        cache.put(&warehouse, &waypoints).await.unwrap();
+        cache.put(&dock, &waypoints).await.unwrap();
+        assert_eq!(cache.get(&warehouse).await.unwrap(), Some(waypoints));
+
+        assert_eq!(cache.invalidate_map("warehouse").await.unwrap(), 1);
+        assert!(cache.get(&warehouse).await.unwrap().is_none());
+        assert!(cache.get(&dock).await.unwrap().is_some());
+        assert_eq!(cache.len().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_graph_changes_invalidate_cached_plans() {
+        let cache = Arc::new(InMemoryPlanCache::new());
+        let store =
+            CacheInvalidatingGraphStore::new(Arc::new(MemoryGraphStore::default()), cache.clone());
+        let waypoints = vec![Position2D { x: 0.0, y: 0.0 }];
+        let warehouse = key("warehouse", (0.0, 0.0), (5.0, 5.0), 0.0);
+
+        cache.put(&warehouse, &waypoints).await.unwrap();
+        store
+            .save_graph_bytes("warehouse", b"graph v2")
+            .await
+            .unwrap();
+        assert!(cache.is_empty().await);
+        assert_eq!(
+            store.load_graph_bytes("warehouse").await.unwrap(),
+            b"graph v2".to_vec()
+        );
+
+        cache.put(&warehouse, &waypoints).await.unwrap();
+        store.delete_graph("warehouse").await.unwrap();
+        assert!(cache.is_empty().await);
+    }
 }
 
 #[cfg(test)]
@@ -723,6 +1575,7 @@ mod event_tests {
                     Position2D { x: 5.0, y: 5.0 },
                     Position2D { x: 10.0, y: 10.0 },
                 ],
+                cache_hit: false,
                 timestamp: Utc::now(),
             },
         ];
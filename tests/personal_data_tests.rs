@@ -0,0 +1,260 @@
+use chrono::Utc;
+use gryphon_app::adapters::inbound::{
+    FileEventStore, InMemoryEventStore, InMemoryKeyStore, InProcessMessageBus,
+};
+use gryphon_app::adapters::outbound::init_noop_logger;
+use gryphon_app::application::{
+    PathPlanClient, PathPlanningPlannerService, PathPlanningWorkerService,
+};
+use gryphon_app::common::*;
+use gryphon_app::config::Config;
+use gryphon_app::domains::gui::GUIEvent;
+use gryphon_app::domains::path_planning::PathPlanRequest;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use uuid::Uuid;
+
+const APP: &str = "app-1";
+
+fn session_started(user_id: &str) -> EventEnvelope {
+    let event = GUIEvent::UserSessionStarted {
+        app_id: APP.to_string(),
+        session_id: Uuid::new_v4(),
+        user_id: user_id.to_string(),
+        timestamp: Utc::now(),
+    };
+    let metadata = EventMetadata {
+        correlation_id: None,
+        causation_id: None,
+        user_id: Some(user_id.to_string()),
+        source: "test".to_string(),
+        reply_to: None,
+        tenant: None,
+    };
+    EventEnvelope::new(&event, "GUIApplication", metadata).unwrap()
+}
+
+fn session_user(envelope: &EventEnvelope) -> String {
+    match serde_json::from_value(envelope.event_data.clone()).unwrap() {
+        GUIEvent::UserSessionStarted { user_id, .. } => user_id,
+        other => panic!("unexpected event {:?}", other),
+    }
+}
+
+/// A store that encrypts over `raw`, with the sessions of alice and bob
+async fn stores_with_sessions() -> (
+    Arc<InMemoryEventStore>,
+    Arc<InMemoryKeyStore>,
+    PersonalDataEventStore,
+) {
+    let raw = Arc::new(InMemoryEventStore::new());
+    let keys = Arc::new(InMemoryKeyStore::new());
+    let store = PersonalDataEventStore::new(
+        raw.clone(),
+        PersonalDataCipher::new(keys.clone(), PersonalDataPolicy::default()),
+    );
+    store
+        .append_events(
+            APP,
+            0,
+            vec![session_started("alice"), session_started("bob")],
+        )
+        .await
+        .unwrap();
+    (raw, keys, store)
+}
+
+#[tokio::test]
+async fn test_personal_fields_are_stored_encrypted() {
+    let (raw, _keys, store) = stores_with_sessions().await;
+
+    for event in raw.load_events(APP, 0).await.unwrap() {
+        let stored = serde_json::to_string(&event).unwrap();
+        assert!(
+            !stored.contains("\"alice\"") && !stored.contains("\"bob\""),
+            "{}",
+            stored
+        );
+        assert!(session_user(&event).starts_with(ENCRYPTED_PREFIX));
+        assert!(event
+            .metadata
+            .user_id
+            .unwrap()
+            .starts_with(ENCRYPTED_PREFIX));
+    }
+
+    let loaded = store.load_events(APP, 0).await.unwrap();
+    let users: Vec<_> = loaded.iter().map(session_user).collect();
+    assert_eq!(users, vec!["alice", "bob"]);
+    assert_eq!(loaded[0].metadata.user_id.as_deref(), Some("alice"));
+}
+
+#[tokio::test]
+async fn test_forgotten_subject_reads_as_placeholder() {
+    let (_raw, keys, store) = stores_with_sessions().await;
+
+    assert!(keys.forget_subject("alice").await.unwrap());
+    assert!(!keys.forget_subject("alice").await.unwrap());
+
+    // The stream still loads and deserializes; only alice's data is gone
+    let loaded = store.load_all_events(None).await.unwrap();
+    let users: Vec<_> = loaded.iter().map(session_user).collect();
+    assert_eq!(users, vec![ERASED_PLACEHOLDER, "bob"]);
+    assert_eq!(
+        loaded[0].metadata.user_id.as_deref(),
+        Some(ERASED_PLACEHOLDER)
+    );
+    assert_eq!(loaded[1].metadata.user_id.as_deref(), Some("bob"));
+}
+
+#[tokio::test]
+async fn test_other_fields_and_events_are_left_alone() {
+    let raw = Arc::new(InMemoryEventStore::new());
+    let store = PersonalDataEventStore::new(
+        raw.clone(),
+        PersonalDataCipher::new(
+            Arc::new(InMemoryKeyStore::new()),
+            PersonalDataPolicy {
+                fields: Vec::new(),
+                metadata_user_id: false,
+            },
+        ),
+    );
+    let event = session_started("alice");
+    store
+        .append_events(APP, 0, vec![event.clone()])
+        .await
+        .unwrap();
+
+    let stored = &raw.load_events(APP, 0).await.unwrap()[0];
+    assert_eq!(stored.event_data, event.event_data);
+    assert_eq!(stored.metadata.user_id.as_deref(), Some("alice"));
+}
+
+#[tokio::test]
+async fn test_esrs_payloads_are_encrypted() {
+    let keys = Arc::new(InMemoryKeyStore::new());
+    let cipher = PersonalDataCipher::new(keys.clone(), PersonalDataPolicy::default());
+    let event = GUIEvent::UserSessionStarted {
+        app_id: APP.to_string(),
+        session_id: Uuid::new_v4(),
+        user_id: "alice".to_string(),
+        timestamp: Utc::now(),
+    };
+
+    let encrypted = cipher.encrypt_event(&event).await.unwrap();
+    let GUIEvent::UserSessionStarted { user_id, .. } = &encrypted else {
+        panic!("unexpected event {:?}", encrypted);
+    };
+    assert!(user_id.starts_with(ENCRYPTED_PREFIX), "{}", user_id);
+
+    keys.forget_subject("alice").await.unwrap();
+    let mut payload = serde_json::to_value(&encrypted).unwrap();
+    cipher
+        .decrypt_payload(event.event_type(), &mut payload)
+        .await
+        .unwrap();
+    assert_eq!(payload["UserSessionStarted"]["user_id"], ERASED_PLACEHOLDER);
+}
+
+#[tokio::test]
+async fn test_planning_over_an_encrypting_bus_can_be_forgotten() {
+    let logger = init_noop_logger();
+    let topics = Config::default().kafka.topics;
+    let keys = Arc::new(InMemoryKeyStore::new());
+    let cipher = PersonalDataCipher::new(keys.clone(), PersonalDataPolicy::default());
+
+    // What Kafka would store: the envelopes as they reach the inner bus
+    let raw: Arc<dyn MessageBus> = Arc::new(InProcessMessageBus::new());
+    let mut audit = raw
+        .subscribe(&topics.path_planning_events, "audit")
+        .await
+        .unwrap();
+    let bus: Arc<dyn MessageBus> = Arc::new(PersonalDataBus::new(raw, cipher.clone()));
+
+    let mut planner = PathPlanningPlannerService::new(
+        "main-path-planner".to_string(),
+        bus.clone(),
+        &topics,
+        logger.clone(),
+    );
+    let worker = PathPlanningWorkerService::new(
+        "worker-1".to_string(),
+        "main-path-planner".to_string(),
+        bus.clone(),
+        &topics,
+        logger.clone(),
+    );
+    let planner_task = tokio::spawn(async move { planner.run().await });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let worker_task = tokio::spawn(async move { worker.run().await });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    // The services read decrypted envelopes, so planning still works
+    let client = PathPlanClient::new(logger)
+        .await
+        .unwrap()
+        .with_message_bus(bus)
+        .with_personal_data(cipher.clone())
+        .with_request_timeout(Duration::from_secs(5));
+    let scenario = client.scenarios[0].clone();
+    client
+        .plan(PathPlanRequest {
+            request_id: "req-1".to_string(),
+            agent_id: scenario.agent_id.clone(),
+            start_position: scenario.start_position.clone(),
+            destination_position: scenario.destination_position.clone(),
+            start_orientation: scenario.start_orientation.clone(),
+            destination_orientation: scenario.destination_orientation.clone(),
+            created_at: Utc::now(),
+        })
+        .await
+        .expect("plan should complete");
+    planner_task.abort();
+    worker_task.abort();
+
+    let mut published = Vec::new();
+    while let Ok(Some(message)) =
+        tokio::time::timeout(Duration::from_millis(100), audit.next()).await
+    {
+        published.push(message.unwrap().envelope);
+    }
+    let users: Vec<_> = published
+        .iter()
+        .filter_map(|envelope| envelope.metadata.user_id.clone())
+        .collect();
+    assert!(users.len() >= 2, "{:?}", users);
+    for user in &users {
+        assert!(user.starts_with(ENCRYPTED_PREFIX), "{}", user);
+    }
+
+    // Stored as published, then read back once both subjects are forgotten
+    let dir = TempDir::new().unwrap();
+    let file_store = Arc::new(FileEventStore::new(dir.path()));
+    let mut versions: HashMap<String, u64> = HashMap::new();
+    for envelope in published {
+        let version = versions.entry(envelope.aggregate_id.clone()).or_default();
+        file_store
+            .append_events(&envelope.aggregate_id, *version, vec![envelope.clone()])
+            .await
+            .unwrap();
+        *version += 1;
+    }
+    assert!(keys.forget_subject(&scenario.agent_id).await.unwrap());
+    assert!(keys.forget_subject("worker-1").await.unwrap());
+
+    let store = PersonalDataEventStore::new(file_store, cipher);
+    let loaded = store.load_all_events(None).await.unwrap();
+    let users: Vec<_> = loaded
+        .iter()
+        .filter_map(|envelope| envelope.metadata.user_id.as_deref())
+        .collect();
+    assert!(users.len() >= 2, "{:?}", users);
+    assert!(
+        users.iter().all(|user| *user == ERASED_PLACEHOLDER),
+        "{:?}",
+        users
+    );
+}
@@ -98,3 +98,138 @@ async fn test_postgres_graph_store_roundtrip() -> Result<(), Box<dyn std::error:
 
     Ok(())
 }
+
+#[cfg(feature = "pg_integration")]
+#[tokio::test]
+async fn test_postgres_plan_cache_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    use gryphon_app::adapters::outbound::postgres_plan_cache::PostgresPlanCache;
+    use gryphon_app::domains::path_planning::{
+        Orientation2D, PlanCache, PlanCacheKey, PlanningAlgorithm, Position2D,
+    };
+
+    let mut dp_cfg = DeadPoolConfig::new();
+    dp_cfg.host = Some(std::env::var("PG_TEST_HOST").unwrap_or_else(|_| "127.0.0.1".to_string()));
+    dp_cfg.port = std::env::var("PG_TEST_PORT")
+        .ok()
+        .and_then(|s| s.parse::<u16>().ok())
+        .or(Some(5432));
+    dp_cfg.user = Some(std::env::var("PG_TEST_USER").unwrap_or_else(|_| "postgres".to_string()));
+    dp_cfg.password =
+        Some(std::env::var("PG_TEST_PASSWORD").unwrap_or_else(|_| "postgres".to_string()));
+    dp_cfg.dbname = Some(std::env::var("PG_TEST_DB").unwrap_or_else(|_| "postgres".to_string()));
+    let pool = dp_cfg
+        .create_pool(None, NoTls)
+        .expect("failed to create test pg pool");
+    let cache = PostgresPlanCache::new(pool)
+        .await
+        .expect("failed to create the plan cache table");
+
+    let key = PlanCacheKey::new(
+        "pg_cache_test_map",
+        &PlanCacheKey::graph_version_of(b"graph"),
+        PlanningAlgorithm::AStar,
+        &Position2D { x: 0.0, y: 0.0 },
+        &Position2D { x: 5.0, y: 5.0 },
+        &Orientation2D { angle: 0.0 },
+        &Orientation2D { angle: 0.0 },
+    );
+    let waypoints = vec![Position2D { x: 0.0, y: 0.0 }, Position2D { x: 5.0, y: 5.0 }];
+
+    cache
+        .put(&key, &waypoints)
+        .await
+        .map_err(|e| format!("pg put err: {:?}", e))?;
+    let loaded = cache
+        .get(&key)
+        .await
+        .map_err(|e| format!("pg get err: {:?}", e))?;
+    assert_eq!(loaded, Some(waypoints));
+
+    let removed = cache
+        .invalidate_map("pg_cache_test_map")
+        .await
+        .map_err(|e| format!("pg invalidate err: {:?}", e))?;
+    assert_eq!(removed, 1);
+    assert!(cache
+        .get(&key)
+        .await
+        .map_err(|e| format!("{:?}", e))?
+        .is_none());
+
+    Ok(())
+}
+
+#[cfg(feature = "pg_integration")]
+#[tokio::test]
+async fn test_repository_load_starts_from_postgres_snapshot(
+) -> Result<(), Box<dyn std::error::Error>> {
+    use gryphon_app::adapters::inbound::InMemoryEventStore;
+    use gryphon_app::adapters::outbound::PostgresSnapshotStore;
+    use gryphon_app::common::{AggregateRepository, EventEnvelope, EventMetadata, EventStore};
+    use gryphon_app::config::PostgresConfig;
+    use gryphon_app::domains::path_planning::{PathPlanner, PathPlanningEvent, PlanningAlgorithm};
+    use std::sync::Arc;
+
+    let config = PostgresConfig {
+        host: std::env::var("PG_TEST_HOST").unwrap_or_else(|_| "127.0.0.1".to_string()),
+        port: std::env::var("PG_TEST_PORT")
+            .ok()
+            .and_then(|s| s.parse::<u16>().ok())
+            .unwrap_or(5432),
+        database: std::env::var("PG_TEST_DB").unwrap_or_else(|_| "postgres".to_string()),
+        username: std::env::var("PG_TEST_USER").unwrap_or_else(|_| "postgres".to_string()),
+        password: std::env::var("PG_TEST_PASSWORD").unwrap_or_else(|_| "postgres".to_string()),
+        max_connections: 2,
+        schema: None,
+    };
+    let snapshots = Arc::new(PostgresSnapshotStore::new(config).await?);
+
+    let planner_id = format!("pg-snapshot-test-{}", uuid::Uuid::new_v4());
+    let metadata = EventMetadata {
+        correlation_id: None,
+        causation_id: None,
+        user_id: None,
+        source: "test".to_string(),
+        reply_to: None,
+        tenant: None,
+    };
+    let events = [
+        PathPlanningEvent::PlannerCreated {
+            planner_id: planner_id.clone(),
+            algorithm: PlanningAlgorithm::AStar,
+            timestamp: chrono::Utc::now(),
+        },
+        PathPlanningEvent::WorkerRegistered {
+            planner_id: planner_id.clone(),
+            worker_id: "worker-1".to_string(),
+            capabilities: vec![PlanningAlgorithm::AStar],
+            timestamp: chrono::Utc::now(),
+        },
+    ];
+    let store = Arc::new(InMemoryEventStore::new());
+    store
+        .append_events(
+            &planner_id,
+            0,
+            events
+                .iter()
+                .map(|event| EventEnvelope::new(event, "PathPlanner", metadata.clone()).unwrap())
+                .collect(),
+        )
+        .await?;
+
+    let repository = AggregateRepository::new(store, |id| {
+        PathPlanner::new(id.to_string(), PlanningAlgorithm::AStar)
+    })
+    .with_snapshots(snapshots);
+    repository
+        .save_snapshot(&planner_id, "PathPlanner")
+        .await?
+        .ok_or("no snapshot saved")?;
+
+    let state = repository.load(&planner_id).await?.ok_or("no state")?;
+    assert_eq!(state.version, 2);
+    assert_eq!(state.snapshot_version, Some(2));
+
+    Ok(())
+}
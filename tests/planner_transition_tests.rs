@@ -0,0 +1,615 @@
+#![cfg(feature = "esrs_migration")]
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use esrs::Aggregate;
+use gryphon_app::common::AggregateRoot;
+use gryphon_app::domains::path_planning::{
+    MultiGoalPlanRequest, Obstacle, ObstacleShape, Orientation2D, PathPlan, PathPlanRequest,
+    PathPlanner, PathPlanningEvent, PlanGoal, PlanStatus, PlanningAlgorithm, Position2D,
+};
+use gryphon_app::esrs::path_planning::{
+    PathPlanner as EsrsPathPlanner, PathPlannerCommand, PathPlannerState,
+};
+use proptest::prelude::*;
+use serde_json::Value;
+use std::collections::HashMap;
+
+const PLANNER: &str = "planner-1";
+
+// Small id pools so generated events keep running into each other
+fn plan_id() -> impl Strategy<Value = String> {
+    (0..4u8).prop_map(|i| format!("plan-{}", i))
+}
+
+fn worker_id() -> impl Strategy<Value = String> {
+    (0..3u8).prop_map(|i| format!("worker-{}", i))
+}
+
+fn obstacle_id() -> impl Strategy<Value = String> {
+    (0..2u8).prop_map(|i| format!("obstacle-{}", i))
+}
+
+fn position() -> impl Strategy<Value = Position2D> {
+    (-10i8..10, -10i8..10).prop_map(|(x, y)| Position2D {
+        x: x as f64,
+        y: y as f64,
+    })
+}
+
+fn orientation() -> impl Strategy<Value = Orientation2D> {
+    (0..4u8).prop_map(|q| Orientation2D {
+        angle: q as f64 * std::f64::consts::FRAC_PI_2,
+    })
+}
+
+fn algorithm() -> impl Strategy<Value = PlanningAlgorithm> {
+    prop_oneof![
+        Just(PlanningAlgorithm::AStar),
+        Just(PlanningAlgorithm::RRT),
+        Just(PlanningAlgorithm::PRM),
+    ]
+}
+
+fn at(seconds: u16) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + Duration::seconds(seconds as i64)
+}
+
+fn plan(id: String) -> PathPlan {
+    PathPlan {
+        id,
+        agent_id: "agent-1".to_string(),
+        start: Position2D { x: 0.0, y: 0.0 },
+        goal: Position2D { x: 1.0, y: 1.0 },
+        start_orientation: Orientation2D { angle: 0.0 },
+        destination_orientation: Orientation2D { angle: 0.0 },
+        waypoints: Vec::new(),
+        status: PlanStatus::Complete,
+        created_at: at(0),
+        finished_at: Some(at(1)),
+        parent_plan_id: None,
+        leg_plan_ids: Vec::new(),
+    }
+}
+
+fn event() -> impl Strategy<Value = PathPlanningEvent> {
+    let planner_id = || PLANNER.to_string();
+    prop_oneof![
+        (algorithm(), any::<u16>()).prop_map(move |(algorithm, t)| {
+            PathPlanningEvent::PlannerCreated {
+                planner_id: planner_id(),
+                algorithm,
+                timestamp: at(t),
+            }
+        }),
+        (plan_id(), position(), position(), orientation(), any::<u16>()).prop_map(
+            move |(plan_id, start, goal, orientation, t)| PathPlanningEvent::PathPlanRequested {
+                planner_id: planner_id(),
+                request_id: format!("request-{}", plan_id),
+                plan_id,
+                agent_id: "agent-1".to_string(),
+                start_position: start,
+                destination_position: goal,
+                start_orientation: orientation.clone(),
+                destination_orientation: orientation,
+                timestamp: at(t),
+            }
+        ),
+        (worker_id(), prop::collection::vec(algorithm(), 0..3), any::<u16>()).prop_map(
+            move |(worker_id, capabilities, t)| PathPlanningEvent::WorkerRegistered {
+                planner_id: planner_id(),
+                worker_id,
+                capabilities,
+                timestamp: at(t),
+            }
+        ),
+        (worker_id(), any::<u16>()).prop_map(move |(worker_id, t)| {
+            PathPlanningEvent::WorkerReady {
+                planner_id: planner_id(),
+                worker_id,
+                timestamp: at(t),
+            }
+        }),
+        (worker_id(), plan_id(), any::<u16>()).prop_map(move |(worker_id, plan_id, t)| {
+            PathPlanningEvent::WorkerBusy {
+                planner_id: planner_id(),
+                worker_id,
+                plan_id,
+                timestamp: at(t),
+            }
+        }),
+        (worker_id(), plan_id(), any::<u16>()).prop_map(move |(worker_id, plan_id, t)| {
+            PathPlanningEvent::WorkerProcessing {
+                planner_id: planner_id(),
+                worker_id,
+                plan_id,
+                timestamp: at(t),
+            }
+        }),
+        (worker_id(), any::<u16>()).prop_map(move |(worker_id, t)| {
+            PathPlanningEvent::WorkerOffline {
+                planner_id: planner_id(),
+                worker_id,
+                reason: "heartbeat lost".to_string(),
+                timestamp: at(t),
+            }
+        }),
+        (worker_id(), any::<u16>()).prop_map(move |(worker_id, t)| {
+            PathPlanningEvent::WorkerHeartbeat {
+                planner_id: planner_id(),
+                worker_id,
+                timestamp: at(t),
+            }
+        }),
+        (plan_id(), worker_id(), 1..120u64, any::<u16>()).prop_map(
+            move |(plan_id, worker_id, timeout_seconds, t)| PathPlanningEvent::PlanAssigned {
+                planner_id: planner_id(),
+                request_id: format!("request-{}", plan_id),
+                plan_id,
+                worker_id,
+                agent_id: "agent-1".to_string(),
+                start_position: Position2D { x: 0.0, y: 0.0 },
+                destination_position: Position2D { x: 1.0, y: 1.0 },
+                start_orientation: Orientation2D { angle: 0.0 },
+                destination_orientation: Orientation2D { angle: 0.0 },
+                timeout_seconds,
+                timestamp: at(t),
+            }
+        ),
+        (plan_id(), worker_id(), any::<u16>()).prop_map(move |(plan_id, worker_id, t)| {
+            PathPlanningEvent::PlanAssignmentAccepted {
+                planner_id: planner_id(),
+                plan_id,
+                worker_id,
+                timestamp: at(t),
+            }
+        }),
+        (plan_id(), worker_id(), any::<u16>()).prop_map(move |(plan_id, worker_id, t)| {
+            PathPlanningEvent::PlanAssignmentRejected {
+                planner_id: planner_id(),
+                plan_id,
+                worker_id,
+                reason: "busy".to_string(),
+                timestamp: at(t),
+            }
+        }),
+        (plan_id(), worker_id(), any::<u16>()).prop_map(move |(plan_id, worker_id, t)| {
+            PathPlanningEvent::PlanAssignmentTimedOut {
+                planner_id: planner_id(),
+                plan_id,
+                worker_id,
+                timestamp: at(t),
+            }
+        }),
+        (
+            plan_id(),
+            prop::option::of(worker_id()),
+            prop::collection::vec(position(), 0..4),
+            any::<bool>(),
+            any::<u16>()
+        )
+            .prop_map(move |(plan_id, worker_id, waypoints, cache_hit, t)| {
+                PathPlanningEvent::PlanCompleted {
+                    planner_id: planner_id(),
+                    plan_id,
+                    worker_id,
+                    waypoints,
+                    cache_hit,
+                    timestamp: at(t),
+                }
+            }),
+        (
+            plan_id(),
+            prop::option::of(worker_id()),
+            "[a-z ]{0,12}",
+            any::<u16>()
+        )
+            .prop_map(move |(plan_id, worker_id, reason, t)| {
+                PathPlanningEvent::PlanFailed {
+                    planner_id: planner_id(),
+                    plan_id,
+                    worker_id,
+                    reason,
+                    timestamp: at(t),
+                }
+            }),
+        (
+            plan_id(),
+            position(),
+            orientation(),
+            prop::collection::vec((position(), orientation()), 0..3),
+            prop::collection::vec(plan_id(), 0..3),
+            any::<u16>()
+        )
+            .prop_map(
+                move |(plan_id, start, orientation, goals, leg_plan_ids, t)| {
+                    PathPlanningEvent::MultiGoalPlanRequested {
+                        planner_id: planner_id(),
+                        request_id: format!("request-{}", plan_id),
+                        plan_id,
+                        agent_id: "agent-1".to_string(),
+                        start_position: start,
+                        start_orientation: orientation,
+                        goals: goals
+                            .into_iter()
+                            .map(|(position, orientation)| PlanGoal {
+                                label: None,
+                                position,
+                                orientation,
+                            })
+                            .collect(),
+                        leg_plan_ids,
+                        order_optimized: false,
+                        timestamp: at(t),
+                    }
+                }
+            ),
+        (
+            plan_id(),
+            prop::collection::vec(position(), 0..4),
+            any::<u16>()
+        )
+            .prop_map(move |(plan_id, waypoints, t)| {
+                PathPlanningEvent::MultiGoalPlanCompleted {
+                    planner_id: planner_id(),
+                    plan_id,
+                    waypoints,
+                    timestamp: at(t),
+                }
+            }),
+        (plan_id(), plan_id(), any::<u16>()).prop_map(move |(plan_id, leg_plan_id, t)| {
+            PathPlanningEvent::MultiGoalPlanFailed {
+                planner_id: planner_id(),
+                plan_id,
+                leg_plan_id,
+                reason: "leg failed".to_string(),
+                timestamp: at(t),
+            }
+        }),
+        (prop::collection::vec(plan_id(), 0..3), any::<u16>()).prop_map(move |(plan_ids, t)| {
+            PathPlanningEvent::PlanBatchRequested {
+                planner_id: planner_id(),
+                batch_id: "batch-1".to_string(),
+                plan_ids,
+                timestamp: at(t),
+            }
+        }),
+        (obstacle_id(), position(), 1..4u8, any::<u16>()).prop_map(
+            move |(id, position, radius, t)| PathPlanningEvent::ObstacleAdded {
+                planner_id: planner_id(),
+                obstacle: Obstacle {
+                    id,
+                    shape: ObstacleShape::Circle {
+                        radius: radius as f64,
+                    },
+                    position,
+                },
+                timestamp: at(t),
+            }
+        ),
+        (obstacle_id(), position(), any::<u16>()).prop_map(move |(obstacle_id, position, t)| {
+            PathPlanningEvent::ObstacleMoved {
+                planner_id: planner_id(),
+                obstacle_id,
+                position,
+                timestamp: at(t),
+            }
+        }),
+        (obstacle_id(), any::<u16>()).prop_map(move |(obstacle_id, t)| {
+            PathPlanningEvent::ObstacleRemoved {
+                planner_id: planner_id(),
+                obstacle_id,
+                timestamp: at(t),
+            }
+        }),
+        (plan_id(), obstacle_id(), any::<u16>()).prop_map(move |(plan_id, obstacle_id, t)| {
+            PathPlanningEvent::PlanInvalidated {
+                planner_id: planner_id(),
+                plan_id,
+                agent_id: "agent-1".to_string(),
+                obstacle_id,
+                start_position: Position2D { x: 0.0, y: 0.0 },
+                destination_position: Position2D { x: 1.0, y: 1.0 },
+                start_orientation: Orientation2D { angle: 0.0 },
+                destination_orientation: Orientation2D { angle: 0.0 },
+                timestamp: at(t),
+            }
+        }),
+        (plan_id(), any::<u16>()).prop_map(move |(plan_id, t)| {
+            PathPlanningEvent::PlanArchived {
+                planner_id: planner_id(),
+                plan: plan(plan_id),
+                timestamp: at(t),
+            }
+        }),
+    ]
+}
+
+/// A planner command with plans named by their index among the active plans,
+/// since each side generates its own plan ids
+#[derive(Debug, Clone)]
+enum Command {
+    RequestPlan(Position2D, Position2D, Orientation2D),
+    RequestMultiGoal(Position2D, Vec<Position2D>, bool),
+    Register(String, Vec<PlanningAlgorithm>),
+    Ready(String),
+    Accept(String, usize),
+    Complete(String, usize, Vec<Position2D>),
+    Fail(String, usize),
+    AddObstacle(String, Position2D, u8),
+    MoveObstacle(String, Position2D),
+    RemoveObstacle(String),
+    Archive(u64),
+}
+
+// Requests range past the workspace bounds so some are rejected
+fn request_position() -> impl Strategy<Value = Position2D> {
+    (-12i8..12, -12i8..12).prop_map(|(x, y)| Position2D {
+        x: x as f64 * 10.0,
+        y: y as f64 * 10.0,
+    })
+}
+
+fn command() -> impl Strategy<Value = Command> {
+    prop_oneof![
+        (request_position(), request_position(), orientation())
+            .prop_map(|(start, goal, orientation)| Command::RequestPlan(start, goal, orientation)),
+        (
+            request_position(),
+            prop::collection::vec(request_position(), 0..3),
+            any::<bool>()
+        )
+            .prop_map(|(start, goals, optimize)| Command::RequestMultiGoal(start, goals, optimize)),
+        (worker_id(), prop::collection::vec(algorithm(), 0..3))
+            .prop_map(|(worker_id, capabilities)| Command::Register(worker_id, capabilities)),
+        worker_id().prop_map(Command::Ready),
+        (worker_id(), 0..6usize).prop_map(|(worker_id, plan)| Command::Accept(worker_id, plan)),
+        (
+            worker_id(),
+            0..6usize,
+            prop::collection::vec(position(), 0..4)
+        )
+            .prop_map(|(worker_id, plan, waypoints)| Command::Complete(worker_id, plan, waypoints)),
+        (worker_id(), 0..6usize).prop_map(|(worker_id, plan)| Command::Fail(worker_id, plan)),
+        (obstacle_id(), position(), 1..4u8)
+            .prop_map(|(id, position, radius)| Command::AddObstacle(id, position, radius)),
+        (obstacle_id(), position()).prop_map(|(id, position)| Command::MoveObstacle(id, position)),
+        obstacle_id().prop_map(Command::RemoveObstacle),
+        prop_oneof![Just(0u64), Just(3600u64)].prop_map(Command::Archive),
+    ]
+}
+
+fn plan_at(plans: &[PathPlan], index: usize) -> String {
+    plans
+        .get(index)
+        .map(|p| p.id.clone())
+        .unwrap_or_else(|| "missing-plan".to_string())
+}
+
+fn goals(positions: Vec<Position2D>) -> Vec<PlanGoal> {
+    positions
+        .into_iter()
+        .map(|position| PlanGoal {
+            label: None,
+            position,
+            orientation: Orientation2D { angle: 0.0 },
+        })
+        .collect()
+}
+
+fn obstacle(id: String, position: Position2D, radius: u8) -> Obstacle {
+    Obstacle {
+        id,
+        shape: ObstacleShape::Circle {
+            radius: radius as f64,
+        },
+        position,
+    }
+}
+
+fn handle_native(planner: &mut PathPlanner, command: Command) -> Result<(), String> {
+    let result = match command {
+        Command::RequestPlan(start, goal, orientation) => {
+            planner.request_path_plan(PathPlanRequest {
+                request_id: "request-1".to_string(),
+                agent_id: "agent-1".to_string(),
+                start_position: start,
+                destination_position: goal,
+                start_orientation: orientation.clone(),
+                destination_orientation: orientation,
+                created_at: Utc::now(),
+            })
+        }
+        Command::RequestMultiGoal(start, positions, optimize_order) => planner
+            .request_multi_goal_plan(MultiGoalPlanRequest {
+                request_id: "request-1".to_string(),
+                agent_id: "agent-1".to_string(),
+                start_position: start,
+                start_orientation: Orientation2D { angle: 0.0 },
+                goals: goals(positions),
+                optimize_order,
+                created_at: Utc::now(),
+            }),
+        Command::Register(worker_id, capabilities) => {
+            planner.register_worker(worker_id, capabilities)
+        }
+        Command::Ready(worker_id) => planner.handle_worker_ready(worker_id),
+        Command::Accept(worker_id, index) => {
+            let plan_id = plan_at(&planner.active_plans, index);
+            planner.handle_plan_assignment_accepted(worker_id, plan_id)
+        }
+        Command::Complete(worker_id, index, waypoints) => {
+            let plan_id = plan_at(&planner.active_plans, index);
+            planner.handle_plan_completed(worker_id, plan_id, waypoints)
+        }
+        Command::Fail(worker_id, index) => {
+            let plan_id = plan_at(&planner.active_plans, index);
+            planner.handle_plan_failed(worker_id, plan_id, "no route".to_string())
+        }
+        Command::AddObstacle(id, position, radius) => {
+            planner.add_obstacle(obstacle(id, position, radius))
+        }
+        Command::MoveObstacle(id, position) => planner.move_obstacle(id, position),
+        Command::RemoveObstacle(id) => planner.remove_obstacle(id),
+        Command::Archive(retention_seconds) => planner
+            .archive_finished_plans(Duration::seconds(retention_seconds as i64), Utc::now())
+            .map(drop),
+    };
+    result.map_err(|e| e.to_string())
+}
+
+fn esrs_command(state: &PathPlannerState, command: Command) -> PathPlannerCommand {
+    match command {
+        Command::RequestPlan(start, goal, orientation) => PathPlannerCommand::RequestPathPlan {
+            request_id: "request-1".to_string(),
+            agent_id: "agent-1".to_string(),
+            start_position: start,
+            destination_position: goal,
+            start_orientation: orientation.clone(),
+            destination_orientation: orientation,
+        },
+        Command::RequestMultiGoal(start, positions, optimize_order) => {
+            PathPlannerCommand::RequestMultiGoalPlan {
+                request_id: "request-1".to_string(),
+                agent_id: "agent-1".to_string(),
+                start_position: start,
+                start_orientation: Orientation2D { angle: 0.0 },
+                goals: goals(positions),
+                optimize_order,
+            }
+        }
+        Command::Register(worker_id, capabilities) => PathPlannerCommand::RegisterWorker {
+            worker_id,
+            capabilities,
+        },
+        Command::Ready(worker_id) => PathPlannerCommand::WorkerReady { worker_id },
+        Command::Accept(worker_id, index) => PathPlannerCommand::PlanAssignmentAccepted {
+            worker_id,
+            plan_id: plan_at(&state.active_plans, index),
+        },
+        Command::Complete(worker_id, index, waypoints) => PathPlannerCommand::PlanCompleted {
+            worker_id,
+            plan_id: plan_at(&state.active_plans, index),
+            waypoints,
+        },
+        Command::Fail(worker_id, index) => PathPlannerCommand::PlanFailed {
+            worker_id,
+            plan_id: plan_at(&state.active_plans, index),
+            reason: "no route".to_string(),
+        },
+        Command::AddObstacle(id, position, radius) => PathPlannerCommand::AddObstacle {
+            obstacle: obstacle(id, position, radius),
+        },
+        Command::MoveObstacle(obstacle_id, position) => PathPlannerCommand::MoveObstacle {
+            obstacle_id,
+            position,
+        },
+        Command::RemoveObstacle(obstacle_id) => PathPlannerCommand::RemoveObstacle { obstacle_id },
+        Command::Archive(retention_seconds) => {
+            PathPlannerCommand::ArchiveFinishedPlans { retention_seconds }
+        }
+    }
+}
+
+/// Replaces what each side generates on its own: times are dropped and
+/// generated ids are numbered in the order they first appear
+#[derive(Default)]
+struct Normalizer {
+    ids: HashMap<String, String>,
+}
+
+impl Normalizer {
+    fn normalize(&mut self, value: Value) -> Value {
+        match value {
+            Value::Object(fields) => Value::Object(
+                fields
+                    .into_iter()
+                    .filter(|(key, _)| {
+                        !matches!(
+                            key.as_str(),
+                            "timestamp"
+                                | "created_at"
+                                | "finished_at"
+                                | "last_heartbeat"
+                                | "assigned_at"
+                                | "timeout_at"
+                        )
+                    })
+                    .map(|(key, value)| (key, self.normalize(value)))
+                    .collect(),
+            ),
+            Value::Array(values) => {
+                Value::Array(values.into_iter().map(|v| self.normalize(v)).collect())
+            }
+            Value::String(s) if uuid::Uuid::parse_str(&s).is_ok() => {
+                let next = format!("id-{}", self.ids.len());
+                Value::String(self.ids.entry(s).or_insert(next).clone())
+            }
+            other => other,
+        }
+    }
+}
+
+proptest! {
+    /// Any event sequence, valid or not, leaves the native and the esrs
+    /// aggregate in the same state
+    #[test]
+    fn test_native_and_esrs_planners_replay_to_the_same_state(
+        events in prop::collection::vec(event(), 0..40)
+    ) {
+        let mut native = PathPlanner::new(PLANNER.to_string(), PlanningAlgorithm::AStar);
+        let mut esrs_state = PathPlannerState {
+            id: native.id.clone(),
+            workspace: native.workspace.clone(),
+            ..PathPlannerState::default()
+        };
+
+        for event in events {
+            // The native aggregate refuses malformed events; esrs must end up the same
+            let _ = native.apply(&event);
+            esrs_state = EsrsPathPlanner::apply_event(esrs_state, event);
+
+            prop_assert_eq!(
+                serde_json::to_value(&native).unwrap(),
+                serde_json::to_value(&esrs_state).unwrap()
+            );
+        }
+    }
+
+    /// Any command sequence makes the native and the esrs aggregate emit the
+    /// same events, and reject the same commands
+    #[test]
+    fn test_native_and_esrs_planners_emit_the_same_events(
+        commands in prop::collection::vec(command(), 0..40)
+    ) {
+        let mut native = PathPlanner::new(PLANNER.to_string(), PlanningAlgorithm::AStar);
+        native.mark_events_as_committed();
+        let mut esrs_state = PathPlannerState {
+            id: native.id.clone(),
+            workspace: native.workspace.clone(),
+            ..PathPlannerState::default()
+        };
+        let mut native_ids = Normalizer::default();
+        let mut esrs_ids = Normalizer::default();
+
+        for command in commands {
+            let native_result = handle_native(&mut native, command.clone());
+            let esrs_result =
+                EsrsPathPlanner::handle_command(&esrs_state, esrs_command(&esrs_state, command));
+            prop_assert_eq!(native_result.is_err(), esrs_result.is_err());
+
+            let native_events = serde_json::to_value(native.uncommitted_events()).unwrap();
+            native.mark_events_as_committed();
+            let esrs_events = esrs_result.unwrap_or_default();
+            for event in esrs_events.clone() {
+                esrs_state = EsrsPathPlanner::apply_event(esrs_state, event);
+            }
+            let esrs_events = serde_json::to_value(&esrs_events).unwrap();
+
+            prop_assert_eq!(native_ids.normalize(native_events), esrs_ids.normalize(esrs_events));
+            prop_assert_eq!(
+                native_ids.normalize(serde_json::to_value(&native).unwrap()),
+                esrs_ids.normalize(serde_json::to_value(&esrs_state).unwrap())
+            );
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use gryphon_app::adapters::inbound::{InMemoryEventStore, InMemorySnapshotStore};
+use gryphon_app::common::*;
+use gryphon_app::domains::path_planning::{PathPlanner, PathPlanningEvent, PlanningAlgorithm};
+use std::sync::Arc;
+
+const PLANNER: &str = "planner-1";
+
+fn start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 5, 1, 14, 0, 0).unwrap()
+}
+
+fn envelope(event: &PathPlanningEvent) -> EventEnvelope {
+    let metadata = EventMetadata {
+        correlation_id: None,
+        causation_id: None,
+        user_id: None,
+        source: "test".to_string(),
+        reply_to: None,
+        tenant: None,
+    };
+    EventEnvelope::new(event, "PathPlanner", metadata).unwrap()
+}
+
+/// A planner created at 14:00 that registers a worker at 14:01, 14:02 and 14:03
+async fn planner_history() -> Arc<InMemoryEventStore> {
+    let mut events = vec![PathPlanningEvent::PlannerCreated {
+        planner_id: PLANNER.to_string(),
+        algorithm: PlanningAlgorithm::AStar,
+        timestamp: start(),
+    }];
+    for minute in 1..=3 {
+        events.push(PathPlanningEvent::WorkerRegistered {
+            planner_id: PLANNER.to_string(),
+            worker_id: format!("worker-{}", minute),
+            capabilities: vec![PlanningAlgorithm::AStar],
+            timestamp: start() + Duration::minutes(minute),
+        });
+    }
+    let store = Arc::new(InMemoryEventStore::new());
+    store
+        .append_events(PLANNER, 0, events.iter().map(envelope).collect())
+        .await
+        .unwrap();
+    store
+}
+
+fn repository(store: Arc<InMemoryEventStore>) -> AggregateRepository<PathPlanner> {
+    AggregateRepository::new(store, |id| {
+        PathPlanner::new(id.to_string(), PlanningAlgorithm::AStar)
+    })
+}
+
+fn worker_ids(planner: &PathPlanner) -> Vec<&str> {
+    planner
+        .registered_workers
+        .iter()
+        .map(|worker| worker.worker_id.as_str())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_load_as_of_version_replays_a_prefix() {
+    let repository = repository(planner_history().await);
+
+    let state = repository
+        .load_as_of(PLANNER, AsOf::Version(2))
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(state.version, 2);
+    assert_eq!(worker_ids(&state.aggregate), vec!["worker-1"]);
+    assert_eq!(state.last_event_at, Some(start() + Duration::minutes(1)));
+    assert_eq!(state.snapshot_version, None);
+    assert!(state.aggregate.uncommitted_events().is_empty());
+
+    let current = repository.load(PLANNER).await.unwrap().unwrap();
+    assert_eq!(current.version, 4);
+    assert_eq!(worker_ids(&current.aggregate).len(), 3);
+}
+
+#[tokio::test]
+async fn test_load_as_of_time_includes_events_up_to_it() {
+    let repository = repository(planner_history().await);
+
+    let at_1402 = repository
+        .load_as_of(PLANNER, AsOf::Time(start() + Duration::minutes(2)))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(at_1402.version, 3);
+    assert_eq!(worker_ids(&at_1402.aggregate), vec!["worker-1", "worker-2"]);
+
+    let at_1402_30 = repository
+        .load_as_of(PLANNER, AsOf::Time(start() + Duration::seconds(150)))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(at_1402_30.version, 3);
+
+    let before_creation = repository
+        .load_as_of(PLANNER, AsOf::Time(start() - Duration::seconds(1)))
+        .await
+        .unwrap();
+    assert!(before_creation.is_none());
+    assert!(repository.load("unknown").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_load_as_of_starts_from_the_best_snapshot() {
+    let store = planner_history().await;
+    let snapshots = Arc::new(InMemorySnapshotStore::new());
+    // Snapshots are marked with an algorithm no event sets, to see which one was used
+    for version in [2, 3] {
+        let mut state = repository(store.clone())
+            .load_as_of(PLANNER, AsOf::Version(version))
+            .await
+            .unwrap()
+            .unwrap()
+            .aggregate;
+        state.algorithm = PlanningAlgorithm::Dijkstra;
+        let snapshot = Snapshot::new(PLANNER, "PathPlanner", version, &state).unwrap();
+        snapshots.save_snapshot(snapshot).await.unwrap();
+    }
+    let repository = repository(store).with_snapshots(snapshots);
+
+    let current = repository.load(PLANNER).await.unwrap().unwrap();
+    assert_eq!(current.snapshot_version, Some(3));
+    assert_eq!(current.version, 4);
+    assert_eq!(current.aggregate.algorithm, PlanningAlgorithm::Dijkstra);
+    assert_eq!(worker_ids(&current.aggregate).len(), 3);
+
+    let at_1401 = repository
+        .load_as_of(PLANNER, AsOf::Time(start() + Duration::minutes(1)))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(at_1401.snapshot_version, Some(2));
+    assert_eq!(at_1401.version, 2);
+    assert_eq!(at_1401.last_event_at, None);
+
+    // No snapshot is old enough for the first event
+    let first = repository
+        .load_as_of(PLANNER, AsOf::Version(1))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(first.snapshot_version, None);
+    assert_eq!(first.aggregate.algorithm, PlanningAlgorithm::AStar);
+}
@@ -0,0 +1,214 @@
+use gryphon_app::adapters::inbound::{FileEventStore, InProcessMessageBus};
+use gryphon_app::common::*;
+use gryphon_app::config::Config;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn envelope(aggregate_id: &str, tenant: Option<&str>) -> EventEnvelope {
+    EventEnvelope {
+        event_id: uuid::Uuid::new_v4(),
+        aggregate_id: aggregate_id.to_string(),
+        aggregate_type: "TestAggregate".to_string(),
+        event_type: "TestEvent".to_string(),
+        event_version: 1,
+        event_data: serde_json::json!({"test": "data"}),
+        metadata: EventMetadata {
+            correlation_id: None,
+            causation_id: None,
+            user_id: None,
+            source: "test".to_string(),
+            reply_to: None,
+            tenant: tenant.map(str::to_string),
+        },
+        occurred_at: chrono::Utc::now(),
+        chain: None,
+    }
+}
+
+async fn next_within(subscription: &mut Box<dyn MessageSubscription>) -> Option<BusMessage> {
+    tokio::time::timeout(Duration::from_millis(100), subscription.next())
+        .await
+        .ok()
+        .and_then(|message| message.unwrap())
+}
+
+#[test]
+fn test_tenant_config_scopes_every_section() {
+    let team_a = Config::default().for_tenant("team_a").unwrap();
+    let team_b = Config::default().for_tenant("team_b").unwrap();
+
+    assert_eq!(team_a.tenant.as_deref(), Some("team_a"));
+    assert_eq!(
+        team_a.kafka.topics.path_planning_events,
+        "team_a.path-planning-events"
+    );
+    assert_eq!(team_a.kafka.topics.dead_letters, "team_a.dead-letters");
+    assert_ne!(
+        team_a.kafka.topics.path_planning_replies,
+        team_b.kafka.topics.path_planning_replies
+    );
+    assert_ne!(team_a.kafka.group_id, team_b.kafka.group_id);
+    assert!(team_a
+        .kafka
+        .topic_settings
+        .overrides
+        .contains_key("team_a.path-planning-replies"));
+
+    assert_eq!(team_a.postgres.schema.as_deref(), Some("tenant_team_a"));
+    assert_eq!(
+        team_a.postgres.connection_options().as_deref(),
+        Some("-c search_path=tenant_team_a")
+    );
+    assert_ne!(team_a.event_store.directory, team_b.event_store.directory);
+    assert!(team_a.event_store.directory.ends_with("team_a"));
+
+    // The planner id only has to be unique within a tenant
+    assert_eq!(
+        team_a.path_planning.planner_id,
+        team_b.path_planning.planner_id
+    );
+    assert_eq!(
+        team_a.event_store.schema_registry_path,
+        Config::default().event_store.schema_registry_path
+    );
+}
+
+#[test]
+fn test_tenant_config_rejects_bad_ids_and_rescoping() {
+    // "team-a" would share the schema tenant_team_a with "team_a"
+    for tenant in [
+        "",
+        "Team",
+        "1team",
+        "team a",
+        "team/../b",
+        "team.a",
+        "team-a",
+    ] {
+        assert!(
+            Config::default().for_tenant(tenant).is_err(),
+            "{:?} should be rejected",
+            tenant
+        );
+    }
+
+    let scoped = Config::default().for_tenant("team_a").unwrap();
+    assert!(scoped.for_tenant("team_b").is_err());
+}
+
+#[tokio::test]
+async fn test_tenant_named_in_config_file_scopes_it() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    let mut content = toml::to_string(&Config::default()).unwrap();
+    content.insert_str(0, "tenant = \"team_a\"\n");
+    tokio::fs::write(&path, content).await.unwrap();
+
+    let config = Config::from_file(&path).await.unwrap();
+
+    assert_eq!(config.tenant.as_deref(), Some("team_a"));
+    assert_eq!(
+        config.kafka.topics.path_planning_events,
+        "team_a.path-planning-events"
+    );
+}
+
+#[tokio::test]
+async fn test_file_stores_of_two_tenants_are_isolated() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = Config::default();
+    config.event_store.directory = dir.path().to_string_lossy().into_owned();
+    let team_a = config.clone().for_tenant("team_a").unwrap();
+    let team_b = config.for_tenant("team_b").unwrap();
+    let store_a = FileEventStore::new(&team_a.event_store.directory);
+    let store_b = FileEventStore::new(&team_b.event_store.directory);
+
+    // Both tenants use the same planner id
+    let planner_id = &team_a.path_planning.planner_id;
+    store_a
+        .append_events(planner_id, 0, vec![envelope(planner_id, Some("team_a"))])
+        .await
+        .unwrap();
+
+    assert_eq!(store_a.load_events(planner_id, 0).await.unwrap().len(), 1);
+    assert!(store_b.load_events(planner_id, 0).await.unwrap().is_empty());
+    assert!(store_b
+        .load_events_by_type("TestEvent", None)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Team B starts its own history at version 0 without a conflict
+    store_b
+        .append_events(planner_id, 0, vec![envelope(planner_id, Some("team_b"))])
+        .await
+        .unwrap();
+    let events_a = store_a.load_events(planner_id, 0).await.unwrap();
+    assert_eq!(events_a.len(), 1);
+    assert_eq!(events_a[0].metadata.tenant.as_deref(), Some("team_a"));
+}
+
+#[tokio::test]
+async fn test_tenant_bus_stamps_and_filters_on_a_shared_topic() {
+    let shared: Arc<dyn MessageBus> = Arc::new(InProcessMessageBus::new());
+    let team_a = TenantScopedBus::new(shared.clone(), "team_a").unwrap();
+    let team_b = TenantScopedBus::new(shared.clone(), "team_b").unwrap();
+    // Consumer groups are per tenant, as `Config::for_tenant` prefixes them
+    let mut sub_a = team_a.subscribe("events", "team_a-planner").await.unwrap();
+    let mut sub_b = team_b.subscribe("events", "team_b-planner").await.unwrap();
+    let mut unscoped = shared.subscribe("events", "audit").await.unwrap();
+
+    team_a
+        .publish("events", &envelope("a", None))
+        .await
+        .unwrap();
+    team_b
+        .publish("events", &envelope("b", None))
+        .await
+        .unwrap();
+    shared
+        .publish("events", &envelope("legacy", None))
+        .await
+        .unwrap();
+
+    let message = next_within(&mut sub_a).await.unwrap();
+    assert_eq!(message.envelope.aggregate_id, "a");
+    assert_eq!(message.envelope.metadata.tenant.as_deref(), Some("team_a"));
+    assert!(next_within(&mut sub_a).await.is_none());
+
+    assert_eq!(
+        next_within(&mut sub_b).await.unwrap().envelope.aggregate_id,
+        "b"
+    );
+    assert!(next_within(&mut sub_b).await.is_none());
+
+    // Subscribers outside the tenant decorator still see everything
+    for expected in ["a", "b", "legacy"] {
+        let message = next_within(&mut unscoped).await.unwrap();
+        assert_eq!(message.envelope.aggregate_id, expected);
+    }
+}
+
+#[tokio::test]
+async fn test_tenant_bus_refuses_envelopes_of_other_tenants() {
+    let shared: Arc<dyn MessageBus> = Arc::new(InProcessMessageBus::new());
+    let team_a = TenantScopedBus::new(shared.clone(), "team_a").unwrap();
+    let mut all = shared.subscribe("events", "audit").await.unwrap();
+
+    let err = team_a
+        .publish("events", &envelope("b", Some("team_b")))
+        .await
+        .unwrap_err();
+    assert!(err.contains("team_b"));
+    team_a
+        .publish("events", &envelope("a", Some("team_a")))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        next_within(&mut all).await.unwrap().envelope.aggregate_id,
+        "a"
+    );
+    assert!(next_within(&mut all).await.is_none());
+    assert!(TenantScopedBus::new(shared, "Team A").is_err());
+}